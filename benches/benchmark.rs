@@ -0,0 +1,171 @@
+use std::{hint::black_box, thread};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use gorilla::{
+    benchmark::{
+        base_words, generate_pattern, generate_pattern_in_parallel, mutate, mutate_in_parallel,
+        mutation_sets,
+    },
+    mutation::{MutationResult, MutationSet},
+    patterns::{token_iterator, tokenize_format_string},
+};
+
+/// Fewer words than `gorilla --benchmark` goes through, as criterion runs
+/// every workload many times
+const WORDS: usize = 2_000;
+const PATTERN_WORDS: usize = 200_000;
+
+fn sets(sets: &[&[&str]]) -> Vec<MutationSet> {
+    sets.iter()
+        .map(|mutations| MutationSet::new(mutations).unwrap())
+        .collect()
+}
+
+/// The stages `gorilla --benchmark` reports, on one thread and on every
+/// core
+fn workload(c: &mut Criterion) {
+    let mutation_sets = mutation_sets();
+    let words = base_words(WORDS);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+
+    let mut group = c.benchmark_group("mutations");
+    group.throughput(Throughput::Elements(mutate(&mutation_sets, &words)));
+    group.bench_function(BenchmarkId::new("threads", 1), |b| {
+        b.iter(|| mutate(&mutation_sets, black_box(&words)))
+    });
+    if threads > 1 {
+        group.bench_function(BenchmarkId::new("threads", threads), |b| {
+            b.iter(|| mutate_in_parallel(&mutation_sets, black_box(&words), threads))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("pattern");
+    group.throughput(Throughput::Elements(PATTERN_WORDS as u64));
+    group.bench_function(BenchmarkId::new("threads", 1), |b| {
+        b.iter(|| generate_pattern(black_box(PATTERN_WORDS)))
+    });
+    if threads > 1 {
+        group.bench_function(BenchmarkId::new("threads", threads), |b| {
+            b.iter(|| generate_pattern_in_parallel(black_box(PATTERN_WORDS), threads))
+        });
+    }
+    group.finish();
+}
+
+/// Handing the words of a word on as they're made, against collecting them
+fn streaming(c: &mut Criterion) {
+    let sets = sets(&[&["append:{0-9}{0-9}", "prepend:{0-9}", "nothing"]]);
+    let words: Vec<String> = (0..WORDS).map(|n| format!("password{n}")).collect();
+
+    let mut group = c.benchmark_group("streaming");
+    group.bench_function("collecting", |b| {
+        b.iter(|| {
+            let mut bytes = 0;
+            for word in &words {
+                let mut mutation_result = MutationResult {
+                    original_word: word.clone(),
+                    mutated_words: vec![],
+                    mutation_set: 0,
+                };
+                sets[0].perform(&mut mutation_result, word);
+                bytes += mutation_result
+                    .mutated_words
+                    .iter()
+                    .map(String::len)
+                    .sum::<usize>();
+            }
+            bytes
+        })
+    });
+    group.bench_function("streaming", |b| {
+        b.iter(|| {
+            let mut bytes = 0;
+            for word in &words {
+                sets[0].perform_streaming(word, &mut |mutated| bytes += mutated.len());
+            }
+            bytes
+        })
+    });
+    group.finish();
+}
+
+/// A pipeline of filters passing the words through borrowed, against
+/// copying every one of them
+fn filters(c: &mut Criterion) {
+    // fewer than 5% of the words get through
+    let sets = sets(&[&["lowercase_all", "replace:@:a", "if_contains:99"]]);
+    let words: Vec<String> = (0..WORDS * 10).map(|n| format!("password{n}")).collect();
+
+    let mut group = c.benchmark_group("filters");
+    group.bench_function("copying", |b| {
+        b.iter(|| {
+            let mut bytes = 0;
+            for word in &words {
+                let replaced = word.to_lowercase().replace('@', "a");
+                if replaced.contains("99") {
+                    bytes += replaced.len();
+                }
+            }
+            bytes
+        })
+    });
+    group.bench_function("borrowing", |b| {
+        b.iter(|| {
+            let mut bytes = 0;
+            for word in &words {
+                sets[0].perform_streaming(word, &mut |mutated| bytes += mutated.len());
+            }
+            bytes
+        })
+    });
+    group.finish();
+}
+
+/// Appending patterns prepared once, against tokenizing and generating the
+/// pattern again for every word
+fn affixes(c: &mut Criterion) {
+    let patterns = ["{0-9}", "{!@#$%}", "{1990-2025}", "{0-9}{0-9}"];
+    let sets = sets(&[
+        &["append:{0-9}"],
+        &["append:{!@#$%}"],
+        &["append:{1990-2025}"],
+        &["append:{0-9}{0-9}"],
+    ]);
+    let words: Vec<String> = (0..WORDS).map(|n| format!("password{n}")).collect();
+
+    let mut group = c.benchmark_group("affixes");
+    group.bench_function("tokenizing", |b| {
+        b.iter(|| {
+            let mut bytes = 0;
+            let mut mutated = String::new();
+            for word in &words {
+                for pattern in patterns {
+                    for affix in token_iterator(&tokenize_format_string(pattern).unwrap()) {
+                        mutated.clear();
+                        mutated.push_str(word);
+                        mutated.push_str(&affix);
+                        bytes += mutated.len();
+                    }
+                }
+            }
+            bytes
+        })
+    });
+    group.bench_function("prepared", |b| {
+        b.iter(|| {
+            let mut bytes = 0;
+            for word in &words {
+                for set in &sets {
+                    set.perform_streaming(word, &mut |mutated| bytes += mutated.len());
+                }
+            }
+            bytes
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, workload, streaming, filters, affixes);
+criterion_main!(benches);
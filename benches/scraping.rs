@@ -0,0 +1,54 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use gorilla::website_scraper::{extract_words, ScrapeOptions};
+
+/// About the size of a big page
+const PAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// A page of paragraphs of made-up words, one in four of them new, so
+/// that keeping the words found so far apart has plenty to do
+fn page() -> String {
+    let mut page = String::from("<html><head><title>Benchmark</title></head><body>\n");
+    let mut n: u64 = 0;
+    while page.len() < PAGE_BYTES {
+        page.push_str("<p>");
+        for _ in 0..20 {
+            page.push_str(&word(n / 4 + n % 4 * 1000));
+            page.push(' ');
+            n += 1;
+        }
+        page.push_str("</p>\n");
+    }
+    page.push_str("</body></html>\n");
+    page
+}
+
+/// The letters of `n` in base 26, long enough to be kept
+fn word(mut n: u64) -> String {
+    let mut word = String::from("go");
+    loop {
+        word.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+        if n == 0 && word.len() > 5 {
+            return word;
+        }
+    }
+}
+
+/// `extract_words` on one big page, which was quadratic in its words
+fn scraping(c: &mut Criterion) {
+    let page = page();
+    let options = ScrapeOptions::default();
+
+    let mut group = c.benchmark_group("scraping");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(page.len() as u64));
+    group.bench_function("extract_words", |b| {
+        b.iter(|| extract_words(black_box(&page), &options))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, scraping);
+criterion_main!(benches);
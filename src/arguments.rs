@@ -1,36 +1,601 @@
-use clap::{Parser, Subcommand};
+use clap::{
+    builder::PossibleValuesParser, error::ErrorKind, parser::ValueSource, Arg, ArgGroup,
+    ArgMatches, Command, CommandFactory, FromArgMatches, Parser, ValueEnum, ValueHint,
+};
+use clap_complete::Shell;
+use clap_mangen::{
+    roff::{bold, italic, roman, Roff},
+    Man,
+};
+
+#[cfg(feature = "scrape")]
+use crate::website_scraper::WordCase;
+use crate::{
+    build_info::BuildInfo,
+    char_sets::CHAR_SETS,
+    crack::HashType,
+    csv_parser::CsvColumn,
+    input::{is_url, InvalidUtf8},
+    keyboard_walk::{KeyboardLayout, WalkLengths},
+    mutation::{ACTIONS, MODIFIERS},
+    number_range::NumberRange,
+    output::{OutputCompression, OutputEncoding, OutputFormat, Unencodable},
+    patterns::TOKENS,
+    phrases::PhraseInput,
+    policy::PRESETS,
+    wordlist::WordlistAction,
+};
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    io::{self, Write},
+    sync::OnceLock,
+};
 
 #[derive(Parser, Debug)]
 #[clap(
     author,
     version,
+    long_version = crate::build_info::long_version(),
     about = "a wordlist generator",
     long_about = "if you want to contribute to this project, check out the github repo: https://github.com/d4rckh/gorilla"
 )]
 pub struct ProgramArgs {
-    #[clap(short = 'i', long = "from-file", help = "Specify the input file")]
-    pub file_input: Option<String>,
+    #[clap(value_name = "WORDS", help = "Words to mutate, before any other input", groups = ["mutate", "wordlist"])]
+    pub words: Vec<String>,
+
+    #[clap(
+        long = "word",
+        group = "mutate",
+        help = "A word to mutate, before any other input (can be repeated)"
+    )]
+    pub word: Vec<String>,
+
+    #[clap(
+        short = 'i',
+        long = "from-file",
+        value_hint = ValueHint::AnyPath,
+        group = "mutate",
+        help = "Specify the input file, directory or glob, - reads the words from standard input (can be repeated)",
+        long_help = "Specify the input file, directory or glob, - reads the words from standard input (can be repeated)\n\n\
+                     Example: gorilla -i names.txt -i 'lists/*.txt' -m append:1"
+    )]
+    pub file_input: Vec<String>,
+
+    #[clap(
+        short = 'r',
+        long = "recursive",
+        group = "mutate",
+        help = "Also read the files in subdirectories of directories given to --from-file"
+    )]
+    pub recursive: bool,
+
+    #[clap(
+        long = "input-skip",
+        default_value = "0",
+        group = "mutate",
+        help = "Skip the first N lines of the input files"
+    )]
+    pub input_skip: u64,
+
+    #[clap(
+        long = "input-count",
+        group = "mutate",
+        help = "Stop after reading N lines of the input files"
+    )]
+    pub input_count: Option<u64>,
+
+    #[clap(
+        long = "input-sample",
+        value_name = "N",
+        conflicts_with = "interleave",
+        group = "mutate",
+        help = "Only mutate N input lines, picked at random from all of the input files (see --seed)"
+    )]
+    pub input_sample: Option<usize>,
+
+    #[clap(
+        long = "invalid-utf8",
+        value_enum,
+        default_value_t = InvalidUtf8::Lossy,
+        group = "mutate",
+        help = "What to do with input lines that aren't valid UTF-8"
+    )]
+    pub invalid_utf8: InvalidUtf8,
+
+    #[clap(
+        long = "input-cache",
+        value_hint = ValueHint::DirPath,
+        value_name = "DIR",
+        group = "mutate",
+        help = "Keep wordlists downloaded from URLs in DIR and read them from there next time (see --scrape-cache-ttl and --scrape-cache-refresh)"
+    )]
+    pub input_cache: Option<String>,
+
+    #[clap(
+        long = "input-filter",
+        value_name = "REGEX",
+        value_parser = Regex::new,
+        group = "output",
+        help = "Only mutate input words matching this regex (can be repeated, keeping words that match any)"
+    )]
+    pub input_filter: Vec<Regex>,
+
+    #[clap(
+        long = "input-filter-not",
+        value_name = "REGEX",
+        value_parser = Regex::new,
+        group = "output",
+        help = "Don't mutate input words matching this regex (can be repeated)"
+    )]
+    pub input_filter_not: Vec<Regex>,
+
+    #[clap(
+        long = "exclude-file",
+        value_hint = ValueHint::FilePath,
+        value_name = "PATH",
+        group = "output",
+        help = "Skip input words that are a line of this file, like a potfile of words already tried (can be repeated)"
+    )]
+    pub exclude_file: Vec<String>,
+
+    #[clap(
+        long = "exclude-output",
+        requires = "exclude_file",
+        group = "output",
+        help = "Also leave out generated words that are in an --exclude-file"
+    )]
+    pub exclude_output: bool,
+
+    #[clap(
+        long = "exclude-approx",
+        requires = "exclude_file",
+        group = "output",
+        help = "Keep the --exclude-file words in a bloom filter, which takes far less memory but also skips about 1% of the other words"
+    )]
+    pub exclude_approx: bool,
+
+    #[clap(
+        long = "policy",
+        value_name = "NAME",
+        value_parser = PossibleValuesParser::new(PRESETS),
+        group = "output",
+        help = "Leave out generated words a password policy wouldn't allow: ad-default (7 characters, 3 of lower, upper, digit and special), nist (8 characters) or pci (12 characters with letters, digits and specials) (can be repeated)"
+    )]
+    pub policy: Vec<String>,
+
+    #[clap(
+        long = "policy-file",
+        value_hint = ValueHint::FilePath,
+        value_name = "PATH",
+        group = "output",
+        help = "Leave out generated words the password policy of this yaml file wouldn't allow (can be repeated)"
+    )]
+    pub policy_file: Vec<String>,
+
+    #[clap(
+        long = "min-strength",
+        value_name = "SCORE",
+        value_parser = clap::value_parser!(u8).range(0..=4),
+        group = "output",
+        help = "Leave out generated words zxcvbn scores below SCORE, from 0 to 4, as for decoys that have to look like real passwords"
+    )]
+    pub min_strength: Option<u8>,
+
+    #[clap(
+        long = "annotate-strength",
+        group = "output",
+        help = "Give the zxcvbn score of every generated word, with --output-format json, json-array or csv"
+    )]
+    pub annotate_strength: bool,
+
+    #[clap(
+        long = "dedup-input",
+        group = "output",
+        help = "Only mutate the first occurrence of every input word, across all inputs"
+    )]
+    pub dedup_input: bool,
+
+    #[clap(
+        long = "dedup-approx",
+        value_name = "CAPACITY",
+        num_args = 0..=1,
+        default_missing_value = "100000000",
+        group = "output",
+        help = "Like --dedup-input, with a fixed-size bloom filter for about CAPACITY words (occasionally skips a new word)"
+    )]
+    pub dedup_approx: Option<u64>,
 
-    #[clap(short = 'l', long = "one-line", help = "Print the output on one line")]
+    #[clap(
+        long = "unique",
+        group = "output",
+        help = "Only output the first occurrence of every generated word"
+    )]
+    pub unique: bool,
+
+    #[clap(
+        long = "unique-approx",
+        value_name = "CAPACITY",
+        num_args = 0..=1,
+        default_missing_value = "100000000",
+        group = "output",
+        help = "Like --unique, with a fixed-size bloom filter for about CAPACITY words (occasionally leaves out a new word)"
+    )]
+    pub unique_approx: Option<u64>,
+
+    #[clap(
+        long = "unique-error-rate",
+        requires = "unique_approx",
+        default_value = "0.01",
+        value_parser = parse_rate,
+        group = "output",
+        help = "How many new words --unique-approx may take for repeats, 0.01 being 1%"
+    )]
+    pub unique_error_rate: f64,
+
+    #[clap(
+        long = "unique-mem",
+        value_name = "MB",
+        requires = "unique_approx",
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "output",
+        help = "Size the --unique-approx filter to MB of memory instead of for CAPACITY words"
+    )]
+    pub unique_mem: Option<u64>,
+
+    #[clap(
+        long = "estimate-unique",
+        conflicts_with_all = ["unique", "unique_approx"],
+        group = "output",
+        help = "Estimate how many of the generated words are distinct, in 16 KB of memory and without leaving any out"
+    )]
+    pub estimate_unique: bool,
+
+    #[clap(
+        long = "sort",
+        group = "output",
+        help = "Output the generated words sorted, once all of them have been generated"
+    )]
+    pub sort: bool,
+
+    #[clap(
+        long = "sort-length",
+        conflicts_with = "sort",
+        group = "output",
+        help = "Like --sort, ordering the words by length first"
+    )]
+    pub sort_length: bool,
+
+    #[clap(
+        long = "sort-memory",
+        value_name = "MB",
+        default_value = "1024",
+        value_parser = clap::value_parser!(u64).range(1..),
+        groups = ["output", "wordlist"],
+        help = "How much memory --sort and gorilla wordlist may use before they spill words to temporary files"
+    )]
+    pub sort_memory: u64,
+
+    #[clap(
+        long = "sort-temp-dir",
+        value_hint = ValueHint::DirPath,
+        value_name = "DIR",
+        groups = ["output", "wordlist"],
+        help = "Where --sort and gorilla wordlist put their temporary files (the system's temporary directory by default)"
+    )]
+    pub sort_temp_dir: Option<String>,
+
+    #[clap(
+        long = "keep-order",
+        group = "wordlist",
+        help = "Keep the words of gorilla wordlist in the order they first come instead of sorting them"
+    )]
+    pub keep_order: bool,
+
+    #[clap(
+        long = "interleave",
+        conflicts_with_all = ["input_skip", "input_count"],
+        group = "mutate",
+        help = "Take turns between the input files and pattern instead of reading them one after the other"
+    )]
+    pub interleave: bool,
+
+    #[clap(
+        long = "csv-input",
+        value_hint = ValueHint::FilePath,
+        requires = "csv_column",
+        group = "mutate",
+        help = "Read words from columns of a CSV file"
+    )]
+    pub csv_input: Option<String>,
+
+    #[clap(
+        long = "csv-column",
+        group = "mutate",
+        help = "Column of --csv-input to read, by header name or position starting at 1 (can be repeated)"
+    )]
+    pub csv_column: Vec<CsvColumn>,
+
+    #[clap(
+        long = "csv-no-header",
+        group = "mutate",
+        help = "The first row of --csv-input is data rather than column names"
+    )]
+    pub csv_no_header: bool,
+
+    #[clap(
+        long = "jsonl-input",
+        value_hint = ValueHint::FilePath,
+        requires = "json_field",
+        group = "mutate",
+        help = "Read words from a field of every record in a JSON Lines file"
+    )]
+    pub jsonl_input: Option<String>,
+
+    #[clap(
+        long = "json-field",
+        group = "mutate",
+        help = "Dotted path of the --jsonl-input field to read, like user.name"
+    )]
+    pub json_field: Option<String>,
+
+    #[clap(
+        short = 'l',
+        long = "one-line",
+        conflicts_with = "output_delimiter",
+        group = "output",
+        help = "Print the output on one line, same as --output-delimiter ' ' followed by a final newline"
+    )]
     pub one_line: bool,
 
+    #[clap(
+        long = "output-delimiter",
+        value_name = "STRING",
+        default_value = "\\n",
+        value_parser = parse_delimiter,
+        conflicts_with = "output_format",
+        group = "output",
+        help = "What follows every generated word, escapes like \\0, \\t and \\n included"
+    )]
+    pub output_delimiter: String,
+
+    #[clap(
+        long = "color",
+        value_enum,
+        default_value = "auto",
+        group = "general",
+        help = "Color the messages gorilla prints (auto: when stderr is a terminal and NO_COLOR isn't set)"
+    )]
+    pub color: ColorChoice,
+
+    #[clap(
+        long = "quiet",
+        group = "general",
+        help = "Only print errors and the summary at the end, without the progress display that's otherwise shown when stderr is a terminal"
+    )]
+    pub quiet: bool,
+
+    #[clap(
+        short = 'v',
+        long = "verbose",
+        action = clap::ArgAction::Count,
+        conflicts_with = "quiet",
+        group = "general",
+        help = "Also print every file and URL read and the lines skipped in each, -vv every skipped line"
+    )]
+    pub verbose: u8,
+
     #[clap(
         short = 't',
         long = "timer",
-        help = "Show the amount of time it took to mutate/compute a word"
+        group = "output",
+        help = "Print the time, the words read and generated and how many words a second every --timer-interval seconds, to stderr"
     )]
     pub timer: bool,
 
+    #[clap(
+        long = "timer-interval",
+        value_name = "SECONDS",
+        default_value_t = 5,
+        requires = "timer",
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "output",
+        help = "How often --timer prints the throughput"
+    )]
+    pub timer_interval: u64,
+
+    #[clap(
+        long = "timer-per-word",
+        conflicts_with = "timer",
+        group = "output",
+        help = "Print every generated word to stderr as well, with the time since the start"
+    )]
+    pub timer_per_word: bool,
+
     #[clap(
         short = 'p',
         long = "from-pattern",
-        help = "Generate words from a pattern"
+        groups = ["mutate", "generate"],
+        help = "Generate words from a pattern",
+        long_help = "Generate words from a pattern, see PATTERNS in the man page for the tokens\n\n\
+                     Example: gorilla -p 'admin{0-9}{0-9}'"
     )]
     pub pattern_input: Option<String>,
 
+    #[clap(
+        long = "range-input",
+        value_name = "START-END[:STEP][:PAD]",
+        group = "generate",
+        help = "Generate every STEPth number from START to END, zero-padded to PAD digits, like 0-9999:1:4 (can be repeated)"
+    )]
+    pub range_input: Vec<NumberRange>,
+
+    #[clap(
+        long = "keyboard-walks",
+        value_name = "LENGTHS",
+        group = "generate",
+        help = "Generate keyboard walks like qwerty or 1qaz2wsx of these lengths, like 4-8 or 6"
+    )]
+    pub keyboard_walks: Option<WalkLengths>,
+
+    #[clap(
+        long = "keyboard-layout",
+        value_enum,
+        default_value_t = KeyboardLayout::Qwerty,
+        group = "generate",
+        help = "Layout of the keyboard to walk on"
+    )]
+    pub keyboard_layout: KeyboardLayout,
+
+    #[clap(
+        long = "keyboard-walk-turns",
+        default_value = "1",
+        group = "generate",
+        help = "How often a keyboard walk may change direction"
+    )]
+    pub keyboard_walk_turns: usize,
+
+    #[clap(
+        long = "keyboard-walk-shift",
+        group = "generate",
+        help = "Also generate every keyboard walk typed with shift held down"
+    )]
+    pub keyboard_walk_shift: bool,
+
+    #[clap(
+        long = "phrase-input",
+        value_name = "FILE:COUNT[:SEPARATOR]",
+        group = "mutate",
+        help = "Generate every phrase of COUNT words from FILE, joined by SEPARATOR"
+    )]
+    pub phrase_input: Option<PhraseInput>,
+
+    #[clap(
+        long = "phrase-skip",
+        default_value = "0",
+        group = "mutate",
+        help = "Start at this phrase of --phrase-input, counting from 0"
+    )]
+    pub phrase_skip: u128,
+
+    #[clap(
+        long = "phrase-limit",
+        group = "mutate",
+        help = "Generate at most this many phrases with --phrase-input"
+    )]
+    pub phrase_limit: Option<u128>,
+
+    #[clap(
+        long = "prince",
+        value_hint = ValueHint::FilePath,
+        value_name = "FILE",
+        group = "generate",
+        help = "Generate the words made of 1 to --prince-max-elements words of FILE put together, shortest first, like the PRINCE attack"
+    )]
+    pub prince_input: Option<String>,
+
+    #[clap(
+        long = "prince-min-len",
+        value_name = "N",
+        default_value_t = 1,
+        group = "generate",
+        help = "The shortest --prince word, in characters"
+    )]
+    pub prince_min_len: usize,
+
+    #[clap(
+        long = "prince-max-len",
+        value_name = "N",
+        default_value_t = 16,
+        group = "generate",
+        help = "The longest --prince word, in characters"
+    )]
+    pub prince_max_len: usize,
+
+    #[clap(
+        long = "prince-max-elements",
+        value_name = "N",
+        default_value_t = 4,
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "generate",
+        help = "The most words of the --prince FILE put together in a word"
+    )]
+    pub prince_max_elements: u64,
+
+    #[clap(
+        long = "prince-skip",
+        default_value = "0",
+        value_name = "N",
+        group = "generate",
+        help = "Start at this word of --prince, counting from 0"
+    )]
+    pub prince_skip: u128,
+
+    #[clap(
+        long = "prince-limit",
+        value_name = "N",
+        group = "generate",
+        help = "Generate at most this many words with --prince"
+    )]
+    pub prince_limit: Option<u128>,
+
+    #[clap(
+        long = "profile",
+        value_hint = ValueHint::FilePath,
+        value_name = "FILE",
+        group = "generate",
+        help = "Generate the candidates of what's known about a person, read from a yaml FILE",
+        long_help = "Generate the candidates of what's known about a person, read from a yaml FILE: their name, \
+                     and names, nicknames, birth_dates, pets, company, teams and keywords, put together like \
+                     name+year, pet+birthday and initials+company\n\n\
+                     Example: gorilla generate --profile jane.yaml -m append:!"
+    )]
+    pub profile: Option<String>,
+
+    #[clap(
+        long = "prepend-pattern",
+        value_name = "PATTERN",
+        group = "output",
+        help = "Put every word of the PATTERN before every input word, before mutating it",
+        long_help = "Put every word of the PATTERN before every input word, before mutating it, like the hybrid attack -a7 of hashcat\n\n\
+                     Example: gorilla mutate -i words.txt --prepend-pattern '{A-Z}'"
+    )]
+    pub prepend_pattern: Option<String>,
+
+    #[clap(
+        long = "append-pattern",
+        value_name = "PATTERN",
+        group = "output",
+        help = "Put every word of the PATTERN after every input word, before mutating it",
+        long_help = "Put every word of the PATTERN after every input word, before mutating it, like the hybrid attack -a6 of hashcat\n\n\
+                     Example: gorilla mutate -i words.txt --append-pattern '{0-9}{0-9}'"
+    )]
+    pub append_pattern: Option<String>,
+
+    #[clap(
+        long = "hybrid-skip",
+        default_value = "0",
+        value_name = "N",
+        group = "output",
+        help = "Start at this word of --prepend-pattern and --append-pattern for every input word, counting from 0"
+    )]
+    pub hybrid_skip: u128,
+
+    #[clap(
+        long = "hybrid-limit",
+        value_name = "N",
+        group = "output",
+        help = "Make at most this many words of every input word with --prepend-pattern and --append-pattern"
+    )]
+    pub hybrid_limit: Option<u128>,
+
     #[clap(
         short = 'q',
         long = "from-formatting",
+        value_hint = ValueHint::FilePath,
+        groups = ["generate", "check"],
         help = "Generate words from custom formatting applied"
     )]
     pub from_formatting: Option<String>,
@@ -38,6 +603,8 @@ pub struct ProgramArgs {
     #[clap(
         short = 'c',
         long = "with-csv",
+        value_hint = ValueHint::FilePath,
+        group = "generate",
         help = "Use a CSV as input for formatting fields"
     )]
     pub csv: Option<String>,
@@ -45,37 +612,1199 @@ pub struct ProgramArgs {
     #[clap(
         short = 'w',
         long = "from-website",
-        help = "Spider a website and generate a wordlist from it's page contents"
+        value_hint = ValueHint::Url,
+        group = "scrape",
+        help = "Spider a website and generate a wordlist from it's page contents (can be repeated)",
+        long_help = "Spider a website and generate a wordlist from it's page contents (can be repeated)\n\n\
+                     Example: gorilla -w https://example.com --crawl-depth 1 -o words.txt"
+    )]
+    pub website_input: Vec<String>,
+
+    #[clap(
+        long = "website-list",
+        value_hint = ValueHint::FilePath,
+        group = "scrape",
+        help = "Specify a file with one website per line to scrape words from"
+    )]
+    pub website_list: Option<String>,
+
+    #[clap(
+        long = "url-stdin",
+        group = "scrape",
+        help = "Scrape the URLs piped to stdin, one per line, as they come",
+        long_help = "Scrape the URLs piped to stdin, one per line, as they come, \
+                     until stdin is closed. Lines starting with # are skipped.\n\n\
+                     Example: tail -f urls.txt | gorilla scrape --url-stdin --crawl-state crawl.json"
+    )]
+    pub url_stdin: bool,
+
+    #[clap(
+        long = "sitemap",
+        group = "scrape",
+        help = "Scrape every page listed in a sitemap.xml (can be repeated)"
+    )]
+    pub sitemap: Vec<String>,
+
+    #[clap(
+        long = "feed",
+        group = "scrape",
+        help = "Scrape the titles, descriptions and categories of an RSS or Atom feed (can be repeated)"
+    )]
+    pub feed: Vec<String>,
+
+    #[clap(
+        long = "feed-follow",
+        group = "scrape",
+        help = "Also scrape the page each feed entry links to"
+    )]
+    pub feed_follow: bool,
+
+    #[clap(
+        long = "crawl-depth",
+        default_value = "0",
+        group = "scrape",
+        help = "Follow the links on scraped pages this many levels deep"
+    )]
+    pub crawl_depth: usize,
+
+    #[clap(
+        long = "crawl-max-pages",
+        group = "scrape",
+        help = "Maximum amount of pages to scrape from sitemaps, feeds and followed links"
+    )]
+    pub crawl_max_pages: Option<usize>,
+
+    #[clap(
+        long = "crawl-include",
+        value_parser = Regex::new,
+        group = "scrape",
+        help = "Only follow links matching this regex (can be repeated)"
+    )]
+    pub crawl_include: Vec<Regex>,
+
+    #[clap(
+        long = "crawl-exclude",
+        value_parser = Regex::new,
+        group = "scrape",
+        help = "Don't follow links matching this regex (can be repeated)"
+    )]
+    pub crawl_exclude: Vec<Regex>,
+
+    #[clap(
+        long = "crawl-offsite",
+        group = "scrape",
+        help = "Also follow links to other hosts than the page they're on"
+    )]
+    pub crawl_offsite: bool,
+
+    #[clap(
+        long = "crawl-state",
+        value_hint = ValueHint::FilePath,
+        value_name = "PATH",
+        group = "scrape",
+        help = "Keep the pages scraped and queued in PATH, and go on from there when it's run again"
+    )]
+    pub crawl_state: Option<String>,
+
+    #[clap(
+        long = "scrape-meta",
+        group = "scrape",
+        help = "Also scrape words from the page title, meta tags and alt/title attributes"
+    )]
+    pub scrape_meta: bool,
+
+    #[clap(
+        long = "scrape-emails",
+        group = "scrape",
+        help = "Also scrape email addresses and derive usernames from them"
+    )]
+    pub scrape_emails: bool,
+
+    #[clap(
+        long = "email-file",
+        value_hint = ValueHint::FilePath,
+        group = "scrape",
+        help = "Save scraped email addresses to this file instead of mutating them"
+    )]
+    pub email_file: Option<String>,
+
+    #[clap(
+        long = "insecure",
+        group = "download",
+        help = "Do not verify TLS certificates when scraping (dangerous)"
+    )]
+    pub insecure: bool,
+
+    #[clap(
+        long = "ca-cert",
+        value_hint = ValueHint::FilePath,
+        group = "download",
+        help = "Trust the CA certificates in this PEM file when scraping"
+    )]
+    pub ca_cert: Option<String>,
+
+    #[clap(
+        long = "timeout",
+        default_value_t = 30,
+        group = "download",
+        help = "Connect and read timeout in seconds for scraping, 0 to wait forever"
+    )]
+    pub timeout: u64,
+
+    #[clap(
+        long = "scrape-save",
+        value_hint = ValueHint::FilePath,
+        group = "scrape",
+        help = "Save the scraped words to this file before mutating them"
+    )]
+    pub scrape_save: Option<String>,
+
+    #[clap(
+        long = "scrape-dedup-case",
+        value_enum,
+        num_args = 0..=1,
+        default_missing_value = "first",
+        group = "scrape",
+        help = "Ignore case when deduplicating scraped words, keeping the first seen casing or lowercasing them"
+    )]
+    pub scrape_dedup_case: Option<WordCase>,
+
+    #[clap(
+        long = "scrape-ngrams",
+        default_value_t = 1,
+        group = "scrape",
+        help = "Also scrape groups of up to N adjacent words, joined with and without spaces"
+    )]
+    pub scrape_ngrams: usize,
+
+    #[clap(
+        long = "scrape-json",
+        group = "scrape",
+        help = "Treat every scraped page as JSON, even without a JSON content type"
+    )]
+    pub scrape_json: bool,
+
+    #[clap(
+        long = "scrape-json-limit",
+        default_value_t = 10 * 1024 * 1024,
+        group = "scrape",
+        help = "Skip JSON documents larger than this many bytes"
+    )]
+    pub scrape_json_limit: usize,
+
+    #[clap(
+        long = "scrape-threads",
+        default_value_t = 4,
+        group = "scrape",
+        help = "Amount of pages to download at the same time"
+    )]
+    pub scrape_threads: usize,
+
+    #[clap(
+        long = "scrape-delay",
+        default_value_t = 0,
+        group = "scrape",
+        help = "Minimum delay in milliseconds between two requests to the same host"
+    )]
+    pub scrape_delay: u64,
+
+    #[clap(
+        long = "max-download-size",
+        default_value_t = 10 * 1024 * 1024,
+        group = "download",
+        help = "Skip pages larger than this many bytes"
+    )]
+    pub max_download_size: u64,
+
+    #[clap(
+        long = "preflight-network",
+        group = "download",
+        help = "Before the run starts, check that every URL it downloads from answers, along with the files it reads and writes"
     )]
-    pub website_input: Option<String>,
+    pub preflight_network: bool,
+
+    #[clap(
+        long = "user-agent",
+        value_name = "STRING",
+        group = "download",
+        help = "Send STRING as the User-Agent of downloads instead of gorilla/VERSION"
+    )]
+    pub user_agent: Option<String>,
+
+    #[clap(
+        long = "scrape-numbers",
+        group = "scrape",
+        help = "Also scrape standalone numbers of 2 to 6 digits (years, postcodes, ...)"
+    )]
+    pub scrape_numbers: bool,
+
+    #[clap(
+        long = "scrape-count",
+        group = "scrape",
+        help = "Count how often scraped words occur and mutate the most frequent ones first"
+    )]
+    pub scrape_count: bool,
+
+    #[clap(
+        long = "scrape-count-file",
+        value_hint = ValueHint::FilePath,
+        group = "scrape",
+        help = "Save the scraped words with their counts (word<TAB>count) to this file"
+    )]
+    pub scrape_count_file: Option<String>,
+
+    #[clap(
+        long = "scrape-cache",
+        value_hint = ValueHint::DirPath,
+        group = "scrape",
+        help = "Cache downloaded pages in this directory and reuse them on later runs"
+    )]
+    pub scrape_cache: Option<String>,
+
+    #[clap(
+        long = "scrape-cache-ttl",
+        default_value = "86400",
+        group = "scrape",
+        help = "Seconds after which a cached page is downloaded again"
+    )]
+    pub scrape_cache_ttl: u64,
+
+    #[clap(
+        long = "scrape-cache-refresh",
+        group = "scrape",
+        help = "Download every page again, updating the cache"
+    )]
+    pub scrape_cache_refresh: bool,
+
+    #[clap(
+        long = "respect-robots",
+        group = "scrape",
+        help = "Skip pages disallowed for gorilla by the site's robots.txt"
+    )]
+    pub respect_robots: bool,
+
+    #[clap(
+        long = "seed",
+        group = "output",
+        help = "Seed for everything random, like --input-sample and the scramble and chance mutations, to repeat a run exactly (random by default)"
+    )]
+    pub seed: Option<u64>,
 
     #[clap(
         short = 'o',
         long = "output-file",
-        help = "Specify the file in which the results will be saved"
+        value_hint = ValueHint::FilePath,
+        groups = ["output", "wordlist"],
+        help = "Specify the file in which the results will be saved",
+        long_help = "Specify the file in which the results will be saved\n\n\
+                     Example: gorilla -i names.txt -m reverse -o reversed.txt"
     )]
     pub file_save: Option<String>,
 
+    #[clap(
+        long = "output-dir",
+        value_hint = ValueHint::DirPath,
+        value_name = "DIR",
+        group = "output",
+        help = "Put a relative --output-file or --output-by-length in DIR"
+    )]
+    pub output_dir: Option<String>,
+
+    #[clap(
+        long = "append",
+        requires = "file_save",
+        group = "output",
+        help = "Add to the end of the --output-file instead of overwriting it"
+    )]
+    pub append: bool,
+
+    #[clap(
+        long = "no-clobber",
+        requires = "file_save",
+        conflicts_with = "append",
+        group = "output",
+        help = "Refuse to overwrite an existing --output-file"
+    )]
+    pub no_clobber: bool,
+
+    #[clap(
+        long = "tee",
+        requires = "file_save",
+        group = "output",
+        help = "Also write the generated words to stdout when writing them to --output-file"
+    )]
+    pub tee: bool,
+
+    #[clap(
+        long = "pipe-to",
+        value_hint = ValueHint::CommandString,
+        value_name = "COMMAND",
+        conflicts_with_all = ["tee", "output_by_length", "dry_run"],
+        group = "output",
+        help = "Run COMMAND and write the words to its stdin, along with the --output-file if there is one, exiting with its exit code"
+    )]
+    pub pipe_to: Option<String>,
+
+    #[clap(
+        long = "flush-every",
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "output",
+        help = "Flush the output every N words, so that what reads it from a pipe or a FIFO gets them at most N words late"
+    )]
+    pub flush_every: Option<u64>,
+
+    #[clap(
+        long = "output-compress",
+        value_enum,
+        requires = "file_save",
+        group = "output",
+        help = "Compress the --output-file, by default when it ends with .gz or .zst"
+    )]
+    pub output_compress: Option<OutputCompression>,
+
+    #[clap(
+        long = "output-compress-level",
+        value_parser = clap::value_parser!(u32).range(0..=22),
+        group = "output",
+        help = "How hard to compress the output, 1-9 for gzip (6 by default) and 1-22 for zstd (3 by default)"
+    )]
+    pub output_compress_level: Option<u32>,
+
+    #[clap(
+        long = "output-split-lines",
+        value_name = "N",
+        requires = "file_save",
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "output",
+        help = "Start a new numbered --output-file (out.0001.txt, out.0002.txt, ...) every N lines"
+    )]
+    pub output_split_lines: Option<u64>,
+
+    #[clap(
+        long = "output-split-bytes",
+        value_name = "N",
+        requires = "file_save",
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "output",
+        help = "Start a new numbered --output-file before it grows past N bytes (before compression)"
+    )]
+    pub output_split_bytes: Option<u64>,
+
+    #[clap(
+        long = "output-by-length",
+        value_hint = ValueHint::AnyPath,
+        value_name = "DIR|TEMPLATE",
+        conflicts_with_all = ["file_save", "output_format", "one_line", "sort", "sort_length", "dry_run"],
+        group = "output",
+        help = "Write the words to a file per length in characters, DIR/len8.txt or a TEMPLATE like out.len{}.txt for 8"
+    )]
+    pub output_by_length: Option<String>,
+
+    #[clap(
+        long = "output-header",
+        group = "output",
+        help = "Start the output files with a comment line saying which gorilla, mutation sets and seed made them, and when"
+    )]
+    pub output_header: bool,
+
+    #[clap(
+        long = "output-header-comment",
+        value_name = "STR",
+        default_value = "#",
+        requires = "output_header",
+        group = "output",
+        help = "What the --output-header line starts with"
+    )]
+    pub output_header_comment: String,
+
+    #[clap(
+        long = "output-format",
+        value_enum,
+        default_value = "text",
+        conflicts_with_all = ["one_line", "sort", "sort_length"],
+        group = "output",
+        help = "Write the generated words as plain text, or along with the words they came from as json lines, a json array or csv"
+    )]
+    pub output_format: OutputFormat,
+
+    #[clap(
+        long = "output-encoding",
+        value_enum,
+        default_value = "utf8",
+        conflicts_with = "output_format",
+        group = "output",
+        help = "Write the generated words in latin1, for targets that hash passwords in that encoding"
+    )]
+    pub output_encoding: OutputEncoding,
+
+    #[clap(
+        long = "unencodable",
+        value_enum,
+        default_value = "skip",
+        group = "output",
+        help = "What to do with the words --output-encoding can't encode: leave them out, or write them as $HEX[...] of their utf8 bytes"
+    )]
+    pub unencodable: Unencodable,
+
+    #[clap(
+        long = "threads",
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        group = "output",
+        help = "Mutate the input words on N threads at once, the output stays the same"
+    )]
+    pub threads: usize,
+
+    #[clap(
+        long = "generate-completions",
+        value_name = "SHELL",
+        value_enum,
+        hide = true,
+        help = "Print the tab completion script for SHELL, then exit"
+    )]
+    pub generate_completions: Option<CompletionShell>,
+
+    #[clap(
+        long = "generate-manpage",
+        hide = true,
+        help = "Print the man page, with the actions of mutations and the tokens of patterns, then exit"
+    )]
+    pub generate_manpage: bool,
+
+    #[clap(
+        long = "benchmark",
+        help = "Measure how many words per second the mutations and patterns make on this machine, with a built-in workload, then exit"
+    )]
+    pub benchmark: bool,
+
+    #[clap(
+        long = "list-mutations",
+        help = "List the actions mutations can have, with their arguments and an example of each, then exit"
+    )]
+    pub list_mutations: bool,
+
+    #[clap(
+        long = "no-config",
+        group = "general",
+        help = "Don't read the defaults in ~/.config/gorilla/config.toml, for runs that only go by their flags"
+    )]
+    pub no_config: bool,
+
+    #[clap(
+        long = "show-config",
+        group = "general",
+        help = "Print the threads, color, mutations file, user agent and output directory the run would use, and where each came from, then exit"
+    )]
+    pub show_config: bool,
+
+    #[clap(
+        long = "dry-run",
+        conflicts_with = "scrape_save",
+        group = "output",
+        help = "Count the words that would be generated, and their size, without writing anything"
+    )]
+    pub dry_run: bool,
+
+    #[clap(
+        long = "dry-run-sample",
+        value_name = "N",
+        requires = "dry_run",
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "output",
+        help = "Only mutate the first N input words for --dry-run and extrapolate from them"
+    )]
+    pub dry_run_sample: Option<u64>,
+
+    #[clap(
+        long = "stats-file",
+        value_hint = ValueHint::FilePath,
+        value_name = "PATH",
+        group = "output",
+        help = "Write a summary of the run as json to PATH at the end"
+    )]
+    pub stats_file: Option<String>,
+
+    #[clap(
+        long = "report",
+        group = "output",
+        help = "Print how many of the generated words there are of every length, and how many have digits, uppercase letters, specials and so on"
+    )]
+    pub report: bool,
+
+    #[clap(
+        long = "checkpoint",
+        value_hint = ValueHint::FilePath,
+        value_name = "PATH",
+        requires = "file_save",
+        conflicts_with_all = [
+            "interleave", "input_skip", "input_count", "input_sample", "sort", "sort_length",
+            "output_split_lines", "output_split_bytes", "output_compress", "append", "no_clobber", "dry_run"
+        ],
+        groups = ["mutate", "generate"],
+        help = "Keep track of how far the run got in PATH, and resume from there when it's run again"
+    )]
+    pub checkpoint: Option<String>,
+
+    #[clap(
+        long = "max-words",
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "output",
+        help = "Stop once N words were generated (after the mutation set that reaches N)"
+    )]
+    pub max_words: Option<u64>,
+
+    #[clap(
+        long = "max-output-bytes",
+        value_name = "N",
+        conflicts_with_all = ["sort", "sort_length"],
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "output",
+        help = "Stop once N bytes were written, before compression (after the mutation set that reaches N)"
+    )]
+    pub max_output_bytes: Option<u64>,
+
+    #[clap(
+        long = "crack",
+        value_hint = ValueHint::FilePath,
+        value_name = "HASHFILE",
+        requires = "hash_type",
+        conflicts_with_all = ["tee", "pipe_to"],
+        group = "output",
+        help = "Check every generated word against the hashes of HASHFILE, one in hex per line, printing hash:word to stdout for the ones found",
+        long_help = "Check every generated word against the hashes of HASHFILE, one in hex per line, printing hash:word to stdout \
+                     for the ones found. The words are only written with --output-file or --output-by-length then.\n\n\
+                     Example: gorilla mutate -i words.txt -m append:{0-9} --crack hashes.txt --hash-type ntlm"
+    )]
+    pub crack: Option<String>,
+
+    #[clap(
+        long = "hash-type",
+        value_enum,
+        requires = "crack",
+        group = "output",
+        help = "What the hashes of --crack are"
+    )]
+    pub hash_type: Option<HashType>,
+
+    #[clap(
+        long = "stop-on-all-found",
+        requires = "crack",
+        group = "output",
+        help = "Stop once every hash of --crack was found"
+    )]
+    pub stop_on_all_found: bool,
+
     #[clap(
         short = 'm',
         long = "mutation",
-        help = "Specify a way to mutate the words. Format is action:param1:param2"
+        groups = ["output", "check", "repl"],
+        help = "Specify a way to mutate the words. Format is action:param1:param2 (can be repeated, chaining the mutations in one set)",
+        long_help = "Specify a way to mutate the words. Format is action:param1:param2 (can be repeated, chaining the mutations in one set). \
+                     --list-mutations, or MUTATIONS in the man page, lists the actions\n\n\
+                     Example: gorilla -i names.txt -m 'k append:{0-9}' -m uppercase_all"
     )]
     pub mutation_string: Vec<String>,
 
+    #[clap(
+        long = "set-m",
+        value_name = "MUTATION",
+        groups = ["output", "check", "repl"],
+        help = "Start another mutation set with this mutation, which the -m after it are chained to (can be repeated)",
+        long_help = "Start another mutation set with this mutation, which the -m after it are chained to (can be repeated)\n\n\
+                     Example: gorilla -i names.txt -m reverse --set-m uppercase_all -m append:!"
+    )]
+    pub set_mutation: Vec<String>,
+
     #[clap(
         short = 'f',
         long = "mutations-file",
-        help = "Specify a path to a yaml file"
+        value_hint = ValueHint::FilePath,
+        groups = ["output", "check", "repl"],
+        help = "Specify a path to a yaml file",
+        long_help = "Specify a path to a yaml file, with a name and the sets of mutations\n\n\
+                     Example: gorilla -i names.txt -f rules.yml"
     )]
     pub mutations_file: Option<String>,
 
-    #[clap(subcommand)]
+    #[clap(
+        long = "explain",
+        group = "output",
+        help = "Print how the mutations of every set turn the first input words into the generated ones, step by step, to stderr"
+    )]
+    pub explain: bool,
+
+    #[clap(
+        long = "explain-limit",
+        value_name = "N",
+        default_value_t = 10,
+        requires = "explain",
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "output",
+        help = "How many input words --explain shows the mutations of"
+    )]
+    pub explain_limit: u64,
+
+    #[clap(
+        long = "preview",
+        value_name = "WORD",
+        num_args = 0..=1,
+        default_missing_value = "password",
+        group = "output",
+        help = "Show the first words every mutation set makes of WORD (password without one) in the mutation sets summary, with --preview WORD or --preview=WORD",
+        long_help = "Show the first words every mutation set makes of WORD in the mutation sets summary, \
+                     and how many it makes of it in all, which takes as long as making them does. \
+                     The WORD goes after --preview or after --preview=, and the word right after \
+                     a bare --preview is taken for it rather than for an input word. Without a WORD \
+                     (at the end, or before another flag), `password` is mutated.\n\n\
+                     Example: gorilla -i names.txt -f rules.yml --preview Summer --dry-run"
+    )]
+    pub preview: Option<String>,
+
+    #[clap(
+        long = "preview-limit",
+        value_name = "N",
+        default_value_t = 5,
+        requires = "preview",
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "output",
+        help = "How many of the words of a set --preview shows"
+    )]
+    pub preview_limit: u64,
+
+    #[clap(
+        long = "exec-timeout",
+        value_name = "SECS",
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u64).range(1..),
+        groups = ["output", "serve"],
+        help = "How long an exec: mutation's command has to answer for a word before the run is given up on"
+    )]
+    pub exec_timeout: u64,
+
+    #[clap(
+        long = "listen",
+        value_name = "ADDR",
+        group = "serve",
+        help = "Serve generated words over HTTP on this address, like 127.0.0.1:8080, for the jobs POSTed to /generate and /estimate"
+    )]
+    pub listen: Option<String>,
+
+    #[clap(
+        long = "sets-dir",
+        value_name = "DIR",
+        value_hint = ValueHint::DirPath,
+        group = "serve",
+        help = "The mutations files the jobs of --listen name their sets from, by the name in each file"
+    )]
+    pub sets_dir: Option<String>,
+
+    #[clap(
+        long = "max-jobs",
+        value_name = "N",
+        default_value_t = 4,
+        value_parser = clap::value_parser!(u64).range(1..),
+        group = "serve",
+        help = "How many requests --listen answers at once, turning the others down with 503"
+    )]
+    pub max_jobs: u64,
+
+    #[clap(
+        long = "allow-exec",
+        group = "serve",
+        help = "Let the mutations of the jobs --listen is sent have exec: actions, which run commands on this machine for anyone who can reach it"
+    )]
+    pub allow_exec: bool,
+
+    /// The command given before the flags, `None` for the flat flags of
+    /// before there were commands
+    #[clap(skip)]
     pub command: Option<Commands>,
+
+    /// What `gorilla wordlist` does, `merge` or `diff`
+    #[clap(skip)]
+    pub wordlist_action: Option<WordlistAction>,
+
+    /// The mutations of `-m` and `--set-m` as the sets they make, in the
+    /// order they were given
+    #[clap(skip)]
+    pub mutation_chains: Vec<Vec<String>>,
+
+    /// The ids of the flags given on the command line, which win over the
+    /// config file
+    #[clap(skip)]
+    pub given: Vec<String>,
 }
 
-#[derive(Subcommand, Debug)]
+/// The shells `--generate-completions` writes scripts for
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+/// When messages are colored
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// `--scrape-dedup-case`, which is only parsed to be refused without the
+/// `scrape` feature
+#[cfg(not(feature = "scrape"))]
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum WordCase {
+    Lower,
+    First,
+}
+
+/// What gorilla is asked to do, each with only the flags that go with it.
+/// They're made of the flags of `ProgramArgs`, so that the run goes by the
+/// same arguments whichever way they were given.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Commands {
-    // does testing things
-    // list_mutations {    },
+    Mutate,
+    Generate,
+    Scrape,
+    Check,
+    Repl,
+    Serve,
+    Wordlist,
+}
+
+/// The groups the flags are in, by what they're for: `general` for the
+/// flags of every command, `output` for those of every command that writes
+/// words, `download` and `scrape` for downloading and scraping pages, and
+/// the others for the flags of that command alone. A command takes the
+/// flags of its groups, and a flag says which groups it's in.
+const FLAG_GROUPS: [&str; 10] = [
+    "general", "output", "download", "scrape", "mutate", "generate", "check", "repl", "serve",
+    "wordlist",
+];
+
+/// Whether the flag with the `id` is in the `group`
+fn in_group(group: &str, id: &str) -> bool {
+    static GROUPS: OnceLock<HashMap<String, Vec<String>>> = OnceLock::new();
+    let groups = GROUPS.get_or_init(|| {
+        let mut command = ProgramArgs::command();
+        command.build();
+        command
+            .get_groups()
+            .map(|group| {
+                let ids = group.get_args().map(|id| id.to_string()).collect();
+                (group.get_id().to_string(), ids)
+            })
+            .collect()
+    });
+    groups
+        .get(group)
+        .is_some_and(|ids| ids.iter().any(|flag| flag == id))
+}
+
+impl Commands {
+    pub const ALL: [Commands; 7] = [
+        Commands::Mutate,
+        Commands::Generate,
+        Commands::Scrape,
+        Commands::Check,
+        Commands::Repl,
+        Commands::Serve,
+        Commands::Wordlist,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Commands::Mutate => "mutate",
+            Commands::Generate => "generate",
+            Commands::Scrape => "scrape",
+            Commands::Check => "check",
+            Commands::Repl => "repl",
+            Commands::Serve => "serve",
+            Commands::Wordlist => "wordlist",
+        }
+    }
+
+    fn about(self) -> &'static str {
+        match self {
+            Commands::Mutate => "Mutate the words of wordlists, stdin, csv or jsonl files and phrases",
+            Commands::Generate => "Generate words from patterns, number ranges, keyboard walks and formatting files, and mutate them",
+            Commands::Scrape => "Scrape the words of websites, sitemaps and feeds, and mutate them",
+            Commands::Check => "Check mutation and formatting files without generating anything",
+            Commands::Repl => "Mutate the words typed at a prompt, to try out mutation sets while writing them",
+            Commands::Serve => "Stream the words of jobs POSTed as json over HTTP, and estimate how many they make",
+            Commands::Wordlist => "Merge wordlists or leave the words of some out of another, sorting them on disk when they don't fit in memory",
+        }
+    }
+
+    /// The groups of `FLAG_GROUPS` with the flags the command takes,
+    /// besides `general`
+    fn flag_groups(self) -> &'static [&'static str] {
+        match self {
+            Commands::Mutate => &["mutate", "output", "download"],
+            Commands::Generate => &["generate", "output"],
+            Commands::Scrape => &["scrape", "output", "download"],
+            Commands::Check => &["check"],
+            Commands::Repl => &["repl"],
+            Commands::Serve => &["serve"],
+            Commands::Wordlist => &["wordlist"],
+        }
+    }
+
+    /// Whether the flag with the `id` goes with the command
+    pub fn takes(self, id: &str) -> bool {
+        std::iter::once(&"general")
+            .chain(self.flag_groups())
+            .any(|group| in_group(group, id))
+    }
+}
+
+/// The cargo feature the flag with the `id` needs, which gorilla can be
+/// built without
+pub fn feature_of(id: &str) -> Option<&'static str> {
+    if in_group("scrape", id) || in_group("download", id) || id == "input_cache" {
+        Some("scrape")
+    } else if [
+        "mutations_file",
+        "from_formatting",
+        "sets_dir",
+        "policy_file",
+        "profile",
+    ]
+    .contains(&id)
+    {
+        Some("yaml")
+    } else if ["min_strength", "annotate_strength"].contains(&id) {
+        Some("strength")
+    } else {
+        None
+    }
+}
+
+/// Whether gorilla was built with the cargo `feature`
+pub fn built_with(feature: &str) -> bool {
+    BuildInfo::get().features.contains(&feature)
+}
+
+/// Why what needs the cargo `feature` can't be done
+pub fn without_feature(feature: &str) -> String {
+    format!("gorilla was built without the {feature} feature, build it with `--features {feature}` to have it")
+}
+
+/// The feature the flag with the `id` needs that gorilla was built without
+fn unbuilt_feature(id: &str) -> Option<&'static str> {
+    feature_of(id).filter(|feature| !built_with(feature))
+}
+
+impl ProgramArgs {
+    /// Parse the command line, with a command or with the flat flags. The
+    /// commands take every flag, but only show the ones that go with them
+    /// and refuse the others.
+    pub fn parse_command_line() -> ProgramArgs {
+        ProgramArgs::try_parse_command_line(env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// The flat flags, and the commands made of them. The flags of the
+    /// features gorilla was built without are hidden, they're only there
+    /// to be refused.
+    pub fn cli() -> Command {
+        // a group a flag is in is made when there's none yet, taking only
+        // one of its flags
+        let groups = || FLAG_GROUPS.map(|group| ArgGroup::new(group).multiple(true));
+        let flat = ProgramArgs::command().groups(groups()).mut_args(|arg| {
+            match unbuilt_feature(arg.get_id().as_str()) {
+                Some(_) => arg.hide(true),
+                None => arg,
+            }
+        });
+        let args = |command: Commands| {
+            flat.get_arguments().map(move |arg| {
+                let id = arg.get_id().as_str();
+                let takes = command.takes(id) && unbuilt_feature(id).is_none();
+                arg.clone().hide(!takes)
+            })
+        };
+        let commands: Vec<Command> = Commands::ALL
+            .iter()
+            .map(|&command| {
+                let sub = Command::new(command.name())
+                    .about(command.about())
+                    .groups(groups())
+                    .hide(command == Commands::Scrape && !built_with("scrape"));
+                if command != Commands::Wordlist {
+                    return sub.args(args(command));
+                }
+                // the actions of `gorilla wordlist` are commands of their
+                // own, with the wordlists in the place of the words
+                sub.subcommand_required(true)
+                    .subcommands(WordlistAction::ALL.map(|action| {
+                        Command::new(action.name())
+                            .about(action.about())
+                            .groups(groups())
+                            .args(args(command))
+                            .mut_arg("words", |arg| {
+                                arg.value_name("WORDLISTS").help(action.wordlists_help())
+                            })
+                    }))
+            })
+            .collect();
+        flat.subcommands(commands)
+    }
+
+    /// Write the tab completion script for `shell` to `out`. Besides the
+    /// flags and commands, it completes the names of the actions after
+    /// `-m`.
+    pub fn write_completions(shell: CompletionShell, out: &mut dyn Write) {
+        let actions = |arg: Arg| {
+            arg.value_parser(PossibleValuesParser::new(
+                ACTIONS.iter().map(|action| action.name),
+            ))
+        };
+        let mut cli = ProgramArgs::cli()
+            .mut_arg("mutation_string", actions)
+            .mut_arg("set_mutation", actions);
+        for command in Commands::ALL {
+            if command == Commands::Wordlist {
+                continue;
+            }
+            cli = cli.mut_subcommand(command.name(), |sub| {
+                sub.mut_arg("mutation_string", actions)
+                    .mut_arg("set_mutation", actions)
+            });
+        }
+        let shell = match shell {
+            CompletionShell::Bash => Shell::Bash,
+            CompletionShell::Zsh => Shell::Zsh,
+            CompletionShell::Fish => Shell::Fish,
+            CompletionShell::Powershell => Shell::PowerShell,
+        };
+        clap_complete::generate(shell, &mut cli, "gorilla", out);
+    }
+
+    /// Write the man page to `out`: the flags and commands as `--help` has
+    /// them, then every action of `ACTIONS` and token of `TOKENS`, so it
+    /// can't say other than the code does.
+    pub fn write_manpage(out: &mut dyn Write) -> io::Result<()> {
+        let man = Man::new(ProgramArgs::cli());
+        man.render_title(out)?;
+        man.render_name_section(out)?;
+        man.render_synopsis_section(out)?;
+        man.render_description_section(out)?;
+        man.render_options_section(out)?;
+        man.render_subcommands_section(out)?;
+
+        let mut roff = Roff::new();
+        roff.control("SH", ["MUTATIONS"]);
+        roff.text([roman(
+            "A mutation is an action and its arguments, split by colons. The actions are:",
+        )]);
+        for action in ACTIONS {
+            let (mutation, word, _) = action.example;
+            roff.control("TP", []);
+            roff.text([bold(action.signature())]);
+            roff.text([roman(action.description)]);
+            roff.control("br", []);
+            roff.text([
+                roman("Example: "),
+                italic(mutation),
+                roman(format!(" makes {} of {word}", action.example_made())),
+            ]);
+        }
+        roff.control("PP", []);
+        roff.text([roman(MODIFIERS.replace('\n', " "))]);
+
+        roff.control("SH", ["PATTERNS"]);
+        roff.text([roman(
+            "A pattern of -p is made of these tokens, and every word it generates has one of the words of each:",
+        )]);
+        for token in TOKENS {
+            let (pattern, made) = token.example;
+            roff.control("TP", []);
+            roff.text([bold(token.syntax)]);
+            roff.text([roman(token.description)]);
+            roff.control("br", []);
+            roff.text([
+                roman("Example: "),
+                italic(pattern),
+                roman(format!(" makes {}", made.join(", "))),
+            ]);
+        }
+        roff.control("PP", []);
+        roff.text([roman("The sets of characters are:")]);
+        for (name, chars) in CHAR_SETS {
+            roff.control("TP", []);
+            roff.text([bold(format!("{{{name}}}"))]);
+            roff.text([roman(*chars)]);
+        }
+        roff.to_writer(out)?;
+
+        man.render_version_section(out)?;
+        man.render_authors_section(out)
+    }
+
+    pub fn try_parse_command_line<I, T>(args: I) -> Result<ProgramArgs, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut cli = ProgramArgs::cli();
+        let matches = cli.try_get_matches_from_mut(args)?;
+
+        let (command, matches) = match matches.subcommand() {
+            Some((name, matches)) => {
+                let command = Commands::ALL
+                    .into_iter()
+                    .find(|command| command.name() == name);
+                (command, matches.clone())
+            }
+            None => (None, matches.clone()),
+        };
+        let (wordlist_action, matches) = match command {
+            Some(Commands::Wordlist) => {
+                let (name, matches) = matches
+                    .subcommand()
+                    .expect("gorilla wordlist needs an action");
+                let action = WordlistAction::ALL
+                    .into_iter()
+                    .find(|action| action.name() == name);
+                (action, matches.clone())
+            }
+            _ => (None, matches),
+        };
+
+        let unbuilt = match command {
+            Some(Commands::Scrape) if !built_with("scrape") => {
+                Some((String::from("`gorilla scrape`"), "scrape"))
+            }
+            _ => cli.get_arguments().find_map(|arg| {
+                let id = arg.get_id().as_str();
+                let feature = unbuilt_feature(id)?;
+                (matches.value_source(id) == Some(ValueSource::CommandLine))
+                    .then(|| (format!("--{}", arg.get_long().unwrap_or(id)), feature))
+            }),
+        };
+        if let Some((flag, feature)) = unbuilt {
+            return Err(cli.error(
+                ErrorKind::ArgumentConflict,
+                format!("{flag} isn't available: {}", without_feature(feature)),
+            ));
+        }
+
+        if let Some(command) = command {
+            let mut sub = cli
+                .find_subcommand_mut(command.name())
+                .expect("the command exists");
+            let mut name = command.name().to_owned();
+            if let Some(action) = wordlist_action {
+                sub = sub
+                    .find_subcommand_mut(action.name())
+                    .expect("the action exists");
+                name = format!("{name} {}", action.name());
+            }
+            let refused = sub.get_arguments().find(|arg| {
+                !command.takes(arg.get_id().as_str())
+                    && matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            });
+            if let Some(arg) = refused {
+                let flag = arg
+                    .get_long()
+                    .map_or_else(|| String::from("WORDS"), |long| format!("--{long}"));
+                return Err(sub.error(
+                    ErrorKind::ArgumentConflict,
+                    format!("{flag} doesn't go with `gorilla {name}`"),
+                ));
+            }
+        }
+
+        let mut args = ProgramArgs::from_arg_matches(&matches)?;
+        if !built_with("scrape") {
+            let inputs = args
+                .file_input
+                .iter()
+                .chain(&args.exclude_file)
+                .chain(&args.csv_input)
+                .chain(&args.jsonl_input)
+                .chain(args.phrase_input.as_ref().map(|phrases| &phrases.path))
+                .chain(&args.prince_input);
+            if let Some(url) = inputs.into_iter().find(|input| is_url(input)) {
+                return Err(cli.error(
+                    ErrorKind::InvalidValue,
+                    format!("{url} can't be downloaded: {}", without_feature("scrape")),
+                ));
+            }
+        }
+        args.command = command;
+        args.wordlist_action = wordlist_action;
+        args.mutation_chains = mutation_chains(&matches);
+        args.given = matches
+            .ids()
+            .filter(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+            .map(|id| id.to_string())
+            .collect();
+        Ok(args)
+    }
+}
+
+/// Every `--set-m` starts a set, and every `-m` is chained to the set before
+/// it, or starts the first one.
+fn mutation_chains(matches: &ArgMatches) -> Vec<Vec<String>> {
+    let given = |id: &str, new_set: bool| {
+        let indices = matches.indices_of(id).into_iter().flatten();
+        let values = matches.get_many::<String>(id).into_iter().flatten();
+        indices
+            .zip(values)
+            .map(move |(index, value)| (index, new_set, value))
+    };
+    let mut mutations: Vec<_> = given("mutation_string", false)
+        .chain(given("set_mutation", true))
+        .collect();
+    mutations.sort_by_key(|&(index, ..)| index);
+
+    let mut chains: Vec<Vec<String>> = vec![];
+    for (_, new_set, mutation) in mutations {
+        match chains.last_mut() {
+            Some(chain) if !new_set => chain.push(mutation.clone()),
+            _ => chains.push(vec![mutation.clone()]),
+        }
+    }
+    chains
+}
+
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate < 1.0 => Ok(rate),
+        _ => Err(String::from("expected a number between 0 and 1, like 0.01")),
+    }
+}
+
+/// Turn the escapes `\0`, `\t`, `\n`, `\r` and `\\` into the characters
+/// they stand for.
+pub fn parse_delimiter(s: &str) -> Result<String, String> {
+    let mut delimiter = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            delimiter.push(c);
+            continue;
+        }
+        delimiter.push(match chars.next() {
+            Some('0') => '\0',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('\\') => '\\',
+            Some(other) => return Err(format!("unknown escape \\{other}")),
+            None => return Err(String::from("a \\ has to be followed by 0, t, n, r or \\")),
+        });
+    }
+
+    if delimiter.is_empty() {
+        return Err(String::from("the delimiter can't be empty"));
+    }
+    Ok(delimiter)
 }
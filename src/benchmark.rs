@@ -0,0 +1,173 @@
+//! The built-in workload of `gorilla --benchmark`, which `cargo bench` runs
+//! as well.
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    mutation::{parse_mutation_string, perform_in_parallel, MutationSet},
+    patterns::{generate_in_parallel, token_iterator, tokenize_format_string},
+};
+
+/// The words the mutations of `--benchmark` are run on
+pub const WORDS: usize = 200_000;
+
+/// What `sets/simple_passwords.yml` does, with the longer numbers and
+/// symbols appended that most rules end up with
+const MUTATIONS: &[&[&str]] = &[
+    &["nothing"],
+    &["reverse"],
+    &["remove_last_letter"],
+    &["uppercase_all"],
+    &["append:{0-9}"],
+    &["2 append:{0-9}"],
+    &["append:{1997-2025}"],
+    &["append:{0-9}{0-9}"],
+    &["prepend:{0-9}", "append:{!@#}"],
+    &["replace:o:0", "replace:a:4", "replace:e:3", "replace:i:1"],
+];
+
+/// The keyspace the pattern stage goes through the first
+/// `PATTERN_WORDS` words of
+const PATTERN: &str = "{a-z}{a-z}{a-z}{a-z}{0-9}{0-9}";
+pub const PATTERN_WORDS: usize = 20_000_000;
+
+/// How fast one part of gorilla was
+pub struct Stage {
+    pub name: &'static str,
+    pub threads: usize,
+    pub words: u64,
+    pub elapsed: Duration,
+}
+
+impl Stage {
+    pub fn words_per_second(&self) -> f64 {
+        self.words as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Run the built-in workload on `words` words and the first
+/// `pattern_words` of the pattern, with the mutations on one thread and on
+/// `threads` threads. Nothing is written, the words made are only counted.
+pub fn run(words: usize, pattern_words: usize, threads: usize) -> Vec<Stage> {
+    let mutation_sets = mutation_sets();
+    let words = base_words(words);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let mut stages = vec![];
+
+    let start = Instant::now();
+    let made = mutate(&mutation_sets, &words);
+    stages.push(Stage {
+        name: "mutations",
+        threads: 1,
+        words: made,
+        elapsed: start.elapsed(),
+    });
+
+    if threads > 1 {
+        let start = Instant::now();
+        let made = mutate_in_parallel(&mutation_sets, &words, threads);
+        stages.push(Stage {
+            name: "mutations",
+            threads,
+            words: made,
+            elapsed: start.elapsed(),
+        });
+    }
+
+    let start = Instant::now();
+    let made = generate_pattern(pattern_words);
+    stages.push(Stage {
+        name: "pattern",
+        threads: 1,
+        words: made,
+        elapsed: start.elapsed(),
+    });
+
+    if threads > 1 {
+        let start = Instant::now();
+        let made = generate_pattern_in_parallel(pattern_words, threads);
+        stages.push(Stage {
+            name: "pattern",
+            threads,
+            words: made,
+            elapsed: start.elapsed(),
+        });
+    }
+
+    stages
+}
+
+/// How many words the mutation sets make of `words`, on one thread
+pub fn mutate(mutation_sets: &[MutationSet], words: &[&str]) -> u64 {
+    let mut made = 0;
+    for word in words {
+        for mutation_set in mutation_sets {
+            mutation_set.perform_streaming(word, &mut |_| made += 1);
+        }
+    }
+    made
+}
+
+/// How many words the mutation sets make of `words`, in batches like
+/// `--threads` mutates them
+pub fn mutate_in_parallel(mutation_sets: &[MutationSet], words: &[&str], threads: usize) -> u64 {
+    let mut made = 0;
+    for batch in words.chunks(crate::pipeline::BATCH_WORDS * threads) {
+        made += perform_in_parallel(mutation_sets, batch, threads)
+            .iter()
+            .flatten()
+            .map(|result| result.mutated_words.len() as u64)
+            .sum::<u64>();
+    }
+    made
+}
+
+/// Go through the first `words` words of the pattern
+pub fn generate_pattern(words: usize) -> u64 {
+    token_iterator(&tokenize_format_string(PATTERN).expect("the pattern is valid"))
+        .take(words)
+        .count() as u64
+}
+
+/// Go through the first `words` words of the pattern in chunks on
+/// `threads` threads, like `--threads` generates them
+pub fn generate_pattern_in_parallel(words: usize, threads: usize) -> u64 {
+    let tokens = tokenize_format_string(PATTERN).expect("the pattern is valid");
+    let mut made = 0;
+    while made < words {
+        let count = (words - made).min(crate::pipeline::PATTERN_CHUNK_WORDS * threads);
+        made += generate_in_parallel(&tokens, made as u128, count, threads)
+            .iter()
+            .map(Vec::len)
+            .sum::<usize>();
+    }
+    made as u64
+}
+
+/// The mutation sets of the workload, `sets/simple_passwords.yml` and a bit
+/// more
+pub fn mutation_sets() -> Vec<MutationSet> {
+    MUTATIONS
+        .iter()
+        .enumerate()
+        .map(|(id, mutations)| {
+            let mutations: Vec<String> = mutations.iter().map(|m| m.to_string()).collect();
+            MutationSet {
+                id: id + 1,
+                mutations: parse_mutation_string(&mutations)
+                    .expect("the benchmark mutations are valid"),
+            }
+        })
+        .collect()
+}
+
+/// Common password bases with numbers after them, the same every run
+pub fn base_words(count: usize) -> Vec<String> {
+    const BASES: &[&str] = &[
+        "password", "dragon", "sunshine", "monkey", "letmein", "football", "shadow", "master",
+        "princess", "qwerty",
+    ];
+    (0..count)
+        .map(|n| format!("{}{}", BASES[n % BASES.len()], n / BASES.len()))
+        .collect()
+}
@@ -0,0 +1,103 @@
+use serde::Serialize;
+use std::{
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::mutation::MutationSet;
+
+/// The commit gorilla was built from, `unknown` outside of a git checkout
+pub const COMMIT: &str = env!("GORILLA_COMMIT");
+
+/// What gorilla was built from and with, for `--version`, the
+/// `--output-header` and the stats file
+#[derive(Debug, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub commit: &'static str,
+    /// The day it was built on, like `2024-03-01`
+    pub build_date: String,
+    /// The cargo features it was built with
+    pub features: Vec<&'static str>,
+}
+
+impl BuildInfo {
+    pub fn get() -> BuildInfo {
+        let build_time: u64 = env!("GORILLA_BUILD_TIME").parse().unwrap_or(0);
+        BuildInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: COMMIT,
+            build_date: utc_time(UNIX_EPOCH + Duration::from_secs(build_time))[..10].to_owned(),
+            features: env!("GORILLA_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// `--version`: the version, and below it where it was built from
+pub fn long_version() -> &'static str {
+    static LONG_VERSION: OnceLock<String> = OnceLock::new();
+    LONG_VERSION.get_or_init(|| {
+        let info = BuildInfo::get();
+        let features = match info.features.as_slice() {
+            [] => String::from("none"),
+            features => features.join(", "),
+        };
+        format!(
+            "{}\ncommit: {}\nbuilt on: {}\nfeatures: {features}",
+            info.version, info.commit, info.build_date
+        )
+    })
+}
+
+/// A time as `2024-03-01T12:30:00Z`
+pub fn utc_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let time_of_day = seconds % 86400;
+
+    // the civil_from_days of Howard Hinnant's date algorithms
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60
+    )
+}
+
+/// The `--output-header` line, starting with `comment`, like
+/// `# generated by gorilla v1.1.0 (3f2a9c1d0b4e), sets: [reverse] [append:1],
+/// seed: 42, date: 2024-03-01T12:30:00Z`
+pub fn output_header(comment: &str, sets: &[MutationSet], seed: u64, time: SystemTime) -> String {
+    let sets: Vec<String> = sets
+        .iter()
+        .map(|set| {
+            let mutations: Vec<String> = set.mutations.iter().map(|m| m.to_string()).collect();
+            format!("[{}]", mutations.join(" -> "))
+        })
+        .collect();
+    format!(
+        "{comment} generated by gorilla v{} ({COMMIT}), sets: {}, seed: {seed}, date: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        sets.join(" "),
+        utc_time(time)
+    )
+}
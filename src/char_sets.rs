@@ -9,3 +9,6 @@ pub const L_CH: &str = "abcdefghijklmnopqrstuvwxyz";
 pub const U_CH: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
 pub const D_CH: &str = "0123456789";
 pub const S_CH: &str = " !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// The sets of `{l}`, `{u}`, `{d}` and `{s}` in patterns, by their letter
+pub const CHAR_SETS: &[(char, &str)] = &[('l', L_CH), ('u', U_CH), ('d', D_CH), ('s', S_CH)];
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    time::UNIX_EPOCH,
+};
+
+/// How much of the start of a wordlist goes into its fingerprint
+const HEAD_SIZE: u64 = 64 * 1024;
+
+/// What a wordlist looked like when a run started, to tell if it's still
+/// the same one when the run is resumed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputFingerprint {
+    pub path: String,
+    pub size: u64,
+    /// Nanoseconds since the epoch
+    pub modified: u128,
+    /// FNV-1a hash of the first `HEAD_SIZE` bytes
+    pub head_hash: u64,
+}
+
+impl InputFingerprint {
+    pub fn of(path: &str) -> io::Result<InputFingerprint> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos());
+
+        let mut head = Vec::new();
+        File::open(path)?.take(HEAD_SIZE).read_to_end(&mut head)?;
+        // FNV-1a, which unlike the std hashers is the same everywhere
+        let head_hash = head.iter().fold(0xcbf29ce484222325, |hash: u64, &b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+        });
+
+        Ok(InputFingerprint {
+            path: path.to_owned(),
+            size: metadata.len(),
+            modified,
+            head_hash,
+        })
+    }
+}
+
+/// How far a `--checkpoint`ed run got
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    pub output_file: String,
+    pub inputs: Vec<InputFingerprint>,
+    /// The `--from-pattern`, when it's the input instead of wordlists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Input lines whose words are all in the output, counting the lines
+    /// of every input like `--input-skip` does, or the words of the pattern
+    pub lines_done: u64,
+    /// How long the output file was after them
+    pub output_bytes: u64,
+}
+
+impl Checkpoint {
+    pub fn new(
+        output_file: &str,
+        inputs: Vec<InputFingerprint>,
+        pattern: Option<String>,
+    ) -> Checkpoint {
+        Checkpoint {
+            version: 1,
+            output_file: output_file.to_owned(),
+            inputs,
+            pattern,
+            lines_done: 0,
+            output_bytes: 0,
+        }
+    }
+
+    /// The checkpoint at `path`, `None` if there is none yet.
+    pub fn load(path: &str) -> io::Result<Option<Checkpoint>> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the checkpoint to a temporary file and rename it into place,
+    /// so that a crash never leaves half of one behind.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        save_json(self, path)
+    }
+
+    /// Why the run can't be resumed from this checkpoint, if it can't.
+    pub fn check(
+        &self,
+        output_file: &str,
+        inputs: &[InputFingerprint],
+        pattern: Option<&str>,
+    ) -> Result<(), String> {
+        if self.version != 1 {
+            return Err(format!("it has an unknown version {}", self.version));
+        }
+        if self.output_file != output_file {
+            return Err(format!("it was made writing to {}", self.output_file));
+        }
+        if self.inputs.len() != inputs.len()
+            || self
+                .inputs
+                .iter()
+                .zip(inputs)
+                .any(|(a, b)| a.path != b.path)
+        {
+            return Err(String::from("it was made reading other wordlists"));
+        }
+        if self.pattern.as_deref() != pattern {
+            return Err(String::from("it was made with another pattern"));
+        }
+        if let Some(changed) = self.inputs.iter().zip(inputs).find(|(a, b)| a != b) {
+            return Err(format!("{} changed since", changed.0.path));
+        }
+        Ok(())
+    }
+}
+
+/// Write `value` as json to a temporary file next to `path` and rename it
+/// into place, so that `path` is always either the old file or the new one.
+pub fn save_json(value: &impl Serialize, path: &str) -> io::Result<()> {
+    let temp_path = format!("{path}.tmp");
+    let written =
+        File::create(&temp_path).and_then(|file| Ok(serde_json::to_writer_pretty(file, value)?));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    fs::rename(&temp_path, path)
+}
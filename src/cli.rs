@@ -0,0 +1,17 @@
+//! What the `gorilla` binary runs, with the `cli` feature. Unlike the rest
+//! of the library, this prints what it's doing to stderr, and reads the
+//! flags of `ProgramArgs` rather than taking its settings one by one.
+
+pub use crate::{
+    arguments::{ColorChoice, Commands, ProgramArgs},
+    config::{config_path, UserConfig},
+    error::GorillaError,
+    load::load_mutation_sets,
+    logging::{set_level, Level},
+    pipeline::Gorilla,
+    repl::{run as run_repl, Repl},
+    run::{http_clients, run},
+    serve::Server,
+    summary::{list_mutations, show_config},
+    wordlist::run as run_wordlist,
+};
@@ -0,0 +1,144 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::arguments::{ColorChoice, ProgramArgs};
+
+/// Defaults kept in `~/.config/gorilla/config.toml`, named like the flags
+/// they stand in for:
+///
+/// ```toml
+/// threads = 4
+/// color = "always"
+/// mutations-file = "/home/me/rules.yml"
+/// user-agent = "Mozilla/5.0"
+/// output-dir = "/home/me/wordlists"
+/// ```
+///
+/// The flags given on the command line win over them.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct UserConfig {
+    pub threads: Option<usize>,
+    pub color: Option<String>,
+    /// Only used when none of `-m`, `--set-m` and `-f` were given
+    pub mutations_file: Option<String>,
+    pub user_agent: Option<String>,
+    pub output_dir: Option<String>,
+}
+
+/// The ids of the flags the config file can give, in the order
+/// `--show-config` lists them
+pub const SETTINGS: [&str; 5] = [
+    "threads",
+    "color",
+    "mutations_file",
+    "user_agent",
+    "output_dir",
+];
+
+/// Where the value of a setting came from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    CommandLine,
+    ConfigFile,
+    Default,
+}
+
+/// `$XDG_CONFIG_HOME/gorilla/config.toml`, or `~/.config/gorilla/config.toml`
+/// without it. `None` when there's no home directory to look in.
+pub fn config_path() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_dir.join("gorilla").join("config.toml"))
+}
+
+impl UserConfig {
+    /// The config at `path`, `None` if there is no file there.
+    pub fn read(path: &Path) -> Result<Option<UserConfig>, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => UserConfig::parse(&text).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<UserConfig, String> {
+        let config: UserConfig = toml::from_str(text).map_err(|e| match e.span() {
+            Some(span) => format!(
+                "line {}: {}",
+                text[..span.start].matches('\n').count() + 1,
+                e.message()
+            ),
+            None => e.message().to_owned(),
+        })?;
+        if config.threads == Some(0) {
+            return Err(String::from("threads has to be at least 1"));
+        }
+        if let Some(color) = &config.color {
+            ColorChoice::from_str(color, true).map_err(|_| {
+                format!("color has to be \"auto\", \"always\" or \"never\", not \"{color}\"")
+            })?;
+        }
+        Ok(config)
+    }
+
+    /// Fill in the settings the command line didn't give, returning the
+    /// ids of the ones that were filled in.
+    pub fn apply(&self, args: &mut ProgramArgs) -> Vec<&'static str> {
+        let given = |id: &str| args.given.iter().any(|given| given == id);
+        let mut applied = vec![];
+
+        let threads = self.threads.filter(|_| !given("threads"));
+        let color = self
+            .color
+            .as_deref()
+            .filter(|_| !given("color"))
+            .and_then(|color| ColorChoice::from_str(color, true).ok());
+        let mutations_file = self
+            .mutations_file
+            .clone()
+            .filter(|_| !given("mutations_file") && args.mutation_chains.is_empty());
+        let user_agent = self.user_agent.clone().filter(|_| !given("user_agent"));
+        let output_dir = self.output_dir.clone().filter(|_| !given("output_dir"));
+
+        if let Some(threads) = threads {
+            args.threads = threads;
+            applied.push("threads");
+        }
+        if let Some(color) = color {
+            args.color = color;
+            applied.push("color");
+        }
+        if mutations_file.is_some() {
+            args.mutations_file = mutations_file;
+            applied.push("mutations_file");
+        }
+        if user_agent.is_some() {
+            args.user_agent = user_agent;
+            applied.push("user_agent");
+        }
+        if output_dir.is_some() {
+            args.output_dir = output_dir;
+            applied.push("output_dir");
+        }
+        applied
+    }
+}
+
+/// Where the setting with the `id` came from, with `applied` the settings
+/// the config file gave
+pub fn source(args: &ProgramArgs, applied: &[&str], id: &str) -> Source {
+    if args.given.iter().any(|given| given == id) {
+        Source::CommandLine
+    } else if applied.contains(&id) {
+        Source::ConfigFile
+    } else {
+        Source::Default
+    }
+}
@@ -0,0 +1,118 @@
+use clap::ValueEnum;
+use md4::Md4;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    io::{self, BufRead},
+};
+
+/// The algorithms of `--hash-type`
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum HashType {
+    Md5,
+    Sha1,
+    Sha256,
+    /// The MD4 of the password in UTF-16LE, as Windows keeps it
+    Ntlm,
+}
+
+impl HashType {
+    /// How many bytes a digest has
+    fn length(self) -> usize {
+        match self {
+            HashType::Md5 | HashType::Ntlm => 16,
+            HashType::Sha1 => 20,
+            HashType::Sha256 => 32,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HashType::Md5 => "md5",
+            HashType::Sha1 => "sha1",
+            HashType::Sha256 => "sha256",
+            HashType::Ntlm => "ntlm",
+        }
+    }
+
+    pub fn hash(self, word: &str) -> Vec<u8> {
+        match self {
+            HashType::Md5 => Md5::digest(word).to_vec(),
+            HashType::Sha1 => Sha1::digest(word).to_vec(),
+            HashType::Sha256 => Sha256::digest(word).to_vec(),
+            HashType::Ntlm => {
+                let utf16: Vec<u8> = word.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                Md4::digest(utf16).to_vec()
+            }
+        }
+    }
+}
+
+/// The hashes of `--crack` that weren't found yet
+pub struct Hashes {
+    hash_type: HashType,
+    left: HashSet<Vec<u8>>,
+    /// How many distinct hashes there were to begin with
+    pub total: usize,
+}
+
+impl Hashes {
+    /// The hashes of `reader`, one in hex per line, along with the numbers
+    /// (from 1) of the lines that aren't hashes of the `hash_type`. Empty
+    /// lines are skipped.
+    pub fn read(reader: impl BufRead, hash_type: HashType) -> io::Result<(Hashes, Vec<u64>)> {
+        let mut left = HashSet::new();
+        let mut invalid = vec![];
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match from_hex(line).filter(|digest| digest.len() == hash_type.length()) {
+                Some(digest) => {
+                    left.insert(digest);
+                }
+                None => invalid.push(index as u64 + 1),
+            }
+        }
+        let total = left.len();
+        Ok((
+            Hashes {
+                hash_type,
+                left,
+                total,
+            },
+            invalid,
+        ))
+    }
+
+    pub fn found(&self) -> usize {
+        self.total - self.left.len()
+    }
+
+    pub fn all_found(&self) -> bool {
+        self.left.is_empty()
+    }
+
+    /// The hash of `word` in hex, if it's one of those not found yet, which
+    /// it then isn't looked for anymore
+    pub fn check(&mut self, word: &str) -> Option<String> {
+        let digest = self.hash_type.hash(word);
+        self.left
+            .remove(&digest)
+            .then(|| digest.iter().map(|b| format!("{b:02x}")).collect())
+    }
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,316 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{arguments::ProgramArgs, checkpoint, error::GorillaError};
+
+/// How often the `--crawl-state` is saved while pages are scraped
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The flags that decide which pages a crawl goes to. A state saved with
+/// other ones is of another crawl.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CrawlSettings {
+    pub crawl_depth: usize,
+    pub crawl_max_pages: Option<usize>,
+    pub crawl_include: Vec<String>,
+    pub crawl_exclude: Vec<String>,
+    pub crawl_offsite: bool,
+}
+
+impl CrawlSettings {
+    pub fn of(args: &ProgramArgs) -> CrawlSettings {
+        let patterns = |regexes: &[regex::Regex]| regexes.iter().map(|r| r.to_string()).collect();
+        CrawlSettings {
+            crawl_depth: args.crawl_depth,
+            crawl_max_pages: args.crawl_max_pages,
+            crawl_include: patterns(&args.crawl_include),
+            crawl_exclude: patterns(&args.crawl_exclude),
+            crawl_offsite: args.crawl_offsite,
+        }
+    }
+
+    /// The flags that are different in `other`, like `--crawl-depth`
+    pub fn changed(&self, other: &CrawlSettings) -> Vec<&'static str> {
+        [
+            (self.crawl_depth != other.crawl_depth, "--crawl-depth"),
+            (
+                self.crawl_max_pages != other.crawl_max_pages,
+                "--crawl-max-pages",
+            ),
+            (self.crawl_include != other.crawl_include, "--crawl-include"),
+            (self.crawl_exclude != other.crawl_exclude, "--crawl-exclude"),
+            (self.crawl_offsite != other.crawl_offsite, "--crawl-offsite"),
+        ]
+        .into_iter()
+        .filter_map(|(changed, flag)| changed.then_some(flag))
+        .collect()
+    }
+}
+
+/// A page waiting to be scraped, and how many links away from the pages
+/// asked for it was found
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueuedPage {
+    pub url: String,
+    pub depth: usize,
+}
+
+/// How far a `--crawl-state`d crawl got, as it's saved
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CrawlState {
+    pub version: u32,
+    pub settings: CrawlSettings,
+    /// Links queued to be followed, which count towards `--crawl-max-pages`
+    /// whether or not their pages could be scraped
+    pub followed: usize,
+    /// Pages not scraped yet, in the order they were queued. Pages that
+    /// couldn't be fetched stay in it, to be tried again.
+    pub queue: Vec<QueuedPage>,
+    /// Pages scraped, or turned down by robots.txt, which aren't fetched
+    /// again
+    pub visited: Vec<String>,
+}
+
+impl CrawlState {
+    /// The state at `path`, `None` if there is none yet.
+    pub fn load(path: &str) -> io::Result<Option<CrawlState>> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// The pages of a crawl as it goes, saved to its `--crawl-state` now and
+/// then
+pub struct Crawl {
+    path: String,
+    settings: CrawlSettings,
+    pub followed: usize,
+    /// Every queued page, with its place in the queue and its depth
+    queue: HashMap<String, (u64, usize)>,
+    places: u64,
+    visited: Vec<String>,
+    saved: Instant,
+}
+
+impl Crawl {
+    /// Go on with the crawl of the `--crawl-state` at `path`, or start one
+    /// when there's none yet or it was saved with other crawl flags
+    pub fn start(args: &ProgramArgs, path: &str) -> Result<Crawl, GorillaError> {
+        let settings = CrawlSettings::of(args);
+        let resumed = CrawlState::load(path).map_err(|e| {
+            GorillaError::Io(format!(
+                "could not read the crawl state {}: {e} (remove it to start over)",
+                path.purple()
+            ))
+        })?;
+        let resumed = resumed.filter(|state| {
+            let changed = state.settings.changed(&settings);
+            if state.version != 1 {
+                info!(
+                    "gorilla: (warning) the crawl state {} has an unknown version {}, starting the crawl over",
+                    path.purple(),
+                    state.version
+                );
+                return false;
+            }
+            if !changed.is_empty() {
+                info!(
+                    "gorilla: (warning) the crawl state {} was saved with another {}, starting the crawl over",
+                    path.purple(),
+                    changed.join(", ").yellow()
+                );
+                return false;
+            }
+            true
+        });
+
+        match &resumed {
+            Some(state) => info!(
+                "gorilla: going on with the crawl of {}, skipping the {} pages scraped before, {} are queued",
+                path.purple(),
+                state.visited.len().to_string().green(),
+                state.queue.len().to_string().green()
+            ),
+            None => info!("gorilla: keeping track of the crawl in {}", path.purple()),
+        }
+        let mut crawl = Crawl::new(path, settings, resumed);
+        crawl.save_or_warn(true);
+        Ok(crawl)
+    }
+
+    /// A crawl saved to `path`, going on from `resumed` if it's given
+    pub fn new(path: &str, settings: CrawlSettings, resumed: Option<CrawlState>) -> Crawl {
+        let mut crawl = Crawl {
+            path: path.to_owned(),
+            settings,
+            followed: 0,
+            queue: HashMap::new(),
+            places: 0,
+            visited: vec![],
+            saved: Instant::now(),
+        };
+        if let Some(resumed) = resumed {
+            crawl.followed = resumed.followed;
+            crawl.visited = resumed.visited;
+            for page in resumed.queue {
+                crawl.queue(&page.url, page.depth);
+            }
+        }
+        crawl
+    }
+
+    /// The pages not scraped yet, in the order they were queued
+    pub fn queued(&self) -> Vec<QueuedPage> {
+        let mut queue: Vec<(&u64, QueuedPage)> = self
+            .queue
+            .iter()
+            .map(|(url, (place, depth))| {
+                let page = QueuedPage {
+                    url: url.clone(),
+                    depth: *depth,
+                };
+                (place, page)
+            })
+            .collect();
+        queue.sort_by_key(|&(place, _)| *place);
+        queue.into_iter().map(|(_, page)| page).collect()
+    }
+
+    pub fn visited(&self) -> &[String] {
+        &self.visited
+    }
+
+    pub fn queue(&mut self, url: &str, depth: usize) {
+        if !self.queue.contains_key(url) {
+            self.queue.insert(url.to_owned(), (self.places, depth));
+            self.places += 1;
+        }
+    }
+
+    /// Take `url` off the queue, for good
+    pub fn visit(&mut self, url: &str) {
+        self.queue.remove(url);
+        self.visited.push(url.to_owned());
+    }
+
+    /// Write the state to a temporary file and rename it into place, so
+    /// that it's never left half written.
+    pub fn save(&mut self) -> io::Result<()> {
+        self.saved = Instant::now();
+        let state = CrawlState {
+            version: 1,
+            settings: self.settings.clone(),
+            followed: self.followed,
+            queue: self.queued(),
+            visited: self.visited.clone(),
+        };
+        checkpoint::save_json(&state, &self.path)
+    }
+
+    /// Save the state if it wasn't in the last `SAVE_INTERVAL`
+    pub fn save_now_and_then(&mut self) -> io::Result<()> {
+        match self.saved.elapsed() >= SAVE_INTERVAL {
+            true => self.save(),
+            false => Ok(()),
+        }
+    }
+
+    /// Save the state, right away or if it wasn't saved in a while, with a
+    /// warning if it can't be
+    pub fn save_or_warn(&mut self, now: bool) {
+        let saved = match now {
+            true => self.save(),
+            false => self.save_now_and_then(),
+        };
+        if let Err(e) = saved {
+            info!(
+                "gorilla: (warning) could not save the crawl state {}: {e}",
+                self.path.purple()
+            );
+        }
+    }
+
+    /// Save the state before gorilla stops at Ctrl+C
+    pub fn save_interrupted(&mut self) {
+        self.save_or_warn(true);
+        eprintln!(
+            "gorilla: saved the crawl state {}, run the same command again to go on",
+            self.path.purple()
+        );
+    }
+
+    /// Remove the state once every page was scraped. The pages that
+    /// couldn't be fetched are still queued, and the state is kept for them
+    /// to be tried again.
+    pub fn finish(mut self) {
+        if !self.queue.is_empty() {
+            self.save_or_warn(true);
+            eprintln!(
+                "gorilla: (warning) {} pages could not be scraped, kept them in the crawl state {}, run the same command again to try them again",
+                self.queue.len().to_string().red(),
+                self.path.purple()
+            );
+            return;
+        }
+        match fs::remove_file(&self.path) {
+            Ok(()) => info!(
+                "gorilla: the crawl is complete, removed the crawl state {}",
+                self.path.purple()
+            ),
+            Err(e) => info!(
+                "gorilla: (warning) could not remove the crawl state {}: {e}",
+                self.path.purple()
+            ),
+        }
+    }
+}
+
+/// The URLs of `--url-stdin`, read on a thread of their own so that the
+/// pages are scraped as they come
+pub struct UrlStdin(Receiver<String>);
+
+impl UrlStdin {
+    pub fn read() -> UrlStdin {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                let url = line.trim();
+                if url.is_empty() || url.starts_with('#') {
+                    continue;
+                }
+                if sender.send(url.to_owned()).is_err() {
+                    break;
+                }
+            }
+        });
+        UrlStdin(receiver)
+    }
+
+    /// The URLs piped in since the last ones, waiting for the next one if
+    /// there aren't any, `None` once stdin is closed. `waiting` is called
+    /// every 200ms while it waits, to stop at Ctrl+C.
+    pub fn next_batch(&self, mut waiting: impl FnMut()) -> Option<Vec<String>> {
+        let first = loop {
+            match self.0.recv_timeout(Duration::from_millis(200)) {
+                Ok(url) => break url,
+                Err(RecvTimeoutError::Timeout) => waiting(),
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        };
+        Some(std::iter::once(first).chain(self.0.try_iter()).collect())
+    }
+}
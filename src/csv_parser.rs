@@ -1,35 +1,131 @@
 use colored::Colorize;
+use std::{io::Read, str::FromStr};
 
 use crate::formatting::FormatFieldAnswer;
 
-pub fn fmt_answers_from_csv(csv_path: &str) -> Vec<Vec<FormatFieldAnswer>> {
+pub fn fmt_answers_from_csv(csv_path: &str) -> Result<Vec<Vec<FormatFieldAnswer>>, csv::Error> {
     let mut answers: Vec<Vec<FormatFieldAnswer>> = Vec::new();
 
-    let mut rdr = csv::Reader::from_path(csv_path).unwrap();
+    let mut rdr = csv::Reader::from_path(csv_path)?;
 
-    eprintln!("gorilla: parsing {} csv file", csv_path.purple());
+    info!("gorilla: parsing {} csv file", csv_path.purple());
 
     let mut headers: Vec<String> = Vec::new();
 
-    for header in rdr.headers().unwrap() {
+    for header in rdr.headers()? {
         headers.push(header.to_owned())
     }
 
     for result in rdr.records() {
-        let row = result.unwrap();
+        let row = result?;
 
         let mut row_answers: Vec<FormatFieldAnswer> = Vec::new();
-        let mut column_no = 0;
-
-        for answer in row.iter() {
+        for (column_no, answer) in row.iter().enumerate() {
             let name = headers[column_no].to_owned();
             let answer = answer.to_owned();
             row_answers.push(FormatFieldAnswer { name, answer });
-            column_no += 1
         }
 
         answers.push(row_answers);
     }
 
-    answers
+    Ok(answers)
+}
+
+/// A column picked with `--csv-column`, either by its header or by its
+/// position (starting at 1, like `cut -f`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum CsvColumn {
+    Name(String),
+    Index(usize),
+}
+
+impl FromStr for CsvColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<usize>() {
+            Ok(0) => Err(String::from("column positions start at 1")),
+            Ok(position) => Ok(CsvColumn::Index(position - 1)),
+            Err(_) => Ok(CsvColumn::Name(s.to_owned())),
+        }
+    }
+}
+
+/// How many rows `words_from_csv_columns` went through
+#[derive(Debug, Default, PartialEq)]
+pub struct CsvStats {
+    pub rows: usize,
+    /// Rows that couldn't be parsed or don't have one of the columns
+    pub malformed: usize,
+}
+
+/// Stream the values of the chosen columns of a CSV to `each_word`, row by
+/// row. The first row is the header unless `has_header` is false, in which
+/// case columns can only be picked by position. Empty values are left out
+/// and malformed rows are counted and skipped.
+pub fn words_from_csv_columns(
+    reader: impl Read,
+    columns: &[CsvColumn],
+    has_header: bool,
+    mut each_word: impl FnMut(String),
+) -> Result<CsvStats, String> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(has_header)
+        .flexible(true)
+        .from_reader(reader);
+
+    let headers: Vec<String> = if has_header {
+        rdr.headers()
+            .map_err(|e| e.to_string())?
+            .iter()
+            .map(|h| h.trim().to_owned())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let indexes: Vec<usize> = columns
+        .iter()
+        .map(|column| match column {
+            CsvColumn::Index(index) => Ok(*index),
+            CsvColumn::Name(name) if !has_header => Err(format!(
+                "column {name} can't be found without a header row, use its position instead"
+            )),
+            CsvColumn::Name(name) => headers.iter().position(|h| h == name).ok_or_else(|| {
+                format!(
+                    "no column named {name}, the columns are: {}",
+                    headers.join(", ")
+                )
+            }),
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut stats = CsvStats::default();
+
+    for result in rdr.records() {
+        stats.rows += 1;
+
+        let Ok(row) = result else {
+            stats.malformed += 1;
+            continue;
+        };
+        let Some(values) = indexes
+            .iter()
+            .map(|&i| row.get(i))
+            .collect::<Option<Vec<_>>>()
+        else {
+            stats.malformed += 1;
+            continue;
+        };
+
+        for value in values {
+            let value = value.trim();
+            if !value.is_empty() {
+                each_word(value.to_owned());
+            }
+        }
+    }
+
+    Ok(stats)
 }
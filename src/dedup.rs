@@ -0,0 +1,191 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+};
+
+/// A set of words, like the input words seen so far for `--dedup-input`,
+/// the words output so far for `--unique` or the words of an `--exclude-file`.
+pub enum SeenWords {
+    /// Remembers every word, so memory grows with the amount of distinct words
+    Exact(HashSet<String>),
+    /// Fixed memory, but now and then a new word is taken for a duplicate
+    Approx(BloomFilter),
+}
+
+impl SeenWords {
+    /// Remember `word`, returning whether it's the first time it was seen.
+    pub fn insert(&mut self, word: &str) -> bool {
+        match self {
+            SeenWords::Exact(set) => {
+                if set.contains(word) {
+                    false
+                } else {
+                    set.insert(word.to_owned())
+                }
+            }
+            SeenWords::Approx(filter) => filter.insert(word),
+        }
+    }
+
+    /// Whether `word` was seen (or with `Approx`, probably seen).
+    pub fn contains(&self, word: &str) -> bool {
+        match self {
+            SeenWords::Exact(set) => set.contains(word),
+            SeenWords::Approx(filter) => filter.contains(word),
+        }
+    }
+}
+
+/// A bloom filter sized for `capacity` words, by default at a 1% false
+/// positive rate.
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    bit_count: u64,
+    hash_count: u32,
+}
+
+impl BloomFilter {
+    pub fn new(capacity: u64) -> BloomFilter {
+        BloomFilter::with_rate(capacity, 0.01)
+    }
+
+    /// A filter for `capacity` words that takes a new word for one that was
+    /// added before with probability `false_positive_rate`.
+    pub fn with_rate(capacity: u64, false_positive_rate: f64) -> BloomFilter {
+        let capacity = capacity.max(1) as f64;
+        // optimal sizes for a false positive rate p: m = -n ln p / ln² 2
+        // bits and k = m / n ln 2 hash functions
+        let bit_count = (-capacity * false_positive_rate.ln() / 2f64.ln().powi(2)).ceil() as u64;
+        let hash_count = ((bit_count as f64 / capacity) * 2f64.ln()).round() as u32;
+
+        BloomFilter {
+            bits: vec![0; bit_count.div_ceil(64) as usize],
+            bit_count,
+            hash_count: hash_count.max(1),
+        }
+    }
+
+    /// A filter taking `bytes` of memory, with as many hash functions as a
+    /// `false_positive_rate` needs. How many words it can take at that rate
+    /// is then up to `capacity`.
+    pub fn with_memory(bytes: u64, false_positive_rate: f64) -> BloomFilter {
+        let bit_count = bytes.saturating_mul(8).max(64);
+        // k = -ln p / ln 2 is optimal whatever the number of bits
+        let hash_count = (-false_positive_rate.ln() / 2f64.ln()).round() as u32;
+
+        BloomFilter {
+            bits: vec![0; bit_count.div_ceil(64) as usize],
+            bit_count,
+            hash_count: hash_count.max(1),
+        }
+    }
+
+    /// About how many words can be added before a new one is taken for one
+    /// added before with probability `false_positive_rate`.
+    pub fn capacity(&self, false_positive_rate: f64) -> u64 {
+        // inverting p = (1 - e^(-kn/m))^k for n
+        let k = f64::from(self.hash_count);
+        let fill = false_positive_rate.powf(1.0 / k);
+        (-(self.bit_count as f64) / k * (1.0 - fill).ln()) as u64
+    }
+
+    /// The probability that a new word is taken for one added before,
+    /// going by how many of the bits are set by now.
+    pub fn false_positive_rate(&self) -> f64 {
+        let set: u64 = self
+            .bits
+            .iter()
+            .map(|bits| u64::from(bits.count_ones()))
+            .sum();
+        (set as f64 / self.bit_count as f64).powi(self.hash_count as i32)
+    }
+
+    /// How much memory the filter takes, in bytes.
+    pub fn size(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    /// The bits `word` is stored in, as the index into `bits` and a mask.
+    fn word_bits(&self, word: &str) -> impl Iterator<Item = (usize, u64)> {
+        // the bit positions are derived from two hashes (double hashing)
+        let mut hasher = DefaultHasher::new();
+        word.hash(&mut hasher);
+        let first = hasher.finish();
+        0x9e37_79b9_7f4a_7c15u64.hash(&mut hasher);
+        let second = hasher.finish() | 1;
+        let bit_count = self.bit_count;
+
+        (0..u64::from(self.hash_count)).map(move |i| {
+            let bit = first.wrapping_add(i.wrapping_mul(second)) % bit_count;
+            ((bit / 64) as usize, 1u64 << (bit % 64))
+        })
+    }
+
+    /// Add `word`, returning `false` if it was (probably) added before.
+    pub fn insert(&mut self, word: &str) -> bool {
+        let mut new = false;
+        for (index, mask) in self.word_bits(word) {
+            if self.bits[index] & mask == 0 {
+                new = true;
+                self.bits[index] |= mask;
+            }
+        }
+        new
+    }
+
+    /// Whether `word` was (probably) added.
+    pub fn contains(&self, word: &str) -> bool {
+        self.word_bits(word)
+            .all(|(index, mask)| self.bits[index] & mask != 0)
+    }
+}
+
+/// How many bits of a word's hash pick its HyperLogLog register
+const HLL_PRECISION: u32 = 14;
+
+/// Counts distinct words without remembering them, for `--estimate-unique`.
+/// It takes 16 KB whatever the number of words, and its count is usually
+/// within 1% of the real one.
+pub struct HyperLogLog {
+    /// The largest number of leading zeros (plus one) seen in the rest of
+    /// the hashes picking each register
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub fn new() -> HyperLogLog {
+        HyperLogLog {
+            registers: vec![0; 1 << HLL_PRECISION],
+        }
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        let mut hasher = DefaultHasher::new();
+        word.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let index = (hash >> (64 - HLL_PRECISION)) as usize;
+        let rank = ((hash << HLL_PRECISION).leading_zeros() + 1).min(64 - HLL_PRECISION + 1) as u8;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    /// The estimated number of distinct words added.
+    pub fn count(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&rank| 2f64.powi(-i32::from(rank)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+
+        // few words leave registers empty, which linear counting is better at
+        let empty = self.registers.iter().filter(|&&rank| rank == 0).count();
+        if estimate <= 2.5 * m && empty > 0 {
+            (m * (m / empty as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
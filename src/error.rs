@@ -0,0 +1,34 @@
+use std::fmt::{self, Display};
+
+/// What made gorilla give up. Every kind has an exit code of its own, so
+/// that scripts can tell them apart.
+#[derive(Debug)]
+pub enum GorillaError {
+    /// Arguments, or a mutations or formatting file, that can't be used.
+    /// Exits with 2, like the usage errors of the argument parser do.
+    Config(String),
+    /// A file that couldn't be read or written, exits with 3
+    Io(String),
+    /// A URL that couldn't be downloaded, exits with 4
+    Network(String),
+}
+
+impl GorillaError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GorillaError::Config(_) => 2,
+            GorillaError::Io(_) => 3,
+            GorillaError::Network(_) => 4,
+        }
+    }
+}
+
+impl Display for GorillaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GorillaError::Config(message)
+            | GorillaError::Io(message)
+            | GorillaError::Network(message) => write!(f, "{message}"),
+        }
+    }
+}
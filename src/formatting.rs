@@ -5,6 +5,7 @@ pub struct FormatPart {
     pub mutations: MutationSet,
 }
 
+#[derive(Default)]
 pub struct FormatSet {
     pub parts: Vec<FormatPart>,
 }
@@ -15,6 +16,7 @@ pub struct FormatField {
 }
 
 pub struct FormattingSets {
+    #[allow(dead_code)]
     pub name: String,
     pub fields: Vec<FormatField>,
     pub sets: Vec<FormatSet>,
@@ -37,18 +39,18 @@ impl FormatFieldAnswer {
 }
 
 impl FormattingSets {
-    pub fn check_answer_names(&self, answers: &Vec<FormatFieldAnswer>) {
+    pub fn check_answer_names(&self, answers: &Vec<FormatFieldAnswer>) -> Result<(), String> {
         let names: Vec<String> = self.fields.iter().map(|x| x.name.to_owned()).collect();
 
         for answer in answers {
             if !names.contains(&answer.name) {
-                eprintln!(
-                    "gorilla: (error) invalid field name: {}. Valid names: {names:?}",
+                return Err(format!(
+                    "invalid field name: {}. Valid names: {names:?}",
                     answer.name
-                );
-                panic!()
+                ));
             }
         }
+        Ok(())
     }
 
     // TODO: foloseste un iterator in loc de functia asta jegoasa
@@ -68,6 +70,7 @@ impl FormattingSets {
                 let mut mutation_result = MutationResult {
                     original_word: p_text.to_owned(),
                     mutated_words: Vec::new(),
+                    mutation_set: 0,
                 };
 
                 part.mutations.perform(&mut mutation_result, &p_text);
@@ -0,0 +1,73 @@
+use crate::patterns::{token_iterator, Token, TokenIter};
+
+/// The words of `--prepend-pattern` and `--append-pattern` put around
+/// every input word, like the hybrid attacks of hashcat. The words made of
+/// an input word are numbered with the suffix changing fastest, so that
+/// `--hybrid-skip` and `--hybrid-limit` can split them between runs.
+pub struct Hybrid {
+    /// The tokens of the patterns, none for the one that wasn't given,
+    /// which makes a single empty word
+    prefixes: Vec<Token>,
+    suffixes: Vec<Token>,
+    skip: u128,
+    limit: Option<u128>,
+}
+
+impl Hybrid {
+    pub fn new(
+        prefixes: Option<Vec<Token>>,
+        suffixes: Option<Vec<Token>>,
+        skip: u128,
+        limit: Option<u128>,
+    ) -> Hybrid {
+        Hybrid {
+            prefixes: prefixes.unwrap_or_default(),
+            suffixes: suffixes.unwrap_or_default(),
+            skip,
+            limit,
+        }
+    }
+
+    /// How many words the patterns make of an input word, skipped or not
+    pub fn keyspace(&self) -> u128 {
+        token_iterator(&self.prefixes)
+            .calculate_total()
+            .saturating_mul(token_iterator(&self.suffixes).calculate_total())
+    }
+
+    /// How many words are made of every input word
+    pub fn words_per_input(&self) -> u128 {
+        let words = self.keyspace().saturating_sub(self.skip);
+        self.limit.map_or(words, |limit| words.min(limit))
+    }
+
+    /// The words made of `word`, from number `--hybrid-skip` on
+    pub fn words<'a>(&'a self, word: &'a str) -> impl Iterator<Item = String> + 'a {
+        let suffix_total = token_iterator(&self.suffixes).calculate_total();
+        let from = |tokens: &[Token], index: u128| -> TokenIter {
+            let mut words = token_iterator(tokens);
+            words.seek(index);
+            words
+        };
+        let mut prefixes = from(&self.prefixes, self.skip / suffix_total);
+        let mut suffixes = from(&self.suffixes, self.skip % suffix_total);
+        let mut prefix = prefixes.next().unwrap_or_default();
+        let mut left = self.words_per_input();
+
+        std::iter::from_fn(move || {
+            if left == 0 {
+                return None;
+            }
+            left -= 1;
+            let suffix = match suffixes.next() {
+                Some(suffix) => suffix,
+                None => {
+                    prefix = prefixes.next()?;
+                    suffixes = from(&self.suffixes, 0);
+                    suffixes.next()?
+                }
+            };
+            Some(format!("{prefix}{word}{suffix}"))
+        })
+    }
+}
@@ -0,0 +1,618 @@
+use crate::output::interrupted;
+#[cfg(feature = "scrape")]
+use crate::website_scraper::download_stream;
+#[cfg(feature = "scrape")]
+pub use crate::website_scraper::HttpClient;
+use clap::ValueEnum;
+use colored::Colorize;
+use flate2::read::MultiGzDecoder;
+use glob::MatchOptions;
+use rand::Rng;
+use regex::Regex;
+use serde_json::Value;
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+use xz2::read::XzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
+
+/// Name used for standard input in the output
+const STDIN_NAME: &str = "standard input";
+
+/// How many bytes at the start of a file are checked for NUL bytes
+const BINARY_CHECK_SIZE: usize = 8192;
+
+/// What wordlists at URLs would be downloaded with. Without the `scrape`
+/// feature there's nothing to download them with, and URLs are refused
+/// with the arguments.
+#[cfg(not(feature = "scrape"))]
+#[derive(Clone)]
+pub struct HttpClient;
+
+#[derive(Debug, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+/// Recognize a compressed stream by its first bytes.
+pub fn detect_compression(start: &[u8]) -> Option<Compression> {
+    if start.starts_with(&[0x1f, 0x8b]) {
+        Some(Compression::Gzip)
+    } else if start.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Some(Compression::Xz)
+    } else if start.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Some(Compression::Zstd)
+    } else {
+        None
+    }
+}
+
+/// Wrap a reader so that gzip, xz and zstd streams are decompressed on
+/// the fly, going by the magic bytes at its start.
+pub fn decompressing_reader<'a, R: Read + 'a>(reader: R) -> io::Result<Box<dyn BufRead + 'a>> {
+    let mut reader = BufReader::new(reader);
+    let compression = detect_compression(reader.fill_buf()?);
+
+    Ok(match compression {
+        Some(Compression::Gzip) => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Some(Compression::Xz) => Box::new(BufReader::new(XzDecoder::new_multi_decoder(reader))),
+        Some(Compression::Zstd) => Box::new(BufReader::new(ZstdDecoder::with_buffer(reader)?)),
+        None => Box::new(reader),
+    })
+}
+
+/// Standard input, which ends at Ctrl+C. std goes back to waiting for the
+/// next line when a read is interrupted, which would keep gorilla from
+/// stopping until one comes.
+struct Interruptible<R>(R);
+
+impl<R: Read> Read for Interruptible<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // a BufReader reads again after the end, which has to stay the end
+        if interrupted() {
+            return Ok(0);
+        }
+        match self.0.read(buf) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted && interrupted() => Ok(0),
+            read => read,
+        }
+    }
+}
+
+/// Whether an input is a web URL rather than a path.
+pub fn is_url(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+/// Open a wordlist for reading line by line, `-` being standard input.
+/// `http(s)://` URLs are downloaded with `client` while they're read, and
+/// compressed wordlists decompressed.
+#[cfg_attr(not(feature = "scrape"), allow(unused_variables))]
+pub fn open_input(path: &str, client: &HttpClient) -> io::Result<Box<dyn BufRead>> {
+    if path == "-" {
+        return decompressing_reader(Interruptible(io::stdin().lock()));
+    }
+
+    #[cfg(feature = "scrape")]
+    if is_url(path) {
+        let body = download_stream(client, path).map_err(|e| io::Error::other(e.to_string()))?;
+        return decompressing_reader(body);
+    }
+    #[cfg(not(feature = "scrape"))]
+    if is_url(path) {
+        return Err(io::Error::other(crate::arguments::without_feature(
+            "scrape",
+        )));
+    }
+
+    decompressing_reader(File::open(path)?)
+}
+
+/// How many lines a local wordlist has, for a progress display. `None` for
+/// standard input and URLs, which can only be read once.
+pub fn count_lines(path: &str) -> Option<u64> {
+    if path == "-" || is_url(path) {
+        return None;
+    }
+
+    let mut reader = decompressing_reader(File::open(path).ok()?).ok()?;
+    let mut lines = 0;
+    let mut last = b'\n';
+    loop {
+        let buffer = reader.fill_buf().ok()?;
+        let Some(&end) = buffer.last() else {
+            break;
+        };
+        lines += buffer.iter().filter(|&&b| b == b'\n').count() as u64;
+        last = end;
+        let length = buffer.len();
+        reader.consume(length);
+    }
+
+    // the last line doesn't need a newline
+    Some(lines + u64::from(last != b'\n'))
+}
+
+/// What to do with input lines that aren't valid UTF-8
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum InvalidUtf8 {
+    /// Replace the invalid bytes with U+FFFD and mutate the line
+    #[default]
+    Lossy,
+    /// Leave the line out
+    Skip,
+    /// Output the line's bytes unchanged, without mutating it
+    Raw,
+}
+
+/// Read one line as bytes into `line`, without its `\n` or `\r\n`.
+/// Returns `false` at the end of the input.
+pub fn read_line_bytes(reader: &mut dyn BufRead, line: &mut Vec<u8>) -> io::Result<bool> {
+    line.clear();
+    if reader.read_until(b'\n', line)? == 0 {
+        return Ok(false);
+    }
+
+    if line.ends_with(b"\n") {
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+    }
+    Ok(true)
+}
+
+/// A line read from an input
+#[derive(Debug, PartialEq)]
+pub enum InputLine {
+    Text(String),
+    /// A line that isn't valid UTF-8, with `--invalid-utf8 raw`
+    Raw(Vec<u8>),
+}
+
+/// A line of an input, borrowed from it until the next one is read
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineRef<'a> {
+    Text(&'a str),
+    Raw(&'a [u8]),
+}
+
+impl InputLine {
+    pub fn as_line_ref(&self) -> LineRef<'_> {
+        match self {
+            InputLine::Text(word) => LineRef::Text(word),
+            InputLine::Raw(bytes) => LineRef::Raw(bytes),
+        }
+    }
+}
+
+impl LineRef<'_> {
+    pub fn to_input_line(self) -> InputLine {
+        match self {
+            LineRef::Text(word) => InputLine::Text(word.to_owned()),
+            LineRef::Raw(bytes) => InputLine::Raw(bytes.to_vec()),
+        }
+    }
+}
+
+/// How much of an input is read at once. Longer lines make it grow.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// The lines of one input, read one at a time. Read errors are reported
+/// and end the input.
+///
+/// The input is read in large chunks, and the lines are handed out as
+/// slices of them, so that reading a huge wordlist doesn't allocate for
+/// every line.
+pub struct InputLines {
+    pub path: String,
+    reader: Box<dyn BufRead>,
+    invalid_utf8: InvalidUtf8,
+    /// What was read of the input, the unused part in `start..end`
+    chunk: Vec<u8>,
+    start: usize,
+    end: usize,
+    /// Where in the chunk to look for the next line end, past what was
+    /// looked through already
+    scanned: usize,
+    /// A line with its invalid bytes replaced, with `--invalid-utf8 lossy`
+    lossy: String,
+    /// Bytes of (decompressed) text read, to tell where reading failed
+    offset: usize,
+    /// Lines read, not counting skipped ones
+    pub lines: usize,
+    /// Lines that weren't valid UTF-8
+    pub invalid_lines: usize,
+    at_end: bool,
+    finished: bool,
+}
+
+impl InputLines {
+    pub fn open(
+        path: &str,
+        invalid_utf8: InvalidUtf8,
+        client: &HttpClient,
+    ) -> io::Result<InputLines> {
+        Ok(InputLines {
+            path: path.to_owned(),
+            reader: open_input(path, client)?,
+            invalid_utf8,
+            chunk: vec![0; CHUNK_SIZE],
+            start: 0,
+            end: 0,
+            scanned: 0,
+            lossy: String::new(),
+            offset: 0,
+            lines: 0,
+            invalid_lines: 0,
+            at_end: false,
+            finished: false,
+        })
+    }
+
+    fn read_failed(&mut self, error: io::Error) {
+        if is_url(&self.path) {
+            eprintln!(
+                "gorilla: (error) the download of {} broke off after {} bytes, the rest of it is missing: {error}",
+                self.path.purple(),
+                self.offset
+            );
+        } else {
+            eprintln!(
+                "gorilla: (error) could not read {} past byte {}: {error}",
+                display_name(&self.path).purple(),
+                self.offset
+            );
+        }
+        self.finished = true;
+    }
+
+    /// Where the next line is in the chunk, without its `\n` or `\r\n`,
+    /// reading more of the input until it's in there whole. The last line
+    /// doesn't need a line end.
+    fn next_range(&mut self) -> Option<(usize, usize)> {
+        while !self.finished {
+            let unscanned = &self.chunk[self.scanned..self.end];
+            if let Some(newline) = unscanned.iter().position(|&b| b == b'\n') {
+                let line_end = self.scanned + newline;
+                let line = (self.start, line_end);
+                self.offset += line_end + 1 - self.start;
+                self.start = line_end + 1;
+                self.scanned = self.start;
+                return match self.chunk[line.0..line_end].ends_with(b"\r") {
+                    true => Some((line.0, line.1 - 1)),
+                    false => Some(line),
+                };
+            }
+            if self.at_end {
+                self.finished = true;
+                if self.start == self.end {
+                    break;
+                }
+                let line = (self.start, self.end);
+                self.offset += self.end - self.start;
+                self.start = self.end;
+                return Some(line);
+            }
+
+            // keep what there is of the line, and make room for the rest
+            self.chunk.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.scanned = self.end;
+            self.start = 0;
+            if self.end == self.chunk.len() {
+                self.chunk.resize(self.chunk.len() * 2, 0);
+            }
+            match self.reader.read(&mut self.chunk[self.end..]) {
+                Ok(0) => self.at_end = true,
+                Ok(read) => self.end += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => self.read_failed(e),
+            }
+        }
+        None
+    }
+
+    /// The next line, along with its number in this input, from 1.
+    pub fn next_line(&mut self) -> Option<(usize, LineRef<'_>)> {
+        let (start, end, valid) = loop {
+            let (start, end) = self.next_range()?;
+            self.lines += 1;
+            if std::str::from_utf8(&self.chunk[start..end]).is_ok() {
+                break (start, end, true);
+            }
+            self.invalid_lines += 1;
+            debug!(
+                "         line {} of {} isn't valid UTF-8",
+                self.lines,
+                display_name(&self.path).purple()
+            );
+            if self.invalid_utf8 != InvalidUtf8::Skip {
+                break (start, end, false);
+            }
+        };
+
+        let bytes = &self.chunk[start..end];
+        let line = match (valid, self.invalid_utf8) {
+            // SAFETY: checked to be UTF-8 just above, looking at it again
+            // would take as long as reading it
+            (true, _) => LineRef::Text(unsafe { std::str::from_utf8_unchecked(bytes) }),
+            (false, InvalidUtf8::Raw) => LineRef::Raw(bytes),
+            (false, _) => {
+                self.lossy = String::from_utf8_lossy(bytes).into_owned();
+                LineRef::Text(&self.lossy)
+            }
+        };
+        Some((self.lines, line))
+    }
+
+    /// Skip up to `count` lines, only looking for their line ends. Returns
+    /// how many were skipped, less than `count` when the input ran out.
+    pub fn skip_lines(&mut self, count: u64) -> u64 {
+        let mut skipped = 0;
+        while skipped < count && self.next_range().is_some() {
+            skipped += 1;
+        }
+        skipped
+    }
+}
+
+impl Iterator for InputLines {
+    type Item = InputLine;
+
+    fn next(&mut self) -> Option<InputLine> {
+        self.next_line().map(|(_, line)| line.to_input_line())
+    }
+}
+
+/// Decides which input words are mutated, with `--input-filter` and
+/// `--input-filter-not`, keeping track of how many each turned down.
+#[derive(Debug, Default)]
+pub struct InputFilter {
+    /// Only keep words matching one of these, when there are any
+    pub include: Vec<Regex>,
+    /// Drop words matching one of these
+    pub exclude: Vec<Regex>,
+    pub rejected_include: usize,
+    pub rejected_exclude: usize,
+}
+
+impl InputFilter {
+    pub fn is_active(&self) -> bool {
+        !self.include.is_empty() || !self.exclude.is_empty()
+    }
+
+    /// Whether `word` should be mutated.
+    pub fn allows(&mut self, word: &str) -> bool {
+        if !self.include.is_empty() && !self.include.iter().any(|r| r.is_match(word)) {
+            self.rejected_include += 1;
+            return false;
+        }
+        if self.exclude.iter().any(|r| r.is_match(word)) {
+            self.rejected_exclude += 1;
+            return false;
+        }
+        true
+    }
+}
+
+/// A uniform random sample of up to `size` items from a stream of unknown
+/// length (reservoir sampling), handed out in the order they came in.
+pub struct Reservoir<T> {
+    size: usize,
+    /// How many items were offered
+    pub seen: u64,
+    items: Vec<(u64, T)>,
+}
+
+impl<T> Reservoir<T> {
+    pub fn new(size: usize) -> Reservoir<T> {
+        Reservoir {
+            size,
+            seen: 0,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, item: T, rng: &mut impl Rng) {
+        if self.items.len() < self.size {
+            self.items.push((self.seen, item));
+        } else {
+            // the item replaces a random one with probability size / seen
+            let slot = rng.gen_range(0..=self.seen);
+            if slot < self.size as u64 {
+                self.items[slot as usize] = (self.seen, item);
+            }
+        }
+        self.seen += 1;
+    }
+
+    pub fn into_sample(mut self) -> Vec<T> {
+        self.items.sort_by_key(|(index, _)| *index);
+        self.items.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+/// How `path` is shown in the output.
+pub fn display_name(path: &str) -> &str {
+    if path == "-" {
+        STDIN_NAME
+    } else {
+        path
+    }
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+/// Whether a file looks binary, going by NUL bytes in its first block.
+/// Compressed files don't count, they're decompressed when read.
+pub fn is_binary_file(path: &Path) -> bool {
+    let mut block = Vec::with_capacity(BINARY_CHECK_SIZE);
+    match File::open(path) {
+        Ok(file) => file
+            .take(BINARY_CHECK_SIZE as u64)
+            .read_to_end(&mut block)
+            .is_ok_and(|_| detect_compression(&block).is_none() && block.contains(&0)),
+        Err(_) => false,
+    }
+}
+
+/// Every regular file in `dir`, descending into subdirectories when
+/// `recursive` is set. Hidden files and directories are left out.
+fn dir_files(dir: &Path, recursive: bool) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        eprintln!(
+            "gorilla: (error) could not read directory {}",
+            dir.display().to_string().purple()
+        );
+        return files;
+    };
+
+    let mut entries: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    entries.sort();
+
+    for path in entries {
+        if is_hidden(&path) {
+            info!(
+                "gorilla: (warning) skipping hidden {}",
+                path.display().to_string().purple()
+            );
+        } else if path.is_dir() {
+            if recursive {
+                files.extend(dir_files(&path, recursive));
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    files
+}
+
+/// Turn an input into the wordlists it stands for: a directory gives the
+/// files inside it and a glob like `wordlists/*.txt` the files matching
+/// it, both sorted so that runs are reproducible. Files found this way
+/// that look binary are skipped with a warning; anything else (including
+/// `-`, URLs and files that don't exist) is passed through as is.
+pub fn expand_input(input: &str, recursive: bool) -> Vec<String> {
+    let path = Path::new(input);
+
+    let found: Vec<PathBuf> = if input == "-" || is_url(input) {
+        return vec![input.to_owned()];
+    } else if path.is_dir() {
+        dir_files(path, recursive)
+    } else if !path.exists() && input.contains(['*', '?', '[']) {
+        let options = MatchOptions {
+            // like shells, don't let `*` match hidden files
+            require_literal_leading_dot: true,
+            ..Default::default()
+        };
+        match glob::glob_with(input, options) {
+            Ok(paths) => {
+                let mut paths: Vec<PathBuf> = paths
+                    .flatten()
+                    .flat_map(|p| {
+                        if p.is_dir() {
+                            dir_files(&p, recursive)
+                        } else {
+                            vec![p]
+                        }
+                    })
+                    .collect();
+                paths.sort();
+                paths
+            }
+            Err(e) => {
+                eprintln!("gorilla: (error) invalid glob {}: {e}", input.purple());
+                return vec![];
+            }
+        }
+    } else {
+        return vec![input.to_owned()];
+    };
+
+    found
+        .into_iter()
+        .filter(|p| {
+            let binary = is_binary_file(p);
+            if binary {
+                info!(
+                    "gorilla: (warning) skipping {}, it looks like a binary file",
+                    p.display().to_string().purple()
+                );
+            }
+            !binary
+        })
+        .map(|p| p.display().to_string())
+        .collect()
+}
+
+/// The value at a dotted path like `user.name` in a JSON value, as text.
+/// Numbers in the path index arrays (`emails.0`). Strings are returned as
+/// they are and numbers and booleans stringified, anything else (null,
+/// objects and arrays) gives `None`.
+pub fn json_field(value: &Value, path: &str) -> Option<String> {
+    let mut current = value;
+    for key in path.split('.') {
+        current = match current {
+            Value::Object(map) => map.get(key)?,
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    match current {
+        Value::String(s) => Some(s.to_owned()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// How many records `words_from_jsonl` went through
+#[derive(Debug, Default, PartialEq)]
+pub struct JsonlStats {
+    pub records: usize,
+    /// Records without the field (or with a null/object/array there)
+    pub missing: usize,
+    /// Lines that aren't valid JSON
+    pub malformed: usize,
+}
+
+/// Stream the `path` field of every record in newline-delimited JSON to
+/// `each_word`. Blank lines are ignored.
+pub fn words_from_jsonl(
+    reader: &mut dyn BufRead,
+    path: &str,
+    mut each_word: impl FnMut(String),
+) -> io::Result<JsonlStats> {
+    let mut stats = JsonlStats::default();
+    let mut line: Vec<u8> = Vec::new();
+
+    while read_line_bytes(reader, &mut line)? {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        stats.records += 1;
+
+        let Ok(record) = serde_json::from_slice::<Value>(&line) else {
+            stats.malformed += 1;
+            continue;
+        };
+
+        match json_field(&record, path) {
+            Some(word) => each_word(word),
+            None => stats.missing += 1,
+        }
+    }
+
+    Ok(stats)
+}
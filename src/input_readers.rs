@@ -0,0 +1,418 @@
+use std::{
+    collections::HashSet,
+    io::{self, BufRead},
+};
+
+use colored::Colorize;
+
+use crate::{
+    dedup::{BloomFilter, SeenWords},
+    error::GorillaError,
+    input::{display_name, is_url, open_input, read_line_bytes, HttpClient, InputLines, Reservoir},
+    patterns::{generate_in_parallel, TokenIter},
+    phrases::{PhraseInput, Phrases},
+    pipeline::{Gorilla, PATTERN_CHUNK_WORDS},
+    prince::Prince,
+};
+
+/// Load the lines of the `--exclude-file`s into a set, or with
+/// `--exclude-approx` a bloom filter sized for all of them.
+pub fn read_blocklist(
+    paths: &[String],
+    approx: bool,
+    client: &HttpClient,
+) -> Result<SeenWords, GorillaError> {
+    // the output would have the words that were to be left out of it, so
+    // an exclude file that can't be read ends the run
+    let for_each_line = |path: &str, each_line: &mut dyn FnMut(&[u8])| {
+        let mut line = Vec::new();
+        open_input(path, client)
+            .and_then(|mut reader| {
+                while read_line_bytes(&mut reader, &mut line)? {
+                    each_line(&line);
+                }
+                Ok(())
+            })
+            .map_err(|e| {
+                let message = format!("could not read {}: {e}", path.purple());
+                match is_url(path) {
+                    true => GorillaError::Network(message),
+                    false => GorillaError::Io(message),
+                }
+            })
+    };
+
+    let mut blocklist = if approx {
+        // the filter has to be sized up front, so the lines are counted first
+        let mut lines = 0;
+        for path in paths {
+            for_each_line(path, &mut |_| lines += 1)?;
+        }
+        let filter = BloomFilter::new(lines);
+        info!(
+            "gorilla: excluding words approximately, using {} KB of memory",
+            filter.size() / 1024
+        );
+        SeenWords::Approx(filter)
+    } else {
+        SeenWords::Exact(HashSet::new())
+    };
+
+    for path in paths {
+        let mut words = 0;
+        for_each_line(path, &mut |line| {
+            blocklist.insert(&String::from_utf8_lossy(line));
+            words += 1;
+        })?;
+        info!(
+            "gorilla: excluding the {} words of {}",
+            words.to_string().green(),
+            display_name(path).purple()
+        );
+    }
+
+    Ok(blocklist)
+}
+
+/// Open an input file, reporting it. `None` when it couldn't be opened.
+pub fn open_file_input(
+    gorilla: &mut Gorilla,
+    path: &str,
+    client: &HttpClient,
+) -> Option<InputLines> {
+    verbose!(
+        "gorilla: reading words from {}",
+        display_name(path).purple()
+    );
+
+    match InputLines::open(path, gorilla.program_args.invalid_utf8, client) {
+        Ok(input) => Some(input),
+        Err(e) => {
+            gorilla.input_failed(path, e);
+            None
+        }
+    }
+}
+
+/// Print how many lines each input file had. How many of them weren't
+/// valid UTF-8 goes in the summary at the end.
+pub fn report_file_inputs(
+    gorilla: &mut Gorilla,
+    words_read: Vec<(String, Option<usize>)>,
+    invalid_lines: usize,
+) {
+    gorilla.invalid_lines += invalid_lines;
+    if words_read.len() > 1 {
+        info!("gorilla: words read per file");
+        for (file_input, read) in words_read {
+            let name = display_name(&file_input).purple();
+            match read {
+                Some(n) => info!(" {name} -> {}", n.to_string().green()),
+                None => info!(" {name} -> {}", "failed".red()),
+            }
+        }
+    }
+}
+
+/// Mutate the words of the pattern after the first `done`, which a
+/// `--checkpoint` has the pattern words numbered by. With `--threads`,
+/// chunks of the keyspace are generated on every thread at once, and then
+/// mutated in their order.
+pub fn mutate_pattern(gorilla: &mut Gorilla, mut words: TokenIter, done: u64) {
+    let threads = gorilla.program_args.threads;
+    if threads == 1 {
+        words.seek(u128::from(done));
+        for (number, word) in (done + 1..).zip(words) {
+            gorilla.current_line = number;
+            gorilla.mutate_word(&word);
+        }
+        return;
+    }
+
+    let total = words.calculate_total();
+    let mut start = u128::from(done);
+    while start < total {
+        let count = (total - start).min((PATTERN_CHUNK_WORDS * threads) as u128) as usize;
+        for chunk in generate_in_parallel(&words.toks, start, count, threads) {
+            for word in chunk {
+                start += 1;
+                gorilla.current_line = start as u64;
+                gorilla.mutate_word(&word);
+            }
+        }
+    }
+}
+
+/// Mutate the words of every input file, in order. `--input-skip` and
+/// `--input-count` select a range of lines across all of them together.
+pub fn read_file_inputs(gorilla: &mut Gorilla, client: &HttpClient, file_inputs: Vec<String>) {
+    let input_skip = gorilla.program_args.input_skip;
+    let input_count = gorilla.program_args.input_count;
+
+    let mut words_read: Vec<(String, Option<usize>)> = Vec::new();
+    let mut invalid_lines = 0;
+    let mut to_skip = gorilla
+        .checkpoint
+        .as_ref()
+        .map_or(input_skip, |checkpoint| checkpoint.lines_done);
+    let mut remaining = input_count;
+    // the last line read or skipped, counting the lines of every input
+    let mut line_number: u64 = 0;
+    let mut sample = gorilla.program_args.input_sample.map(Reservoir::new);
+
+    for file_input in file_inputs {
+        if remaining == Some(0) {
+            break;
+        }
+
+        let Some(mut input) = open_file_input(gorilla, &file_input, client) else {
+            words_read.push((file_input, None));
+            continue;
+        };
+
+        let skipped = input.skip_lines(to_skip);
+        to_skip -= skipped;
+        line_number += skipped;
+
+        while remaining != Some(0) {
+            let Some((number, line)) = input.next_line() else {
+                break;
+            };
+            remaining = remaining.map(|r| r - 1);
+            gorilla.current_line = line_number + number as u64;
+            match &mut sample {
+                Some(sample) => sample.add(line.to_input_line(), &mut gorilla.rng),
+                None => gorilla.mutate_line(line),
+            }
+        }
+
+        verbose!(
+            "         {} lines, {} of them not valid UTF-8{}",
+            input.lines.to_string().green(),
+            input.invalid_lines.to_string().red(),
+            match skipped {
+                0 => String::new(),
+                skipped => format!(", after skipping {skipped}"),
+            }
+        );
+        line_number += input.lines as u64;
+        invalid_lines += input.invalid_lines;
+        words_read.push((file_input, Some(input.lines)));
+    }
+
+    report_file_inputs(gorilla, words_read, invalid_lines);
+
+    if let Some(sample) = sample {
+        let total = sample.seen;
+        let lines = sample.into_sample();
+        info!(
+            "gorilla: sampled {} of {} input lines (--seed {})",
+            lines.len().to_string().green(),
+            total,
+            gorilla.seed
+        );
+
+        let sampled = lines.len();
+        gorilla.mutate_batch();
+        let before = gorilla.mutation_counter;
+        for line in lines {
+            gorilla.mutate_line(line.as_line_ref());
+        }
+        gorilla.mutate_batch();
+        if sampled > 0 {
+            let words = gorilla.mutation_counter - before;
+            info!(
+                "         they gave {} words, about {} for the whole input",
+                words,
+                (words * total / sampled as u64).to_string().green()
+            );
+        }
+        gorilla.input_sample = Some((sampled, total));
+    }
+
+    if input_skip > 0 || input_count.is_some() {
+        if line_number > input_skip {
+            gorilla.input_range = Some((input_skip + 1, line_number));
+        } else {
+            info!(
+                "gorilla: (warning) the input has only {line_number} lines, nothing left after skipping {input_skip}"
+            );
+        }
+    }
+}
+
+/// Like `read_file_inputs`, but taking one word from each input file (and
+/// from the pattern, if any) in turn, for `--interleave`.
+pub fn interleave_inputs(
+    gorilla: &mut Gorilla,
+    client: &HttpClient,
+    file_inputs: Vec<String>,
+    mut pattern: Option<TokenIter>,
+) {
+    let mut words_read: Vec<(String, Option<usize>)> = Vec::new();
+    let mut inputs: Vec<InputLines> = Vec::new();
+    for file_input in file_inputs {
+        match open_file_input(gorilla, &file_input, client) {
+            Some(input) => inputs.push(input),
+            None => words_read.push((file_input, None)),
+        }
+    }
+
+    loop {
+        let mut read_any = false;
+
+        for input in &mut inputs {
+            if let Some((_, line)) = input.next_line() {
+                read_any = true;
+                gorilla.mutate_line(line);
+            }
+        }
+
+        if let Some(words) = &mut pattern {
+            match words.next() {
+                Some(word) => {
+                    read_any = true;
+                    gorilla.mutate_word(&word);
+                }
+                None => pattern = None,
+            }
+        }
+
+        if !read_any {
+            break;
+        }
+    }
+
+    let invalid_lines = inputs.iter().map(|input| input.invalid_lines).sum();
+    words_read.extend(
+        inputs
+            .into_iter()
+            .map(|input| (input.path, Some(input.lines))),
+    );
+    report_file_inputs(gorilla, words_read, invalid_lines);
+}
+
+/// Mutate the phrases of `--phrase-input`, from `--phrase-skip` on and at
+/// most `--phrase-limit` of them.
+pub fn read_phrases(gorilla: &mut Gorilla, client: &HttpClient, phrase_input: &PhraseInput) {
+    let words: Result<Vec<String>, io::Error> =
+        open_input(&phrase_input.path, client).and_then(|reader| {
+            reader
+                .lines()
+                .map(|line| line.map(|l| l.trim().to_owned()))
+                .filter(|line| !matches!(line, Ok(l) if l.is_empty()))
+                .collect()
+        });
+    let words = match words {
+        Ok(words) => words,
+        Err(e) => {
+            gorilla.input_failed(&phrase_input.path, e);
+            return;
+        }
+    };
+
+    let phrases = Phrases::new(words, phrase_input.count, phrase_input.separator.clone());
+    let skip = gorilla.program_args.phrase_skip;
+    let limit = gorilla.program_args.phrase_limit;
+
+    let keyspace = phrases
+        .keyspace()
+        .map_or(String::from("more than 2^128"), |k| k.to_string());
+    info!(
+        "gorilla: {} phrases of {} words from the {} words in {}",
+        keyspace.green(),
+        phrase_input.count,
+        phrases.word_count(),
+        display_name(&phrase_input.path).purple()
+    );
+    if let Some(b_size) = phrases.calculate_size() {
+        let mb_size = b_size / 1048576;
+        let gb_size = b_size / 1073741824;
+        let tb_size = b_size / 1099511627776;
+        info!("         sizes before mutations: {b_size} bytes / {mb_size} MB / {gb_size} GB / {tb_size} TB");
+    }
+
+    let mut generated: u128 = 0;
+    for phrase in phrases.iter_from(skip) {
+        if limit.is_some_and(|limit| generated == limit) {
+            break;
+        }
+        gorilla.mutate_word(&phrase);
+        generated += 1;
+    }
+
+    let end = skip + generated;
+    if phrases.keyspace().is_none_or(|keyspace| end < keyspace) {
+        info!(
+            "gorilla: generated phrases {} to {}, continue with --phrase-skip {}",
+            (skip + 1).to_string().green(),
+            end.to_string().green(),
+            end
+        );
+    }
+}
+
+/// `--prince`: the words of a wordlist put together, shortest first
+pub fn read_prince(gorilla: &mut Gorilla, client: &HttpClient, path: &str) {
+    let words: Result<Vec<String>, io::Error> = open_input(path, client).and_then(|reader| {
+        reader
+            .lines()
+            .map(|line| line.map(|l| l.trim().to_owned()))
+            .collect()
+    });
+    let words = match words {
+        Ok(words) => words,
+        Err(e) => {
+            gorilla.input_failed(path, e);
+            return;
+        }
+    };
+
+    let args = &gorilla.program_args;
+    let prince = Prince::new(
+        words,
+        args.prince_min_len,
+        args.prince_max_len,
+        args.prince_max_elements as usize,
+    );
+    let skip = args.prince_skip;
+    let limit = args.prince_limit;
+
+    let keyspace = prince
+        .keyspace()
+        .map_or(String::from("more than 2^128"), |k| k.to_string());
+    info!(
+        "gorilla: {} words of {} to {} characters from {} chains of the {} words in {}",
+        keyspace.green(),
+        args.prince_min_len,
+        args.prince_max_len,
+        prince.chain_count(),
+        prince.element_count(),
+        display_name(path).purple()
+    );
+    if let Some(b_size) = prince.calculate_size() {
+        let mb_size = b_size / 1048576;
+        let gb_size = b_size / 1073741824;
+        let tb_size = b_size / 1099511627776;
+        info!("         sizes before mutations: {b_size} bytes / {mb_size} MB / {gb_size} GB / {tb_size} TB");
+    }
+
+    let mut generated: u128 = 0;
+    for word in prince.iter_from(skip) {
+        if limit.is_some_and(|limit| generated == limit) {
+            break;
+        }
+        gorilla.mutate_word(&word);
+        generated += 1;
+    }
+
+    let end = skip + generated;
+    if prince.keyspace().is_none_or(|keyspace| end < keyspace) {
+        info!(
+            "gorilla: generated the --prince words {} to {}, continue with --prince-skip {}",
+            (skip + 1).to_string().green(),
+            end.to_string().green(),
+            end
+        );
+    }
+}
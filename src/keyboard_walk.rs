@@ -0,0 +1,206 @@
+use clap::ValueEnum;
+use std::{collections::HashSet, str::FromStr};
+
+/// Keyboard layouts keyboard walks can be generated on
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Qwertz,
+    Azerty,
+}
+
+impl KeyboardLayout {
+    /// The rows of the layout from the number row down, without and with shift.
+    fn rows(self) -> ([&'static str; 4], [&'static str; 4]) {
+        match self {
+            KeyboardLayout::Qwerty => (
+                ["1234567890-=", "qwertyuiop[]", "asdfghjkl;'", "zxcvbnm,./"],
+                ["!@#$%^&*()_+", "QWERTYUIOP{}", "ASDFGHJKL:\"", "ZXCVBNM<>?"],
+            ),
+            KeyboardLayout::Qwertz => (
+                ["1234567890ß", "qwertzuiopü+", "asdfghjklöä#", "yxcvbnm,.-"],
+                ["!\"§$%&/()=?", "QWERTZUIOPÜ*", "ASDFGHJKLÖÄ'", "YXCVBNM;:_"],
+            ),
+            KeyboardLayout::Azerty => (
+                [
+                    "&é\"'(-è_çà)=",
+                    "azertyuiop^$",
+                    "qsdfghjklmù*",
+                    "wxcvbn,;:!",
+                ],
+                ["1234567890°+", "AZERTYUIOP¨£", "QSDFGHJKLM%µ", "WXCVBN?./§"],
+            ),
+        }
+    }
+
+    pub fn keyboard(self) -> Keyboard {
+        let (keys, shifted) = self.rows();
+        Keyboard::from_rows(&keys, &shifted)
+    }
+}
+
+/// The lengths of the walks to generate, `4-8` or just `6`
+#[derive(Clone, Debug, PartialEq)]
+pub struct WalkLengths {
+    pub min: usize,
+    pub max: usize,
+}
+
+/// Longer walks than this would take forever to enumerate
+const MAX_WALK_LENGTH: usize = 16;
+
+impl FromStr for WalkLengths {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (min, max) = s.split_once('-').unwrap_or((s, s));
+        let min: usize = min.parse().map_err(|_| format!("invalid length {min:?}"))?;
+        let max: usize = max.parse().map_err(|_| format!("invalid length {max:?}"))?;
+
+        if min == 0 || min > max {
+            return Err(format!("invalid length range {s:?}"));
+        }
+        if max > MAX_WALK_LENGTH {
+            return Err(format!(
+                "walks longer than {MAX_WALK_LENGTH} keys aren't supported"
+            ));
+        }
+
+        Ok(WalkLengths { min, max })
+    }
+}
+
+/// Where a key's neighbours are, as (row, column) offsets. Each row is
+/// shifted a little to the right of the one above it, so the keys touching
+/// `s` are `a` and `d` in its row, `w` and `e` above and `z` and `x` below.
+const DIRECTIONS: [(isize, isize); 6] = [(0, 1), (0, -1), (-1, 0), (-1, 1), (1, -1), (1, 0)];
+
+type Position = (usize, usize);
+
+pub struct Keyboard {
+    keys: Vec<Vec<char>>,
+    shifted: Vec<Vec<char>>,
+}
+
+impl Keyboard {
+    /// A keyboard with the given rows, top to bottom.
+    pub fn from_rows(keys: &[&str], shifted: &[&str]) -> Keyboard {
+        Keyboard {
+            keys: keys.iter().map(|row| row.chars().collect()).collect(),
+            shifted: shifted.iter().map(|row| row.chars().collect()).collect(),
+        }
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.keys
+            .iter()
+            .enumerate()
+            .flat_map(|(row, keys)| (0..keys.len()).map(move |column| (row, column)))
+    }
+
+    /// The key next to `position` in `direction`, if there is one.
+    fn step(&self, (row, column): Position, (rows, columns): (isize, isize)) -> Option<Position> {
+        let row = row.checked_add_signed(rows)?;
+        let column = column.checked_add_signed(columns)?;
+        (column < self.keys.get(row)?.len()).then_some((row, column))
+    }
+
+    /// Extend `path` key by key, changing direction at most `turns` times.
+    fn extend_walk(
+        &self,
+        path: &mut Vec<Position>,
+        direction: Option<usize>,
+        turns: usize,
+        lengths: &WalkLengths,
+        walks: &mut Vec<Vec<Position>>,
+    ) {
+        if path.len() >= lengths.min {
+            walks.push(path.clone());
+        }
+        if path.len() == lengths.max {
+            return;
+        }
+
+        let last = path[path.len() - 1];
+        for (d, &offset) in DIRECTIONS.iter().enumerate() {
+            let Some(next) = self.step(last, offset) else {
+                continue;
+            };
+            let turn = direction.is_some_and(|current| current != d);
+            if path.contains(&next) || (turn && turns == 0) {
+                continue;
+            }
+
+            path.push(next);
+            self.extend_walk(path, Some(d), turns - usize::from(turn), lengths, walks);
+            path.pop();
+        }
+    }
+
+    /// Walks made of two or more straight strokes of the same direction
+    /// and length, each starting one key to the right of the one before,
+    /// like `1qaz2wsx` or `1q2w3e4r`.
+    fn parallel_walks(&self, lengths: &WalkLengths, walks: &mut Vec<Vec<Position>>) {
+        for stroke in 2..=lengths.max / 2 {
+            for strokes in 2..=lengths.max / stroke {
+                if stroke * strokes < lengths.min {
+                    continue;
+                }
+
+                for start in self.positions() {
+                    for &offset in &DIRECTIONS {
+                        let walk: Option<Vec<Position>> = (0..strokes)
+                            .map(|i| {
+                                let mut key = self.step(start, (0, i as isize))?;
+                                let mut keys = vec![key];
+                                for _ in 1..stroke {
+                                    key = self.step(key, offset)?;
+                                    keys.push(key);
+                                }
+                                Some(keys)
+                            })
+                            .collect::<Option<Vec<_>>>()
+                            .map(|keys| keys.concat());
+
+                        walks.extend(walk);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every walk with a length in `lengths`, changing direction at most
+    /// `turns` times, followed by the parallel ones. With `shift`, every
+    /// walk is followed by the same walk typed with shift held down.
+    pub fn walks(&self, lengths: &WalkLengths, turns: usize, shift: bool) -> Vec<String> {
+        let mut paths: Vec<Vec<Position>> = Vec::new();
+        for start in self.positions() {
+            self.extend_walk(&mut vec![start], None, turns, lengths, &mut paths);
+        }
+        self.parallel_walks(lengths, &mut paths);
+
+        let type_walk = |rows: &Vec<Vec<char>>, path: &Vec<Position>| -> String {
+            path.iter()
+                .map(|&(row, column)| rows[row][column])
+                .collect()
+        };
+
+        // a parallel walk can also be a walk with turns
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut walks: Vec<String> = Vec::new();
+        for path in &paths {
+            let mut typed = vec![type_walk(&self.keys, path)];
+            if shift {
+                typed.push(type_walk(&self.shifted, path));
+            }
+            for walk in typed {
+                if seen.insert(walk.clone()) {
+                    walks.push(walk);
+                }
+            }
+        }
+
+        walks
+    }
+}
@@ -0,0 +1,126 @@
+//! The wordlist generation of gorilla, for programs that would rather not
+//! run the `gorilla` binary: mutation sets and the files they're kept in,
+//! the patterns of `--from-pattern`, the seeded choices of the random
+//! mutations, the password policies of `--policy`, the targeted candidates
+//! of `--profile`, and the scraping of websites with the `scrape` feature. The files are read with the `yaml` feature.
+//! With `wasm` and without the default features, it builds for
+//! `wasm32-unknown-unknown` with a JavaScript interface.
+//!
+//! Nothing here prints anything, but for the `cli` module that the
+//! `gorilla` binary runs, with the `cli` feature. The functions hand back
+//! the words, and what went wrong as errors or as parts of what they
+//! return.
+//!
+//! ```
+//! # #[cfg(feature = "yaml")] {
+//! use gorilla::{mutation::MutationSet, yaml_parser::get_mutation_sets};
+//!
+//! let yaml = "\
+//! name: example
+//! mutation_sets:
+//!   - [reverse]
+//!   - [\"append:{0-1}\", uppercase_all]
+//! ";
+//! let mut sets = get_mutation_sets(yaml)?;
+//! sets.push(MutationSet::new(&["2 k append:!"])?);
+//!
+//! let mut candidates = vec![];
+//! for set in &sets {
+//!     set.perform_streaming("cat", &mut |word| candidates.push(word.to_owned()));
+//! }
+//! assert_eq!(candidates, ["tac", "CAT0", "CAT1", "cat", "cat!!"]);
+//! # }
+//! # Ok::<(), String>(())
+//! ```
+
+// the messages of the cli modules, which are declared after them
+#[cfg(feature = "cli")]
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "cli")]
+mod arguments;
+#[cfg(feature = "cli")]
+pub mod benchmark;
+#[cfg(feature = "cli")]
+mod build_info;
+pub mod char_sets;
+#[cfg(feature = "cli")]
+mod checkpoint;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "cli")]
+mod config;
+#[cfg(feature = "cli")]
+mod crack;
+#[cfg(all(feature = "cli", feature = "scrape"))]
+mod crawl_state;
+#[cfg(feature = "cli")]
+mod csv_parser;
+#[cfg(feature = "cli")]
+mod dedup;
+#[cfg(feature = "cli")]
+mod error;
+pub mod formatting;
+#[cfg(feature = "cli")]
+mod hybrid;
+#[cfg(feature = "cli")]
+mod input;
+#[cfg(feature = "cli")]
+mod input_readers;
+#[cfg(feature = "cli")]
+mod keyboard_walk;
+#[cfg(feature = "cli")]
+mod load;
+#[cfg(feature = "cli")]
+mod logging;
+pub mod mutation;
+#[cfg(feature = "cli")]
+mod number_range;
+#[cfg(feature = "cli")]
+mod output;
+#[cfg(feature = "scrape")]
+pub mod page_cache;
+pub mod patterns;
+#[cfg(feature = "cli")]
+mod phrases;
+#[cfg(feature = "cli")]
+mod pipeline;
+pub mod policy;
+#[cfg(feature = "cli")]
+mod preflight;
+#[cfg(feature = "cli")]
+mod prince;
+pub mod profile;
+#[cfg(feature = "cli")]
+mod progress;
+pub mod random;
+#[cfg(feature = "cli")]
+mod repl;
+#[cfg(feature = "cli")]
+mod report;
+#[cfg(feature = "cli")]
+mod run;
+#[cfg(all(feature = "cli", feature = "scrape"))]
+mod scraping;
+#[cfg(feature = "cli")]
+mod serve;
+#[cfg(feature = "cli")]
+mod sort;
+#[cfg(feature = "cli")]
+mod stats;
+#[cfg(feature = "cli")]
+mod strength;
+#[cfg(feature = "cli")]
+mod summary;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "scrape")]
+pub mod website_scraper;
+#[cfg(feature = "cli")]
+mod wordlist;
+#[cfg(feature = "yaml")]
+pub mod yaml_parser;
+
+#[cfg(feature = "cli")]
+mod tests;
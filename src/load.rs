@@ -0,0 +1,267 @@
+use std::{fs, io};
+
+use colored::Colorize;
+
+#[cfg(feature = "yaml")]
+use crate::yaml_parser::{
+    parse_formatting_yaml, parse_mutations_yaml, parse_policy_yaml, parse_profile_yaml,
+};
+use crate::{
+    arguments::ProgramArgs,
+    crack::Hashes,
+    error::GorillaError,
+    formatting::FormattingSets,
+    hybrid::Hybrid,
+    mutation::{parse_mutation_string, MutationSet, MutationsFile},
+    output::OutputFormat,
+    patterns::tokenize_format_string,
+    pipeline::Gorilla,
+    policy::Policy,
+    profile::Profile,
+};
+
+/// The sets of `-m` and `--set-m`, followed by those of the mutations file,
+/// numbered from 1.
+pub fn load_mutation_sets(
+    mutation_chains: &[Vec<String>],
+    mutations_file: Option<&str>,
+) -> Result<Vec<MutationSet>, GorillaError> {
+    let mut mutation_sets = vec![];
+    for mutations in mutation_chains {
+        mutation_sets.push(MutationSet {
+            id: 0,
+            mutations: parse_mutation_string(mutations)
+                .map_err(|e| GorillaError::Config(format!("--mutation: {e}")))?,
+        })
+    }
+
+    if let Some(mutations_file) = mutations_file {
+        let mut file = read_mutations_file(mutations_file)?;
+        info!("gorilla: loading {} yaml mutations", file.name.purple());
+        mutation_sets.append(&mut file.mutation_sets)
+    }
+
+    for (id, mutation_set) in mutation_sets.iter_mut().enumerate() {
+        mutation_set.id = id + 1;
+    }
+    Ok(mutation_sets)
+}
+
+#[cfg(feature = "yaml")]
+pub fn read_mutations_file(path: &str) -> Result<MutationsFile, GorillaError> {
+    let yaml_input = &fs::read_to_string(path).map_err(|e| {
+        GorillaError::Io(format!(
+            "could not read the mutations file {}: {e}",
+            path.purple()
+        ))
+    })?;
+    parse_mutations_file(path, yaml_input)
+}
+
+/// The mutations file `path`, already read as `yaml_input`
+#[cfg(feature = "yaml")]
+pub fn parse_mutations_file(path: &str, yaml_input: &str) -> Result<MutationsFile, GorillaError> {
+    parse_mutations_yaml(yaml_input).map_err(|e| {
+        GorillaError::Config(format!(
+            "the mutations file {} is invalid, {e}",
+            path.purple()
+        ))
+    })
+}
+
+/// The config file and the `:load` of the repl can name a mutations file
+/// without the `yaml` feature to read it with
+#[cfg(not(feature = "yaml"))]
+pub fn read_mutations_file(path: &str) -> Result<MutationsFile, GorillaError> {
+    Err(GorillaError::Config(format!(
+        "could not read the mutations file {}, {}",
+        path.purple(),
+        crate::arguments::without_feature("yaml")
+    )))
+}
+
+#[cfg(not(feature = "yaml"))]
+pub fn parse_mutations_file(path: &str, _yaml_input: &str) -> Result<MutationsFile, GorillaError> {
+    read_mutations_file(path)
+}
+
+/// `gorilla check`: the mutation sets were parsed by now, the formatting
+/// file is parsed too, and nothing is generated.
+pub fn check_files(gorilla: &Gorilla) -> Result<(), GorillaError> {
+    let args = &gorilla.program_args;
+    let mutations = !args.mutation_chains.is_empty() || args.mutations_file.is_some();
+    if !mutations && args.from_formatting.is_none() {
+        return Err(GorillaError::Config(String::from(
+            "gorilla check needs a --mutations-file, --mutation or --from-formatting to check",
+        )));
+    }
+
+    if let Some(formatting_path) = &args.from_formatting {
+        let fmt_sets = read_formatting(formatting_path)?;
+        eprintln!(
+            "gorilla: the formatting file {} is fine, with {} fields",
+            formatting_path.purple(),
+            fmt_sets.fields.len().to_string().green()
+        );
+    }
+    if mutations {
+        eprintln!(
+            "gorilla: the {} mutation sets are fine",
+            gorilla.mutation_sets.len().to_string().green()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(feature = "yaml")]
+pub fn read_formatting(path: &str) -> Result<FormattingSets, GorillaError> {
+    let yaml_input = fs::read_to_string(path).map_err(|e| {
+        GorillaError::Io(format!(
+            "could not read the formatting file {}: {e}",
+            path.purple()
+        ))
+    })?;
+    parse_formatting_yaml(&yaml_input).map_err(|e| {
+        GorillaError::Config(format!(
+            "the formatting file {} is invalid, {e}",
+            path.purple()
+        ))
+    })
+}
+
+#[cfg(not(feature = "yaml"))]
+pub fn read_formatting(path: &str) -> Result<FormattingSets, GorillaError> {
+    Err(GorillaError::Config(format!(
+        "could not read the formatting file {}, {}",
+        path.purple(),
+        crate::arguments::without_feature("yaml")
+    )))
+}
+
+/// The hashes of `--crack`, warning about the lines that aren't hashes
+pub fn load_hashes(args: &ProgramArgs) -> Result<Option<Hashes>, GorillaError> {
+    let (Some(path), Some(hash_type)) = (&args.crack, args.hash_type) else {
+        return Ok(None);
+    };
+    if args.file_save.is_none() && args.output_format != OutputFormat::Text {
+        return Err(GorillaError::Config(String::from(
+            "--crack with an --output-format needs an --output-file for the records, stdout has the hashes it finds",
+        )));
+    }
+
+    let (hashes, invalid) = fs::File::open(path)
+        .and_then(|file| Hashes::read(io::BufReader::new(file), hash_type))
+        .map_err(|e| {
+            GorillaError::Io(format!("could not read the hashes {}: {e}", path.purple()))
+        })?;
+    if !invalid.is_empty() {
+        let shown: Vec<String> = invalid.iter().take(10).map(u64::to_string).collect();
+        info!(
+            "gorilla: (warning) {} lines of {} aren't {} hashes, leaving them out: line{} {}{}",
+            invalid.len().to_string().red(),
+            path.purple(),
+            hash_type.name(),
+            if invalid.len() > 1 { "s" } else { "" },
+            shown.join(", "),
+            if invalid.len() > shown.len() {
+                ", ..."
+            } else {
+                ""
+            }
+        );
+    }
+    if hashes.total == 0 {
+        return Err(GorillaError::Config(format!(
+            "there are no {} hashes in {}",
+            hash_type.name(),
+            path.purple()
+        )));
+    }
+    info!(
+        "gorilla: cracking {} {} hashes of {}",
+        hashes.total.to_string().green(),
+        hash_type.name(),
+        path.purple()
+    );
+    Ok(Some(hashes))
+}
+
+/// The `--prepend-pattern` and `--append-pattern`, if either was given
+pub fn load_hybrid(args: &ProgramArgs) -> Result<Option<Hybrid>, GorillaError> {
+    if args.prepend_pattern.is_none() && args.append_pattern.is_none() {
+        return Ok(None);
+    }
+    let tokenize = |pattern: &Option<String>| {
+        pattern
+            .as_deref()
+            .map(|pattern| {
+                tokenize_format_string(pattern).map_err(|e| {
+                    GorillaError::Config(format!(
+                        "the pattern {} is invalid, {e}",
+                        pattern.purple()
+                    ))
+                })
+            })
+            .transpose()
+    };
+    Ok(Some(Hybrid::new(
+        tokenize(&args.prepend_pattern)?,
+        tokenize(&args.append_pattern)?,
+        args.hybrid_skip,
+        args.hybrid_limit,
+    )))
+}
+
+/// The presets of `--policy`, followed by the policies of the
+/// `--policy-file`s
+pub fn load_policies(args: &ProgramArgs) -> Result<Vec<Policy>, GorillaError> {
+    let mut policies = vec![];
+    for name in &args.policy {
+        // the names were checked by the argument parser
+        policies.extend(Policy::preset(name));
+    }
+    for path in &args.policy_file {
+        policies.push(read_policy_file(path)?);
+    }
+    Ok(policies)
+}
+
+#[cfg(feature = "yaml")]
+pub fn read_policy_file(path: &str) -> Result<Policy, GorillaError> {
+    let yaml_input = fs::read_to_string(path).map_err(|e| {
+        GorillaError::Io(format!(
+            "could not read the policy file {}: {e}",
+            path.purple()
+        ))
+    })?;
+    parse_policy_yaml(&yaml_input).map_err(|e| {
+        GorillaError::Config(format!("the policy file {} is invalid, {e}", path.purple()))
+    })
+}
+
+#[cfg(feature = "yaml")]
+pub fn read_profile_file(path: &str) -> Result<Profile, GorillaError> {
+    let yaml_input = fs::read_to_string(path).map_err(|e| {
+        GorillaError::Io(format!("could not read the profile {}: {e}", path.purple()))
+    })?;
+    parse_profile_yaml(&yaml_input)
+        .map_err(|e| GorillaError::Config(format!("the profile {} is invalid, {e}", path.purple())))
+}
+
+#[cfg(not(feature = "yaml"))]
+pub fn read_profile_file(path: &str) -> Result<Profile, GorillaError> {
+    Err(GorillaError::Config(format!(
+        "could not read the profile {}, {}",
+        path.purple(),
+        crate::arguments::without_feature("yaml")
+    )))
+}
+
+#[cfg(not(feature = "yaml"))]
+pub fn read_policy_file(path: &str) -> Result<Policy, GorillaError> {
+    Err(GorillaError::Config(format!(
+        "could not read the policy file {}, {}",
+        path.purple(),
+        crate::arguments::without_feature("yaml")
+    )))
+}
@@ -1,5 +1,38 @@
-use std::{fs, io::Write};
+use std::sync::atomic::{AtomicU8, Ordering};
 
-use colored::*;
+/// How much gorilla says about what it's doing, on stderr. Errors and the
+/// summary at the end of a run are always printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// `--quiet`: errors and the summary only
+    Quiet,
+    /// What the run is about to do, and warnings
+    Info,
+    /// `-v`: every file and URL read, and the lines skipped in each
+    Verbose,
+    /// `-vv`: every line that was skipped
+    Debug,
+}
 
-// TODO: write logging utility
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+impl Level {
+    /// The level of `--quiet` and of `-v` given `verbose` times
+    pub fn from_flags(quiet: bool, verbose: u8) -> Level {
+        match (quiet, verbose) {
+            (true, _) => Level::Quiet,
+            (false, 0) => Level::Info,
+            (false, 1) => Level::Verbose,
+            (false, _) => Level::Debug,
+        }
+    }
+}
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Whether messages of `level` are printed
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
@@ -0,0 +1,33 @@
+/// Messages are printed around the progress display, if there is one.
+macro_rules! eprintln {
+    ($($arg:tt)*) => {
+        $crate::progress::suspend(|| std::eprintln!($($arg)*))
+    };
+}
+
+/// What the run is about to do, and warnings, which `--quiet` leaves out
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::logging::enabled($crate::logging::Level::Info) {
+            eprintln!($($arg)*)
+        }
+    };
+}
+
+/// Every file and URL read, with `-v`
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::logging::enabled($crate::logging::Level::Verbose) {
+            eprintln!($($arg)*)
+        }
+    };
+}
+
+/// Every line skipped, with `-vv`
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::logging::enabled($crate::logging::Level::Debug) {
+            eprintln!($($arg)*)
+        }
+    };
+}
@@ -1,242 +1,110 @@
-mod arguments;
-mod char_sets;
-mod csv_parser;
-mod formatting;
-mod mutation;
-mod patterns;
-mod website_scraper;
-mod yaml_parser;
-
-mod tests;
-
 use std::{
-    fs::{self, File, OpenOptions},
-    io::{self, BufRead, BufReader, Write},
-    time::SystemTime,
+    env,
+    io::{self, IsTerminal},
+    path::Path,
+    process,
 };
 
-use clap::Parser;
 use colored::Colorize;
-use mutation::MutationResult;
-
-use crate::{
-    arguments::ProgramArgs,
-    csv_parser::fmt_answers_from_csv,
-    formatting::FormatFieldAnswer,
-    mutation::{parse_mutation_string, MutationSet},
-    patterns::{token_iterator, tokenize_format_string},
-    website_scraper::{download_page, extract_words},
-    yaml_parser::{get_mutation_sets, parse_formatting_yaml},
+use gorilla::cli::{
+    config_path, http_clients, list_mutations, load_mutation_sets, run, run_repl, run_wordlist,
+    set_level, show_config, ColorChoice, Commands, Gorilla, GorillaError, Level, ProgramArgs, Repl,
+    Server, UserConfig,
 };
 
-struct Gorilla {
-    program_args: ProgramArgs,
-    mutation_sets: Vec<MutationSet>,
-    file_save: Option<File>,
-    mutation_counter: u32,
-    word_counter: u32,
-    start_time: SystemTime,
-    output_separator: String,
+fn main() {
+    let mut gorilla = Gorilla::new(ProgramArgs::parse_command_line());
+    if let Err(error) = dispatch(&mut gorilla) {
+        gorilla.exit_with(error);
+    }
+    process::exit(gorilla.exit_code());
 }
 
-impl Gorilla {
-    fn mutate_word(&mut self, word: String) {
-        let mut mutation_result = MutationResult {
-            original_word: word.clone(),
-            mutated_words: vec![],
-        };
-
-        self.word_counter += 1;
-
-        for mutation_set in &self.mutation_sets {
-            mutation_set.perform(&mut mutation_result, &word);
-
-            if let Some(save_file) = &mut self.file_save {
-                mutation_result.save_to_file(save_file)
+/// Read the config file and hand the run to the command asked for. Errors
+/// returned end it with their exit code.
+fn dispatch(gorilla: &mut Gorilla) -> Result<(), GorillaError> {
+    let config_file = config_path().filter(|_| !gorilla.program_args.no_config);
+    let from_config = match &config_file {
+        Some(path) => match UserConfig::read(path) {
+            Ok(config) => {
+                config.map_or_else(Vec::new, |config| config.apply(&mut gorilla.program_args))
             }
-
-            for s in &mutation_result.mutated_words {
-                self.mutation_counter += 1;
-
-                if self.file_save.is_some() {
-                    continue;
-                }
-
-                if self.program_args.timer {
-                    eprint!(
-                        "(in {:?}) ",
-                        SystemTime::now()
-                            .duration_since(self.start_time)
-                            .expect("time may have gone backwards")
-                    );
-                }
-
-                print!("{s}{}", self.output_separator)
+            Err(e) => {
+                return Err(GorillaError::Config(format!(
+                    "could not read the config file {}: {e}",
+                    path.display().to_string().purple()
+                )))
             }
-        }
-    }
-}
-
-fn main() {
-    let mut gorilla = Gorilla {
-        program_args: ProgramArgs::parse(),
-        mutation_sets: vec![],
-        file_save: None,
-        mutation_counter: 0,
-        word_counter: 0,
-        start_time: SystemTime::now(),
-        output_separator: String::from('\n'),
+        },
+        None => vec![],
     };
 
-    if gorilla.program_args.one_line {
-        gorilla.output_separator = String::from(' ')
-    }
-
-    if !gorilla.program_args.mutation_string.is_empty() {
-        gorilla.mutation_sets.push(MutationSet {
-            mutations: parse_mutation_string(&gorilla.program_args.mutation_string),
-        })
-    }
+    set_level(Level::from_flags(
+        gorilla.program_args.quiet,
+        gorilla.program_args.verbose,
+    ));
+    colored::control::set_override(match gorilla.program_args.color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && io::stderr().is_terminal()
+        }
+    });
 
-    if let Some(mutations_file) = &gorilla.program_args.mutations_file {
-        let yaml_input = &fs::read_to_string(mutations_file).unwrap();
-        gorilla
-            .mutation_sets
-            .append(&mut get_mutation_sets(yaml_input))
+    if let Some(shell) = gorilla.program_args.generate_completions {
+        ProgramArgs::write_completions(shell, &mut gorilla.stdout);
+        gorilla.flush_stdout();
+        return Ok(());
     }
 
-    if gorilla.mutation_sets.is_empty() {
-        eprintln!("gorilla: (warning) missing mutation sets");
-        gorilla.mutation_sets.push(MutationSet::empty_set())
-    } else {
-        eprintln!("gorilla: mutation sets summary");
-        for mutation_set in &gorilla.mutation_sets {
-            eprint!(" {}", "word".dimmed());
-            for mutation in &mutation_set.mutations {
-                eprint!(" -> {}", mutation.to_string().blue());
-            }
-            eprintln!()
-        }
+    if gorilla.program_args.generate_manpage {
+        ProgramArgs::write_manpage(&mut gorilla.stdout)
+            .map_err(|e| GorillaError::Io(format!("could not write the man page: {e}")))?;
+        gorilla.flush_stdout();
+        return Ok(());
     }
 
-    if let Some(formatting_path) = &gorilla.program_args.from_formatting {
-        let yaml_input = &fs::read_to_string(formatting_path)
-            .expect("could not open file containing custom formats");
-        let fmt_sets = parse_formatting_yaml(yaml_input);
-
-        if let Some(csv_path) = &gorilla.program_args.csv {
-            let answer_sets = fmt_answers_from_csv(csv_path);
-            fmt_sets.check_answer_names(answer_sets.first().unwrap());
-
-            for fmt_answers in answer_sets {
-                for gen_word in fmt_sets.generate_words(fmt_answers) {
-                    gorilla.mutate_word(gen_word);
-                }
-            }
-        } else {
-            let mut fmt_answers: Vec<FormatFieldAnswer> = Vec::new();
-
-            for q in &fmt_sets.fields {
-                let mut buffer = String::new();
-
-                if let Some(question) = &q.question {
-                    eprint!("(?) {}: ", question.blue())
-                } else {
-                    eprint!("(?) Fill in {}: ", q.name.blue())
-                }
-                io::stdout().flush().unwrap();
-                io::stdin().read_line(&mut buffer).unwrap();
-
-                fmt_answers.push(FormatFieldAnswer {
-                    name: q.name.to_owned(),
-                    answer: buffer.trim().to_owned(),
-                })
-            }
-
-            // reset start time bcuz we dont want to time how much it took the user to
-            // answer the questions
-            gorilla.start_time = SystemTime::now();
-
-            for gen_word in fmt_sets.generate_words(fmt_answers) {
-                gorilla.mutate_word(gen_word);
-            }
-        }
+    if gorilla.program_args.list_mutations {
+        list_mutations(gorilla);
+        gorilla.flush_stdout();
+        return Ok(());
     }
 
-    if let Some(file_save) = &gorilla.program_args.file_save {
-        eprintln!("gorilla: using file {} as output", file_save.purple());
-        gorilla.file_save = Some(
-            OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(file_save)
-                .expect("Could not output file"),
-        )
+    if gorilla.program_args.show_config {
+        show_config(gorilla, config_file.as_deref(), &from_config);
+        gorilla.flush_stdout();
+        return Ok(());
     }
 
-    if let Some(file_input) = &gorilla.program_args.file_input {
-        eprintln!("gorilla: reading words from {}", file_input.purple());
-
-        let file_input = File::open(file_input).unwrap();
-        let reader = BufReader::new(file_input);
-        let words_iter = reader.lines();
-
-        for (_, l) in words_iter.enumerate() {
-            let line = l.unwrap();
-            gorilla.mutate_word(line);
+    if let Some(dir) = gorilla.program_args.output_dir.clone() {
+        let args = &mut gorilla.program_args;
+        for path in [&mut args.file_save, &mut args.output_by_length]
+            .into_iter()
+            .flatten()
+        {
+            if Path::new(path).is_relative() {
+                *path = Path::new(&dir).join(&path).to_string_lossy().into_owned();
+            }
         }
     }
 
-    if let Some(pattern_input) = &gorilla.program_args.pattern_input {
-        let tokens = tokenize_format_string(pattern_input);
-        let ac_toks = token_iterator(&tokens);
-
-        let total_words = ac_toks.calculate_total();
-        let b_size = ac_toks.calculate_size();
-        let mb_size = b_size / 1048576;
-        let gb_size = b_size / 1073741824;
-        let tb_size = b_size / 1099511627776;
-
-        eprintln!(
-            "gorilla: will generate {} words from a pattern {}",
-            total_words,
-            pattern_input.purple()
-        );
-        eprintln!("         sizes before mutations: {b_size} bytes / {mb_size} MB / {gb_size} GB / {tb_size} TB");
-
-        for word in ac_toks {
-            gorilla.mutate_word(word);
-        }
+    if gorilla.program_args.command == Some(Commands::Repl) {
+        let mutation_sets = load_mutation_sets(&gorilla.program_args.mutation_chains, None)?;
+        let repl = Repl::new(mutation_sets, gorilla.program_args.mutations_file.clone())?;
+        return run_repl(repl);
     }
 
-    if let Some(website) = &gorilla.program_args.website_input {
-        eprintln!(
-            "gorilla: scraping words from a website {}",
-            website.purple()
-        );
-
-        let page_contents = download_page(website).unwrap();
-        let words = extract_words(&page_contents);
-
-        for word in words {
-            gorilla.mutate_word(word)
-        }
+    if gorilla.program_args.command == Some(Commands::Serve)
+        || gorilla.program_args.listen.is_some()
+    {
+        return Server::bind(&gorilla.program_args)?.run();
     }
 
-    if gorilla.program_args.one_line {
-        println!()
+    if let Some(action) = gorilla.program_args.wordlist_action {
+        let (_, input_client) = http_clients(&gorilla.program_args)?;
+        return run_wordlist(action, &gorilla.program_args, &input_client);
     }
 
-    let end_time = SystemTime::now();
-
-    let runtime_dur = end_time
-        .duration_since(gorilla.start_time)
-        .expect("Clock may have gone backwards");
-
-    eprintln!(
-        "gorilla: {} in {runtime_dur:?}. {} words -> {} words",
-        "finished".green().bold(),
-        gorilla.word_counter.to_string().red(),
-        gorilla.mutation_counter.to_string().green()
-    );
+    run(gorilla)
 }
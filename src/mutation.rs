@@ -1,16 +1,30 @@
 use std::{
     cmp::Ordering,
     fmt::{self, Display},
-    fs,
-    io::Write,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Mutex, OnceLock, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use crate::patterns::{token_iterator, tokenize_format_string};
+use serde::Serialize;
+
+use crate::{
+    patterns::{token_iterator, tokenize_format_string, Token},
+    random::Random,
+};
 
 #[derive(Debug)]
 pub enum Action {
-    Prepend(String),
-    Append(String),
+    Prepend(Affixes),
+    Append(Affixes),
+    /// The first of the `COMMON_SUFFIXES`, or of the table of a mutations
+    /// file, appended one after another
+    CommonSuffixes(Vec<String>),
     Replace(String, String),
 
     FirstLetter,
@@ -30,6 +44,13 @@ pub enum Action {
     // bool indicates if the condition should be negated
     IfCharacterLength(bool, Ordering, usize),
     IfContains(bool, String),
+
+    // random, with the choices of `MutationSet::set_random`
+    Scramble(Random),
+    /// Keeps the word the given percent of the time
+    Chance(u8, Random),
+
+    Exec(Plugin),
 }
 
 #[derive(Debug)]
@@ -39,6 +60,344 @@ pub enum MutationBuildError {
     InvalidArgument(String),
 }
 
+impl Display for MutationBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MutationBuildError::ActionDoesNotExist => write!(f, "action does not exist"),
+            MutationBuildError::MissingArguments => write!(f, "missing arguments"),
+            MutationBuildError::InvalidArgument(reason) => {
+                write!(f, "invalid argument: {}", reason)
+            }
+        }
+    }
+}
+
+/// Suffixes people often put after a word, likeliest first: the 100 that
+/// most of the 30,000 passwords of zxcvbn's `passwords` frequency list end
+/// in after a letter, a list counted from Mark Burnett's 10 million leaked
+/// passwords (Xato, 2015), with ties going to the suffix of the more common
+/// password. With the likeliest first, a run cut short by a limit still has
+/// the suffixes most worth trying.
+pub const COMMON_SUFFIXES: [&str; 100] = [
+    "1",
+    "123",
+    "2",
+    "12",
+    "69",
+    "11",
+    "3",
+    "7",
+    "01",
+    "5",
+    "99",
+    "4",
+    "1234",
+    "6",
+    "22",
+    "9",
+    "8",
+    "13",
+    "12345",
+    "0",
+    "10",
+    "23",
+    "21",
+    "123456",
+    "666",
+    "2000",
+    "777",
+    "88",
+    "77",
+    "00",
+    "2010",
+    "20",
+    "33",
+    "24",
+    "007",
+    "14",
+    "321",
+    "19",
+    "111",
+    "1234567",
+    "66",
+    "55",
+    "123456789",
+    "34",
+    "18",
+    "420",
+    "100",
+    "17",
+    "15",
+    "2011",
+    "812",
+    "32",
+    "1995",
+    "456",
+    "25",
+    "1998",
+    "31",
+    "16",
+    "1996",
+    "02",
+    "1994",
+    "45",
+    "50",
+    "101",
+    "26",
+    "95",
+    "09",
+    "98",
+    "03",
+    "999",
+    "27",
+    "42",
+    "2002",
+    "44",
+    "1992",
+    "64",
+    "56",
+    "316",
+    "51",
+    "40",
+    "911",
+    "08",
+    "67",
+    "200",
+    "123123",
+    "1991",
+    "1993",
+    "28",
+    "1988",
+    "36",
+    "333",
+    "222",
+    "80",
+    "76",
+    "350",
+    "07",
+    "!",
+    "500",
+    ".",
+    "71",
+];
+
+/// How many of the suffixes `common_suffixes` appends without an N
+pub const DEFAULT_COMMON_SUFFIXES: usize = 50;
+
+/// How many words an append or prepend pattern may have to be generated
+/// once, instead of for every word
+const PREPARED_AFFIXES: u128 = 1 << 16;
+
+/// What an append or prepend adds to a word, from its pattern. Tokenized
+/// when the mutation is built, and generated then too unless there are
+/// too many words to keep.
+#[derive(Debug)]
+pub struct Affixes {
+    pattern: String,
+    tokens: Vec<Token>,
+    words: Option<Vec<String>>,
+}
+
+impl Affixes {
+    pub fn new(pattern: &str) -> Result<Affixes, MutationBuildError> {
+        let tokens =
+            tokenize_format_string(pattern).map_err(MutationBuildError::InvalidArgument)?;
+        let iter = token_iterator(&tokens);
+        let words = (iter.calculate_total() <= PREPARED_AFFIXES).then(|| iter.collect());
+        Ok(Affixes {
+            pattern: pattern.to_owned(),
+            tokens,
+            words,
+        })
+    }
+
+    fn for_each(&self, mut f: impl FnMut(&str)) {
+        match &self.words {
+            Some(words) => words.iter().for_each(|word| f(word)),
+            None => token_iterator(&self.tokens).for_each(|word| f(&word)),
+        }
+    }
+}
+
+impl Display for Affixes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+/// How long an `exec` plugin has to answer for a word, unless the set is
+/// given another with `MutationSet::set_exec_timeout`
+pub const EXEC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The command of an `exec` mutation, run by the shell. It's started when
+/// the first word comes and kept running for the rest of them: every word
+/// is written to its stdin on a line of its own, and it answers with the
+/// words it makes of it, a line each, then an empty line. Once it fails it
+/// makes no more words, and `error` says why.
+#[derive(Debug)]
+pub struct Plugin {
+    command: String,
+    timeout: Duration,
+    process: Mutex<Option<PluginProcess>>,
+    error: OnceLock<String>,
+}
+
+#[derive(Debug)]
+struct PluginProcess {
+    child: Child,
+    /// Closed first when the plugin is stopped, which ends it
+    stdin: Option<ChildStdin>,
+    /// The lines of its stdout, read on a thread of their own so that
+    /// they can be waited for with the timeout
+    lines: Receiver<io::Result<String>>,
+}
+
+impl Plugin {
+    fn new(command: &str) -> Plugin {
+        Plugin {
+            command: command.to_owned(),
+            timeout: EXEC_TIMEOUT,
+            process: Mutex::new(None),
+            error: OnceLock::new(),
+        }
+    }
+
+    /// Why the plugin stopped making words, if it did
+    pub fn error(&self) -> Option<&str> {
+        self.error.get().map(String::as_str)
+    }
+
+    fn start(&self) -> io::Result<PluginProcess> {
+        let (shell, flag) = match cfg!(windows) {
+            true => ("cmd", "/C"),
+            false => ("sh", "-c"),
+        };
+        let mut child = Command::new(shell)
+            .args([flag, &self.command])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let failed = line.is_err();
+                if sender.send(line).is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Ok(PluginProcess {
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    fn perform(&self, word: &str, sink: &mut dyn FnMut(&str)) {
+        if self.error.get().is_some() {
+            return;
+        }
+
+        // the words are handed on once the plugin is free for other threads
+        let made = {
+            let mut process = self.process.lock().unwrap_or_else(PoisonError::into_inner);
+            if process.is_none() {
+                match self.start() {
+                    Ok(started) => *process = Some(started),
+                    Err(e) => return self.fail(format!("couldn't be started: {e}")),
+                }
+            }
+            let made = process
+                .as_mut()
+                .expect("the plugin was started")
+                .exchange(word, self.timeout);
+            if made.is_err() {
+                if let Some(mut failed) = process.take() {
+                    let _ = failed.child.kill();
+                }
+            }
+            made
+        };
+        match made {
+            Ok(words) => words.iter().for_each(|word| sink(word)),
+            Err(reason) => self.fail(reason),
+        }
+    }
+
+    fn fail(&self, reason: String) {
+        let _ = self
+            .error
+            .set(format!("the exec plugin `{}` {reason}", self.command));
+    }
+}
+
+impl PluginProcess {
+    /// Give the plugin a word, and take the words it makes of it
+    fn exchange(&mut self, word: &str, timeout: Duration) -> Result<Vec<String>, String> {
+        let stdin = self.stdin.as_mut().expect("stdin is open while it runs");
+        if let Err(e) = writeln!(stdin, "{word}").and_then(|()| stdin.flush()) {
+            return Err(match self.exit_status() {
+                Some(status) => format!("ended ({status}) before it was given {word:?}"),
+                None => format!("couldn't be given {word:?}: {e}"),
+            });
+        }
+
+        let deadline = Instant::now() + timeout;
+        let mut words = vec![];
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(left) {
+                Ok(Ok(line)) if line.is_empty() => return Ok(words),
+                Ok(Ok(line)) => words.push(line),
+                Ok(Err(e)) => return Err(format!("couldn't be read from: {e}")),
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(format!(
+                        "didn't answer for {word:?} within {:?}, it was stopped",
+                        timeout
+                    ))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(match self.exit_status() {
+                        Some(status) => format!("ended ({status}) before answering for {word:?}"),
+                        None => format!("closed its stdout before answering for {word:?}"),
+                    })
+                }
+            }
+        }
+    }
+
+    /// How the plugin ended, given a moment to, after its stdout closed
+    fn exit_status(&mut self) -> Option<std::process::ExitStatus> {
+        for _ in 0..20 {
+            if let Ok(Some(status)) = self.child.try_wait() {
+                return Some(status);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        None
+    }
+}
+
+impl Drop for PluginProcess {
+    /// Ending its stdin ends the plugin, which gets the timeout to exit
+    /// before it's killed
+    fn drop(&mut self) {
+        self.stdin = None;
+        let deadline = Instant::now() + EXEC_TIMEOUT;
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+                _ => return,
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
 pub struct Mutation {
     pub action: Action,
     pub times: usize,
@@ -46,36 +405,155 @@ pub struct Mutation {
 }
 
 pub struct MutationSet {
+    /// Where the set is among all of them, from 1, to tell them apart in
+    /// the statistics. 0 for the sets of a formatting file.
+    pub id: usize,
     pub mutations: Vec<Mutation>,
 }
 
+/// A mutations file: its name, and its sets in the order they're in
+pub struct MutationsFile {
+    pub name: String,
+    pub mutation_sets: Vec<MutationSet>,
+}
+
+#[derive(Serialize)]
 pub struct MutationResult {
+    #[serde(rename = "word")]
     pub original_word: String,
+    #[serde(rename = "mutations")]
     pub mutated_words: Vec<String>,
+    /// The `id` of the mutation set the words came from last
+    #[serde(skip)]
+    pub mutation_set: usize,
 }
 
 impl MutationResult {
-    pub fn save_to_file(&self, file: &mut fs::File) {
+    /// `{"word":...,"mutations":[...]}`, without a newline
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("words are always valid json")
+    }
+
+    /// An `original,mutated` row for every mutated word
+    pub fn to_csv(&self) -> String {
+        let mut rows = csv::Writer::from_writer(Vec::new());
         for mutated in &self.mutated_words {
-            let log_entry = format!("{}\n", mutated);
-            file.write_all(log_entry.as_bytes()).expect("write failed");
+            rows.write_record([&self.original_word, mutated])
+                .expect("write failed");
         }
+        let rows = rows.into_inner().expect("write failed");
+        String::from_utf8(rows).expect("words are valid UTF-8")
     }
 }
 
 impl MutationSet {
+    /// The set of the mutations in `mutation_strings`, chained in that
+    /// order, like `-m` builds it.
+    ///
+    /// ```
+    /// use gorilla::mutation::MutationSet;
+    ///
+    /// let set = MutationSet::new(&["reverse", "append:{0-1}"])?;
+    /// let mut words = vec![];
+    /// set.perform_streaming("cat", &mut |word| words.push(word.to_owned()));
+    /// assert_eq!(words, ["tac0", "tac1"]);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn new<S: AsRef<str>>(mutation_strings: &[S]) -> Result<MutationSet, String> {
+        Ok(MutationSet {
+            id: 1,
+            mutations: parse_mutation_string(mutation_strings)?,
+        })
+    }
+
     pub fn perform(&self, mutation_result: &mut MutationResult, word: &str) {
-        let mut result: Vec<String> = vec![word.to_owned()];
+        let mut result: Vec<String> = vec![];
+        self.perform_streaming(word, &mut |mutated| result.push(mutated.to_owned()));
+
+        mutation_result.mutated_words = result;
+        mutation_result.mutation_set = self.id;
+    }
 
-        for mutation in &self.mutations {
-            let mut new_result: Vec<String> = vec![];
-            for s in &result {
-                mutation.perform(&mut new_result, s)
+    /// Like `perform`, but handing every word to `sink` as soon as it's
+    /// made instead of collecting them. Each mutation only keeps the words
+    /// it makes of one word of the mutation before it, the same order
+    /// comes out.
+    pub fn perform_streaming(&self, word: &str, sink: &mut dyn FnMut(&str)) {
+        perform_from(&self.mutations, word, sink)
+    }
+
+    /// What each mutation made of `word` and of the words the mutations
+    /// before it made, for `--explain`. Only the first `max_words` words a
+    /// mutation makes of a word are followed. `None` without mutations.
+    pub fn explain(&self, word: &str, max_words: usize) -> Option<Trace> {
+        explain_from(&self.mutations, word, max_words)
+    }
+
+    /// The first `max_words` words the set makes of `word`, and how many it
+    /// makes in all, for `--preview`. Nothing is counted towards a run.
+    ///
+    /// ```
+    /// use gorilla::mutation::MutationSet;
+    ///
+    /// let set = MutationSet::new(&["append:{0-9}"])?;
+    /// let (words, total) = set.preview("cat", 2);
+    /// assert_eq!(words, ["cat0", "cat1"]);
+    /// assert_eq!(total, 10);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn preview(&self, word: &str, max_words: usize) -> (Vec<String>, u64) {
+        let mut words = vec![];
+        let mut total = 0;
+        self.perform_streaming(word, &mut |made| {
+            if words.len() < max_words {
+                words.push(made.to_owned());
+            }
+            total += 1;
+        });
+        (words, total)
+    }
+
+    /// Why an `exec` plugin of the set stopped making words, if one did
+    pub fn plugin_error(&self) -> Option<&str> {
+        self.mutations
+            .iter()
+            .find_map(|mutation| match &mutation.action {
+                Action::Exec(plugin) => plugin.error(),
+                _ => None,
+            })
+    }
+
+    /// Whether the set has `exec` plugins, which run commands
+    pub fn runs_commands(&self) -> bool {
+        self.mutations
+            .iter()
+            .any(|mutation| matches!(mutation.action, Action::Exec(_)))
+    }
+
+    /// Where the random mutations of the set, like `scramble`, take their
+    /// choices from. Each of them gets choices of its own from `random`.
+    pub fn set_random(&mut self, random: Random) {
+        for (n, mutation) in self.mutations.iter_mut().enumerate() {
+            if let Action::Scramble(choices) | Action::Chance(_, choices) = &mut mutation.action {
+                *choices = random.derive(n as u64);
             }
-            result = new_result
         }
+    }
+
+    /// Whether the set has random mutations, which `set_random` decides
+    pub fn is_random(&self) -> bool {
+        self.mutations
+            .iter()
+            .any(|mutation| matches!(mutation.action, Action::Scramble(_) | Action::Chance(..)))
+    }
 
-        mutation_result.mutated_words = result
+    /// How long the `exec` plugins of the set have to answer for a word
+    pub fn set_exec_timeout(&mut self, timeout: Duration) {
+        for mutation in &mut self.mutations {
+            if let Action::Exec(plugin) = &mut mutation.action {
+                plugin.timeout = timeout;
+            }
+        }
     }
 
     pub fn empty_set() -> MutationSet {
@@ -85,31 +563,145 @@ impl MutationSet {
             keep_original: false,
         };
         MutationSet {
+            id: 1,
             mutations: vec![mutation],
         }
     }
 }
 
+fn perform_from(mutations: &[Mutation], word: &str, sink: &mut dyn FnMut(&str)) {
+    match mutations.split_first() {
+        Some((mutation, rest)) => {
+            mutation.perform_streaming(word, &mut |mutated| perform_from(rest, mutated, sink))
+        }
+        None => sink(word),
+    }
+}
+
+/// The words a mutation made of one word
+#[derive(Debug)]
+pub struct Trace {
+    /// The mutation, as the summary of the mutation sets prints it
+    pub mutation: String,
+    pub words: Vec<TraceStep>,
+    /// How many more words it made than were followed
+    pub skipped: u64,
+}
+
+#[derive(Debug)]
+pub struct TraceStep {
+    pub word: String,
+    /// What the next mutation made of the word, `None` after the last one
+    pub next: Option<Trace>,
+}
+
+fn explain_from(mutations: &[Mutation], word: &str, max_words: usize) -> Option<Trace> {
+    let (mutation, rest) = mutations.split_first()?;
+    let mut trace = Trace {
+        mutation: mutation.to_string(),
+        words: vec![],
+        skipped: 0,
+    };
+    mutation.perform_streaming(word, &mut |made| {
+        if trace.words.len() < max_words {
+            trace.words.push(TraceStep {
+                word: made.to_owned(),
+                next: explain_from(rest, made, max_words),
+            });
+        } else {
+            trace.skipped += 1;
+        }
+    });
+    Some(trace)
+}
+
+/// Run every mutation set on every word, split between `threads` threads.
+/// The results are in the order of the words, and those of a word in the
+/// order of the sets, as if it was done on one thread.
+pub fn perform_in_parallel(
+    mutation_sets: &[MutationSet],
+    words: &[&str],
+    threads: usize,
+) -> Vec<Vec<MutationResult>> {
+    let perform = |words: &[&str]| -> Vec<Vec<MutationResult>> {
+        words
+            .iter()
+            .map(|&word| {
+                mutation_sets
+                    .iter()
+                    .map(|mutation_set| {
+                        let mut mutation_result = MutationResult {
+                            original_word: word.to_owned(),
+                            mutated_words: vec![],
+                            mutation_set: 0,
+                        };
+                        mutation_set.perform(&mut mutation_result, word);
+                        mutation_result
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    if threads <= 1 || words.len() < 2 {
+        return perform(words);
+    }
+
+    let chunk_size = words.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let workers: Vec<_> = words
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || perform(chunk)))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("a mutation thread panicked"))
+            .collect()
+    })
+}
+
 impl Mutation {
-    pub fn perform(&self, result: &mut Vec<String>, input: &str) {
+    /// Hand every word this mutation makes of `input` to `sink`, without
+    /// allocating any for the mutations that keep or shorten it. A word
+    /// that passes a filter, or that a mutation leaves as it is, is `input`
+    /// itself.
+    pub fn perform_streaming(&self, input: &str, sink: &mut dyn FnMut(&str)) {
         if self.keep_original {
-            result.push(input.to_owned());
+            sink(input);
         }
 
         match &self.action {
-            Action::Prepend(s) => {
-                for word in token_iterator(&tokenize_format_string(s)) {
-                    result.push(format!("{}{}", word.repeat(self.times), input))
-                }
+            Action::Prepend(affixes) => {
+                let mut mutated = String::new();
+                affixes.for_each(|word| {
+                    mutated.clear();
+                    (0..self.times).for_each(|_| mutated.push_str(word));
+                    mutated.push_str(input);
+                    sink(&mutated)
+                })
             }
-            Action::Append(s) => {
-                for word in token_iterator(&tokenize_format_string(s)) {
-                    result.push(format!("{}{}", input, word.repeat(self.times)))
+            Action::Append(affixes) => {
+                let mut mutated = String::new();
+                affixes.for_each(|word| {
+                    mutated.clear();
+                    mutated.push_str(input);
+                    (0..self.times).for_each(|_| mutated.push_str(word));
+                    sink(&mutated)
+                })
+            }
+            Action::CommonSuffixes(suffixes) => {
+                let mut mutated = String::new();
+                for suffix in suffixes {
+                    mutated.clear();
+                    mutated.push_str(input);
+                    (0..self.times).for_each(|_| mutated.push_str(suffix));
+                    sink(&mutated)
                 }
             }
             Action::Replace(s, b) => {
-                if input.contains(s) || !self.keep_original {
-                    result.push(input.replace(s, b))
+                if input.contains(s) {
+                    sink(&input.replace(s, b))
+                } else if !self.keep_original {
+                    sink(input)
                 }
             }
             Action::RemoveFirstLetter => {
@@ -117,38 +709,65 @@ impl Mutation {
                 for _ in 0..self.times {
                     chrs.next();
                 }
-                result.push(chrs.as_str().to_string())
+                sink(chrs.as_str())
             }
             Action::RemoveLastLetter => {
                 let mut chrs = input.chars();
                 for _ in 0..self.times {
                     chrs.next_back();
                 }
-                result.push(chrs.as_str().to_string())
+                sink(chrs.as_str())
             }
             Action::IfCharacterLength(not, ord, number) => {
                 if (input.len().cmp(number) == *ord) != *not {
-                    result.push(input.to_owned())
+                    sink(input)
                 }
             }
             Action::IfContains(not, string) => {
                 if input.contains(string) != *not {
-                    result.push(input.to_owned())
+                    sink(input)
+                }
+            }
+            Action::FirstLetter => {
+                sink(input.chars().next().map_or("", |x| &input[..x.len_utf8()]))
+            }
+            Action::Reverse if input.chars().nth(1).is_none() => sink(input),
+            Action::Reverse => sink(&input.chars().rev().collect::<String>()),
+            // only ascii is sure to stay the same when there's nothing to
+            // change the case of
+            Action::UppercaseAll
+                if input.is_ascii() && !input.bytes().any(|b| b.is_ascii_lowercase()) =>
+            {
+                sink(input)
+            }
+            Action::UppercaseAll => sink(&input.to_uppercase()),
+            Action::LowercaseAll
+                if input.is_ascii() && !input.bytes().any(|b| b.is_ascii_uppercase()) =>
+            {
+                sink(input)
+            }
+            Action::LowercaseAll => sink(&input.to_lowercase()),
+            Action::Clone => {
+                sink(input);
+                sink(input)
+            }
+            Action::Scramble(random) => {
+                let mut chars: Vec<char> = input.chars().collect();
+                let mut numbers = random.for_word(input);
+                for i in (1..chars.len()).rev() {
+                    chars.swap(i, numbers.below(i as u64 + 1) as usize);
                 }
+                sink(&chars.into_iter().collect::<String>())
             }
-            Action::FirstLetter => result.push(
-                input
-                    .chars()
-                    .next()
-                    .map_or(String::from(""), |x| x.to_string()),
-            ),
-            Action::Reverse => result.push(input.chars().rev().collect()),
-            Action::UppercaseAll => result.push(input.to_uppercase()),
-            Action::LowercaseAll => result.push(input.to_lowercase()),
-            Action::Clone => result.append(&mut vec![input.to_owned(), input.to_owned()]),
-            Action::Wipe => result.push(String::new()),
-            Action::Nothing => result.push(input.to_owned()),
+            Action::Chance(percent, random) => {
+                if random.for_word(input).below(100) < *percent as u64 {
+                    sink(input)
+                }
+            }
+            Action::Wipe => sink(""),
+            Action::Nothing => sink(input),
             Action::Remove => (),
+            Action::Exec(plugin) => plugin.perform(input, sink),
         }
     }
 }
@@ -162,6 +781,7 @@ impl Display for Mutation {
         match &self.action {
             Action::Prepend(s) => write!(f, "prepend: {}", s),
             Action::Append(s) => write!(f, "append: {}", s),
+            Action::CommonSuffixes(suffixes) => write!(f, "common suffixes: {}", suffixes.len()),
             Action::Replace(s, b) => write!(f, "replace: {} -> {}", s, b),
             Action::Reverse => write!(f, "reverse"),
             Action::RemoveFirstLetter => write!(f, "remove 1st letter"),
@@ -178,6 +798,9 @@ impl Display for Mutation {
                 write!(f, "if length {:?} {} = {}", ord, number, !not)
             }
             Action::IfContains(not, string) => write!(f, "if contains {} = {}", string, !not),
+            Action::Scramble(_) => write!(f, "scramble"),
+            Action::Chance(percent, _) => write!(f, "chance: {}%", percent),
+            Action::Exec(plugin) => write!(f, "exec: {}", plugin.command),
         }?;
 
         if self.keep_original {
@@ -192,14 +815,263 @@ impl Display for Mutation {
 //   mutation { action }
 // }
 
-macro_rules! check_action_args {
-    ($action:expr, $requiredArgs:expr, $actualArgs:expr) => {
-        if $actualArgs > ($requiredArgs - 1) {
-            Ok($action)
-        } else {
-            Err(MutationBuildError::MissingArguments)
+/// An action of mutation strings. Parsing goes by these, and
+/// `--list-mutations` lists them, so the two always agree.
+pub struct ActionSpec {
+    pub name: &'static str,
+    /// What follows the name, like `:FROM:TO` for `["FROM", "TO"]`, the
+    /// ones in brackets being optional
+    pub arguments: &'static [&'static str],
+    pub description: &'static str,
+    /// A mutation string with the action, a word and the words it makes of it
+    pub example: (&'static str, &'static str, &'static [&'static str]),
+    build: fn(&[&str], &str) -> Result<Action, MutationBuildError>,
+}
+
+impl ActionSpec {
+    /// The action as it's written, like `replace:FROM:TO`
+    pub fn signature(&self) -> String {
+        let mut signature = String::from(self.name);
+        for argument in self.arguments {
+            signature.push(':');
+            signature.push_str(argument);
+        }
+        signature
+    }
+
+    /// The words the example makes, like `0cat, 1cat`, and `""` for the
+    /// empty word
+    pub fn example_made(&self) -> String {
+        match self.example.2 {
+            [] => String::from("(nothing)"),
+            made => made
+                .iter()
+                .map(|word| match *word {
+                    "" => "\"\"",
+                    word => word,
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+pub const ACTIONS: &[ActionSpec] = &[
+    ActionSpec {
+        name: "prepend",
+        arguments: &["STR"],
+        description: "adds STR before the word, once for every word of a pattern like {0-9}",
+        example: ("prepend:{0-1}", "cat", &["0cat", "1cat"]),
+        build: |arguments, _| Affixes::new(arguments[0]).map(Action::Prepend),
+    },
+    ActionSpec {
+        name: "append",
+        arguments: &["STR"],
+        description: "adds STR after the word, once for every word of a pattern like {0-9}",
+        example: ("append:123", "cat", &["cat123"]),
+        build: |arguments, _| Affixes::new(arguments[0]).map(Action::Append),
+    },
+    ActionSpec {
+        name: "common_suffixes",
+        arguments: &["[N]"],
+        description: "adds each of the first N of a table of common suffixes counted from leaked passwords, likeliest first (50 without an N, up to 100)",
+        example: ("common_suffixes:3", "cat", &["cat1", "cat123", "cat2"]),
+        build: |arguments, _| common_suffixes(arguments, &COMMON_SUFFIXES),
+    },
+    ActionSpec {
+        name: "replace",
+        arguments: &["FROM", "TO"],
+        description: "replaces every FROM in the word with TO",
+        example: ("replace:a:4", "cat", &["c4t"]),
+        build: |arguments, _| {
+            Ok(Action::Replace(
+                arguments[0].to_owned(),
+                arguments[1].to_owned(),
+            ))
+        },
+    },
+    ActionSpec {
+        name: "if_length",
+        arguments: &["<N|=N|>N"],
+        description: "keeps the word if it's shorter than, as long as or longer than N bytes",
+        example: ("if_length:<5", "cat", &["cat"]),
+        build: if_length,
+    },
+    ActionSpec {
+        name: "if_contains",
+        arguments: &["STR"],
+        description: "keeps the word if it contains STR",
+        example: ("if_contains:a", "cat", &["cat"]),
+        build: |arguments, options| {
+            Ok(Action::IfContains(
+                options.contains('!'),
+                arguments[0].to_owned(),
+            ))
+        },
+    },
+    ActionSpec {
+        name: "reverse",
+        arguments: &[],
+        description: "reverses the word",
+        example: ("reverse", "cat", &["tac"]),
+        build: |_, _| Ok(Action::Reverse),
+    },
+    ActionSpec {
+        name: "clone",
+        arguments: &[],
+        description: "makes the word twice",
+        example: ("clone", "cat", &["cat", "cat"]),
+        build: |_, _| Ok(Action::Clone),
+    },
+    ActionSpec {
+        name: "wipe",
+        arguments: &[],
+        description: "empties the word",
+        example: ("wipe", "cat", &[""]),
+        build: |_, _| Ok(Action::Wipe),
+    },
+    ActionSpec {
+        name: "1st_letter",
+        arguments: &[],
+        description: "keeps only the first letter of the word",
+        example: ("1st_letter", "cat", &["c"]),
+        build: |_, _| Ok(Action::FirstLetter),
+    },
+    ActionSpec {
+        name: "nothing",
+        arguments: &[],
+        description: "leaves the word as it is",
+        example: ("nothing", "cat", &["cat"]),
+        build: |_, _| Ok(Action::Nothing),
+    },
+    ActionSpec {
+        name: "uppercase_all",
+        arguments: &[],
+        description: "uppercases the word",
+        example: ("uppercase_all", "cat", &["CAT"]),
+        build: |_, _| Ok(Action::UppercaseAll),
+    },
+    ActionSpec {
+        name: "lowercase_all",
+        arguments: &[],
+        description: "lowercases the word",
+        example: ("lowercase_all", "CaT", &["cat"]),
+        build: |_, _| Ok(Action::LowercaseAll),
+    },
+    ActionSpec {
+        name: "remove_last_letter",
+        arguments: &[],
+        description: "removes the last letter of the word",
+        example: ("remove_last_letter", "cat", &["ca"]),
+        build: |_, _| Ok(Action::RemoveLastLetter),
+    },
+    ActionSpec {
+        name: "remove_first_letter",
+        arguments: &[],
+        description: "removes the first letter of the word",
+        example: ("remove_first_letter", "cat", &["at"]),
+        build: |_, _| Ok(Action::RemoveFirstLetter),
+    },
+    ActionSpec {
+        name: "remove",
+        arguments: &[],
+        description: "drops the word, to keep only the words of a k before it",
+        example: ("k remove", "cat", &["cat"]),
+        build: |_, _| Ok(Action::Remove),
+    },
+    ActionSpec {
+        name: "scramble",
+        arguments: &[],
+        description: "puts the letters of the word in a random order, the one of the --seed",
+        example: ("scramble", "cat", &["atc"]),
+        build: |_, _| Ok(Action::Scramble(Random::default())),
+    },
+    ActionSpec {
+        name: "chance",
+        arguments: &["PERCENT"],
+        description: "keeps the word PERCENT% of the time, picked with the --seed",
+        example: ("chance:100", "cat", &["cat"]),
+        build: |arguments, _| {
+            let percent = arguments[0]
+                .parse()
+                .ok()
+                .filter(|percent| *percent <= 100)
+                .ok_or_else(|| {
+                    MutationBuildError::InvalidArgument(format!(
+                        "{} isn't a percentage from 0 to 100",
+                        arguments[0]
+                    ))
+                })?;
+            Ok(Action::Chance(percent, Random::default()))
+        },
+    },
+    ActionSpec {
+        name: "exec",
+        arguments: &["COMMAND"],
+        description: "gives the word to COMMAND on a line, which answers with the words it makes of it on a line each and an empty line after them",
+        example: (
+            "exec:while read w; do echo $w; echo $w!; echo; done",
+            "cat",
+            &["cat", "cat!"],
+        ),
+        build: |arguments, _| Ok(Action::Exec(Plugin::new(arguments[0]))),
+    },
+];
+
+/// What can come before the action of a mutation, after the list of `ACTIONS`
+pub const MODIFIERS: &str = "\
+Before the action, a number repeats it (2 append:1: cat -> cat11), k keeps the
+word it was given as well (k reverse: cat -> cat, tac) and ! turns the conditions
+around (! if_contains:a: cat -> (nothing)).";
+
+fn if_length(arguments: &[&str], options: &str) -> Result<Action, MutationBuildError> {
+    let mut number_chrs = arguments[0].chars();
+    let ordering = match number_chrs.next() {
+        Some('>') => Ordering::Greater,
+        Some('<') => Ordering::Less,
+        Some('=') => Ordering::Equal,
+        _ => {
+            return Err(MutationBuildError::InvalidArgument(String::from(
+                "missing operator",
+            )))
         }
     };
+
+    let number: usize = number_chrs.as_str().parse().map_err(|_| {
+        MutationBuildError::InvalidArgument(format!("{} is not a length", number_chrs.as_str()))
+    })?;
+
+    Ok(Action::IfCharacterLength(
+        options.contains('!'),
+        ordering,
+        number,
+    ))
+}
+
+/// The `common_suffixes:N` of the first N of `table`
+fn common_suffixes<S: AsRef<str>>(
+    arguments: &[&str],
+    table: &[S],
+) -> Result<Action, MutationBuildError> {
+    let count = match arguments.first() {
+        None | Some(&"") => DEFAULT_COMMON_SUFFIXES.min(table.len()),
+        Some(count) => count
+            .parse()
+            .ok()
+            .filter(|count| (1..=table.len()).contains(count))
+            .ok_or_else(|| {
+                MutationBuildError::InvalidArgument(format!(
+                    "{count} isn't a number of suffixes from 1 to {}",
+                    table.len()
+                ))
+            })?,
+    };
+    Ok(Action::CommonSuffixes(
+        table[..count]
+            .iter()
+            .map(|suffix| suffix.as_ref().to_owned())
+            .collect(),
+    ))
 }
 
 impl Action {
@@ -208,75 +1080,61 @@ impl Action {
         arguments: Vec<&str>,
         options: &str,
     ) -> Result<Action, MutationBuildError> {
-        let argc = arguments.len();
-
-        match action {
-            "prepend" => {
-                check_action_args!(Action::Prepend(arguments[0].to_owned()), 1, argc)
-            }
-            "append" => {
-                check_action_args!(Action::Append(arguments[0].to_owned()), 1, argc)
-            }
-            "replace" => {
-                check_action_args!(
-                    Action::Replace(arguments[0].to_owned(), arguments[1].to_owned()),
-                    2,
-                    argc
-                )
-            }
-            "if_length" => {
-                check_action_args!(
-                    {
-                        let arg_chrs: Vec<char> = arguments[0].chars().collect();
-                        let first_chr = arg_chrs.first().unwrap();
-
-                        let ordering = match first_chr {
-                            '>' => Ordering::Greater,
-                            '<' => Ordering::Less,
-                            '=' => Ordering::Equal,
-                            _ => {
-                                return Err(MutationBuildError::InvalidArgument(String::from(
-                                    "missing operator",
-                                )))
-                            }
-                        };
-
-                        let mut number_chrs = arguments[0].chars();
-                        number_chrs.next();
-                        let number: usize = number_chrs.as_str().parse().unwrap();
-
-                        Action::IfCharacterLength(options.contains('!'), ordering, number)
-                    },
-                    1,
-                    argc
-                )
-            }
-            "if_contains" => {
-                check_action_args!(
-                    Action::IfContains(options.contains('!'), arguments[0].to_owned()),
-                    1,
-                    argc
-                )
-            }
-            "reverse" => Ok(Action::Reverse),
-            "clone" => Ok(Action::Clone),
-            "wipe" => Ok(Action::Wipe),
-            "1st_letter" => Ok(Action::FirstLetter),
-            "nothing" => Ok(Action::Nothing),
-            "uppercase_all" => Ok(Action::UppercaseAll),
-            "lowercase_all" => Ok(Action::LowercaseAll),
-            "remove_last_letter" => Ok(Action::RemoveLastLetter),
-            "remove_first_letter" => Ok(Action::RemoveFirstLetter),
-            "remove" => Ok(Action::Remove),
-            _ => Err(MutationBuildError::ActionDoesNotExist),
+        let spec = ACTIONS
+            .iter()
+            .find(|spec| spec.name == action)
+            .ok_or(MutationBuildError::ActionDoesNotExist)?;
+        let required = spec
+            .arguments
+            .iter()
+            .filter(|argument| !argument.starts_with('['))
+            .count();
+        if arguments.len() < required {
+            return Err(MutationBuildError::MissingArguments);
         }
+        (spec.build)(&arguments, options)
     }
 }
 
-pub fn parse_mutation_string(mutation_strings: &Vec<String>) -> Vec<Mutation> {
+/// The mutations of a mutation set, or why one of them couldn't be built.
+///
+/// ```
+/// use gorilla::mutation::parse_mutation_string;
+///
+/// let mutations = parse_mutation_string(&["k reverse", "append:!"])?;
+/// assert_eq!(mutations.len(), 2);
+/// assert!(parse_mutation_string(&["append"]).is_err());
+/// # Ok::<(), String>(())
+/// ```
+pub fn parse_mutation_string<S: AsRef<str>>(
+    mutation_strings: &[S],
+) -> Result<Vec<Mutation>, String> {
+    parse_mutations_with_suffixes(mutation_strings, None::<&[&str]>)
+}
+
+/// Like `parse_mutation_string`, with `common_suffixes` taking its suffixes
+/// from `suffixes` instead of the `COMMON_SUFFIXES` when it's given, like
+/// the `common_suffixes` of a mutations file.
+///
+/// ```
+/// use gorilla::mutation::{parse_mutations_with_suffixes, MutationSet};
+///
+/// let suffixes = ["2024!", "42"];
+/// let mutations = parse_mutations_with_suffixes(&["common_suffixes"], Some(&suffixes[..]))?;
+/// let set = MutationSet { id: 1, mutations };
+/// let (words, _) = set.preview("cat", 5);
+/// assert_eq!(words, ["cat2024!", "cat42"]);
+/// assert!(parse_mutations_with_suffixes(&["common_suffixes:3"], Some(&suffixes[..])).is_err());
+/// # Ok::<(), String>(())
+/// ```
+pub fn parse_mutations_with_suffixes<S: AsRef<str>, T: AsRef<str>>(
+    mutation_strings: &[S],
+    suffixes: Option<&[T]>,
+) -> Result<Vec<Mutation>, String> {
     let mut mutations: Vec<Mutation> = vec![];
 
     for mutation_string in mutation_strings {
+        let mutation_string = mutation_string.as_ref();
         let mut mutation_split: Vec<&str> = mutation_string
             .split(':')
             // .into_iter()
@@ -301,19 +1159,36 @@ pub fn parse_mutation_string(mutation_strings: &Vec<String>) -> Vec<Mutation> {
         }
 
         mutation_split.remove(0);
+        // the command of an exec goes to the shell as it's written, colons
+        // and spaces around them included
+        if mutation_action == "exec" {
+            mutation_split = mutation_string
+                .split_once(':')
+                .map(|(_, command)| vec![command])
+                .unwrap_or_default();
+        }
 
-        match Action::from_string(mutation_action, mutation_split, mutation_options) {
+        let action = match suffixes {
+            Some(suffixes) if mutation_action == "common_suffixes" => {
+                common_suffixes(&mutation_split, suffixes)
+            }
+            _ => Action::from_string(mutation_action, mutation_split, mutation_options),
+        };
+        match action {
             Ok(m) => mutations.push(Mutation {
                 action: m,
                 times: mutation_runtimes,
                 keep_original: mutation_options.contains('k'),
             }),
-            Err(e) => eprintln!(
-                "warning: couldn't build mutation {} ({:?})",
-                mutation_action, e
-            ),
+            Err(e) => {
+                return Err(format!(
+                    "couldn't build mutation {} ({})",
+                    mutation_string.trim(),
+                    e
+                ))
+            }
         }
     }
 
-    mutations
+    Ok(mutations)
 }
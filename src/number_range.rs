@@ -0,0 +1,113 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Every `step`th number from `start` to `end` (both included), written
+/// with at least `pad` digits, as given to `--range-input START-END[:STEP][:PAD]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberRange {
+    pub start: u64,
+    pub end: u64,
+    pub step: u64,
+    pub pad: usize,
+}
+
+impl FromStr for NumberRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let bounds = parts.next().unwrap_or("");
+        let (start, end) = bounds
+            .split_once('-')
+            .ok_or_else(|| format!("expected START-END, got {bounds:?}"))?;
+        let start: u64 = start
+            .parse()
+            .map_err(|_| format!("invalid start {start:?}"))?;
+        let end: u64 = end.parse().map_err(|_| format!("invalid end {end:?}"))?;
+
+        let step = match parts.next() {
+            None | Some("") => 1,
+            Some(step) => step.parse().map_err(|_| format!("invalid step {step:?}"))?,
+        };
+        let pad = match parts.next() {
+            None | Some("") => 0,
+            Some(pad) => pad
+                .parse()
+                .map_err(|_| format!("invalid padding {pad:?}"))?,
+        };
+
+        if parts.next().is_some() {
+            return Err(String::from("expected START-END[:STEP][:PAD]"));
+        }
+        if start > end {
+            return Err(format!("the start {start} is larger than the end {end}"));
+        }
+        if step == 0 {
+            return Err(String::from("the step can't be 0"));
+        }
+
+        Ok(NumberRange {
+            start,
+            end,
+            step,
+            pad,
+        })
+    }
+}
+
+impl Display for NumberRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}:{}:{}", self.start, self.end, self.step, self.pad)
+    }
+}
+
+impl NumberRange {
+    /// How many numbers of the range lie between `low` and `high`.
+    fn count_between(&self, low: u64, high: u64) -> u128 {
+        let high = high.min(self.end);
+        if high < self.start || high < low {
+            return 0;
+        }
+
+        let step = u128::from(self.step);
+        let first = u128::from(low.saturating_sub(self.start)).div_ceil(step);
+        let last = u128::from(high - self.start) / step;
+        (last + 1).saturating_sub(first)
+    }
+
+    pub fn calculate_total(&self) -> u128 {
+        self.count_between(self.start, self.end)
+    }
+
+    /// Size of all the numbers, one per line, in bytes.
+    pub fn calculate_size(&self) -> u128 {
+        let mut size = 0;
+        let mut low = 0;
+
+        // numbers with the same amount of digits at a time
+        for digits in 1..=20 {
+            let high = 10u64.checked_pow(digits).map_or(u64::MAX, |h| h - 1);
+            let width = (digits as usize).max(self.pad) as u128 + 1;
+            size += width * self.count_between(low, high);
+
+            if high == u64::MAX {
+                break;
+            }
+            low = high + 1;
+        }
+
+        size
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = String> + '_ {
+        let mut next = Some(self.start);
+
+        std::iter::from_fn(move || {
+            let number = next?;
+            next = number.checked_add(self.step).filter(|&n| n <= self.end);
+            Some(format!("{number:0width$}", width = self.pad))
+        })
+    }
+}
@@ -0,0 +1,536 @@
+use clap::ValueEnum;
+use flate2::write::GzEncoder;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, StdoutLock, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, ExitStatus, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+};
+use zstd::stream::write::Encoder as ZstdEncoder;
+
+use crate::sort::char_count;
+
+/// How the output file is compressed
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum OutputCompression {
+    Gzip,
+    Zstd,
+}
+
+impl OutputCompression {
+    /// The compression an output path asks for by its extension.
+    pub fn from_path(path: &str) -> Option<OutputCompression> {
+        if path.ends_with(".gz") {
+            Some(OutputCompression::Gzip)
+        } else if path.ends_with(".zst") {
+            Some(OutputCompression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    pub fn default_level(self) -> u32 {
+        match self {
+            OutputCompression::Gzip => 6,
+            OutputCompression::Zstd => 3,
+        }
+    }
+}
+
+/// What `--output-format` writes for every input word
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// The generated words, one per line
+    #[default]
+    Text,
+    /// A JSON object with the word and its mutations per line (JSON Lines)
+    Json,
+    /// The same objects in one JSON array
+    JsonArray,
+    /// `original,mutated` rows, after an `original,mutated` header
+    Csv,
+}
+
+/// What `--output-encoding` writes the generated words in
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum OutputEncoding {
+    #[default]
+    Utf8,
+    /// ISO 8859-1, a byte per character, the first 256 code points only
+    Latin1,
+}
+
+/// What to do with the words `--output-encoding` can't encode
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Unencodable {
+    /// Leave them out
+    #[default]
+    Skip,
+    /// Write them as `$HEX[...]` of their UTF-8 bytes, which hashcat and
+    /// john read as those bytes
+    Hex,
+}
+
+impl OutputEncoding {
+    pub fn can_encode(self, word: &str) -> bool {
+        match self {
+            OutputEncoding::Utf8 => true,
+            OutputEncoding::Latin1 => word.chars().all(|c| u32::from(c) <= 0xFF),
+        }
+    }
+
+    /// `word` in this encoding. Bytes that aren't UTF-8, like the lines of
+    /// `--invalid-utf8 raw`, are left as they are, and characters the
+    /// encoding doesn't have become `?`.
+    pub fn encode(self, word: &[u8]) -> Cow<'_, [u8]> {
+        if self == OutputEncoding::Utf8 || word.is_ascii() {
+            return Cow::Borrowed(word);
+        }
+        match std::str::from_utf8(word) {
+            Ok(word) => Cow::Owned(
+                word.chars()
+                    .map(|c| u8::try_from(c).unwrap_or(b'?'))
+                    .collect(),
+            ),
+            Err(_) => Cow::Borrowed(word),
+        }
+    }
+}
+
+/// `word` as `$HEX[...]`, for words that can't be written as they are
+pub fn hex_word(word: &[u8]) -> String {
+    let hex: String = word.iter().map(|b| format!("{b:02x}")).collect();
+    format!("$HEX[{hex}]")
+}
+
+enum Writer {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(ZstdEncoder<'static, BufWriter<File>>),
+}
+
+/// The file given to `--output-file`, buffered and possibly compressed,
+/// keeping count of the bytes written to it before compression.
+pub struct OutputFile {
+    writer: Writer,
+    pub bytes: u64,
+}
+
+impl OutputFile {
+    pub fn new(file: File, compression: Option<(OutputCompression, u32)>) -> io::Result<Self> {
+        let file = BufWriter::new(file);
+        let writer = match compression {
+            None => Writer::Plain(file),
+            Some((OutputCompression::Gzip, level)) => {
+                Writer::Gzip(GzEncoder::new(file, flate2::Compression::new(level)))
+            }
+            Some((OutputCompression::Zstd, level)) => {
+                Writer::Zstd(ZstdEncoder::new(file, level as i32)?)
+            }
+        };
+
+        Ok(OutputFile { writer, bytes: 0 })
+    }
+
+    /// Write out everything, ending the compressed stream. Returns how
+    /// large the file is on disk.
+    pub fn finish(self) -> io::Result<u64> {
+        let file = match self.writer {
+            Writer::Plain(file) => file,
+            Writer::Gzip(encoder) => encoder.finish()?,
+            Writer::Zstd(encoder) => encoder.finish()?,
+        };
+        let file = file.into_inner().map_err(|e| e.into_error())?;
+        Ok(file.metadata()?.len())
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = match &mut self.writer {
+            Writer::Plain(file) => file.write(buf)?,
+            Writer::Gzip(encoder) => encoder.write(buf)?,
+            Writer::Zstd(encoder) => encoder.write(buf)?,
+        };
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Writer::Plain(file) => file.flush(),
+            Writer::Gzip(encoder) => encoder.flush(),
+            Writer::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// What to do when an output file already exists
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OpenMode {
+    Overwrite,
+    Append,
+    NoClobber,
+}
+
+pub fn open_output(path: &str, mode: OpenMode) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    match mode {
+        OpenMode::Overwrite => options.create(true).write(true).truncate(true),
+        OpenMode::Append => options.create(true).append(true),
+        OpenMode::NoClobber => options.create_new(true).write(true),
+    };
+    options.open(path)
+}
+
+/// The path of part `number` of a split output: `out.txt` becomes
+/// `out.0001.txt`, `out.txt.gz` becomes `out.0001.txt.gz`.
+pub fn split_path(path: &str, number: usize) -> String {
+    let name_start = path.rfind('/').map_or(0, |i| i + 1);
+    // skipping the dot of hidden files
+    match path.get(name_start + 1..).and_then(|name| name.find('.')) {
+        Some(i) => {
+            let dot = name_start + 1 + i;
+            format!("{}.{number:04}{}", &path[..dot], &path[dot..])
+        }
+        None => format!("{path}.{number:04}"),
+    }
+}
+
+/// When to move on to the next part of a split output
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SplitLimits {
+    pub lines: Option<u64>,
+    /// Before compression
+    pub bytes: Option<u64>,
+}
+
+impl SplitLimits {
+    pub fn is_splitting(&self) -> bool {
+        self.lines.is_some() || self.bytes.is_some()
+    }
+}
+
+/// A file the output was written to
+#[derive(Debug, PartialEq)]
+pub struct OutputPart {
+    pub path: String,
+    pub lines: u64,
+    pub on_disk: u64,
+}
+
+/// The `--output-file`, rolling over to the next numbered file whenever one
+/// of the `SplitLimits` is reached. Files are only ever split between lines
+/// (words followed by the `--output-delimiter`), so every line has to be
+/// handed to `write` in one piece.
+pub struct RollingOutput {
+    path: String,
+    mode: OpenMode,
+    compression: Option<(OutputCompression, u32)>,
+    limits: SplitLimits,
+    current: OutputFile,
+    current_path: String,
+    current_lines: u64,
+    at_line_start: bool,
+    delimiter: Vec<u8>,
+    /// The files finished so far
+    pub parts: Vec<OutputPart>,
+    /// Bytes written to all of the files, before compression
+    pub bytes: u64,
+}
+
+impl RollingOutput {
+    /// Open the (first) output file.
+    pub fn new(
+        path: &str,
+        mode: OpenMode,
+        compression: Option<(OutputCompression, u32)>,
+        limits: SplitLimits,
+    ) -> io::Result<RollingOutput> {
+        let current_path = if limits.is_splitting() {
+            split_path(path, 1)
+        } else {
+            path.to_owned()
+        };
+        let current = OutputFile::new(open_output(&current_path, mode)?, compression)?;
+
+        Ok(RollingOutput {
+            path: path.to_owned(),
+            mode,
+            compression,
+            limits,
+            current,
+            current_path,
+            current_lines: 0,
+            at_line_start: true,
+            delimiter: b"\n".to_vec(),
+            parts: Vec::new(),
+            bytes: 0,
+        })
+    }
+
+    /// End lines with `delimiter` instead of a newline.
+    pub fn set_delimiter(&mut self, delimiter: &[u8]) {
+        self.delimiter = delimiter.to_vec();
+    }
+
+    /// Whether `line` has to go to the next file.
+    fn is_full(&self, line: &[u8]) -> bool {
+        let current_bytes = self.current.bytes;
+        self.limits
+            .lines
+            .is_some_and(|max| self.current_lines >= max)
+            || self
+                .limits
+                .bytes
+                .is_some_and(|max| current_bytes > 0 && current_bytes + line.len() as u64 > max)
+    }
+
+    fn roll(&mut self) -> io::Result<()> {
+        let next_path = split_path(&self.path, self.parts.len() + 2);
+        let next = OutputFile::new(open_output(&next_path, self.mode)?, self.compression)?;
+
+        let finished = std::mem::replace(&mut self.current, next);
+        self.parts.push(OutputPart {
+            path: std::mem::replace(&mut self.current_path, next_path),
+            lines: self.current_lines,
+            on_disk: finished.finish()?,
+        });
+        self.current_lines = 0;
+        Ok(())
+    }
+
+    /// Finish the last file, returning every file written.
+    pub fn finish(mut self) -> io::Result<Vec<OutputPart>> {
+        self.parts.push(OutputPart {
+            path: self.current_path,
+            lines: self.current_lines,
+            on_disk: self.current.finish()?,
+        });
+        Ok(self.parts)
+    }
+}
+
+impl Write for RollingOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.at_line_start && self.limits.is_splitting() && self.is_full(buf) {
+            self.roll()?;
+        }
+
+        self.current.write_all(buf)?;
+        self.bytes += buf.len() as u64;
+        self.current_lines += match self.delimiter.as_slice() {
+            [delimiter] => buf.iter().filter(|&b| b == delimiter).count(),
+            delimiter => buf
+                .windows(delimiter.len())
+                .filter(|&w| w == delimiter)
+                .count(),
+        } as u64;
+        if !buf.is_empty() {
+            self.at_line_start = buf.ends_with(&self.delimiter);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.current.flush()
+    }
+}
+
+/// The files of `--output-by-length`, one for every length of word in
+/// characters. A file is only created once a word of its length comes up,
+/// and they're all kept open and buffered until the end.
+pub struct LengthBuckets {
+    /// The path of every file, `{}` standing for the length
+    template: String,
+    compression: Option<(OutputCompression, u32)>,
+    delimiter: Vec<u8>,
+    encoding: OutputEncoding,
+    /// Each file with the number of words written to it
+    files: BTreeMap<usize, (OutputFile, u64)>,
+    /// The `--output-header` every file starts with
+    pub header: Option<Vec<u8>>,
+    /// Bytes written to all of the files, before compression
+    pub bytes: u64,
+}
+
+impl LengthBuckets {
+    /// `target` is either a template like `out.len{}.txt` or a directory,
+    /// which then gets `len1.txt`, `len2.txt` and so on.
+    pub fn new(target: &str, delimiter: &[u8], encoding: OutputEncoding) -> LengthBuckets {
+        let template = if target.contains("{}") {
+            target.to_owned()
+        } else {
+            format!("{}/len{{}}.txt", target.trim_end_matches('/'))
+        };
+        let compression = OutputCompression::from_path(&template).map(|c| (c, c.default_level()));
+
+        LengthBuckets {
+            template,
+            compression,
+            delimiter: delimiter.to_vec(),
+            encoding,
+            files: BTreeMap::new(),
+            header: None,
+            bytes: 0,
+        }
+    }
+
+    pub fn path(&self, length: usize) -> String {
+        self.template.replacen("{}", &length.to_string(), 1)
+    }
+
+    pub fn write_word(&mut self, word: &[u8]) -> io::Result<()> {
+        let length = char_count(word);
+        if !self.files.contains_key(&length) {
+            let path = self.path(length);
+            if let Some(dir) = Path::new(&path).parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut file =
+                OutputFile::new(open_output(&path, OpenMode::Overwrite)?, self.compression)?;
+            if let Some(header) = &self.header {
+                file.write_all(header)?;
+            }
+            self.files.insert(length, (file, 0));
+        }
+
+        let (file, words) = self
+            .files
+            .get_mut(&length)
+            .expect("the file was just opened");
+        // counted in characters of the word, not bytes of its encoding
+        let word = self.encoding.encode(word);
+        file.write_all(&word)?;
+        file.write_all(&self.delimiter)?;
+        *words += 1;
+        self.bytes += (word.len() + self.delimiter.len()) as u64;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.files
+            .values_mut()
+            .try_for_each(|(file, _)| file.flush())
+    }
+
+    /// Finish every file, returning them by length, shortest first.
+    pub fn finish(self) -> io::Result<Vec<(usize, OutputPart)>> {
+        let paths: Vec<String> = self.files.keys().map(|&length| self.path(length)).collect();
+        self.files
+            .into_iter()
+            .zip(paths)
+            .map(|((length, (file, words)), path)| {
+                Ok((
+                    length,
+                    OutputPart {
+                        path,
+                        lines: words,
+                        on_disk: file.finish()?,
+                    },
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Where the words go when they aren't written to a file, or along with
+/// it: stdout, or the stdin of the `--pipe-to` command.
+pub enum WordStream {
+    /// Locked for the whole run and buffered, so that a word doesn't cost a
+    /// write of its own. `flush` is up to the caller.
+    Stdout(BufWriter<StdoutLock<'static>>),
+    Pipe(BufWriter<ChildStdin>),
+    /// Swallows what's still written after the reader went away
+    Closed,
+}
+
+impl WordStream {
+    pub fn stdout() -> WordStream {
+        WordStream::Stdout(BufWriter::with_capacity(64 * 1024, io::stdout().lock()))
+    }
+}
+
+impl Write for WordStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            WordStream::Stdout(stdout) => stdout.write(buf),
+            WordStream::Pipe(stdin) => stdin.write(buf),
+            WordStream::Closed => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            WordStream::Stdout(stdout) => stdout.flush(),
+            WordStream::Pipe(stdin) => stdin.flush(),
+            WordStream::Closed => Ok(()),
+        }
+    }
+}
+
+/// Start the `--pipe-to` command, split into arguments like a shell would
+/// but without expanding anything, with its stdin ready for the words.
+pub fn spawn_pipe(command: &str) -> io::Result<(Child, WordStream)> {
+    let words =
+        shell_words::split(command).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let Some((program, args)) = words.split_first() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "it's empty"));
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    let stdin = child.stdin.take().expect("stdin was piped");
+    Ok((child, WordStream::Pipe(BufWriter::new(stdin))))
+}
+
+/// The exit code a shell would give for `status`, 128 plus the signal for
+/// a command that was killed.
+pub fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return 128 + signal;
+    }
+    status.code().unwrap_or(1)
+}
+
+/// Set by the first Ctrl+C, so that the output is finished and the
+/// statistics printed before gorilla exits.
+pub static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_: libc::c_int) {
+    if INTERRUPTED.swap(true, Ordering::SeqCst) {
+        // a second Ctrl+C, gorilla may be stuck waiting for input
+        unsafe { libc::_exit(130) };
+    }
+}
+
+/// Catch Ctrl+C, setting `INTERRUPTED` instead of exiting right away. On
+/// unix, a read it comes in the middle of isn't restarted, so that gorilla
+/// waiting for a line of stdin stops without it.
+pub fn handle_interrupts() {
+    #[cfg(unix)]
+    unsafe {
+        // without SA_RESTART
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGINT, &action, std::ptr::null_mut());
+    }
+    #[cfg(windows)]
+    let _ = ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+    });
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
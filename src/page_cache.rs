@@ -0,0 +1,181 @@
+use crate::website_scraper::Page;
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime},
+};
+
+/// Pages downloaded earlier, stored in a directory so that tuning a run
+/// doesn't download the same pages over and over.
+///
+/// Every page is stored once, under a hash of the URL it was finally
+/// served from. A URL that redirected somewhere else gets a small
+/// `.redirect` entry pointing at that final URL instead of its own copy.
+#[derive(Clone, Debug)]
+pub struct PageCache {
+    pub dir: PathBuf,
+    /// Entries older than this are downloaded again
+    pub ttl: Duration,
+    /// Ignore existing entries, but still store what gets downloaded
+    pub refresh: bool,
+}
+
+/// Used to give every temporary file a unique name, so that workers
+/// storing the same page at the same time don't trip over each other.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// FNV-1a, which unlike `DefaultHasher` gives the same hash for a URL no
+/// matter which version of Rust gorilla was built with.
+fn url_hash(url: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in url.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+impl PageCache {
+    fn entry_path(&self, url: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{}.{extension}", url_hash(url)))
+    }
+
+    fn is_fresh(&self, path: &Path) -> bool {
+        let Ok(modified) = fs::metadata(path).and_then(|m| m.modified()) else {
+            return false;
+        };
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        age <= self.ttl
+    }
+
+    /// Read an entry if it exists and is younger than the TTL.
+    fn read_fresh(&self, path: &Path) -> Option<String> {
+        if !self.is_fresh(path) {
+            return None;
+        }
+        fs::read_to_string(path).ok()
+    }
+
+    /// The URL `url` redirected to when it was stored, or `url` itself.
+    fn final_url(&self, url: &str) -> String {
+        match self.read_fresh(&self.entry_path(url, "redirect")) {
+            Some(target) => target,
+            None => url.to_owned(),
+        }
+    }
+
+    /// The cached page for `url`, following a redirect entry if there is
+    /// one. `None` when it isn't cached, has expired or `refresh` is set.
+    pub fn get(&self, url: &str) -> Option<Page> {
+        if self.refresh {
+            return None;
+        }
+
+        let final_url = self.final_url(url);
+
+        // first line is the URL (to rule out hash collisions), then the
+        // content type, then the body
+        let entry = self.read_fresh(&self.entry_path(&final_url, "page"))?;
+        let (stored_url, rest) = entry.split_once('\n')?;
+        let (content_type, body) = rest.split_once('\n')?;
+        if stored_url != final_url {
+            return None;
+        }
+
+        Some(Page {
+            body: body.to_owned(),
+            content_type: Some(content_type.to_owned()).filter(|t| !t.is_empty()),
+            from_cache: true,
+            cache_error: None,
+        })
+    }
+
+    /// Like `get`, but reading the body from the entry as it's needed
+    /// rather than all at once, for entries too large to keep in memory.
+    pub fn open(&self, url: &str) -> Option<BufReader<File>> {
+        if self.refresh {
+            return None;
+        }
+
+        let final_url = self.final_url(url);
+        let path = self.entry_path(&final_url, "page");
+        if !self.is_fresh(&path) {
+            return None;
+        }
+
+        let mut reader = BufReader::new(File::open(path).ok()?);
+        let mut stored_url = String::new();
+        reader.read_line(&mut stored_url).ok()?;
+        if stored_url.strip_suffix('\n') != Some(final_url.as_str()) {
+            return None;
+        }
+        reader.skip_until(b'\n').ok()?;
+
+        Some(reader)
+    }
+
+    /// Store a page downloaded from `url`, which ended up at `final_url`
+    /// after following redirects.
+    pub fn put(&self, url: &str, final_url: &str, page: &Page) -> io::Result<()> {
+        self.write_atomically(&self.entry_path(final_url, "page"), |file| {
+            write!(
+                file,
+                "{final_url}\n{}\n{}",
+                page.content_type.as_deref().unwrap_or(""),
+                page.body
+            )
+        })?;
+        self.put_redirect(url, final_url)
+    }
+
+    /// Like `put`, but copying the body from `body` as it's downloaded.
+    /// Nothing is stored if reading it fails partway.
+    pub fn put_reader(&self, url: &str, final_url: &str, body: &mut dyn Read) -> io::Result<()> {
+        self.write_atomically(&self.entry_path(final_url, "page"), |file| {
+            write!(file, "{final_url}\n\n")?;
+            io::copy(body, file).map(|_| ())
+        })?;
+        self.put_redirect(url, final_url)
+    }
+
+    fn put_redirect(&self, url: &str, final_url: &str) -> io::Result<()> {
+        if url != final_url {
+            self.write_atomically(&self.entry_path(url, "redirect"), |file| {
+                file.write_all(final_url.as_bytes())
+            })
+        } else {
+            // the URL may have redirected before, but doesn't anymore
+            match fs::remove_file(self.entry_path(url, "redirect")) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    /// Write to a temporary file first and rename it into place, so that
+    /// other workers never read a half-written entry.
+    fn write_atomically(
+        &self,
+        path: &Path,
+        write: impl FnOnce(&mut File) -> io::Result<()>,
+    ) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let temp_path = self.dir.join(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let written = File::create(&temp_path).and_then(|mut file| write(&mut file));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+        fs::rename(&temp_path, path)
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     fmt::{self, Display},
-    vec,
+    thread, vec,
 };
 
 use crate::char_sets;
@@ -24,7 +24,53 @@ impl Display for Token {
     }
 }
 
-pub fn tokenize_format_string(input: &str) -> Vec<Token> {
+/// A kind of token of patterns, as the man page describes it. Along with the
+/// `char_sets::CHAR_SETS` of `{l}` and the like, these are all the tokens
+/// `tokenize_format_string` knows.
+pub struct TokenSpec {
+    pub syntax: &'static str,
+    pub description: &'static str,
+    /// A pattern and every word it makes
+    pub example: (&'static str, &'static [&'static str]),
+}
+
+pub const TOKENS: &[TokenSpec] = &[
+    TokenSpec {
+        syntax: "text",
+        description: "anything outside of braces is itself in every word",
+        example: ("pw!", &["pw!"]),
+    },
+    TokenSpec {
+        syntax: "{X-Y}",
+        description: "every character from X to Y",
+        example: ("pw{a-c}", &["pwa", "pwb", "pwc"]),
+    },
+    TokenSpec {
+        syntax: "{N-M}",
+        description:
+            "every number from N to M, when there are 4 characters or more between the braces",
+        example: ("pw{8-10}", &["pw8", "pw9", "pw10"]),
+    },
+    TokenSpec {
+        syntax: "{l}, {u}, {d}, {s}",
+        description: "every lowercase letter, uppercase letter, digit or symbol",
+        example: ("{d}", &["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]),
+    },
+];
+
+/// Split a pattern into its tokens, or tell why it can't be.
+///
+/// ```
+/// use gorilla::patterns::{token_iterator, tokenize_format_string};
+///
+/// let tokens = tokenize_format_string("pw{8-10}")?;
+/// let words: Vec<String> = token_iterator(&tokens).collect();
+/// assert_eq!(words, ["pw8", "pw9", "pw10"]);
+///
+/// assert!(tokenize_format_string("pw{a-10}").is_err());
+/// # Ok::<(), String>(())
+/// ```
+pub fn tokenize_format_string(input: &str) -> Result<Vec<Token>, String> {
     let mut result: Vec<Token> = vec![];
     let mut inside_repeat = false;
 
@@ -39,29 +85,43 @@ pub fn tokenize_format_string(input: &str) -> Vec<Token> {
             inside_repeat = !inside_repeat;
             let inside_len = cur.chars().collect::<Vec<char>>().len();
             if inside_len >= 4 && cur.contains('-') {
-                let start_num = cur.split('-').nth(0).unwrap();
-                let end_num = cur.split('-').nth(1).unwrap();
-                result.push(Token::Numbers(
-                    start_num.parse::<u32>().unwrap(),
-                    end_num.parse::<u32>().unwrap(),
-                    0,
-                ))
+                let number = |number: &str| {
+                    number
+                        .parse::<u32>()
+                        .map_err(|_| format!("{{{cur}}} isn't a range of numbers"))
+                };
+                let (start_num, end_num) = cur.split_once('-').expect("it has a -");
+                let end_num = end_num.split('-').next().unwrap_or_default();
+                let (start, end) = (number(start_num)?, number(end_num)?);
+                if start > end {
+                    return Err(format!(
+                        "{{{cur}}} goes from a larger number to a smaller one"
+                    ));
+                }
+                result.push(Token::Numbers(start, end, 0))
             } else if inside_len > 2 {
-                let ch_start = cur.chars().next().unwrap();
-                let ch_end = cur.chars().nth(2).unwrap();
+                let ch_start = cur.chars().next().expect("it has 3 characters");
+                let ch_end = cur.chars().nth(2).expect("it has 3 characters");
+                // the surrogates in between aren't characters of their own
+                if ch_start > ch_end || (ch_start <= '\u{d7ff}' && ch_end >= '\u{e000}') {
+                    return Err(format!("{{{cur}}} isn't a range of characters"));
+                }
                 result.push(Token::Repeat(
                     ch_start as u32,
                     ch_end as u32,
                     ch_start as u32,
                 ));
             } else if inside_len == 1 {
-                result.push(match cur.chars().next().unwrap() {
-                    'l' => Token::CharSet(char_sets::L_CH.to_owned(), 0),
-                    'u' => Token::CharSet(char_sets::U_CH.to_owned(), 0),
-                    'd' => Token::CharSet(char_sets::D_CH.to_owned(), 0),
-                    's' => Token::CharSet(char_sets::S_CH.to_owned(), 0),
-                    _ => Token::String(String::from("")),
-                })
+                let letter = cur.chars().next().expect("it has a character");
+                result.push(
+                    match char_sets::CHAR_SETS
+                        .iter()
+                        .find(|(name, _)| *name == letter)
+                    {
+                        Some((_, chars)) => Token::CharSet((*chars).to_owned(), 0),
+                        None => Token::String(String::from("")),
+                    },
+                )
             }
             cur.clear();
         } else {
@@ -74,7 +134,7 @@ pub fn tokenize_format_string(input: &str) -> Vec<Token> {
 
     // for token in &result{ println!("(debug) tokenized: {}", token) }
 
-    result
+    Ok(result)
 }
 pub struct TokenIter {
     pub toks: Vec<Token>,
@@ -190,6 +250,37 @@ impl Iterator for TokenIter {
 }
 
 impl TokenIter {
+    /// Go to the word at `index`, as if `index` words had been taken. The
+    /// first token that varies changes fastest.
+    pub fn seek(&mut self, index: u128) {
+        self.done = index >= self.calculate_total();
+        if self.done {
+            return;
+        }
+
+        let mut rest = index;
+        for tok in &mut self.toks {
+            match tok {
+                Token::String(_) => {}
+                Token::Numbers(start, end, cur) => {
+                    let size = u128::from(*end - *start) + 1;
+                    *cur = (rest % size) as u32;
+                    rest /= size;
+                }
+                Token::Repeat(start, end, cur) => {
+                    let size = u128::from(*end - *start) + 1;
+                    *cur = *start + (rest % size) as u32;
+                    rest /= size;
+                }
+                Token::CharSet(ch_set, cur) => {
+                    let size = ch_set.len() as u128;
+                    *cur = (rest % size) as usize;
+                    rest /= size;
+                }
+            }
+        }
+    }
+
     pub fn calculate_total(&self) -> u128 {
         let mut result: u128 = 1;
 
@@ -225,3 +316,30 @@ impl TokenIter {
         sample_str.len() as u128 * self.calculate_total()
     }
 }
+
+/// The `count` words of the pattern from `start` on, generated in chunks
+/// on `threads` threads, the chunks in order.
+pub fn generate_in_parallel(
+    tokens: &[Token],
+    start: u128,
+    count: usize,
+    threads: usize,
+) -> Vec<Vec<String>> {
+    let chunk_size = count.div_ceil(threads.max(1)).max(1);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..count)
+            .step_by(chunk_size)
+            .map(|offset| {
+                scope.spawn(move || {
+                    let mut words = token_iterator(tokens);
+                    words.seek(start + offset as u128);
+                    words.take(chunk_size.min(count - offset)).collect()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("a pattern thread panicked"))
+            .collect()
+    })
+}
@@ -0,0 +1,129 @@
+use std::str::FromStr;
+
+/// `--phrase-input FILE:COUNT[:SEPARATOR]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhraseInput {
+    pub path: String,
+    pub count: u32,
+    pub separator: String,
+}
+
+impl FromStr for PhraseInput {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+        let path = parts.next().unwrap_or("");
+        let count = parts
+            .next()
+            .ok_or_else(|| String::from("expected FILE:COUNT[:SEPARATOR]"))?;
+        let count: u32 = count
+            .parse()
+            .map_err(|_| format!("invalid word count {count:?}"))?;
+
+        if path.is_empty() {
+            return Err(String::from("expected FILE:COUNT[:SEPARATOR]"));
+        }
+        if count == 0 {
+            return Err(String::from("the word count can't be 0"));
+        }
+
+        Ok(PhraseInput {
+            path: path.to_owned(),
+            count,
+            separator: parts.next().unwrap_or("").to_owned(),
+        })
+    }
+}
+
+/// Every ordered combination of `count` words from a list (the same word
+/// can come up more than once, like with diceware), numbered so that a run
+/// can start at any phrase.
+pub struct Phrases {
+    words: Vec<String>,
+    count: u32,
+    separator: String,
+}
+
+impl Phrases {
+    /// Duplicate words are dropped, they'd only repeat phrases.
+    pub fn new(words: Vec<String>, count: u32, separator: String) -> Phrases {
+        let mut unique: Vec<String> = Vec::new();
+        for word in words {
+            if !unique.contains(&word) {
+                unique.push(word);
+            }
+        }
+
+        Phrases {
+            words: unique,
+            count,
+            separator,
+        }
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    /// How many phrases there are, `None` if that doesn't even fit in a u128.
+    pub fn keyspace(&self) -> Option<u128> {
+        (self.words.len() as u128).checked_pow(self.count)
+    }
+
+    /// Size of all the phrases, one per line, in bytes.
+    pub fn calculate_size(&self) -> Option<u128> {
+        let n = self.words.len() as u128;
+        let word_bytes: u128 = self.words.iter().map(|w| w.len() as u128).sum();
+        let fixed_bytes = self.separator.len() as u128 * u128::from(self.count - 1) + 1;
+
+        // every word is in every position of n^(count - 1) phrases
+        let per_position = n.checked_pow(self.count - 1)?;
+        let words_size = word_bytes
+            .checked_mul(per_position)?
+            .checked_mul(u128::from(self.count))?;
+        words_size.checked_add(self.keyspace()?.checked_mul(fixed_bytes)?)
+    }
+
+    /// The phrases from number `skip` (counting from 0) on.
+    pub fn iter_from(&self, skip: u128) -> impl Iterator<Item = String> + '_ {
+        let n = self.words.len() as u128;
+        let mut finished = n == 0 || self.keyspace().is_some_and(|keyspace| skip >= keyspace);
+
+        // the index of the word in every position, last position first
+        let mut digits: Vec<usize> = Vec::new();
+        let mut rest = skip;
+        if !finished {
+            for _ in 0..self.count {
+                digits.push((rest % n) as usize);
+                rest /= n;
+            }
+        }
+
+        std::iter::from_fn(move || {
+            if finished {
+                return None;
+            }
+
+            let phrase = digits
+                .iter()
+                .rev()
+                .map(|&d| self.words[d].as_str())
+                .collect::<Vec<&str>>()
+                .join(&self.separator);
+
+            // count up, like an odometer
+            finished = true;
+            for digit in digits.iter_mut() {
+                *digit += 1;
+                if *digit < self.words.len() {
+                    finished = false;
+                    break;
+                }
+                *digit = 0;
+            }
+
+            Some(phrase)
+        })
+    }
+}
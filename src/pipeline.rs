@@ -0,0 +1,1139 @@
+use std::{
+    env,
+    fmt::Display,
+    io::{self, Write},
+    process::{self, Child},
+    time::{Duration, Instant, SystemTime},
+};
+
+use colored::Colorize;
+use rand::{rngs::StdRng, SeedableRng};
+
+#[cfg(feature = "scrape")]
+use crate::crawl_state::Crawl;
+use crate::{
+    arguments::ProgramArgs,
+    checkpoint::Checkpoint,
+    crack::Hashes,
+    dedup::{HyperLogLog, SeenWords},
+    error::GorillaError,
+    hybrid::Hybrid,
+    input::{is_url, InputFilter, InputLine, LineRef},
+    mutation::{perform_in_parallel, MutationResult, MutationSet},
+    output::{
+        self, hex_word, interrupted, LengthBuckets, OutputFormat, OutputPart, RollingOutput,
+        Unencodable, WordStream,
+    },
+    policy::Policy,
+    progress::{Progress, Timer},
+    report::WordReport,
+    sort::ExternalSort,
+    strength,
+    summary::{finish_run, print_trace},
+};
+
+/// How often the output file is flushed, at most
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many words before mutations the hybrid patterns can make before
+/// it's worth a warning
+pub const HYBRID_WARNING_WORDS: u128 = 1_000_000_000;
+
+/// Input words mutated at once by each of the `--threads`. Every one of
+/// them may give a lot of words, which are all kept until they're written.
+pub const BATCH_WORDS: usize = 512;
+
+/// How many words of a pattern every thread generates at once with
+/// `--threads`
+pub const PATTERN_CHUNK_WORDS: usize = 64 * 1024;
+
+/// How many of the words a mutation makes of a word `--explain` follows
+pub const EXPLAIN_WORDS: usize = 5;
+
+/// What `--dry-run` counted
+pub struct DryRun {
+    pub sample: Option<u64>,
+    /// Input words mutated, and left out once the sample was full
+    pub sampled: u64,
+    pub unsampled: u64,
+    /// Lines that would have been output as they are, with `--invalid-utf8 raw`
+    pub raw: (u64, u64),
+}
+
+/// What a mutation set contributed to the run
+#[derive(Clone, Copy, Default)]
+pub struct SetCounts {
+    /// Words it came up with, before `--exclude-output` and `--unique`
+    /// left any out
+    pub generated: u64,
+    /// Of those, the words written
+    pub written: u64,
+    /// Bytes of the written words, each with its delimiter
+    pub bytes: u64,
+}
+
+impl DryRun {
+    /// Whether the next input word is still part of the sample.
+    pub fn take_sample(&mut self) -> bool {
+        if self.sample.is_some_and(|sample| self.sampled >= sample) {
+            self.unsampled += 1;
+            return false;
+        }
+        self.sampled += 1;
+        true
+    }
+}
+
+/// Everything a run keeps track of, from its flags to the words it wrote
+pub struct Gorilla {
+    pub program_args: ProgramArgs,
+    pub mutation_sets: Vec<MutationSet>,
+    pub file_save: Option<RollingOutput>,
+    /// When `file_save` was last flushed
+    pub last_flush: Instant,
+    pub mutation_counter: u64,
+    pub word_counter: u64,
+    pub start_time: SystemTime,
+    pub output_separator: String,
+    /// Input words seen so far, with `--dedup-input`/`--dedup-approx`
+    pub seen_inputs: Option<SeenWords>,
+    pub duplicate_inputs: u64,
+    pub input_filter: InputFilter,
+    /// The `--seed` (or a random one) that `rng` was seeded with
+    pub seed: u64,
+    pub rng: StdRng,
+    /// Lines sampled and lines read, with `--input-sample`
+    pub input_sample: Option<(usize, u64)>,
+    /// The words of the `--exclude-file`s
+    pub blocklist: Option<SeenWords>,
+    pub excluded_inputs: u64,
+    pub excluded_outputs: u64,
+    /// The `--policy` and `--policy-file` policies, with how many generated
+    /// words each of them didn't allow
+    pub policies: Vec<(Policy, u64)>,
+    /// The patterns put around every input word, with `--prepend-pattern`
+    /// or `--append-pattern`
+    pub hybrid: Option<Hybrid>,
+    /// The hashes of `--crack` that weren't found yet
+    pub hashes: Option<Hashes>,
+    /// Generated words zxcvbn scored below `--min-strength`
+    pub weak_outputs: u64,
+    /// The scores of the words of the record being written, with
+    /// `--annotate-strength`
+    pub record_strengths: Vec<u8>,
+    /// The words output so far, with `--unique`/`--unique-approx`
+    pub unique_outputs: Option<SeenWords>,
+    pub duplicate_outputs: u64,
+    /// Counts the distinct words output, with `--estimate-unique`
+    pub distinct_outputs: Option<HyperLogLog>,
+    /// The output, held back until the end with `--sort`/`--sort-length`
+    pub sorter: Option<ExternalSort>,
+    pub progress: Option<Progress>,
+    /// Bytes written to stdout, for the progress display
+    pub stdout_bytes: u64,
+    /// Input words written with `--output-format` json/json-array/csv
+    pub records_written: u64,
+    pub dry_run: Option<DryRun>,
+    /// Words `--output-encoding` couldn't encode, left out or hex-wrapped
+    pub unencodable_words: u64,
+    /// Reused for every word written, with its delimiter
+    pub line: Vec<u8>,
+    /// Input lines waiting to be mutated with `--threads`
+    pub batch: Vec<(u64, InputLine)>,
+    /// Counted for every mutation set, in the same order
+    pub set_counts: Vec<SetCounts>,
+    /// How far the run got, with `--checkpoint`
+    pub checkpoint: Option<Checkpoint>,
+    /// How long the output file already was when the run was resumed
+    pub checkpoint_offset: u64,
+    /// The input line being mutated, counting the lines of every input
+    pub current_line: u64,
+    /// The wordlists read, after expanding directories and globs
+    pub input_paths: Vec<String>,
+    /// Raw scraped words written to `--scrape-save`
+    pub scrape_saved: u64,
+    /// Input lines that weren't valid UTF-8
+    pub invalid_lines: usize,
+    /// The first and the last input line processed, with `--input-skip`
+    /// or `--input-count`
+    pub input_range: Option<(u64, u64)>,
+    /// The `--max-words`/`--max-output-bytes` limit that ended the run
+    pub stopped_by: Option<String>,
+    /// The files of `--output-by-length`
+    pub length_buckets: Option<LengthBuckets>,
+    /// Words written per length, once they're finished
+    pub words_per_length: Vec<(usize, u64)>,
+    /// The lengths and characters of the words written, for `--report`
+    pub report: Option<WordReport>,
+    /// Where words go when they aren't written to a file, or along with it
+    pub stdout: WordStream,
+    /// The `--pipe-to` command, until it's waited for
+    pub pipe: Option<Child>,
+    /// The exit code of the `--pipe-to` command
+    pub pipe_status: Option<i32>,
+    /// Inputs that couldn't be read, deciding the exit code
+    pub failed_inputs: Vec<GorillaError>,
+    /// Set once the run is being given up on
+    pub giving_up: bool,
+    /// Input words `--explain` was shown for
+    pub explained: u64,
+    pub timer: Option<Timer>,
+}
+
+impl Gorilla {
+    /// A run of `program_args`, with nothing done yet
+    pub fn new(program_args: ProgramArgs) -> Gorilla {
+        Gorilla {
+            program_args,
+            mutation_sets: vec![],
+            file_save: None,
+            last_flush: Instant::now(),
+            mutation_counter: 0,
+            word_counter: 0,
+            start_time: SystemTime::now(),
+            output_separator: String::from('\n'),
+            seen_inputs: None,
+            duplicate_inputs: 0,
+            input_filter: InputFilter::default(),
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
+            input_sample: None,
+            blocklist: None,
+            excluded_inputs: 0,
+            excluded_outputs: 0,
+            policies: vec![],
+            hybrid: None,
+            hashes: None,
+            weak_outputs: 0,
+            record_strengths: vec![],
+            unique_outputs: None,
+            duplicate_outputs: 0,
+            distinct_outputs: None,
+            sorter: None,
+            progress: None,
+            stdout_bytes: 0,
+            records_written: 0,
+            dry_run: None,
+            unencodable_words: 0,
+            line: vec![],
+            batch: vec![],
+            set_counts: vec![],
+            checkpoint: None,
+            checkpoint_offset: 0,
+            current_line: 0,
+            input_paths: vec![],
+            scrape_saved: 0,
+            invalid_lines: 0,
+            input_range: None,
+            stopped_by: None,
+            length_buckets: None,
+            words_per_length: vec![],
+            report: None,
+            stdout: WordStream::stdout(),
+            pipe: None,
+            pipe_status: None,
+            failed_inputs: vec![],
+            giving_up: false,
+            explained: 0,
+            timer: None,
+        }
+    }
+
+    /// Output a line that can't be mutated as it is, for `--invalid-utf8 raw`.
+    pub fn output_raw(&mut self, line: &[u8]) {
+        self.tick_progress();
+
+        if !self.complies(&String::from_utf8_lossy(line)) {
+            return;
+        }
+        let strength = self.strength_of(&String::from_utf8_lossy(line), None);
+        if self.too_weak(strength) {
+            return;
+        }
+        if let Some(unique_outputs) = &mut self.unique_outputs {
+            if !unique_outputs.insert(&String::from_utf8_lossy(line)) {
+                self.duplicate_outputs += 1;
+                return;
+            }
+        }
+        if let Some(distinct_outputs) = &mut self.distinct_outputs {
+            distinct_outputs.insert(&String::from_utf8_lossy(line));
+        }
+
+        if let Some(dry_run) = &mut self.dry_run {
+            if dry_run.take_sample() {
+                self.word_counter += 1;
+                self.mutation_counter += 1;
+                if let Some(report) = &mut self.report {
+                    report.add(&String::from_utf8_lossy(line));
+                }
+                dry_run.raw.0 += 1;
+                dry_run.raw.1 += (line.len() + self.output_separator.len()) as u64;
+            }
+            return;
+        }
+
+        self.word_counter += 1;
+        self.mutation_counter += 1;
+        if let Some(report) = &mut self.report {
+            report.add(&String::from_utf8_lossy(line));
+        }
+
+        if self.hashes.is_some() {
+            self.crack(&String::from_utf8_lossy(line));
+            if !self.writes_candidates() {
+                self.stop_at_limit();
+                return;
+            }
+        }
+
+        if self.program_args.output_format != OutputFormat::Text {
+            let word = String::from_utf8_lossy(line).into_owned();
+            self.record_strengths.extend(strength);
+            self.output_record(&MutationResult {
+                original_word: word.clone(),
+                mutated_words: vec![word],
+                mutation_set: 0,
+            });
+        } else if let Some(sorter) = &mut self.sorter {
+            if let Err(e) = sorter.push(line) {
+                self.sort_failed(e);
+            }
+        } else if let Some(buckets) = &mut self.length_buckets {
+            if let Err(e) = buckets.write_word(line) {
+                self.file_failed(e);
+            }
+        } else if let Some(save_file) = &mut self.file_save {
+            let line = [line, self.output_separator.as_bytes()].concat();
+            if let Err(e) = save_file.write_all(&line) {
+                self.file_failed(e);
+            }
+            self.tee(&line);
+        } else {
+            let line = [line, self.output_separator.as_bytes()].concat();
+            if let Err(e) = self.stdout.write_all(&line) {
+                self.stdout_failed(e);
+            }
+            self.stdout_bytes += line.len() as u64;
+        }
+        self.flush_every();
+
+        self.stop_at_limit();
+    }
+
+    pub fn mutate_line(&mut self, line: LineRef) {
+        match line {
+            LineRef::Text(word) => self.mutate_word(word),
+            LineRef::Raw(bytes) if self.program_args.threads > 1 => {
+                self.queue(InputLine::Raw(bytes.to_vec()))
+            }
+            LineRef::Raw(bytes) => {
+                self.stop_if_interrupted();
+                self.output_raw(bytes)
+            }
+        }
+    }
+
+    /// End the run like a limit does on the first Ctrl+C.
+    pub fn stop_if_interrupted(&mut self) {
+        if interrupted() {
+            self.stopped_by = Some(String::from("Ctrl+C"));
+            finish_run(self);
+            process::exit(130);
+        }
+    }
+
+    /// Like `stop_if_interrupted`, saving the `--crawl-state` first
+    #[cfg(feature = "scrape")]
+    pub fn stop_crawl_if_interrupted(&mut self, crawl: &mut Option<Crawl>) {
+        if let (true, Some(crawl)) = (interrupted(), crawl) {
+            crawl.save_interrupted();
+        }
+        self.stop_if_interrupted();
+    }
+
+    pub fn mutate_word(&mut self, word: &str) {
+        self.tick_progress();
+
+        if !self.input_filter.allows(word) {
+            return;
+        }
+
+        if self
+            .blocklist
+            .as_ref()
+            .is_some_and(|blocklist| blocklist.contains(word))
+        {
+            self.excluded_inputs += 1;
+            return;
+        }
+
+        if let Some(seen_inputs) = &mut self.seen_inputs {
+            if !seen_inputs.insert(word) {
+                self.duplicate_inputs += 1;
+                return;
+            }
+        }
+
+        if let Some(hybrid) = self.hybrid.take() {
+            for hybrid_word in hybrid.words(word) {
+                self.mutate_allowed(&hybrid_word);
+            }
+            self.hybrid = Some(hybrid);
+            return;
+        }
+        self.mutate_allowed(word);
+    }
+
+    /// Mutate `word`, an input word the filters let through or one of the
+    /// words the hybrid patterns made of it
+    pub fn mutate_allowed(&mut self, word: &str) {
+        if self
+            .dry_run
+            .as_mut()
+            .is_some_and(|dry_run| !dry_run.take_sample())
+        {
+            return;
+        }
+
+        if self.program_args.explain && self.explained < self.program_args.explain_limit {
+            self.explained += 1;
+            self.explain(word);
+        }
+
+        if self.program_args.threads > 1 {
+            self.queue(InputLine::Text(word.to_owned()));
+            return;
+        }
+        self.stop_if_interrupted();
+        self.write_mutations(word, None, None);
+    }
+
+    /// Print what every mutation set makes of `word`, as a tree of the
+    /// words each mutation made of the ones before.
+    pub fn explain(&self, word: &str) {
+        eprintln!("gorilla: explaining {}", format!("{word:?}").yellow());
+        for mutation_set in &self.mutation_sets {
+            eprintln!("         set {}", mutation_set.id);
+            if let Some(trace) = mutation_set.explain(word, EXPLAIN_WORDS) {
+                print_trace(&trace, "         ");
+            }
+        }
+    }
+
+    /// Whether every `--policy` allows `word`, counting the ones that don't
+    pub fn complies(&mut self, word: &str) -> bool {
+        let mut complies = true;
+        for (policy, rejected) in &mut self.policies {
+            if !policy.allows(word) {
+                *rejected += 1;
+                complies = false;
+            }
+        }
+        complies
+    }
+
+    /// Print `hash:word` to stdout right away if `word` is one of the
+    /// hashes of `--crack` that's left
+    pub fn crack(&mut self, word: &str) {
+        let Some(hash) = self.hashes.as_mut().and_then(|hashes| hashes.check(word)) else {
+            return;
+        };
+        let found = format!("{hash}:{word}\n");
+        if let Err(e) = self
+            .stdout
+            .write_all(found.as_bytes())
+            .and_then(|()| self.stdout.flush())
+        {
+            self.stdout_failed(e);
+        }
+        self.stdout_bytes += found.len() as u64;
+    }
+
+    /// Whether the generated words are written with `--crack`, which has
+    /// stdout for the hashes it finds
+    pub fn writes_candidates(&self) -> bool {
+        self.hashes.is_none() || self.file_save.is_some() || self.length_buckets.is_some()
+    }
+
+    /// Whether words are scored, for `--min-strength` or
+    /// `--annotate-strength`
+    pub fn scores_strength(&self) -> bool {
+        self.program_args.min_strength.is_some() || self.program_args.annotate_strength
+    }
+
+    /// The zxcvbn score of `word` when words are scored, unless it was
+    /// `scored` on another thread already
+    pub fn strength_of(&self, word: &str, scored: Option<u8>) -> Option<u8> {
+        self.scores_strength()
+            .then(|| scored.unwrap_or_else(|| strength::score(word)))
+    }
+
+    /// Whether a word with the `strength` is left out by `--min-strength`,
+    /// counting it if it is
+    pub fn too_weak(&mut self, strength: Option<u8>) -> bool {
+        let too_weak = strength
+            .zip(self.program_args.min_strength)
+            .is_some_and(|(strength, min)| strength < min);
+        if too_weak {
+            self.weak_outputs += 1;
+        }
+        too_weak
+    }
+
+    /// Keep a line for the next batch of `--threads`, along with its line
+    /// number for the `--checkpoint`.
+    pub fn queue(&mut self, line: InputLine) {
+        self.batch.push((self.current_line, line));
+        if self.batch.len() >= BATCH_WORDS * self.program_args.threads {
+            self.mutate_batch();
+        }
+    }
+
+    /// Mutate the words of the batch on every thread at once, then write
+    /// them one after the other, in the order they were read.
+    pub fn mutate_batch(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+        let batch = std::mem::take(&mut self.batch);
+        let lines_read = self.current_line;
+
+        let words: Vec<&str> = batch
+            .iter()
+            .filter_map(|(_, line)| match line {
+                InputLine::Text(word) => Some(word.as_str()),
+                InputLine::Raw(_) => None,
+            })
+            .collect();
+        let threads = self.program_args.threads;
+        let results = perform_in_parallel(&self.mutation_sets, &words, threads);
+        let mut strengths = self
+            .scores_strength()
+            .then(|| strength::score_in_parallel(&results, threads).into_iter());
+        let mut results = results.into_iter();
+
+        for (line_number, line) in batch {
+            self.current_line = line_number;
+            self.stop_if_interrupted();
+            match line {
+                InputLine::Text(word) => {
+                    let strengths = strengths.as_mut().and_then(Iterator::next);
+                    self.write_mutations(&word, results.next(), strengths)
+                }
+                InputLine::Raw(bytes) => self.output_raw(&bytes),
+            }
+        }
+        self.current_line = lines_read;
+    }
+
+    /// Run every mutation set on `word` and write what they give, unless
+    /// `mutated` has their results already, and `strengths` the scores of
+    /// their words.
+    pub fn write_mutations(
+        &mut self,
+        word: &str,
+        mutated: Option<Vec<MutationResult>>,
+        strengths: Option<Vec<Vec<u8>>>,
+    ) {
+        let mut mutated = mutated.map(Vec::into_iter);
+        let mut strengths = strengths.map(Vec::into_iter);
+
+        self.word_counter += 1;
+
+        // every mutation set's words go into one record
+        let mut record: Vec<String> = Vec::new();
+
+        // out of `self` while they're used, so that its methods can be called
+        let mutation_sets = std::mem::take(&mut self.mutation_sets);
+        for mutation_set in &mutation_sets {
+            // a limit is only checked between mutation sets, so that the
+            // output doesn't depend on how the words of a set are written
+            if self.reached_limit().is_some() {
+                break;
+            }
+
+            // written as the set comes up with them, unless they were
+            // mutated on another thread already
+            match mutated.as_mut().and_then(Iterator::next) {
+                Some(result) => {
+                    let strengths = strengths.as_mut().and_then(Iterator::next);
+                    for (i, mutated_word) in result.mutated_words.iter().enumerate() {
+                        let strength = strengths.as_ref().map(|strengths| strengths[i]);
+                        self.output_word(result.mutation_set, mutated_word, strength, &mut record);
+                    }
+                }
+                None => mutation_set.perform_streaming(word, &mut |mutated_word| {
+                    self.output_word(mutation_set.id, mutated_word, None, &mut record)
+                }),
+            }
+        }
+
+        self.mutation_sets = mutation_sets;
+
+        if let Some(error) = self
+            .mutation_sets
+            .iter()
+            .find_map(MutationSet::plugin_error)
+        {
+            let error = GorillaError::Io(error.to_owned());
+            self.exit_with(error);
+        }
+
+        if self.program_args.output_format != OutputFormat::Text {
+            self.output_record(&MutationResult {
+                original_word: word.to_owned(),
+                mutated_words: record,
+                mutation_set: 0,
+            });
+        }
+        self.stop_at_limit();
+
+        // so that not much is lost if gorilla gets killed, and so that the
+        // words on stdout keep coming when the input words come slowly
+        if (self.word_counter.is_multiple_of(4096) || self.file_save.is_none())
+            && self.last_flush.elapsed() >= FLUSH_INTERVAL
+        {
+            self.flush();
+        }
+        self.tick_timer();
+    }
+
+    /// Write a word the mutation set with the `id` came up with, unless
+    /// it's left out. With `--output-format` json or csv it goes into the
+    /// record of its input word instead.
+    pub fn output_word(
+        &mut self,
+        mutation_set: usize,
+        word: &str,
+        strength: Option<u8>,
+        record: &mut Vec<String>,
+    ) {
+        self.set_counts[mutation_set - 1].generated += 1;
+
+        if self.program_args.exclude_output
+            && self
+                .blocklist
+                .as_ref()
+                .is_some_and(|blocklist| blocklist.contains(word))
+        {
+            self.excluded_outputs += 1;
+            return;
+        }
+
+        if !self.complies(word) {
+            return;
+        }
+        let strength = self.strength_of(word, strength);
+        if self.too_weak(strength) {
+            return;
+        }
+
+        if let Some(unique_outputs) = &mut self.unique_outputs {
+            if !unique_outputs.insert(word) {
+                self.duplicate_outputs += 1;
+                return;
+            }
+        }
+
+        let plain = word;
+        let encoding = self.program_args.output_encoding;
+        let hex;
+        let word = if encoding.can_encode(word) {
+            word
+        } else {
+            self.unencodable_words += 1;
+            match self.program_args.unencodable {
+                Unencodable::Skip => return,
+                Unencodable::Hex => {
+                    hex = hex_word(word.as_bytes());
+                    &hex
+                }
+            }
+        };
+        let encoded = encoding.encode(word.as_bytes());
+
+        let counts = &mut self.set_counts[mutation_set - 1];
+        counts.written += 1;
+        counts.bytes += (encoded.len() + self.output_separator.len()) as u64;
+
+        if let Some(distinct_outputs) = &mut self.distinct_outputs {
+            distinct_outputs.insert(word);
+        }
+        if let Some(report) = &mut self.report {
+            report.add(word);
+        }
+
+        self.mutation_counter += 1;
+        // for the words of an input word that makes a lot of them
+        if self.mutation_counter.is_multiple_of(1 << 16) {
+            self.tick_timer();
+        }
+
+        if self.dry_run.is_some() {
+            return;
+        }
+
+        if self.hashes.is_some() {
+            self.crack(plain);
+            if !self.writes_candidates() {
+                return;
+            }
+        }
+
+        if self.program_args.output_format != OutputFormat::Text {
+            record.push(word.to_owned());
+            self.record_strengths.extend(strength);
+            return;
+        }
+
+        if let Some(sorter) = &mut self.sorter {
+            if let Err(e) = sorter.push(word.as_bytes()) {
+                self.sort_failed(e);
+            }
+            return;
+        }
+
+        if let Some(buckets) = &mut self.length_buckets {
+            if let Err(e) = buckets.write_word(word.as_bytes()) {
+                self.file_failed(e);
+            }
+            return;
+        }
+
+        // in one piece, so that a split output never splits a word
+        let mut line = std::mem::take(&mut self.line);
+        line.clear();
+        line.extend_from_slice(&encoded);
+        line.extend_from_slice(self.output_separator.as_bytes());
+
+        if let Some(save_file) = &mut self.file_save {
+            if let Err(e) = save_file.write_all(&line) {
+                self.file_failed(e);
+            }
+            self.tee(&line);
+        } else {
+            if let Err(e) = self.stdout.write_all(&line) {
+                self.stdout_failed(e);
+            }
+            self.stdout_bytes += line.len() as u64;
+        }
+        self.line = line;
+        self.flush_every();
+
+        if self.program_args.timer_per_word {
+            eprintln!(
+                "(in {:?}) {word}",
+                SystemTime::now()
+                    .duration_since(self.start_time)
+                    .expect("time may have gone backwards")
+            );
+        }
+    }
+
+    /// Write to the output file or stdout as is.
+    pub fn output_text(&mut self, text: &[u8]) {
+        if let Some(save_file) = &mut self.file_save {
+            if let Err(e) = save_file.write_all(text) {
+                self.file_failed(e);
+            }
+            self.tee(text);
+        } else {
+            if let Err(e) = self.stdout.write_all(text) {
+                self.stdout_failed(e);
+            }
+            self.stdout_bytes += text.len() as u64;
+        }
+    }
+
+    /// With `--tee`, write what went to the output file to stdout as well.
+    pub fn tee(&mut self, text: &[u8]) {
+        if self.program_args.tee {
+            if let Err(e) = self.stdout.write_all(text) {
+                self.stdout_failed(e);
+            }
+        }
+    }
+
+    /// Write what comes before the first record of the `--output-format`.
+    pub fn start_records(&mut self) {
+        match self.program_args.output_format {
+            OutputFormat::JsonArray => self.output_text(b"["),
+            OutputFormat::Csv if self.program_args.annotate_strength => {
+                self.output_text(b"original,mutated,strength\n")
+            }
+            OutputFormat::Csv => self.output_text(b"original,mutated\n"),
+            OutputFormat::Text | OutputFormat::Json => {}
+        }
+    }
+
+    /// Write an input word and its mutations in the `--output-format`.
+    pub fn output_record(&mut self, result: &MutationResult) {
+        let strengths = std::mem::take(&mut self.record_strengths);
+        let annotate = self.program_args.annotate_strength;
+        let json = || match annotate {
+            true => strength::annotated_json(result, &strengths),
+            false => result.to_json(),
+        };
+        let record = match self.program_args.output_format {
+            OutputFormat::Json => format!("{}\n", json()),
+            OutputFormat::JsonArray if self.records_written == 0 => format!("\n{}", json()),
+            OutputFormat::JsonArray => format!(",\n{}", json()),
+            OutputFormat::Csv if annotate => strength::annotated_csv(result, &strengths),
+            OutputFormat::Csv => result.to_csv(),
+            OutputFormat::Text => unreachable!("text isn't written as records"),
+        };
+        self.output_text(record.as_bytes());
+        self.records_written += 1;
+    }
+
+    /// Finish writing the output, returning how many bytes were written
+    /// and the files they went to.
+    pub fn finish_output(&mut self) -> Option<(u64, Vec<OutputPart>)> {
+        if self.program_args.output_format == OutputFormat::JsonArray {
+            match self.records_written {
+                0 => self.output_text(b"]\n"),
+                _ => self.output_text(b"\n]\n"),
+            }
+        }
+        if let Some(sorter) = self.sorter.take() {
+            self.write_sorted(sorter);
+        }
+        // ending the line, the words are followed by spaces
+        if self.program_args.one_line && self.dry_run.is_none() {
+            self.output_text(b"\n");
+        }
+
+        if let Some(buckets) = self.length_buckets.take() {
+            let bytes = buckets.bytes;
+            return match buckets.finish() {
+                Ok(files) => {
+                    let (lengths, parts) = files
+                        .into_iter()
+                        .map(|(length, part)| ((length, part.lines), part))
+                        .unzip();
+                    self.words_per_length = lengths;
+                    Some((bytes, parts))
+                }
+                Err(e) => self.file_failed(e),
+            };
+        }
+
+        let output = self.file_save.take()?;
+        let bytes = output.bytes;
+        match output.finish() {
+            Ok(parts) => Some((bytes, parts)),
+            Err(e) => self.file_failed(e),
+        }
+    }
+
+    pub fn write_sorted(&mut self, sorter: ExternalSort) {
+        info!(
+            "gorilla: writing {} sorted words{}",
+            sorter.words.to_string().green(),
+            match sorter.run_count() {
+                0 => String::new(),
+                runs => format!(", merging {runs} temporary files"),
+            }
+        );
+
+        let separator = self.output_separator.as_bytes();
+        let encoding = self.program_args.output_encoding;
+        let stdout = &mut self.stdout;
+        let save_file = &mut self.file_save;
+        let mut tee = self.program_args.tee;
+        // errors writing the output, apart from errors reading the
+        // temporary files, with whether they were on stdout
+        let mut failed: Vec<(io::Error, bool)> = Vec::new();
+        let merged = sorter.finish(&mut |word| {
+            let line = [&encoding.encode(word), separator].concat();
+            let Some(save_file) = save_file else {
+                return stdout.write_all(&line).map_err(|e| {
+                    failed.push((e, true));
+                    io::Error::other("writing failed")
+                });
+            };
+            if let Err(e) = save_file.write_all(&line) {
+                failed.push((e, false));
+                return Err(io::Error::other("writing failed"));
+            }
+            if tee {
+                if let Err(e) = stdout.write_all(&line) {
+                    // the output file is still written to the end
+                    tee = false;
+                    failed.push((e, true));
+                }
+            }
+            Ok(())
+        });
+
+        for (e, to_stdout) in failed {
+            match to_stdout {
+                true => self.stdout_failed(e),
+                false => self.file_failed(e),
+            }
+        }
+        if let Err(e) = merged {
+            self.sort_failed(e);
+        }
+    }
+
+    /// The `--max-words` or `--max-output-bytes` limit, once it's reached.
+    pub fn reached_limit(&self) -> Option<String> {
+        let args = &self.program_args;
+        if let Some(max) = args.max_words.filter(|&max| self.mutation_counter >= max) {
+            return Some(format!("--max-words {max}"));
+        }
+        if let Some(max) = args
+            .max_output_bytes
+            .filter(|&max| self.bytes_written() >= max)
+        {
+            return Some(format!("--max-output-bytes {max}"));
+        }
+        if args.stop_on_all_found && self.hashes.as_ref().is_some_and(Hashes::all_found) {
+            return Some(String::from("--stop-on-all-found"));
+        }
+        None
+    }
+
+    /// End the run like it ended by itself if a limit is reached.
+    pub fn stop_at_limit(&mut self) {
+        if let Some(limit) = self.reached_limit() {
+            self.stopped_by = Some(limit);
+            finish_run(self);
+            process::exit(self.exit_code());
+        }
+    }
+
+    /// Record in the `--checkpoint` that the first `lines_done` input lines
+    /// are done, having written `output_bytes` in this run.
+    pub fn save_checkpoint(&mut self, lines_done: u64, output_bytes: u64) {
+        let (Some(checkpoint), Some(path)) = (&mut self.checkpoint, &self.program_args.checkpoint)
+        else {
+            return;
+        };
+
+        checkpoint.lines_done = lines_done;
+        checkpoint.output_bytes = self.checkpoint_offset + output_bytes;
+        if let Err(e) = checkpoint.save(path) {
+            info!(
+                "gorilla: (warning) could not save the checkpoint {}: {e}",
+                path.purple()
+            );
+        }
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        if let Some(buckets) = &self.length_buckets {
+            return buckets.bytes;
+        }
+        self.file_save
+            .as_ref()
+            .map_or(self.stdout_bytes, |save_file| save_file.bytes)
+    }
+
+    pub fn tick_timer(&mut self) {
+        if let Some(timer) = &mut self.timer {
+            timer.check(self.word_counter, self.mutation_counter);
+        }
+    }
+
+    pub fn tick_progress(&mut self) {
+        let bytes = self.bytes_written();
+        if let Some(progress) = &mut self.progress {
+            progress.tick(self.mutation_counter, bytes);
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(save_file) = &mut self.file_save {
+            let flushed = save_file.flush();
+            let bytes = save_file.bytes;
+            if let Err(e) = flushed {
+                self.file_failed(e);
+            }
+            self.save_checkpoint(self.current_line, bytes);
+        }
+        self.flush_stdout();
+        if let Some(buckets) = &mut self.length_buckets {
+            if let Err(e) = buckets.flush() {
+                self.file_failed(e);
+            }
+        }
+        self.last_flush = Instant::now();
+    }
+
+    /// With `--flush-every`, flush once that many words were written since
+    /// the last time.
+    pub fn flush_every(&mut self) {
+        if self
+            .program_args
+            .flush_every
+            .is_some_and(|every| self.mutation_counter.is_multiple_of(every))
+        {
+            self.flush();
+        }
+    }
+
+    /// Write out the words buffered for stdout or the `--pipe-to` command.
+    pub fn flush_stdout(&mut self) {
+        if let Err(e) = self.stdout.flush() {
+            self.stdout_failed(e);
+        }
+    }
+
+    pub fn file_failed(&mut self, error: io::Error) -> ! {
+        let args = &self.program_args;
+        let path = args
+            .file_save
+            .clone()
+            .or_else(|| args.output_by_length.clone())
+            .unwrap_or_default();
+        // finishing the files would only fail again
+        self.file_save = None;
+        self.length_buckets = None;
+        // a FIFO whose reader went away, like stdout's
+        if error.kind() == io::ErrorKind::BrokenPipe {
+            self.stopped_by = Some(String::from("--output-file"));
+            finish_run(self);
+            process::exit(self.exit_code());
+        }
+        self.exit_with(GorillaError::Io(format!(
+            "could not write to {}: {error}, it ends with the words written up to there",
+            path.purple()
+        )))
+    }
+
+    /// Handle a failed write to stdout, or to the `--pipe-to` command.
+    /// Only the reader going away lets the run go on, or end on its own.
+    pub fn stdout_failed(&mut self, error: io::Error) {
+        let piped = self.pipe.is_some();
+        if error.kind() == io::ErrorKind::BrokenPipe {
+            if self.program_args.tee {
+                self.program_args.tee = false;
+                self.stdout = WordStream::Closed;
+                match piped {
+                    true => info!("gorilla: (warning) the --pipe-to command stopped reading, only writing to the output file from here"),
+                    false => info!("gorilla: (warning) stdout was closed, only writing to the output file from here"),
+                }
+                return;
+            }
+            // like a command that cracked every hash and quit, or `head`
+            // once it has enough words, which isn't an error
+            self.stdout = WordStream::Closed;
+            self.stopped_by = Some(String::from(match piped {
+                true => "--pipe-to",
+                false => "stdout",
+            }));
+            finish_run(self);
+            process::exit(self.exit_code());
+        }
+
+        self.exit_with(GorillaError::Io(match piped {
+            true => format!("could not write to the --pipe-to command: {error}"),
+            false => format!("could not write to stdout: {error}"),
+        }));
+    }
+
+    /// Close the stdin of the `--pipe-to` command and wait for it to exit,
+    /// returning its exit code.
+    pub fn finish_pipe(&mut self) -> Option<i32> {
+        let mut child = self.pipe.take()?;
+        if let Err(e) = self.stdout.flush() {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                eprintln!("gorilla: (error) could not write to the --pipe-to command: {e}");
+            }
+        }
+        self.stdout = WordStream::Closed;
+
+        match child.wait() {
+            Ok(status) => Some(output::exit_code(status)),
+            Err(e) => {
+                eprintln!("gorilla: (error) could not wait for the --pipe-to command: {e}");
+                Some(1)
+            }
+        }
+    }
+
+    pub fn sort_failed(&mut self, error: io::Error) -> ! {
+        let temp_dir = self
+            .program_args
+            .sort_temp_dir
+            .clone()
+            .unwrap_or_else(|| env::temp_dir().display().to_string());
+        self.sorter = None;
+        self.exit_with(GorillaError::Io(format!(
+            "could not sort the output with temporary files in {}: {error}",
+            temp_dir.purple()
+        )))
+    }
+
+    /// Report an input that couldn't be read. The run goes on with the
+    /// other inputs, but ends with the exit code of the first such error.
+    pub fn input_failed(&mut self, path: &str, error: impl Display) {
+        let message = format!("could not read {}: {error}", path.purple());
+        eprintln!("gorilla: (error) {message}");
+        self.failed_inputs.push(match is_url(path) {
+            true => GorillaError::Network(message),
+            false => GorillaError::Io(message),
+        });
+    }
+
+    /// Give up on the run with the exit code of `error`, after finishing
+    /// the output written so far so that it can still be used.
+    pub fn exit_with(&mut self, error: GorillaError) -> ! {
+        // an error while finishing the output exits right away
+        if self.giving_up {
+            eprintln!("gorilla: (error) {error}");
+            process::exit(error.exit_code());
+        }
+        self.giving_up = true;
+
+        if let Some(progress) = &self.progress {
+            progress.finish(self.mutation_counter, self.bytes_written());
+        }
+        eprintln!("gorilla: (error) {error}");
+
+        // unless the output file is what failed
+        let output = match (&self.file_save, &self.program_args.file_save) {
+            (None, Some(_)) => None,
+            _ => self.finish_output(),
+        };
+        match output {
+            Some((bytes, parts)) if bytes > 0 => {
+                let paths: Vec<String> = parts
+                    .iter()
+                    .map(|part| part.path.purple().to_string())
+                    .collect();
+                eprintln!(
+                    "         the {} bytes written before are kept in {}",
+                    bytes.to_string().green(),
+                    paths.join(", ")
+                );
+            }
+            _ if self.stdout_bytes > 0 => eprintln!(
+                "         {} bytes were written to stdout before",
+                self.stdout_bytes.to_string().green()
+            ),
+            _ => {}
+        }
+        // what's written isn't worth another error
+        let _ = self.stdout.flush();
+        self.finish_pipe();
+        if let (Some(_), Some(path)) = (&self.checkpoint, &self.program_args.checkpoint) {
+            eprintln!(
+                "         the run can be resumed from the checkpoint {}",
+                path.purple()
+            );
+        }
+        process::exit(error.exit_code())
+    }
+
+    /// The exit code of the first input that couldn't be read, or else
+    /// that of the `--pipe-to` command.
+    pub fn exit_code(&self) -> i32 {
+        self.failed_inputs
+            .first()
+            .map_or(self.pipe_status.unwrap_or(0), GorillaError::exit_code)
+    }
+}
@@ -0,0 +1,129 @@
+//! Password policies: the length, character classes and banned words a
+//! password has to comply with to be set, for leaving out the candidates
+//! that couldn't be anyone's password.
+
+/// The names of the built-in policies, for `Policy::preset`
+pub const PRESETS: [&str; 3] = ["ad-default", "nist", "pci"];
+
+/// A kind of character a policy can ask for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Lower,
+    Upper,
+    /// Either case
+    Letter,
+    Digit,
+    /// Anything that's none of the others, like `!` or a space
+    Special,
+}
+
+impl CharClass {
+    /// The four classes of `Policy::min_classes`
+    pub const BASIC: [CharClass; 4] = [
+        CharClass::Lower,
+        CharClass::Upper,
+        CharClass::Digit,
+        CharClass::Special,
+    ];
+
+    pub fn from_name(name: &str) -> Option<CharClass> {
+        match name {
+            "lower" => Some(CharClass::Lower),
+            "upper" => Some(CharClass::Upper),
+            "letter" => Some(CharClass::Letter),
+            "digit" => Some(CharClass::Digit),
+            "special" => Some(CharClass::Special),
+            _ => None,
+        }
+    }
+
+    pub fn matches(self, c: char) -> bool {
+        match self {
+            CharClass::Lower => c.is_lowercase(),
+            CharClass::Upper => c.is_uppercase(),
+            CharClass::Letter => c.is_alphabetic(),
+            CharClass::Digit => c.is_numeric(),
+            CharClass::Special => !c.is_alphanumeric(),
+        }
+    }
+}
+
+/// What a word needs to be a password under a policy. Lengths are counted
+/// in characters.
+///
+/// ```
+/// use gorilla::policy::Policy;
+///
+/// let policy = Policy::preset("ad-default").unwrap();
+/// assert!(policy.allows("Summer24"));
+/// assert!(!policy.allows("summer24"));
+/// assert!(!policy.allows("Sum24"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Policy {
+    pub name: String,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    /// Classes every word has characters of
+    pub required: Vec<CharClass>,
+    /// How many of the basic classes, lower, upper, digit and special, a
+    /// word has characters of at least
+    pub min_classes: usize,
+    /// Words that can't be in a password in any case, like the user name,
+    /// in lowercase
+    pub banned: Vec<String>,
+}
+
+impl Policy {
+    /// A built-in policy: `ad-default` for the complexity requirements of
+    /// Active Directory, `nist` for NIST SP 800-63B and `pci` for PCI DSS 4
+    pub fn preset(name: &str) -> Option<Policy> {
+        let policy = Policy {
+            name: name.to_owned(),
+            ..Policy::default()
+        };
+        match name {
+            "ad-default" => Some(Policy {
+                min_length: Some(7),
+                min_classes: 3,
+                ..policy
+            }),
+            "nist" => Some(Policy {
+                min_length: Some(8),
+                ..policy
+            }),
+            "pci" => Some(Policy {
+                min_length: Some(12),
+                required: vec![CharClass::Letter, CharClass::Digit, CharClass::Special],
+                ..policy
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn allows(&self, word: &str) -> bool {
+        let length = word.chars().count();
+        if self.min_length.is_some_and(|min| length < min)
+            || self.max_length.is_some_and(|max| length > max)
+        {
+            return false;
+        }
+        let has = |class: CharClass| word.chars().any(|c| class.matches(c));
+        if !self.required.iter().all(|&class| has(class)) {
+            return false;
+        }
+        if CharClass::BASIC
+            .into_iter()
+            .filter(|&class| has(class))
+            .count()
+            < self.min_classes
+        {
+            return false;
+        }
+        let lowercase = word.to_lowercase();
+        !self
+            .banned
+            .iter()
+            .any(|banned| lowercase.contains(banned.as_str()))
+    }
+}
@@ -0,0 +1,234 @@
+use colored::Colorize;
+use std::{
+    fs::{self, File, OpenOptions},
+    path::Path,
+    process,
+};
+
+#[cfg(feature = "scrape")]
+use crate::website_scraper::local_path;
+use crate::{
+    arguments::ProgramArgs,
+    error::GorillaError,
+    input::{expand_input, is_url, HttpClient},
+    patterns::tokenize_format_string,
+};
+
+/// Everything wrong with a run that can be told before it starts: the
+/// files it reads that are missing or unreadable, the mutations and
+/// formatting files that don't parse, and the output directories that
+/// can't be written to. With `client`, the URLs it downloads from are
+/// asked for too. All the problems are found, not only the first.
+pub fn check(args: &ProgramArgs, client: Option<&HttpClient>) -> Vec<GorillaError> {
+    let mut preflight = Preflight {
+        client,
+        problems: vec![],
+    };
+
+    if let Err(e) = crate::load::load_mutation_sets(&args.mutation_chains, None) {
+        preflight.problems.push(e);
+    }
+    if let Some(path) = &args.mutations_file {
+        if let Err(e) = crate::load::read_mutations_file(path) {
+            preflight.problems.push(e);
+        }
+    }
+    if let Some(pattern) = &args.pattern_input {
+        if let Err(e) = tokenize_format_string(pattern) {
+            preflight.problems.push(GorillaError::Config(format!(
+                "the pattern {} is invalid, {e}",
+                pattern.purple()
+            )));
+        }
+    }
+    if let Err(e) = crate::load::load_hybrid(args) {
+        preflight.problems.push(e);
+    }
+    if let Some(path) = &args.from_formatting {
+        if let Err(e) = crate::load::read_formatting(path) {
+            preflight.problems.push(e);
+        }
+    }
+    if let Some(path) = &args.profile {
+        if let Err(e) = crate::load::read_profile_file(path) {
+            preflight.problems.push(e);
+        }
+    }
+    for path in &args.policy_file {
+        if let Err(e) = crate::load::read_policy_file(path) {
+            preflight.problems.push(e);
+        }
+    }
+
+    for input in &args.file_input {
+        let files = expand_input(input, args.recursive);
+        if files.is_empty() {
+            preflight.problems.push(GorillaError::Io(format!(
+                "there are no wordlists in {}",
+                input.purple()
+            )));
+        }
+        for file in files.iter().filter(|file| *file != "-") {
+            preflight.source(file, "wordlist");
+        }
+    }
+    for path in &args.exclude_file {
+        preflight.source(path, "exclude file");
+    }
+    let read = [
+        (args.csv_input.as_ref(), "csv file"),
+        (args.jsonl_input.as_ref(), "json lines file"),
+        (
+            args.phrase_input.as_ref().map(|phrases| &phrases.path),
+            "phrase wordlist",
+        ),
+        (args.prince_input.as_ref(), "--prince wordlist"),
+        (args.csv.as_ref(), "answers"),
+        (args.website_list.as_ref(), "website list"),
+        (args.ca_cert.as_ref(), "--ca-cert certificates"),
+    ];
+    for (path, what) in read {
+        if let Some(path) = path {
+            preflight.source(path, what);
+        }
+    }
+
+    #[cfg(feature = "scrape")]
+    for page in args
+        .website_input
+        .iter()
+        .chain(&args.sitemap)
+        .chain(&args.feed)
+    {
+        match local_path(page) {
+            Some(path) if !path.exists() => preflight.problems.push(GorillaError::Io(format!(
+                "the page {} doesn't exist",
+                page.purple()
+            ))),
+            Some(_) => {}
+            None if is_url(page) => preflight.url(page),
+            None => preflight.problems.push(GorillaError::Io(format!(
+                "{} is neither a URL nor a file that exists",
+                page.purple()
+            ))),
+        }
+    }
+
+    // the directories of --output-by-length are made as they're needed
+    let written = [
+        (args.file_save.as_ref(), "output file", false),
+        (
+            args.output_by_length.as_ref(),
+            "--output-by-length files",
+            true,
+        ),
+        (args.stats_file.as_ref(), "--stats-file", false),
+        (args.checkpoint.as_ref(), "checkpoint", false),
+        (args.scrape_save.as_ref(), "--scrape-save file", false),
+        (args.email_file.as_ref(), "email file", false),
+        (
+            args.scrape_count_file.as_ref(),
+            "--scrape-count-file",
+            false,
+        ),
+    ];
+    for (path, what, made) in written {
+        if let Some(path) = path.filter(|_| !args.dry_run) {
+            preflight.destination(path, what, made);
+        }
+    }
+    if let Some(dir) = &args.sort_temp_dir {
+        preflight.directory(
+            Path::new(dir),
+            &format!("the --sort-temp-dir {}", dir.purple()),
+        );
+    }
+
+    preflight.problems
+}
+
+struct Preflight<'a> {
+    #[cfg_attr(not(feature = "scrape"), allow(dead_code))]
+    client: Option<&'a HttpClient>,
+    problems: Vec<GorillaError>,
+}
+
+impl Preflight<'_> {
+    /// A file or URL that's read
+    fn source(&mut self, path: &str, what: &str) {
+        if is_url(path) {
+            return self.url(path);
+        }
+        let opened = File::open(path).and_then(|file| match file.metadata()?.is_dir() {
+            true => Err(std::io::Error::other("it's a directory")),
+            false => Ok(()),
+        });
+        if let Err(e) = opened {
+            self.problems.push(GorillaError::Io(format!(
+                "could not read the {what} {}: {e}",
+                path.purple()
+            )));
+        }
+    }
+
+    /// A URL that's downloaded, when the network is checked. Only whether
+    /// it answers is asked, with a HEAD request.
+    #[cfg(feature = "scrape")]
+    fn url(&mut self, url: &str) {
+        let Some(client) = self.client else {
+            return;
+        };
+        match client.agent.head(url).call() {
+            Ok(_) => {}
+            // servers that don't take HEAD requests are there all the same
+            Err(ureq::Error::StatusCode(405 | 501)) => {}
+            Err(e) => self.problems.push(GorillaError::Network(format!(
+                "could not reach {}: {e}",
+                url.purple()
+            ))),
+        }
+    }
+
+    /// URLs were refused with the arguments without the `scrape` feature
+    #[cfg(not(feature = "scrape"))]
+    fn url(&mut self, _url: &str) {}
+
+    /// A file that's written, in a directory that has to be there unless
+    /// it's `made` by the run
+    fn destination(&mut self, path: &str, what: &str, made: bool) {
+        let parent = Path::new(path)
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let dir = match made {
+            true => parent
+                .ancestors()
+                .find(|dir| dir.exists())
+                .unwrap_or(Path::new(".")),
+            false => parent,
+        };
+        if !dir.exists() {
+            self.problems.push(GorillaError::Io(format!(
+                "the directory of the {what} {} doesn't exist",
+                path.purple()
+            )));
+            return;
+        }
+        self.directory(dir, &format!("the {what} {}", path.purple()));
+    }
+
+    /// A directory that files are made in, tried by making one
+    fn directory(&mut self, dir: &Path, of: &str) {
+        let probe = dir.join(format!(".gorilla-preflight-{}", process::id()));
+        let made = OpenOptions::new().write(true).create_new(true).open(&probe);
+        match made {
+            Ok(_) => {
+                let _ = fs::remove_file(&probe);
+            }
+            Err(e) => self.problems.push(GorillaError::Io(format!(
+                "can't write to {} for {of}: {e}",
+                dir.display().to_string().purple()
+            ))),
+        }
+    }
+}
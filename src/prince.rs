@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+
+/// The candidates of the PRINCE attack: every word made of 1 to
+/// `max_elements` words of a wordlist put together, shortest first, so
+/// that passwords of no known structure come up early. Of the same length,
+/// the chains of element lengths with the fewest words come first, like
+/// `4+4` before `2+6` when there are fewer words of 4 letters to combine.
+/// Lengths are counted in characters, and the candidates are numbered so
+/// that a run can start at any of them.
+pub struct Prince {
+    /// The distinct words of every length, those of length `n` at `n`
+    elements: Vec<Vec<String>>,
+    chains: Vec<Chain>,
+}
+
+/// The lengths of the elements of a candidate, in their order, like `2+6`
+struct Chain {
+    lengths: Vec<usize>,
+    /// How many candidates it makes, `None` if that doesn't fit in a u128
+    keyspace: Option<u128>,
+}
+
+impl Prince {
+    /// Duplicate and empty words are dropped, and so are the words longer
+    /// than `max_len`, which can't be an element of any candidate.
+    pub fn new(words: Vec<String>, min_len: usize, max_len: usize, max_elements: usize) -> Prince {
+        let mut elements: Vec<Vec<String>> = vec![vec![]; max_len + 1];
+        let mut seen = HashSet::new();
+        for word in words {
+            let length = word.chars().count();
+            if length == 0 || length > max_len || !seen.insert(word.clone()) {
+                continue;
+            }
+            elements[length].push(word);
+        }
+
+        let mut chains = vec![];
+        for length in min_len.max(1)..=max_len {
+            let mut of_length = vec![];
+            compose(&elements, length, max_elements, &mut vec![], &mut of_length);
+            of_length
+                .sort_by_key(|chain| (chain.keyspace.unwrap_or(u128::MAX), chain.lengths.len()));
+            chains.extend(of_length);
+        }
+
+        Prince { elements, chains }
+    }
+
+    pub fn element_count(&self) -> usize {
+        self.elements.iter().map(Vec::len).sum()
+    }
+
+    pub fn chain_count(&self) -> usize {
+        self.chains.len()
+    }
+
+    /// How many candidates there are, `None` if that doesn't even fit in a
+    /// u128.
+    pub fn keyspace(&self) -> Option<u128> {
+        self.chains
+            .iter()
+            .try_fold(0u128, |total, chain| total.checked_add(chain.keyspace?))
+    }
+
+    /// Size of all the candidates, one per line, in bytes.
+    pub fn calculate_size(&self) -> Option<u128> {
+        self.chains.iter().try_fold(0u128, |total, chain| {
+            let counts: Vec<u128> = chain
+                .lengths
+                .iter()
+                .map(|&length| self.elements[length].len() as u128)
+                .collect();
+            // every word is in its position of as many candidates as the
+            // other positions make together
+            let mut size = chain.keyspace?;
+            for (position, &length) in chain.lengths.iter().enumerate() {
+                let word_bytes: u128 = self.elements[length].iter().map(|w| w.len() as u128).sum();
+                let others = counts
+                    .iter()
+                    .enumerate()
+                    .filter(|&(other, _)| other != position)
+                    .try_fold(1u128, |product, (_, &count)| product.checked_mul(count))?;
+                size = size.checked_add(word_bytes.checked_mul(others)?)?;
+            }
+            total.checked_add(size)
+        })
+    }
+
+    /// The candidates from number `skip` (counting from 0) on.
+    pub fn iter_from(&self, skip: u128) -> impl Iterator<Item = String> + '_ {
+        // the chain the candidate is in, and where it is in the chain
+        let mut chain = 0;
+        let mut rest = skip;
+        while let Some(keyspace) = self.chains.get(chain).and_then(|chain| chain.keyspace) {
+            if rest < keyspace {
+                break;
+            }
+            rest -= keyspace;
+            chain += 1;
+        }
+
+        // the index of the word in every position, last position first
+        let mut digits: Vec<usize> = vec![];
+        if let Some(first) = self.chains.get(chain) {
+            for &length in first.lengths.iter().rev() {
+                let count = self.elements[length].len() as u128;
+                digits.push((rest % count) as usize);
+                rest /= count;
+            }
+        }
+
+        std::iter::from_fn(move || {
+            let lengths = &self.chains.get(chain)?.lengths;
+            let candidate: String = digits
+                .iter()
+                .rev()
+                .zip(lengths)
+                .map(|(&digit, &length)| self.elements[length][digit].as_str())
+                .collect();
+
+            // count up, like an odometer, and on to the next chain once
+            // this one's done
+            let mut finished = true;
+            for (digit, &length) in digits.iter_mut().zip(lengths.iter().rev()) {
+                *digit += 1;
+                if *digit < self.elements[length].len() {
+                    finished = false;
+                    break;
+                }
+                *digit = 0;
+            }
+            if finished {
+                chain += 1;
+                digits = self
+                    .chains
+                    .get(chain)
+                    .map_or_else(Vec::new, |next| vec![0; next.lengths.len()]);
+            }
+
+            Some(candidate)
+        })
+    }
+}
+
+/// Every chain of `length` with at most `max_elements` elements that there
+/// are words for, `lengths` being the start of it
+fn compose(
+    elements: &[Vec<String>],
+    length: usize,
+    max_elements: usize,
+    lengths: &mut Vec<usize>,
+    chains: &mut Vec<Chain>,
+) {
+    if length == 0 {
+        let keyspace = lengths.iter().try_fold(1u128, |product, &length| {
+            product.checked_mul(elements[length].len() as u128)
+        });
+        chains.push(Chain {
+            lengths: lengths.clone(),
+            keyspace,
+        });
+        return;
+    }
+    if lengths.len() == max_elements {
+        return;
+    }
+    for first in 1..=length {
+        if elements[first].is_empty() {
+            continue;
+        }
+        lengths.push(first);
+        compose(elements, length - first, max_elements, lengths, chains);
+        lengths.pop();
+    }
+}
@@ -0,0 +1,234 @@
+//! Targeted wordlists: the names, dates, pets and the like of a person put
+//! together the ways people make passwords of them, in the spirit of CUPP.
+
+use std::collections::HashSet;
+
+/// What goes between the parts of a candidate
+pub const SEPARATORS: [&str; 4] = ["", ".", "_", "-"];
+
+/// What people put after a word when a password needs a digit or a symbol
+const SUFFIXES: [&str; 4] = ["1", "12", "123", "!"];
+
+/// A date of a profile, like a birthday
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    /// A date written `YYYY-MM-DD`
+    pub fn parse(date: &str) -> Option<Date> {
+        let mut parts = date.splitn(3, '-');
+        let mut part = |digits: usize| {
+            parts
+                .next()
+                .filter(|part| part.len() == digits && part.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|part| part.parse::<u16>().ok())
+        };
+        let (year, month, day) = (part(4)?, part(2)?, part(2)?);
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        Some(Date {
+            year,
+            month: month as u8,
+            day: day as u8,
+        })
+    }
+
+    /// The ways the date is written in passwords: `1990`, `90`, `1204`,
+    /// `0412`, `120490`, `041290`, `12041990`, `04121990` and `19900412`
+    /// for the 12th of April 1990
+    pub fn numbers(self) -> Vec<String> {
+        let yyyy = format!("{:04}", self.year);
+        let yy = format!("{:02}", self.year % 100);
+        let mm = format!("{:02}", self.month);
+        let dd = format!("{:02}", self.day);
+        vec![
+            yyyy.clone(),
+            yy.clone(),
+            format!("{dd}{mm}"),
+            format!("{mm}{dd}"),
+            format!("{dd}{mm}{yy}"),
+            format!("{mm}{dd}{yy}"),
+            format!("{dd}{mm}{yyyy}"),
+            format!("{mm}{dd}{yyyy}"),
+            format!("{yyyy}{mm}{dd}"),
+        ]
+    }
+}
+
+/// What's known about the target of a wordlist. Names can have several
+/// words, like `Jane Doe`, which are used on their own and for initials.
+/// The other fields are used as a word each, without their spaces.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Profile {
+    /// The names of the target and of those close to them, the target's
+    /// first
+    pub names: Vec<String>,
+    pub nicknames: Vec<String>,
+    pub birth_dates: Vec<Date>,
+    pub pets: Vec<String>,
+    pub company: Vec<String>,
+    pub teams: Vec<String>,
+    /// Anything else that means something to the target
+    pub keywords: Vec<String>,
+}
+
+impl Profile {
+    /// The words of the profile, in lowercase, the target's names first
+    pub fn words(&self) -> Vec<String> {
+        let names = self.names.iter().flat_map(|name| name.split_whitespace());
+        let others = self
+            .nicknames
+            .iter()
+            .chain(&self.pets)
+            .chain(&self.company)
+            .chain(&self.teams)
+            .chain(&self.keywords)
+            .map(|word| word.as_str());
+        let mut seen = HashSet::new();
+        names
+            .map(str::to_owned)
+            .chain(others.map(|word| word.split_whitespace().collect()))
+            .map(|word: String| word.to_lowercase())
+            .filter(|word| !word.is_empty() && seen.insert(word.clone()))
+            .collect()
+    }
+
+    /// The initials of the names of more than one word, like `jd` for
+    /// `Jane Doe`
+    pub fn initials(&self) -> Vec<String> {
+        self.names
+            .iter()
+            .filter(|name| name.split_whitespace().count() > 1)
+            .map(|name| {
+                name.split_whitespace()
+                    .filter_map(|part| part.chars().next())
+                    .flat_map(char::to_lowercase)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Every candidate of the profile, without duplicates, the simplest
+    /// first: the words in lowercase and capitalized, the numbers of the
+    /// dates, the words with a number after or before them, the words with a
+    /// common suffix, two words together, and the initials with the company
+    /// and the numbers. All but the numbers before a word have each of the
+    /// `SEPARATORS` between their parts.
+    ///
+    /// ```
+    /// use gorilla::profile::{Date, Profile};
+    ///
+    /// let profile = Profile {
+    ///     names: vec![String::from("Jane Doe")],
+    ///     pets: vec![String::from("Rex")],
+    ///     birth_dates: vec![Date::parse("1990-04-12").unwrap()],
+    ///     ..Profile::default()
+    /// };
+    /// let candidates = profile.candidates();
+    /// assert!(candidates.contains(&String::from("Rex0412")));
+    /// assert!(candidates.contains(&String::from("jane.doe")));
+    /// assert!(candidates.contains(&String::from("jd1990")));
+    /// ```
+    pub fn candidates(&self) -> Vec<String> {
+        let words = self.words();
+        let forms: Vec<String> = words
+            .iter()
+            .flat_map(|word| [word.clone(), capitalize(word)])
+            .collect();
+        let numbers: Vec<String> = self
+            .birth_dates
+            .iter()
+            .flat_map(|date| date.numbers())
+            .collect();
+        let initials = self.initials();
+        let company: Vec<String> = self
+            .company
+            .iter()
+            .map(|company| {
+                company
+                    .split_whitespace()
+                    .collect::<String>()
+                    .to_lowercase()
+            })
+            .filter(|company| !company.is_empty())
+            .collect();
+
+        let mut candidates = Candidates::default();
+        for form in &forms {
+            candidates.push(form.clone());
+        }
+        for number in &numbers {
+            candidates.push(number.clone());
+        }
+        for form in &forms {
+            for number in &numbers {
+                for separator in SEPARATORS {
+                    candidates.push(format!("{form}{separator}{number}"));
+                }
+                candidates.push(format!("{number}{form}"));
+            }
+        }
+        for form in &forms {
+            for suffix in SUFFIXES {
+                candidates.push(format!("{form}{suffix}"));
+            }
+        }
+        for first in &words {
+            for second in words.iter().filter(|&second| second != first) {
+                for separator in SEPARATORS {
+                    candidates.push(format!("{first}{separator}{second}"));
+                    candidates.push(format!(
+                        "{}{separator}{}",
+                        capitalize(first),
+                        capitalize(second)
+                    ));
+                }
+            }
+        }
+        for initials in &initials {
+            let upper = initials.to_uppercase();
+            for company in &company {
+                for separator in SEPARATORS {
+                    candidates.push(format!("{initials}{separator}{company}"));
+                    candidates.push(format!("{upper}{separator}{}", capitalize(company)));
+                }
+            }
+            for number in &numbers {
+                for separator in SEPARATORS {
+                    candidates.push(format!("{initials}{separator}{number}"));
+                    candidates.push(format!("{upper}{separator}{number}"));
+                }
+            }
+        }
+        candidates.words
+    }
+}
+
+/// The candidates so far, each once
+#[derive(Default)]
+struct Candidates {
+    words: Vec<String>,
+    seen: HashSet<String>,
+}
+
+impl Candidates {
+    fn push(&mut self, word: String) {
+        if self.seen.insert(word.clone()) {
+            self.words.push(word);
+        }
+    }
+}
+
+/// `word` with its first letter in uppercase
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,138 @@
+use indicatif::{HumanBytes, HumanCount, ProgressBar, ProgressStyle};
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+/// The bar being shown, so that messages can be printed around it
+static BAR: OnceLock<ProgressBar> = OnceLock::new();
+
+/// Run `print` with the progress display cleared away, so that what it
+/// prints doesn't end up in the middle of it.
+pub fn suspend<R>(print: impl FnOnce() -> R) -> R {
+    match BAR.get() {
+        Some(bar) if !bar.is_finished() => bar.suspend(print),
+        _ => print(),
+    }
+}
+
+/// How often the progress fields are updated, in input words
+const UPDATE_EVERY: u64 = 1024;
+
+/// The progress display on stderr. It's redrawn by a timer of its own, the
+/// counting done while generating is only copied over every `UPDATE_EVERY`
+/// words.
+pub struct Progress {
+    bar: ProgressBar,
+    /// Input words read so far, filtered out ones included
+    pub words: u64,
+}
+
+impl Progress {
+    /// Show the display, with a percentage and ETA when the number of input
+    /// words is known.
+    pub fn start(total: Option<u64>) -> Progress {
+        let (bar, template) = match total {
+            Some(total) => (
+                ProgressBar::new(total),
+                "{spinner} [{elapsed_precise}] {bar:30} {percent}% {human_pos}/{human_len} words -> {msg}, ETA {eta}",
+            ),
+            None => (
+                ProgressBar::new_spinner(),
+                "{spinner} [{elapsed_precise}] {human_pos} words -> {msg}",
+            ),
+        };
+        bar.set_style(
+            ProgressStyle::with_template(template)
+                .expect("invalid progress template")
+                .progress_chars("=> "),
+        );
+        bar.enable_steady_tick(Duration::from_millis(200));
+
+        let _ = BAR.set(bar.clone());
+        Progress { bar, words: 0 }
+    }
+
+    /// Count an input word, updating the display now and then.
+    pub fn tick(&mut self, generated: u64, bytes: u64) {
+        self.words += 1;
+        if self.words.is_multiple_of(UPDATE_EVERY) {
+            self.update(generated, bytes);
+        }
+    }
+
+    fn update(&self, generated: u64, bytes: u64) {
+        self.bar.set_position(self.words);
+        let seconds = self.bar.elapsed().as_secs_f64().max(0.001);
+        self.bar.set_message(format!(
+            "{} generated ({}/s), {}",
+            HumanCount(generated),
+            HumanCount((generated as f64 / seconds) as u64),
+            HumanBytes(bytes)
+        ));
+    }
+
+    /// Leave the display at its final state.
+    pub fn finish(&self, generated: u64, bytes: u64) {
+        self.update(generated, bytes);
+        if self.bar.length().is_some() {
+            // lines skipped as invalid UTF-8 were counted in the total
+            self.bar.set_length(self.words);
+        }
+        self.bar.finish();
+        // the display stays, the summary goes below it
+        eprintln!();
+    }
+}
+
+/// The throughput reports of `--timer`, every `interval`. Only a look at
+/// the clock is left on the way of the words.
+pub struct Timer {
+    interval: Duration,
+    start: Instant,
+    last: Instant,
+    /// Words generated by the last report
+    last_generated: u64,
+}
+
+impl Timer {
+    pub fn new(interval: Duration) -> Timer {
+        let now = Instant::now();
+        Timer {
+            interval,
+            start: now,
+            last: now,
+            last_generated: 0,
+        }
+    }
+
+    /// Report the counts if the interval has passed since the last report.
+    pub fn check(&mut self, words: u64, generated: u64) {
+        if self.last.elapsed() >= self.interval {
+            eprintln!("gorilla: {}", self.report(Instant::now(), words, generated));
+        }
+    }
+
+    /// `[00:00:10] 1,024 words in, 96,000 out, 9,800/s now, 9,600/s on
+    /// average`, as of `now`
+    pub fn report(&mut self, now: Instant, words: u64, generated: u64) -> String {
+        let elapsed = now - self.start;
+        let since_last = (now - self.last).as_secs_f64().max(0.001);
+        let now_rate = (generated - self.last_generated) as f64 / since_last;
+        let average = generated as f64 / elapsed.as_secs_f64().max(0.001);
+        self.last = now;
+        self.last_generated = generated;
+
+        let seconds = elapsed.as_secs();
+        format!(
+            "[{:02}:{:02}:{:02}] {} words in, {} out, {}/s now, {}/s on average",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+            HumanCount(words),
+            HumanCount(generated),
+            HumanCount(now_rate as u64),
+            HumanCount(average as u64)
+        )
+    }
+}
@@ -0,0 +1,64 @@
+/// The random choices of the mutations that make them, like `scramble`
+/// and `chance`, seeded by the program that runs them. gorilla seeds it
+/// from the random number generator of the run, so that `--seed` repeats
+/// them.
+///
+/// A word gets the same choices whatever thread it's mutated on and
+/// whatever was mutated before it: they only depend on the seed, on where
+/// the mutation is in its set, and on the word.
+///
+/// ```
+/// use gorilla::{mutation::MutationSet, random::Random};
+///
+/// let mut set = MutationSet::new(&["scramble"])?;
+/// set.set_random(Random::new(42));
+/// let (first, _) = set.preview("password", 1);
+/// let (again, _) = set.preview("password", 1);
+/// assert_eq!(first, again);
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Random {
+    seed: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random { seed }
+    }
+
+    /// The choices of the `n`th of several users of the same seed, which
+    /// are different from those of the others
+    pub fn derive(&self, n: u64) -> Random {
+        Random::new(SplitMix64(self.seed ^ n.wrapping_mul(0x9e37_79b9_7f4a_7c15)).next_u64())
+    }
+
+    /// The numbers to make the choices about `word` with
+    pub fn for_word(&self, word: &str) -> SplitMix64 {
+        // FNV-1a, which is the same on every platform and version
+        let hash = word.bytes().fold(0xcbf2_9ce4_8422_2325, |hash: u64, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        SplitMix64(self.seed ^ hash)
+    }
+}
+
+/// A small generator of random numbers, good enough to shuffle and pick
+/// words with, but not for anything secret
+#[derive(Debug)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 to `n - 1`
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+}
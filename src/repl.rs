@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+
+use colored::Colorize;
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+use crate::{error::GorillaError, mutation::MutationSet};
+
+const HELP: &str = "\
+type a word to see what the mutation sets make of it, or one of
+  :load PATH   load a mutations file, in place of the one loaded before
+  :reload      load the mutations file again, after editing it
+  :sets        list the mutation sets, and which are off
+  :toggle N    turn mutation set N off, or back on
+  :split       show the words of every set on their own, or all together
+  :help        show this
+  :quit        leave (Ctrl+D does too)";
+
+/// The mutation sets `gorilla repl` tries words on
+pub struct Repl {
+    /// The sets of `-m` and `--set-m`, which stay when a file is loaded
+    command_line: Vec<MutationSet>,
+    /// The mutations file loaded last, and its sets
+    file: Option<String>,
+    from_file: Vec<MutationSet>,
+    /// The numbers of the sets turned off, from 1
+    off: HashSet<usize>,
+    /// Whether the words of every set are shown on their own
+    split: bool,
+}
+
+/// What a line typed at the prompt led to
+#[derive(Debug, PartialEq)]
+pub enum Reply {
+    /// Lines to print
+    Print(Vec<String>),
+    Quit,
+}
+
+impl Repl {
+    pub fn new(command_line: Vec<MutationSet>, file: Option<String>) -> Result<Repl, GorillaError> {
+        let from_file = match &file {
+            Some(path) => crate::load::read_mutations_file(path)?.mutation_sets,
+            None => vec![],
+        };
+        Ok(Repl {
+            command_line,
+            file,
+            from_file,
+            off: HashSet::new(),
+            split: false,
+        })
+    }
+
+    /// Every set with its number, from 1
+    fn sets(&self) -> impl Iterator<Item = (usize, &MutationSet)> {
+        (1..).zip(self.command_line.iter().chain(&self.from_file))
+    }
+
+    /// Mutate a word, or run one of the `:` commands.
+    pub fn handle(&mut self, line: &str) -> Result<Reply, String> {
+        let Some(command) = line.strip_prefix(':') else {
+            let lines = self.mutate(line);
+            if let Some(error) = self.sets().find_map(|(_, set)| set.plugin_error()) {
+                return Err(error.to_owned());
+            }
+            return Ok(Reply::Print(lines));
+        };
+        let (command, argument) = command
+            .trim()
+            .split_once(' ')
+            .map_or((command.trim(), ""), |(command, argument)| {
+                (command, argument.trim())
+            });
+
+        match command {
+            "quit" | "q" => return Ok(Reply::Quit),
+            "help" => return Ok(Reply::Print(vec![String::from(HELP)])),
+            "load" if argument.is_empty() => return Err(String::from(":load needs a PATH")),
+            "load" => self.load(argument.to_owned())?,
+            "reload" => match self.file.clone() {
+                Some(path) => self.load(path)?,
+                None => return Err(String::from("no mutations file was loaded yet")),
+            },
+            "sets" => {}
+            "toggle" => {
+                let number: usize = argument
+                    .parse()
+                    .ok()
+                    .filter(|&number| number >= 1 && number <= self.sets().count())
+                    .ok_or_else(|| format!("there's no mutation set {argument}"))?;
+                if !self.off.remove(&number) {
+                    self.off.insert(number);
+                }
+            }
+            "split" => {
+                self.split = !self.split;
+                let shown = if self.split {
+                    "on their own"
+                } else {
+                    "together"
+                };
+                return Ok(Reply::Print(vec![format!(
+                    "the words of the sets are shown {shown}"
+                )]));
+            }
+            _ => return Err(format!("unknown command :{command}, see :help")),
+        }
+        Ok(Reply::Print(self.list_sets()))
+    }
+
+    fn load(&mut self, path: String) -> Result<(), String> {
+        self.from_file = crate::load::read_mutations_file(&path)
+            .map_err(|e| e.to_string())?
+            .mutation_sets;
+        self.file = Some(path);
+        self.off.clear();
+        Ok(())
+    }
+
+    fn list_sets(&self) -> Vec<String> {
+        let sets: Vec<String> = self
+            .sets()
+            .map(|(number, set)| {
+                let state = if self.off.contains(&number) {
+                    "off".red()
+                } else {
+                    "on".green()
+                };
+                format!("{number:>3} {state:<3}  {}", describe(set))
+            })
+            .collect();
+        if sets.is_empty() {
+            return vec![String::from("no mutation sets, :load a mutations file")];
+        }
+        sets
+    }
+
+    /// The words the sets that are on make of `word`
+    fn mutate(&self, word: &str) -> Vec<String> {
+        if self.sets().all(|(number, _)| self.off.contains(&number)) {
+            return vec![String::from("(no mutation set is on)")];
+        }
+        let mut lines = vec![];
+        for (number, set) in self.sets().filter(|(number, _)| !self.off.contains(number)) {
+            if self.split {
+                lines.push(
+                    format!("set {number}: {}", describe(set))
+                        .dimmed()
+                        .to_string(),
+                );
+                let start = lines.len();
+                set.perform_streaming(word, &mut |mutated| lines.push(format!("  {mutated}")));
+                if lines.len() == start {
+                    lines.push(String::from("  (nothing)"));
+                }
+            } else {
+                set.perform_streaming(word, &mut |mutated| lines.push(mutated.to_owned()));
+            }
+        }
+        lines
+    }
+}
+
+/// `word -> reverse -> append: 1`, like the mutation sets summary
+fn describe(set: &MutationSet) -> String {
+    let mut description = String::from("word");
+    for mutation in &set.mutations {
+        description.push_str(" -> ");
+        description.push_str(&mutation.to_string());
+    }
+    description
+}
+
+/// Read lines at a prompt until `:quit` or the end of the input.
+pub fn run(mut repl: Repl) -> Result<(), GorillaError> {
+    let mut editor = DefaultEditor::new()
+        .map_err(|e| GorillaError::Io(format!("could not start the prompt: {e}")))?;
+    info!(
+        "gorilla: {} mutation sets loaded, type a word to mutate it or {} for the commands",
+        repl.sets().count().to_string().green(),
+        ":help".yellow()
+    );
+
+    loop {
+        let line = match editor.readline("gorilla> ") {
+            Ok(line) => line,
+            // Ctrl+C drops the line typed so far
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(e) => return Err(GorillaError::Io(format!("could not read the prompt: {e}"))),
+        };
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(&line);
+
+        match repl.handle(&line) {
+            Ok(Reply::Print(lines)) => lines.iter().for_each(|line| println!("{line}")),
+            Ok(Reply::Quit) => return Ok(()),
+            Err(e) => eprintln!("gorilla: (error) {e}"),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use colored::Colorize;
+
+use crate::stats::{CompositionStats, LengthStats, ReportStats};
+
+/// What `--report` counts of the generated words, as they're written
+#[derive(Debug, Default)]
+pub struct WordReport {
+    /// The words of every length in characters, those of length `n` at `n`
+    lengths: Vec<u64>,
+    words: u64,
+    digits: u64,
+    lowercase: u64,
+    uppercase: u64,
+    /// ASCII characters that are neither letters nor digits, spaces included
+    specials: u64,
+    non_ascii: u64,
+}
+
+impl WordReport {
+    pub fn add(&mut self, word: &str) {
+        let length = word.chars().count();
+        if self.lengths.len() <= length {
+            self.lengths.resize(length + 1, 0);
+        }
+        self.lengths[length] += 1;
+        self.words += 1;
+
+        let has = |kind: fn(&char) -> bool| u64::from(word.chars().any(|c| kind(&c)));
+        self.digits += has(char::is_ascii_digit);
+        self.lowercase += has(|c| c.is_lowercase());
+        self.uppercase += has(|c| c.is_uppercase());
+        self.specials += has(|c| c.is_ascii() && !c.is_ascii_alphanumeric());
+        self.non_ascii += has(|c| !c.is_ascii());
+    }
+
+    /// The lengths there are words of, with how many there are
+    fn words_per_length(&self) -> Vec<(usize, u64)> {
+        self.lengths
+            .iter()
+            .enumerate()
+            .filter(|&(_, &words)| words > 0)
+            .map(|(length, &words)| (length, words))
+            .collect()
+    }
+
+    fn percent(&self, words: u64) -> f64 {
+        match self.words {
+            0 => 0.0,
+            all => (words as f64 * 1000.0 / all as f64).round() / 10.0,
+        }
+    }
+
+    fn composition(&self) -> CompositionStats {
+        CompositionStats {
+            digits: self.percent(self.digits),
+            lowercase: self.percent(self.lowercase),
+            uppercase: self.percent(self.uppercase),
+            specials: self.percent(self.specials),
+            non_ascii: self.percent(self.non_ascii),
+        }
+    }
+
+    pub fn stats(&self) -> ReportStats {
+        ReportStats {
+            lengths: self
+                .words_per_length()
+                .into_iter()
+                .map(|(length, words)| LengthStats { length, words })
+                .collect(),
+            composition: self.composition(),
+        }
+    }
+
+    pub fn print(&self) {
+        print_lengths(&self.words_per_length());
+        if self.words == 0 {
+            return;
+        }
+        let composition = self.composition();
+        eprintln!("gorilla: of the generated words,");
+        for (percent, what) in [
+            (composition.digits, "digits"),
+            (composition.lowercase, "lowercase letters"),
+            (composition.uppercase, "uppercase letters"),
+            (composition.specials, "special characters"),
+            (composition.non_ascii, "characters that aren't ASCII"),
+        ] {
+            eprintln!(
+                "         {} have {what}",
+                format!("{percent:>5.1}%").green()
+            );
+        }
+    }
+}
+
+/// Print how many words there are of every length, as a histogram
+pub fn print_lengths(words_per_length: &[(usize, u64)]) {
+    let Some(most) = words_per_length.iter().map(|&(_, words)| words).max() else {
+        return;
+    };
+    eprintln!("gorilla: words per length");
+    let width = most.to_string().len();
+    for (length, words) in words_per_length {
+        let bar = "#".repeat((words * 30).div_ceil(most) as usize);
+        eprintln!(
+            " {length:>3} -> {} {}",
+            format!("{words:>width$}").green(),
+            bar.dimmed()
+        );
+    }
+}
@@ -0,0 +1,880 @@
+use std::{
+    collections::HashSet,
+    env, fs,
+    io::{self, IsTerminal},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use colored::Colorize;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    arguments::{Commands, ProgramArgs},
+    build_info,
+    checkpoint::{Checkpoint, InputFingerprint},
+    csv_parser::{fmt_answers_from_csv, words_from_csv_columns},
+    dedup::{BloomFilter, HyperLogLog, SeenWords},
+    error::GorillaError,
+    formatting::FormatFieldAnswer,
+    input::{
+        display_name, expand_input, is_url, open_input, words_from_jsonl, HttpClient, InputFilter,
+    },
+    input_readers::{
+        interleave_inputs, mutate_pattern, read_blocklist, read_file_inputs, read_phrases,
+        read_prince,
+    },
+    load::{
+        check_files, load_hashes, load_hybrid, load_mutation_sets, load_policies, read_formatting,
+        read_profile_file,
+    },
+    mutation::MutationSet,
+    output::{
+        handle_interrupts, spawn_pipe, split_path, LengthBuckets, OpenMode, OutputCompression,
+        OutputFormat, RollingOutput, SplitLimits,
+    },
+    patterns::{token_iterator, tokenize_format_string, TokenIter},
+    pipeline::{DryRun, Gorilla, SetCounts, HYBRID_WARNING_WORDS},
+    preflight,
+    progress::{Progress, Timer},
+    random::Random,
+    report::WordReport,
+    sort::{ExternalSort, SortOrder},
+    summary::{finish_run, format_rate, known_total, preview_line, report_benchmark},
+};
+#[cfg(feature = "scrape")]
+use crate::{
+    page_cache::PageCache,
+    scraping::scrape_inputs,
+    website_scraper::{build_client, HttpOptions},
+};
+
+/// Everything the run of `gorilla mutate`, `generate`, `scrape` and `check`
+/// does, once the other commands were dispatched. Errors returned end it
+/// with their exit code.
+pub fn run(gorilla: &mut Gorilla) -> Result<(), GorillaError> {
+    // the first Ctrl+C ends the run like a limit does, with the output
+    // finished and the statistics printed
+    handle_interrupts();
+
+    if gorilla.program_args.command.is_none()
+        && !gorilla.program_args.benchmark
+        && env::args_os().len() > 1
+    {
+        info!(
+            "gorilla: (warning) flags without a command are deprecated, give them to {}, {} or {} instead",
+            "gorilla mutate".yellow(),
+            "gorilla generate".yellow(),
+            "gorilla scrape".yellow()
+        );
+    }
+
+    if gorilla.program_args.benchmark {
+        report_benchmark(gorilla.program_args.threads);
+        return Ok(());
+    }
+
+    let (client, input_client) = http_clients(&gorilla.program_args)?;
+
+    if gorilla.program_args.command != Some(Commands::Check) {
+        let network = gorilla.program_args.preflight_network.then_some(&client);
+        let mut problems = preflight::check(&gorilla.program_args, network);
+        if problems.len() > 1 {
+            for problem in &problems {
+                eprintln!("gorilla: (error) {problem}");
+            }
+            let first = problems.swap_remove(0);
+            let summary = format!(
+                "{} problems were found, nothing was generated",
+                problems.len() + 1
+            );
+            return Err(match first {
+                GorillaError::Config(_) => GorillaError::Config(summary),
+                GorillaError::Io(_) => GorillaError::Io(summary),
+                GorillaError::Network(_) => GorillaError::Network(summary),
+            });
+        } else if let Some(problem) = problems.pop() {
+            return Err(problem);
+        }
+    }
+
+    gorilla.seed = gorilla.program_args.seed.unwrap_or_else(rand::random);
+    gorilla.rng = StdRng::seed_from_u64(gorilla.seed);
+
+    gorilla.input_filter = InputFilter {
+        include: gorilla.program_args.input_filter.clone(),
+        exclude: gorilla.program_args.input_filter_not.clone(),
+        ..Default::default()
+    };
+
+    if let Some(capacity) = gorilla.program_args.dedup_approx {
+        let filter = BloomFilter::new(capacity);
+        info!(
+            "gorilla: deduplicating input words approximately, using {} KB of memory",
+            filter.size() / 1024
+        );
+        gorilla.seen_inputs = Some(SeenWords::Approx(filter));
+    } else if gorilla.program_args.dedup_input {
+        gorilla.seen_inputs = Some(SeenWords::Exact(HashSet::new()));
+    }
+
+    let sort_order = if gorilla.program_args.sort {
+        Some(SortOrder::Lexicographic)
+    } else if gorilla.program_args.sort_length {
+        Some(SortOrder::Length)
+    } else {
+        None
+    };
+    if let (Some(order), false) = (sort_order, gorilla.program_args.dry_run) {
+        let temp_dir = gorilla
+            .program_args
+            .sort_temp_dir
+            .as_ref()
+            .map_or_else(env::temp_dir, PathBuf::from);
+        let memory_cap = gorilla.program_args.sort_memory.saturating_mul(1024 * 1024);
+        gorilla.sorter = Some(ExternalSort::new(order, memory_cap as usize, temp_dir));
+        info!(
+            "gorilla: sorting the output, nothing is written until every word has been generated"
+        );
+    }
+
+    if let Some(capacity) = gorilla.program_args.unique_approx {
+        let rate = gorilla.program_args.unique_error_rate;
+        let filter = match gorilla.program_args.unique_mem {
+            Some(megabytes) => {
+                BloomFilter::with_memory(megabytes.saturating_mul(1024 * 1024), rate)
+            }
+            None => BloomFilter::with_rate(capacity, rate),
+        };
+        info!(
+            "gorilla: deduplicating the output approximately, using {} KB of memory",
+            filter.size() / 1024
+        );
+        info!(
+            "         enough for about {} distinct words at a {} false positive rate",
+            filter.capacity(rate).to_string().green(),
+            format_rate(rate).yellow()
+        );
+        gorilla.unique_outputs = Some(SeenWords::Approx(filter));
+    } else if gorilla.program_args.unique {
+        gorilla.unique_outputs = Some(SeenWords::Exact(HashSet::new()));
+    } else if gorilla.program_args.estimate_unique {
+        gorilla.distinct_outputs = Some(HyperLogLog::new());
+    }
+    if gorilla.program_args.report {
+        gorilla.report = Some(WordReport::default());
+    }
+
+    gorilla.output_separator = if gorilla.program_args.one_line {
+        String::from(' ')
+    } else {
+        gorilla.program_args.output_delimiter.clone()
+    };
+
+    gorilla.mutation_sets = load_mutation_sets(
+        &gorilla.program_args.mutation_chains,
+        gorilla.program_args.mutations_file.as_deref(),
+    )?;
+    let exec_timeout = Duration::from_secs(gorilla.program_args.exec_timeout);
+    // the random mutations take their choices from the one generator of
+    // the run too
+    let random = Random::new(gorilla.rng.gen());
+    for mutation_set in &mut gorilla.mutation_sets {
+        mutation_set.set_exec_timeout(exec_timeout);
+        mutation_set.set_random(random.derive(mutation_set.id as u64));
+    }
+    let random_sets = gorilla.mutation_sets.iter().any(MutationSet::is_random);
+    if gorilla.program_args.command != Some(Commands::Check)
+        && (random_sets || gorilla.program_args.input_sample.is_some())
+    {
+        match gorilla.program_args.seed {
+            Some(seed) => info!(
+                "gorilla: seeded the random choices with --seed {}",
+                seed.to_string().yellow()
+            ),
+            None => info!(
+                "gorilla: seeded the random choices with {}, --seed {} repeats them",
+                gorilla.seed.to_string().yellow(),
+                gorilla.seed
+            ),
+        }
+    }
+    if gorilla.program_args.annotate_strength
+        && gorilla.program_args.output_format == OutputFormat::Text
+    {
+        return Err(GorillaError::Config(String::from(
+            "--annotate-strength needs an --output-format of json, json-array or csv to put the scores in",
+        )));
+    }
+    gorilla.policies = load_policies(&gorilla.program_args)?
+        .into_iter()
+        .map(|policy| (policy, 0))
+        .collect();
+    gorilla.hybrid = load_hybrid(&gorilla.program_args)?;
+    gorilla.hashes = load_hashes(&gorilla.program_args)?;
+    let profile = match &gorilla.program_args.profile {
+        Some(path) => Some(read_profile_file(path)?),
+        None => None,
+    };
+    if gorilla.mutation_sets.is_empty() {
+        if gorilla.program_args.command != Some(Commands::Check) {
+            info!("gorilla: (warning) missing mutation sets");
+        }
+        gorilla.mutation_sets.push(MutationSet::empty_set())
+    } else {
+        info!("gorilla: mutation sets summary");
+        for mutation_set in &gorilla.mutation_sets {
+            let mut line = format!(" {}", "word".dimmed());
+            for mutation in &mutation_set.mutations {
+                line.push_str(&format!(" -> {}", mutation.to_string().blue()));
+            }
+            info!("{line}");
+            if let Some(word) = &gorilla.program_args.preview {
+                let limit = gorilla.program_args.preview_limit as usize;
+                info!("{}", preview_line(mutation_set, word, limit));
+            }
+        }
+    }
+
+    gorilla.set_counts = vec![SetCounts::default(); gorilla.mutation_sets.len()];
+
+    if gorilla.program_args.command == Some(Commands::Check) {
+        return check_files(gorilla);
+    }
+
+    if gorilla.program_args.dry_run {
+        info!(
+            "gorilla: {}, counting the words instead of writing them",
+            "dry run".yellow().bold()
+        );
+        gorilla.dry_run = Some(DryRun {
+            sample: gorilla.program_args.dry_run_sample,
+            sampled: 0,
+            unsampled: 0,
+            raw: (0, 0),
+        });
+    }
+
+    if gorilla.program_args.output_header
+        && matches!(
+            gorilla.program_args.output_format,
+            OutputFormat::Json | OutputFormat::JsonArray
+        )
+    {
+        return Err(GorillaError::Config(String::from(
+            "--output-header would make the json invalid, the --stats-file has the same details",
+        )));
+    }
+
+    let resuming = gorilla.program_args.checkpoint.is_some() && start_checkpoint(gorilla)?;
+
+    if let Some(formatting_path) = &gorilla.program_args.from_formatting {
+        let fmt_sets = read_formatting(formatting_path)?;
+        info!(
+            "gorilla: loading {} formatting sets",
+            fmt_sets.name.purple()
+        );
+
+        if let Some(csv_path) = &gorilla.program_args.csv {
+            let answer_sets = fmt_answers_from_csv(csv_path).map_err(|e| match e.kind() {
+                csv::ErrorKind::Io(_) => GorillaError::Io(format!(
+                    "could not read the answers {}: {e}",
+                    csv_path.purple()
+                )),
+                _ => GorillaError::Config(format!(
+                    "the answers {} are invalid, {e}",
+                    csv_path.purple()
+                )),
+            })?;
+            let Some(first_answers) = answer_sets.first() else {
+                return Err(GorillaError::Config(format!(
+                    "the answers {} have no rows",
+                    csv_path.purple()
+                )));
+            };
+            fmt_sets.check_answer_names(first_answers).map_err(|e| {
+                GorillaError::Config(format!("the answers {} have an {e}", csv_path.purple()))
+            })?;
+
+            for fmt_answers in answer_sets {
+                for gen_word in fmt_sets.generate_words(fmt_answers) {
+                    gorilla.mutate_word(&gen_word);
+                }
+            }
+        } else {
+            let mut fmt_answers: Vec<FormatFieldAnswer> = Vec::new();
+
+            for q in &fmt_sets.fields {
+                let mut buffer = String::new();
+
+                if let Some(question) = &q.question {
+                    eprint!("(?) {}: ", question.blue())
+                } else {
+                    eprint!("(?) Fill in {}: ", q.name.blue())
+                }
+                gorilla.flush_stdout();
+                io::stdin().read_line(&mut buffer).map_err(|e| {
+                    GorillaError::Io(format!("could not read an answer from stdin: {e}"))
+                })?;
+
+                fmt_answers.push(FormatFieldAnswer {
+                    name: q.name.to_owned(),
+                    answer: buffer.trim().to_owned(),
+                })
+            }
+
+            // reset start time bcuz we dont want to time how much it took the user to
+            // answer the questions
+            gorilla.start_time = SystemTime::now();
+
+            for gen_word in fmt_sets.generate_words(fmt_answers) {
+                gorilla.mutate_word(&gen_word);
+            }
+        }
+    }
+
+    if let (Some(file_save), true) = (&gorilla.program_args.file_save, gorilla.dry_run.is_some()) {
+        info!("         {} won't be touched", file_save.purple());
+    } else if let Some(file_save) = &gorilla.program_args.file_save {
+        info!("gorilla: using file {} as output", file_save.purple());
+        let args = &gorilla.program_args;
+        let mode = if args.append || resuming {
+            OpenMode::Append
+        } else if args.no_clobber {
+            OpenMode::NoClobber
+        } else {
+            OpenMode::Overwrite
+        };
+        let limits = SplitLimits {
+            lines: args.output_split_lines,
+            bytes: args.output_split_bytes,
+        };
+        if limits.is_splitting() && args.output_format == OutputFormat::JsonArray {
+            return Err(GorillaError::Config(String::from(
+                "a json array can't be split over several files, use --output-format json instead",
+            )));
+        }
+
+        let compression = args
+            .output_compress
+            .or_else(|| OutputCompression::from_path(file_save));
+        let level = |compression: OutputCompression| {
+            args.output_compress_level
+                .unwrap_or(compression.default_level())
+        };
+        if let Some(compression) = compression {
+            info!(
+                "         compressed with {} level {}",
+                format!("{compression:?}").to_lowercase(),
+                level(compression)
+            );
+        }
+        let first_path = if limits.is_splitting() {
+            info!(
+                "         split into {}, {} and so on",
+                split_path(file_save, 1).purple(),
+                split_path(file_save, 2).purple()
+            );
+            split_path(file_save, 1)
+        } else {
+            file_save.clone()
+        };
+
+        match RollingOutput::new(file_save, mode, compression.map(|c| (c, level(c))), limits) {
+            Ok(mut output) => {
+                output.set_delimiter(gorilla.output_separator.as_bytes());
+                gorilla.file_save = Some(output);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(GorillaError::Io(format!(
+                    "{} already exists, leave out --no-clobber to overwrite it or use --append",
+                    first_path.purple()
+                )));
+            }
+            Err(e) => {
+                return Err(GorillaError::Io(format!(
+                    "could not open the output file {}: {e}",
+                    first_path.purple()
+                )));
+            }
+        }
+    }
+
+    if let Some(command) = &gorilla.program_args.pipe_to {
+        let (child, stream) = spawn_pipe(command).map_err(|e| {
+            GorillaError::Io(format!(
+                "could not run the --pipe-to command {}: {e}",
+                command.purple()
+            ))
+        })?;
+        info!("gorilla: writing the words to {}", command.purple());
+        gorilla.pipe = Some(child);
+        gorilla.stdout = stream;
+        // the command gets the words along with the output file, in the
+        // place of stdout with --tee
+        if gorilla.program_args.file_save.is_some() {
+            gorilla.program_args.tee = true;
+        }
+    }
+
+    if let Some(target) = &gorilla.program_args.output_by_length {
+        let buckets = LengthBuckets::new(
+            target,
+            gorilla.output_separator.as_bytes(),
+            gorilla.program_args.output_encoding,
+        );
+        info!(
+            "gorilla: writing the words to a file per length, {} for 8 characters and so on",
+            buckets.path(8).purple()
+        );
+        gorilla.length_buckets = Some(buckets);
+    }
+
+    // a resumed run adds to the output of the run it picks up from, which
+    // has a header already
+    if gorilla.program_args.output_header && gorilla.dry_run.is_none() && !resuming {
+        let header = build_info::output_header(
+            &gorilla.program_args.output_header_comment,
+            &gorilla.mutation_sets,
+            gorilla.seed,
+            SystemTime::now(),
+        );
+        match &mut gorilla.length_buckets {
+            Some(buckets) => buckets.header = Some(header.into_bytes()),
+            None => gorilla.output_text(header.as_bytes()),
+        }
+    }
+
+    if gorilla.dry_run.is_none() {
+        gorilla.start_records();
+    }
+
+    if !gorilla.program_args.exclude_file.is_empty() {
+        gorilla.blocklist = Some(read_blocklist(
+            &gorilla.program_args.exclude_file,
+            gorilla.program_args.exclude_approx,
+            &input_client,
+        )?);
+    }
+
+    let args = &gorilla.program_args;
+    let no_input = args.word.is_empty()
+        && args.words.is_empty()
+        && args.file_input.is_empty()
+        && args.csv_input.is_none()
+        && args.jsonl_input.is_none()
+        && args.pattern_input.is_none()
+        && args.range_input.is_empty()
+        && args.keyboard_walks.is_none()
+        && args.phrase_input.is_none()
+        && args.prince_input.is_none()
+        && args.profile.is_none()
+        && args.from_formatting.is_none()
+        && args.website_input.is_empty()
+        && args.website_list.is_none()
+        && !args.url_stdin
+        && args.sitemap.is_empty()
+        && args.feed.is_empty();
+
+    // `-i -`, or words piped in without any other input, are read from stdin
+    let mut file_inputs: Vec<String> = args
+        .file_input
+        .iter()
+        .flat_map(|input| expand_input(input, args.recursive))
+        .collect();
+    if no_input && !io::stdin().is_terminal() {
+        file_inputs.push(String::from("-"));
+    }
+    if args.url_stdin && file_inputs.iter().any(|input| input == "-") {
+        return Err(GorillaError::Config(String::from(
+            "--url-stdin reads URLs from stdin, which can't have words to read as well",
+        )));
+    }
+
+    if args.insecure && file_inputs.iter().any(|input| is_url(input)) {
+        info!(
+            "gorilla: {} TLS certificate verification is disabled, anyone in the middle can tamper with the downloaded wordlists",
+            "(warning)".red().bold()
+        );
+    }
+
+    let mut pattern_words: Option<TokenIter> = None;
+
+    if let Some(pattern_input) = &gorilla.program_args.pattern_input {
+        let tokens = tokenize_format_string(pattern_input).map_err(|e| {
+            GorillaError::Config(format!(
+                "the pattern {} is invalid, {e}",
+                pattern_input.purple()
+            ))
+        })?;
+        let ac_toks = token_iterator(&tokens);
+
+        let total_words = ac_toks.calculate_total();
+        let b_size = ac_toks.calculate_size();
+        let mb_size = b_size / 1048576;
+        let gb_size = b_size / 1073741824;
+        let tb_size = b_size / 1099511627776;
+
+        info!(
+            "gorilla: will generate {} words from a pattern {}",
+            total_words,
+            pattern_input.purple()
+        );
+        info!("         sizes before mutations: {b_size} bytes / {mb_size} MB / {gb_size} GB / {tb_size} TB");
+
+        pattern_words = Some(ac_toks);
+    }
+
+    if let Some(hybrid) = &gorilla.hybrid {
+        let args = &gorilla.program_args;
+        let patterns: Vec<String> = [
+            ("--prepend-pattern", &args.prepend_pattern),
+            ("--append-pattern", &args.append_pattern),
+        ]
+        .into_iter()
+        .filter_map(|(flag, pattern)| Some(format!("{flag} {}", pattern.as_ref()?.purple())))
+        .collect();
+        let per_word = hybrid.words_per_input();
+        info!(
+            "gorilla: every input word becomes {} words with {}",
+            per_word.to_string().green(),
+            patterns.join(" and ")
+        );
+        let total = known_total(args, &file_inputs, pattern_words.as_ref()).map(|inputs| {
+            let total = u128::from(inputs).saturating_mul(per_word);
+            info!("         {inputs} input words will make {total} words before mutations");
+            total
+        });
+        if total.unwrap_or(per_word) >= HYBRID_WARNING_WORDS {
+            info!("gorilla: (warning) that's over a billion words before mutations, --hybrid-skip and --hybrid-limit can split them between runs");
+        }
+    }
+
+    let args = &gorilla.program_args;
+    if io::stderr().is_terminal()
+        && !args.quiet
+        && !args.timer
+        && !args.timer_per_word
+        && ((args.file_save.is_some() && !args.tee)
+            || args.output_by_length.is_some()
+            || args.pipe_to.is_some()
+            || !io::stdout().is_terminal())
+    {
+        let total = known_total(args, &file_inputs, pattern_words.as_ref());
+        gorilla.progress = Some(Progress::start(total));
+    }
+    if gorilla.program_args.timer {
+        gorilla.timer = Some(Timer::new(Duration::from_secs(
+            gorilla.program_args.timer_interval,
+        )));
+    }
+
+    let words: Vec<String> = [
+        gorilla.program_args.word.clone(),
+        gorilla.program_args.words.clone(),
+    ]
+    .concat();
+    if !words.is_empty() {
+        info!(
+            "gorilla: mutating {} words from the command line",
+            words.len().to_string().green()
+        );
+        for word in words {
+            gorilla.mutate_word(&word);
+        }
+    }
+
+    gorilla.input_paths = file_inputs.clone();
+    if gorilla.program_args.interleave {
+        interleave_inputs(gorilla, &input_client, file_inputs, pattern_words.take());
+    } else {
+        read_file_inputs(gorilla, &input_client, file_inputs);
+    }
+
+    if let Some(jsonl_input) = gorilla.program_args.jsonl_input.clone() {
+        let field = gorilla.program_args.json_field.clone().unwrap_or_default();
+        info!(
+            "gorilla: reading words from json lines {}",
+            display_name(&jsonl_input).purple()
+        );
+
+        let result = open_input(&jsonl_input, &input_client).and_then(|mut reader| {
+            words_from_jsonl(&mut reader, &field, |word| gorilla.mutate_word(&word))
+        });
+
+        match result {
+            Ok(stats) => info!(
+                "         read {} records, skipped {} without {} and {} that aren't valid json",
+                stats.records.to_string().green(),
+                stats.missing.to_string().red(),
+                field.purple(),
+                stats.malformed.to_string().red()
+            ),
+            Err(e) => gorilla.input_failed(&jsonl_input, e),
+        }
+    }
+
+    if let Some(csv_input) = gorilla.program_args.csv_input.clone() {
+        let columns = gorilla.program_args.csv_column.clone();
+        let has_header = !gorilla.program_args.csv_no_header;
+        info!(
+            "gorilla: reading words from csv file {}",
+            display_name(&csv_input).purple()
+        );
+
+        let result = open_input(&csv_input, &input_client)
+            .map_err(|e| e.to_string())
+            .and_then(|reader| {
+                words_from_csv_columns(reader, &columns, has_header, |word| {
+                    gorilla.mutate_word(&word)
+                })
+            });
+
+        match result {
+            Ok(stats) => info!(
+                "         read {} rows, skipped {} malformed ones",
+                stats.rows.to_string().green(),
+                stats.malformed.to_string().red()
+            ),
+            Err(e) => gorilla.input_failed(&csv_input, e),
+        }
+    }
+
+    if let Some(pattern_words) = pattern_words {
+        let done = gorilla
+            .checkpoint
+            .as_ref()
+            .map_or(0, |checkpoint| checkpoint.lines_done);
+        mutate_pattern(gorilla, pattern_words, done);
+    }
+
+    if let Some(lengths) = gorilla.program_args.keyboard_walks.clone() {
+        let layout = gorilla.program_args.keyboard_layout;
+        let walks = layout.keyboard().walks(
+            &lengths,
+            gorilla.program_args.keyboard_walk_turns,
+            gorilla.program_args.keyboard_walk_shift,
+        );
+
+        info!(
+            "gorilla: will generate {} keyboard walks of {} to {} keys on {}",
+            walks.len(),
+            lengths.min,
+            lengths.max,
+            format!("{layout:?}").to_lowercase().purple()
+        );
+
+        for walk in walks {
+            gorilla.mutate_word(&walk);
+        }
+    }
+
+    if let Some(phrase_input) = gorilla.program_args.phrase_input.clone() {
+        read_phrases(gorilla, &input_client, &phrase_input);
+    }
+
+    if let Some(path) = gorilla.program_args.prince_input.clone() {
+        read_prince(gorilla, &input_client, &path);
+    }
+
+    if let Some(profile) = profile {
+        let candidates = profile.candidates();
+        info!(
+            "gorilla: {} candidates from the profile of {}",
+            candidates.len().to_string().green(),
+            profile.names[0].purple()
+        );
+        for candidate in candidates {
+            gorilla.mutate_word(&candidate);
+        }
+    }
+
+    for range in gorilla.program_args.range_input.clone() {
+        let total_words = range.calculate_total();
+        let b_size = range.calculate_size();
+        let mb_size = b_size / 1048576;
+        let gb_size = b_size / 1073741824;
+        let tb_size = b_size / 1099511627776;
+
+        info!(
+            "gorilla: will generate {} words from the range {}",
+            total_words,
+            range.to_string().purple()
+        );
+        info!("         sizes before mutations: {b_size} bytes / {mb_size} MB / {gb_size} GB / {tb_size} TB");
+
+        for word in range.words() {
+            gorilla.mutate_word(&word);
+        }
+    }
+
+    #[cfg(feature = "scrape")]
+    scrape_inputs(gorilla, &client)?;
+
+    // stdin ends early at Ctrl+C
+    gorilla.stop_if_interrupted();
+    finish_run(gorilla);
+    Ok(())
+}
+
+/// The client pages are downloaded with, and the one of the wordlists at
+/// URLs, which have a cache of their own
+#[cfg(feature = "scrape")]
+pub fn http_clients(args: &ProgramArgs) -> Result<(HttpClient, HttpClient), GorillaError> {
+    let cache = |dir: &Option<String>| {
+        dir.as_ref().map(|dir| PageCache {
+            dir: PathBuf::from(dir),
+            ttl: Duration::from_secs(args.scrape_cache_ttl),
+            refresh: args.scrape_cache_refresh,
+        })
+    };
+    let client = build_client(&HttpOptions {
+        insecure: args.insecure,
+        ca_cert: args.ca_cert.clone(),
+        timeout: match args.timeout {
+            0 => None,
+            seconds => Some(Duration::from_secs(seconds)),
+        },
+        max_download_size: Some(args.max_download_size),
+        cache: cache(&args.scrape_cache),
+        user_agent: args.user_agent.clone(),
+    })
+    .map_err(|e| {
+        GorillaError::Io(format!(
+            "could not load the --ca-cert certificates {}: {e}",
+            args.ca_cert.as_deref().unwrap_or_default().purple()
+        ))
+    })?;
+
+    let input_client = HttpClient {
+        cache: cache(&args.input_cache),
+        ..client.clone()
+    };
+    Ok((client, input_client))
+}
+
+/// Without the `scrape` feature there's nothing to download, the flags
+/// and URLs that would were refused with the arguments
+#[cfg(not(feature = "scrape"))]
+pub fn http_clients(_args: &ProgramArgs) -> Result<(HttpClient, HttpClient), GorillaError> {
+    Ok((HttpClient, HttpClient))
+}
+
+/// Load the `--checkpoint`, or start a new one. Returns whether an earlier
+/// run is resumed, after cutting its output back to what the checkpoint
+/// covers.
+pub fn start_checkpoint(gorilla: &mut Gorilla) -> Result<bool, GorillaError> {
+    let args = &gorilla.program_args;
+    let path = args.checkpoint.clone().unwrap_or_default();
+    let output_file = args.file_save.clone().unwrap_or_default();
+
+    if OutputCompression::from_path(&output_file).is_some() {
+        return Err(GorillaError::Config(String::from(
+            "--checkpoint can't resume a compressed output file",
+        )));
+    }
+    let other_inputs = !args.word.is_empty()
+        || !args.words.is_empty()
+        || args.csv_input.is_some()
+        || args.jsonl_input.is_some()
+        || !args.range_input.is_empty()
+        || args.keyboard_walks.is_some()
+        || args.phrase_input.is_some()
+        || args.prince_input.is_some()
+        || args.profile.is_some()
+        || args.from_formatting.is_some()
+        || !args.website_input.is_empty()
+        || args.website_list.is_some()
+        || !args.sitemap.is_empty()
+        || !args.feed.is_empty();
+    let inputs: Vec<String> = args
+        .file_input
+        .iter()
+        .flat_map(|input| expand_input(input, args.recursive))
+        .collect();
+    // the words of a pattern are numbered like the lines of a wordlist
+    let usable = match &args.pattern_input {
+        Some(_) => inputs.is_empty(),
+        None => !inputs.is_empty() && !inputs.iter().any(|i| i == "-" || is_url(i)),
+    };
+    if other_inputs || !usable {
+        return Err(GorillaError::Config(String::from(
+            "--checkpoint only works with local wordlists given to --from-file, or a --from-pattern on its own",
+        )));
+    }
+    let pattern = args.pattern_input.clone();
+
+    let fingerprints: Vec<InputFingerprint> = inputs
+        .iter()
+        .map(|input| {
+            InputFingerprint::of(input)
+                .map_err(|e| GorillaError::Io(format!("could not read {}: {e}", input.purple())))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let checkpoint = match Checkpoint::load(&path) {
+        Ok(Some(checkpoint)) => checkpoint,
+        Ok(None) => {
+            let checkpoint = Checkpoint::new(&output_file, fingerprints, pattern);
+            if let Err(e) = checkpoint.save(&path) {
+                return Err(GorillaError::Io(format!(
+                    "could not write the checkpoint {}: {e}",
+                    path.purple()
+                )));
+            }
+            info!("gorilla: keeping track of the run in {}", path.purple());
+            gorilla.checkpoint = Some(checkpoint);
+            return Ok(false);
+        }
+        Err(e) => {
+            return Err(GorillaError::Io(format!(
+                "could not read the checkpoint {}: {e}",
+                path.purple()
+            )))
+        }
+    };
+
+    if let Err(reason) = checkpoint.check(&output_file, &fingerprints, pattern.as_deref()) {
+        return Err(GorillaError::Config(format!(
+            "can't resume from the checkpoint {}, {reason} (remove it to start over)",
+            path.purple()
+        )));
+    }
+
+    // words written after the checkpoint was saved are written again
+    let truncated = fs::OpenOptions::new()
+        .write(true)
+        .open(&output_file)
+        .and_then(|file| {
+            if file.metadata()?.len() < checkpoint.output_bytes {
+                return Err(io::Error::other(
+                    "it's shorter than when the checkpoint was saved",
+                ));
+            }
+            file.set_len(checkpoint.output_bytes)
+        });
+    if let Err(e) = truncated {
+        return Err(GorillaError::Io(format!(
+            "can't resume writing {}: {e}",
+            output_file.purple()
+        )));
+    }
+
+    info!(
+        "gorilla: resuming from the checkpoint {}, skipping the {} input {} done before",
+        path.purple(),
+        checkpoint.lines_done.to_string().green(),
+        match pattern {
+            Some(_) => "words",
+            None => "lines",
+        }
+    );
+    info!(
+        "         {} continues at byte {}",
+        output_file.purple(),
+        checkpoint.output_bytes.to_string().green()
+    );
+    gorilla.checkpoint_offset = checkpoint.output_bytes;
+    gorilla.checkpoint = Some(checkpoint);
+    Ok(true)
+}
@@ -0,0 +1,530 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Write},
+    time::Duration,
+};
+
+use colored::Colorize;
+
+use crate::{
+    crawl_state::{Crawl, UrlStdin},
+    error::GorillaError,
+    input::HttpClient,
+    pipeline::Gorilla,
+    website_scraper::{
+        collect_sitemap_urls, email_usernames, extract_emails, extract_json_words, extract_links,
+        extract_words, fetch_feed, fetch_robots, html_files, is_binary_url, local_path,
+        robots_allows, split_url, words_from_segments, CrawlFilter, Fetcher, RobotsRules,
+        ScrapeOptions,
+    },
+};
+
+/// Scrape the websites, sitemaps and feeds, and the html files of the
+/// directories among the websites
+pub fn scrape_inputs(gorilla: &mut Gorilla, client: &HttpClient) -> Result<(), GorillaError> {
+    let mut websites = gorilla.program_args.website_input.clone();
+
+    if let Some(website_list) = &gorilla.program_args.website_list {
+        let list = fs::read_to_string(website_list).map_err(|e| {
+            GorillaError::Io(format!(
+                "could not read the website list {}: {e}",
+                website_list.purple()
+            ))
+        })?;
+        websites.extend(
+            list.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(str::to_owned),
+        );
+    }
+
+    // directories are replaced by the html files inside them
+    let mut websites: Vec<String> = websites
+        .into_iter()
+        .flat_map(|website| match local_path(&website) {
+            Some(path) if path.is_dir() => {
+                info!("gorilla: reading html files from {}", website.purple());
+                let found = html_files(&path);
+                for (dir, e) in &found.unreadable {
+                    info!(
+                        "gorilla: (warning) could not read directory {}: {e}",
+                        dir.display()
+                    );
+                }
+                for file in &found.skipped {
+                    verbose!("gorilla: skipping {} (not an html file)", file.display());
+                }
+                found
+                    .files
+                    .into_iter()
+                    .map(|p| p.display().to_string())
+                    .collect()
+            }
+            _ => vec![website],
+        })
+        .collect();
+
+    let args = &gorilla.program_args;
+    let crawling = args.url_stdin || args.crawl_state.is_some();
+    let scraping = !websites.is_empty()
+        || crawling
+        || !gorilla.program_args.sitemap.is_empty()
+        || !gorilla.program_args.feed.is_empty();
+
+    if scraping && gorilla.program_args.insecure {
+        info!(
+            "gorilla: {} TLS certificate verification is disabled, anyone in the middle can tamper with the scraped pages",
+            "(warning)".red().bold()
+        );
+    }
+
+    // pages found in sitemaps and feeds, bounded by --crawl-max-pages
+    let mut crawled_pages = 0;
+    for sitemap in &gorilla.program_args.sitemap {
+        info!("gorilla: reading pages from sitemap {}", sitemap.purple());
+        let max_pages = gorilla
+            .program_args
+            .crawl_max_pages
+            .map(|max| max.saturating_sub(crawled_pages));
+        let found = collect_sitemap_urls(client, sitemap, max_pages);
+        for (url, e) in &found.failed {
+            eprintln!("gorilla: (error) could not read sitemap {url}: {e}");
+        }
+        let pages = found.pages;
+        info!("         found {} pages", pages.len());
+        crawled_pages += pages.len();
+        websites.extend(pages);
+    }
+
+    let mut feeds: Vec<(String, Vec<String>)> = Vec::new();
+    for feed_url in &gorilla.program_args.feed {
+        info!("gorilla: reading feed {}", feed_url.purple());
+        let feed = match fetch_feed(client, feed_url) {
+            Ok(feed) => feed,
+            Err(e) => {
+                let message = format!("could not read feed {}: {e}", feed_url.purple());
+                eprintln!("gorilla: (error) {message}");
+                gorilla.failed_inputs.push(GorillaError::Network(message));
+                continue;
+            }
+        };
+
+        if gorilla.program_args.feed_follow {
+            let max_pages = gorilla
+                .program_args
+                .crawl_max_pages
+                .map_or(usize::MAX, |max| max.saturating_sub(crawled_pages));
+            let links: Vec<String> = feed.links.into_iter().take(max_pages).collect();
+            info!("         following {} entries", links.len());
+            crawled_pages += links.len();
+            websites.extend(links);
+        }
+
+        feeds.push((feed_url.clone(), feed.text));
+    }
+
+    if !websites.is_empty() || !feeds.is_empty() || crawling {
+        let crawl_budget = gorilla
+            .program_args
+            .crawl_max_pages
+            .map(|max| max.saturating_sub(crawled_pages));
+        scrape_websites(gorilla, client, websites, feeds, crawl_budget)?;
+    }
+    Ok(())
+}
+
+/// Scrape every website (or local file), along with the text already read
+/// from feeds, deduplicating the words across all of them before they're
+/// mutated. With `--crawl-depth` the links on the pages are followed too,
+/// up to `crawl_budget` of them.
+pub fn scrape_websites(
+    gorilla: &mut Gorilla,
+    client: &HttpClient,
+    websites: Vec<String>,
+    mut feeds: Vec<(String, Vec<String>)>,
+    crawl_budget: Option<usize>,
+) -> Result<(), GorillaError> {
+    let args = &gorilla.program_args;
+    let write_failed = |path: &Option<String>, e: io::Error| {
+        GorillaError::Io(format!(
+            "could not write to {}: {e}",
+            path.as_deref().unwrap_or_default().purple()
+        ))
+    };
+    let create = |path: &str| {
+        File::create(path)
+            .map_err(|e| GorillaError::Io(format!("could not create {}: {e}", path.purple())))
+    };
+
+    let mut seen_words: HashSet<String> = HashSet::new();
+    let mut robots_cache: HashMap<String, RobotsRules> = HashMap::new();
+    let mut contributions: Vec<(String, Option<usize>)> = Vec::new();
+    let mut scrape_save = args
+        .scrape_save
+        .as_ref()
+        .map(|path| {
+            info!("gorilla: saving raw scraped words to {}", path.purple());
+            create(path)
+        })
+        .transpose()?;
+    let scrape_save_path = args.scrape_save.clone();
+    let mut numbers_found = 0;
+    let mut seen_emails: HashSet<String> = HashSet::new();
+    let scrape_emails = args.scrape_emails || args.email_file.is_some();
+    let mut email_file = args
+        .email_file
+        .as_ref()
+        .map(|path| {
+            info!("gorilla: saving scraped emails to {}", path.purple());
+            create(path)
+        })
+        .transpose()?;
+    let scrape_options = ScrapeOptions {
+        meta: args.scrape_meta,
+        case: args.scrape_dedup_case.unwrap_or_default(),
+        ngrams: args.scrape_ngrams,
+        max_json_size: Some(args.scrape_json_limit),
+        numbers: args.scrape_numbers,
+    };
+    let scrape_json = args.scrape_json;
+    let counting = args.scrape_count || args.scrape_count_file.is_some();
+    // dedup key -> (first spelling, occurrences), only kept when counting
+    let mut word_counts: HashMap<String, (String, usize)> = HashMap::new();
+    let respect_robots = args.respect_robots;
+    let crawl_depth = args.crawl_depth;
+    let mut crawl_filter = CrawlFilter {
+        include: args.crawl_include.clone(),
+        exclude: args.crawl_exclude.clone(),
+        offsite: args.crawl_offsite,
+        ..Default::default()
+    };
+    let mut crawl = args
+        .crawl_state
+        .as_deref()
+        .map(|path| Crawl::start(args, path))
+        .transpose()?;
+    let url_stdin = args.url_stdin.then(UrlStdin::read);
+    // one for the whole crawl, so that --scrape-delay holds from a depth
+    // to the next and from a batch of --url-stdin to the next
+    let fetcher = Fetcher::new(
+        client,
+        args.scrape_threads,
+        Duration::from_millis(args.scrape_delay),
+    );
+
+    // every URL queued or turned down, so each one is only considered once
+    let mut crawl_seen: HashSet<String> = HashSet::new();
+    // the pages to scrape at every depth, the websites asked for at 0
+    let mut queued: Vec<Vec<String>> = vec![vec![]; crawl_depth + 1];
+    // the links queued, which count towards --crawl-max-pages
+    let mut linked = 0;
+    // of those, the pages scraped, and the ones robots.txt disallowed,
+    // that weren't pages or that couldn't be fetched
+    let (mut followed, mut skipped_links) = (0, 0);
+    if let Some(crawl) = &crawl {
+        crawl_seen.extend(crawl.visited().iter().cloned());
+        for page in crawl.queued() {
+            crawl_seen.insert(page.url.clone());
+            queued[page.depth.min(crawl_depth)].push(page.url);
+        }
+        linked = crawl.followed;
+    }
+    for website in websites {
+        if crawl_seen.insert(website.clone()) {
+            if let Some(crawl) = &mut crawl {
+                crawl.queue(&website, 0);
+            }
+            queued[0].push(website);
+        }
+    }
+
+    // deduplicates the words of one source and mutates the new ones,
+    // returning how many it contributed
+    let mut add_words = |gorilla: &mut Gorilla, words: Vec<(String, usize)>| {
+        let mut contributed = 0;
+        for (word, count) in words {
+            let key = scrape_options.dedup_key(&word);
+
+            if counting {
+                word_counts
+                    .entry(key.clone())
+                    .or_insert((word.clone(), 0))
+                    .1 += count;
+            }
+
+            if seen_words.insert(key) {
+                contributed += 1;
+                if scrape_options.numbers && word.bytes().all(|b| b.is_ascii_digit()) {
+                    numbers_found += 1;
+                }
+
+                // when counting, words are only mutated once every page is read
+                if !counting {
+                    if let Some(scrape_save) = &mut scrape_save {
+                        if let Err(e) = writeln!(scrape_save, "{word}") {
+                            gorilla.exit_with(write_failed(&scrape_save_path, e));
+                        }
+                        gorilla.scrape_saved += 1;
+                    }
+
+                    gorilla.mutate_word(&word)
+                }
+            }
+        }
+
+        contributed
+    };
+
+    // with --url-stdin, every batch of the URLs piped in is crawled the
+    // same way as the websites given before it
+    loop {
+        for depth in 0..=crawl_depth {
+            let round = std::mem::take(&mut queued[depth]);
+            if round.is_empty() && feeds.is_empty() {
+                continue;
+            }
+            let mut allowed_websites: Vec<String> = Vec::new();
+
+            for website in round {
+                let is_local = local_path(&website).is_some();
+
+                if respect_robots && !is_local {
+                    let origin = split_url(&website).map_or(String::new(), |(o, _)| o);
+                    let rules = robots_cache
+                        .entry(origin)
+                        .or_insert_with(|| fetch_robots(client, &website));
+
+                    if !robots_allows(rules, &website) {
+                        info!(
+                            "gorilla: (warning) skipping {} (disallowed by robots.txt)",
+                            website.purple()
+                        );
+                        if let Some(crawl) = &mut crawl {
+                            crawl.visit(&website);
+                        }
+                        if depth > 0 {
+                            skipped_links += 1;
+                        }
+                        contributions.push((website, None));
+                        continue;
+                    }
+                }
+
+                if is_binary_url(&website) && !is_local {
+                    verbose!(
+                        "gorilla: skipping {} (not a page words can be scraped from)",
+                        website.purple()
+                    );
+                    if let Some(crawl) = &mut crawl {
+                        crawl.visit(&website);
+                    }
+                    if depth > 0 {
+                        skipped_links += 1;
+                    }
+                    contributions.push((website, None));
+                    continue;
+                }
+
+                allowed_websites.push(website);
+            }
+
+            let pages = fetcher.fetch(allowed_websites);
+
+            for (feed, segments) in feeds.drain(..) {
+                verbose!("gorilla: scraping words from a feed {}", feed.purple());
+                let contributed =
+                    add_words(gorilla, words_from_segments(&segments, &scrape_options));
+                contributions.push((feed, Some(contributed)));
+            }
+
+            for (website, result) in pages {
+                gorilla.stop_crawl_if_interrupted(&mut crawl);
+                if local_path(&website).is_some() {
+                    verbose!("gorilla: scraping words from a file {}", website.purple());
+                } else {
+                    verbose!(
+                        "gorilla: scraping words from a website {}",
+                        website.purple()
+                    );
+                }
+
+                if let Some(e) = result
+                    .as_ref()
+                    .ok()
+                    .and_then(|page| page.cache_error.as_ref())
+                {
+                    info!("gorilla: (warning) could not cache {website}: {e}");
+                }
+                let page = match result {
+                    Ok(page) if page.from_cache => {
+                        verbose!("         (from the cache)");
+                        page
+                    }
+                    Ok(page) => page,
+                    Err(e) => {
+                        let message = format!("could not scrape {}: {e}", website.purple());
+                        eprintln!("gorilla: (error) {message}");
+                        // pages found by crawling may well be gone, only the
+                        // ones asked for fail the run
+                        if depth == 0 {
+                            gorilla.failed_inputs.push(match local_path(&website) {
+                                Some(_) => GorillaError::Io(message),
+                                None => GorillaError::Network(message),
+                            });
+                        } else {
+                            skipped_links += 1;
+                        }
+                        contributions.push((website, None));
+                        continue;
+                    }
+                };
+                if depth > 0 {
+                    followed += 1;
+                }
+
+                let mut words = if scrape_json || page.is_json() {
+                    if scrape_options
+                        .max_json_size
+                        .is_some_and(|max| page.body.len() > max)
+                    {
+                        info!(
+                            "gorilla: (warning) {} is larger than --scrape-json-limit, skipping it",
+                            website.purple()
+                        );
+                    }
+
+                    match extract_json_words(&page.body, &scrape_options) {
+                        Ok(words) => words,
+                        Err(e) => {
+                            eprintln!(
+                                "gorilla: (error) could not parse json from {}: {e}",
+                                website.purple()
+                            );
+                            contributions.push((website, None));
+                            continue;
+                        }
+                    }
+                } else {
+                    extract_words(&page.body, &scrape_options)
+                };
+
+                if scrape_emails {
+                    for email in extract_emails(&page.body) {
+                        words.extend(email_usernames(&email).into_iter().map(|u| (u, 1)));
+
+                        if let Some(email_file) = &mut email_file {
+                            if seen_emails.insert(email.clone()) {
+                                writeln!(email_file, "{email}").map_err(|e| {
+                                    write_failed(&gorilla.program_args.email_file, e)
+                                })?;
+                            }
+                        } else {
+                            words.push((email, 1));
+                        }
+                    }
+                }
+
+                if depth < crawl_depth && local_path(&website).is_none() && !page.is_json() {
+                    for link in extract_links(&page.body, &website) {
+                        if crawl_budget.is_some_and(|max| linked >= max) {
+                            break;
+                        }
+                        if crawl_seen.insert(link.clone()) && crawl_filter.allows(&website, &link) {
+                            linked += 1;
+                            if let Some(crawl) = &mut crawl {
+                                crawl.queue(&link, depth + 1);
+                            }
+                            queued[depth + 1].push(link);
+                        }
+                    }
+                }
+
+                gorilla.stop_crawl_if_interrupted(&mut crawl);
+                let contributed = add_words(gorilla, words);
+                contributions.push((website.clone(), Some(contributed)));
+                if let Some(crawl) = &mut crawl {
+                    crawl.followed = linked;
+                    crawl.visit(&website);
+                    crawl.save_or_warn(false);
+                }
+            }
+        }
+
+        let Some(urls) = &url_stdin else {
+            break;
+        };
+        let Some(batch) = urls.next_batch(|| gorilla.stop_crawl_if_interrupted(&mut crawl)) else {
+            break;
+        };
+        for url in batch {
+            if crawl_seen.insert(url.clone()) {
+                if let Some(crawl) = &mut crawl {
+                    crawl.queue(&url, 0);
+                }
+                queued[0].push(url);
+            } else {
+                verbose!(
+                    "gorilla: skipping {} (queued or scraped before)",
+                    url.purple()
+                );
+            }
+        }
+    }
+
+    if crawl_depth > 0 {
+        info!(
+            "gorilla: followed {} links, skipped {} that robots.txt disallowed, that weren't pages or that couldn't be fetched, turned down {} on other hosts, {} not matching --crawl-include and {} matching --crawl-exclude",
+            followed.to_string().green(),
+            skipped_links,
+            crawl_filter.rejected_offsite,
+            crawl_filter.rejected_include,
+            crawl_filter.rejected_exclude
+        );
+    }
+
+    if counting {
+        let mut counted: Vec<(String, usize)> = word_counts.into_values().collect();
+        counted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        if let Some(count_file) = &gorilla.program_args.scrape_count_file {
+            info!("gorilla: saving word counts to {}", count_file.purple());
+            let mut file = create(count_file)?;
+            for (word, count) in &counted {
+                writeln!(file, "{word}\t{count}")
+                    .map_err(|e| write_failed(&gorilla.program_args.scrape_count_file, e))?;
+            }
+        }
+
+        for (word, _) in counted {
+            if let Some(scrape_save) = &mut scrape_save {
+                writeln!(scrape_save, "{word}").map_err(|e| write_failed(&scrape_save_path, e))?;
+                gorilla.scrape_saved += 1;
+            }
+
+            gorilla.mutate_word(&word)
+        }
+    }
+
+    info!("gorilla: words contributed per website");
+    for (website, contributed) in contributions {
+        match contributed {
+            Some(n) => info!(" {} -> {}", website.purple(), n.to_string().green()),
+            None => info!(" {} -> {}", website.purple(), "skipped".red()),
+        }
+    }
+
+    if scrape_options.numbers {
+        info!(
+            "gorilla: found {} numbers on the scraped pages",
+            numbers_found.to_string().green()
+        );
+    }
+
+    if let Some(crawl) = crawl {
+        crawl.finish();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,676 @@
+use colored::Colorize;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    arguments::ProgramArgs,
+    error::GorillaError,
+    mutation::MutationSet,
+    patterns::{token_iterator, tokenize_format_string, Token},
+};
+
+/// Where `gorilla serve` listens without `--listen`
+const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+
+/// The most a request line and its headers are read of
+const MAX_HEAD: usize = 16 * 1024;
+
+/// The largest job that's read
+const MAX_BODY: usize = 1024 * 1024;
+
+/// How long the whole of a request has to arrive in, so that a slow client
+/// doesn't hold on to a job it isn't sending, however little it sends at a
+/// time
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// About how many bytes of words `/generate` sends at a time
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// How many of the input words `/estimate` mutates to scale up from
+const ESTIMATE_SAMPLE: usize = 1000;
+
+/// A response other than the words, and why, as `{"error": "..."}`
+type Refusal = (u16, String);
+
+/// What's POSTed to `/generate` and `/estimate`:
+///
+/// ```json
+/// {"words": ["admin"], "pattern": "{0-9}{0-9}", "sets": ["simple"],
+///  "mutations": [["reverse", "append:!"]], "max_words": 1000}
+/// ```
+///
+/// The words are mutated before those of the pattern. `sets` are the names
+/// of the mutations files of `--sets-dir`, and `mutations` are sets like
+/// those of `-m`; without either, the words are sent as they are. The
+/// `exec:` actions of `mutations` run commands on the server, so they're
+/// turned down without `--allow-exec`, while those of the `--sets-dir`
+/// files were put there by whoever runs it.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Job {
+    #[serde(default)]
+    words: Vec<String>,
+    pattern: Option<String>,
+    #[serde(default)]
+    sets: Vec<String>,
+    #[serde(default)]
+    mutations: Vec<Vec<String>>,
+    max_words: Option<u64>,
+}
+
+/// A job with its pattern and its mutation sets parsed
+struct Prepared {
+    words: Vec<String>,
+    tokens: Option<Vec<Token>>,
+    /// The sets of `mutations`, then those of the mutations files named by
+    /// `sets`, or the set that changes nothing
+    sets: Vec<MutationSet>,
+    max_words: Option<u64>,
+}
+
+/// A mutations file of `--sets-dir`. Its sets are built again for every job
+/// that names it, as the plugins of `exec:` sets answer one job at a time.
+struct SetsFile {
+    path: String,
+    yaml: String,
+}
+
+impl SetsFile {
+    fn mutation_sets(&self, exec_timeout: Duration) -> Vec<MutationSet> {
+        let mut file = crate::load::parse_mutations_file(&self.path, &self.yaml)
+            .expect("the file was parsed when the server started");
+        for set in &mut file.mutation_sets {
+            set.set_exec_timeout(exec_timeout);
+        }
+        file.mutation_sets
+    }
+}
+
+impl Prepared {
+    fn input_words(&self) -> impl Iterator<Item = String> + '_ {
+        let pattern_words = self.tokens.as_deref().map(token_iterator);
+        self.words
+            .iter()
+            .cloned()
+            .chain(pattern_words.into_iter().flatten())
+    }
+
+    fn sets(&self) -> impl Iterator<Item = &MutationSet> {
+        self.sets.iter()
+    }
+
+    fn plugin_error(&self) -> Option<String> {
+        self.sets()
+            .find_map(MutationSet::plugin_error)
+            .map(str::to_owned)
+    }
+}
+
+/// `gorilla serve`, or `--listen`: a small HTTP server that generates the
+/// words of the jobs POSTed to it, one thread for each
+pub struct Server {
+    listener: TcpListener,
+    /// The mutations files of `--sets-dir`, by their names
+    named_sets: Arc<HashMap<String, SetsFile>>,
+    max_jobs: u64,
+    exec_timeout: Duration,
+    read_timeout: Duration,
+    /// Whether the `mutations` of a job can have `exec:` actions
+    allow_exec: bool,
+    /// How many jobs are being answered
+    running: Arc<AtomicU64>,
+}
+
+/// A job counted in `running`, until it's dropped. Dropping it is how the
+/// count goes down, so that a job that panics doesn't keep its place.
+struct Slot(Arc<AtomicU64>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Server {
+    pub fn bind(args: &ProgramArgs) -> Result<Server, GorillaError> {
+        let exec_timeout = Duration::from_secs(args.exec_timeout);
+        let named_sets = match &args.sets_dir {
+            Some(dir) => read_sets_dir(Path::new(dir))?,
+            None => HashMap::new(),
+        };
+        let address = args.listen.as_deref().unwrap_or(DEFAULT_LISTEN);
+        let listener = TcpListener::bind(address).map_err(|e| {
+            GorillaError::Network(format!("could not listen on {}: {e}", address.purple()))
+        })?;
+        Ok(Server {
+            listener,
+            named_sets: Arc::new(named_sets),
+            max_jobs: args.max_jobs,
+            exec_timeout,
+            read_timeout: READ_TIMEOUT,
+            allow_exec: args.allow_exec,
+            running: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// How long a request has to arrive in, 30 seconds unless it's set
+    pub fn set_read_timeout(&mut self, timeout: Duration) {
+        self.read_timeout = timeout;
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Answer requests until gorilla is stopped
+    pub fn run(&self) -> Result<(), GorillaError> {
+        let address = self
+            .local_addr()
+            .map_err(|e| GorillaError::Network(format!("could not listen: {e}")))?;
+        info!(
+            "gorilla: serving on {}, {} jobs at a time",
+            address.to_string().purple(),
+            self.max_jobs.to_string().green()
+        );
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    info!("gorilla: (warning) could not accept a connection: {e}");
+                    continue;
+                }
+            };
+            let deadline = Instant::now() + self.read_timeout;
+
+            // only this thread starts jobs, so none start between the
+            // check and the count
+            if self.running.load(Ordering::SeqCst) >= self.max_jobs {
+                let max_jobs = self.max_jobs;
+                thread::spawn(move || refuse_busy(stream, deadline, max_jobs));
+                continue;
+            }
+            self.running.fetch_add(1, Ordering::SeqCst);
+            let slot = Slot(Arc::clone(&self.running));
+            let named_sets = Arc::clone(&self.named_sets);
+            let (exec_timeout, allow_exec) = (self.exec_timeout, self.allow_exec);
+            thread::spawn(move || {
+                let _slot = slot;
+                answer(
+                    stream,
+                    deadline,
+                    &named_sets,
+                    Limits {
+                        exec_timeout,
+                        allow_exec,
+                    },
+                );
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The mutations files of `dir`, by the names in them
+fn read_sets_dir(dir: &Path) -> Result<HashMap<String, SetsFile>, GorillaError> {
+    let entries = fs::read_dir(dir).map_err(|e| {
+        GorillaError::Io(format!(
+            "could not read the --sets-dir {}: {e}",
+            dir.display().to_string().purple()
+        ))
+    })?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension == "yml" || extension == "yaml")
+        })
+        .collect();
+    paths.sort();
+
+    let mut named_sets = HashMap::new();
+    for path in paths {
+        let path = path.to_string_lossy().into_owned();
+        let yaml = fs::read_to_string(&path).map_err(|e| {
+            GorillaError::Io(format!(
+                "could not read the mutations file {}: {e}",
+                path.purple()
+            ))
+        })?;
+        let file = crate::load::parse_mutations_file(&path, &yaml)?;
+        verbose!(
+            "gorilla: serving the {} sets of {} as {}",
+            file.mutation_sets.len().to_string().green(),
+            path.purple(),
+            file.name.purple()
+        );
+        if named_sets
+            .insert(file.name.clone(), SetsFile { path, yaml })
+            .is_some()
+        {
+            return Err(GorillaError::Config(format!(
+                "more than one mutations file of the --sets-dir {} is named {}",
+                dir.display().to_string().purple(),
+                file.name.purple()
+            )));
+        }
+    }
+    Ok(named_sets)
+}
+
+/// What the jobs of a server may do
+#[derive(Clone, Copy)]
+struct Limits {
+    exec_timeout: Duration,
+    allow_exec: bool,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    /// The headers, with their names lowercased
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    /// Turn down what a web page could have sent: a browser sends a form
+    /// or text/plain to another site without asking, but not json, and
+    /// tells the site the page came from in `Origin`.
+    fn check_sender(&self) -> Result<(), Refusal> {
+        let content_type = self.header("content-type").unwrap_or_default();
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        if !media_type.eq_ignore_ascii_case("application/json") {
+            return Err((
+                415,
+                format!(
+                    "a job is sent as application/json, not {}",
+                    match content_type {
+                        "" => "without a Content-Type",
+                        content_type => content_type,
+                    }
+                ),
+            ));
+        }
+        if let Some(origin) = self.header("origin") {
+            let origin_host = origin.split_once("://").map(|(_, host)| host);
+            if origin_host.is_none() || origin_host != self.header("host") {
+                return Err((403, format!("jobs aren't taken from pages of {origin}")));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn answer(
+    mut stream: TcpStream,
+    deadline: Instant,
+    named_sets: &HashMap<String, SetsFile>,
+    limits: Limits,
+) {
+    let peer = stream.peer_addr().map_or_else(
+        |_| String::from("an unknown address"),
+        |peer| peer.to_string(),
+    );
+    let request = match read_request(&stream, deadline) {
+        Ok(request) => request,
+        Err((status, message)) => {
+            verbose!("gorilla: turned down a request from {peer} with {status}: {message}");
+            let _ = respond_error(&mut stream, status, &message);
+            return;
+        }
+    };
+
+    let answered = match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/generate") => request
+            .check_sender()
+            .and_then(|_| prepare(&request.body, named_sets, limits))
+            .and_then(|job| {
+                generate(&job, &mut stream)
+                    .map_err(|e| (500, e.to_string()))
+                    .map(|words| format!("{words} words"))
+            }),
+        ("POST", "/estimate") => request
+            .check_sender()
+            .and_then(|_| prepare(&request.body, named_sets, limits))
+            .and_then(|job| estimate(&job))
+            .and_then(|estimate| {
+                let body = estimate.to_string() + "\n";
+                respond(&mut stream, 200, "application/json", body.as_bytes())
+                    .map_err(|e| (500, e.to_string()))
+                    .map(|_| estimate["words"].to_string() + " words estimated")
+            }),
+        (_, "/generate" | "/estimate") => Err((405, format!("{} only takes POST", request.path))),
+        _ => Err((
+            404,
+            format!("there's no {}, only /generate and /estimate", request.path),
+        )),
+    };
+
+    match answered {
+        Ok(what) => verbose!(
+            "gorilla: {} {} from {peer}, {what}",
+            request.method,
+            request.path
+        ),
+        Err((500, message)) => {
+            // the words may have been sent in part, and the response is
+            // left unfinished for the client to tell
+            info!(
+                "gorilla: (warning) {} {} from {peer} failed, {message}",
+                request.method, request.path
+            );
+        }
+        Err((status, message)) => {
+            verbose!(
+                "gorilla: turned down {} {} from {peer} with {status}: {message}",
+                request.method,
+                request.path
+            );
+            let _ = respond_error(&mut stream, status, &message);
+        }
+    }
+}
+
+/// There are `--max-jobs` jobs running already
+fn refuse_busy(mut stream: TcpStream, deadline: Instant, max_jobs: u64) {
+    // reading the request first lets the client read the response, which
+    // is lost when the socket is closed with the request unread
+    if read_request(&stream, deadline).is_ok() {
+        verbose!("gorilla: turned down a request with 503, {max_jobs} jobs are running");
+        let message = format!("{max_jobs} jobs are running already, try again later");
+        let _ = respond_error(&mut stream, 503, &message);
+    }
+}
+
+/// A connection read from until a deadline, the time left being the
+/// timeout of every read
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        let mut stream = self.stream;
+        stream.read(buf)
+    }
+}
+
+/// Why a request couldn't be read, which is 408 when it was too slow
+fn read_error(e: io::Error, what: &str) -> Refusal {
+    match e.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+            (408, String::from("the request took too long to arrive"))
+        }
+        _ => (400, format!("could not read {what}: {e}")),
+    }
+}
+
+fn read_request(stream: &TcpStream, deadline: Instant) -> Result<Request, Refusal> {
+    let mut reader = BufReader::new(DeadlineReader { stream, deadline });
+    let mut head = vec![];
+    let mut head_bytes = 0;
+    loop {
+        let mut line = String::new();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|e| read_error(e, "the request"))?;
+        if read == 0 {
+            return Err((400, String::from("the request ended in its headers")));
+        }
+        head_bytes += read;
+        if head_bytes > MAX_HEAD {
+            return Err((431, String::from("the headers are too long")));
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            break;
+        }
+        head.push(line.to_owned());
+    }
+
+    let mut request_line = head[0].split(' ');
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Err((400, format!("{} isn't an HTTP request", head[0])));
+    };
+    let path = target.split('?').next().unwrap_or(target);
+
+    let headers: HashMap<String, String> = head[1..]
+        .iter()
+        .filter_map(|header| {
+            let (name, value) = header.split_once(':')?;
+            Some((name.trim().to_ascii_lowercase(), value.trim().to_owned()))
+        })
+        .collect();
+    let length: usize = match headers.get("content-length") {
+        Some(length) => length
+            .parse()
+            .map_err(|_| (400, format!("the Content-Length {length} isn't a number")))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err((413, format!("a job can be {MAX_BODY} bytes at most")));
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| read_error(e, "the job"))?;
+
+    Ok(Request {
+        method: method.to_owned(),
+        path: path.to_owned(),
+        headers,
+        body,
+    })
+}
+
+/// Parse a job, turning down the ones that can't be generated before any
+/// word is sent
+fn prepare(
+    body: &[u8],
+    named_sets: &HashMap<String, SetsFile>,
+    limits: Limits,
+) -> Result<Prepared, Refusal> {
+    let job: Job =
+        serde_json::from_slice(body).map_err(|e| (400, format!("the job is invalid, {e}")))?;
+    if job.words.is_empty() && job.pattern.is_none() {
+        return Err((400, String::from("the job has neither words nor a pattern")));
+    }
+    let tokens = match &job.pattern {
+        Some(pattern) => Some(
+            tokenize_format_string(pattern)
+                .map_err(|e| (400, format!("the pattern {pattern} is invalid, {e}")))?,
+        ),
+        None => None,
+    };
+
+    let mut sets = vec![];
+    for mutations in &job.mutations {
+        let mut set = MutationSet::new(mutations).map_err(|e| (400, format!("mutations: {e}")))?;
+        if set.runs_commands() && !limits.allow_exec {
+            return Err((
+                403,
+                String::from(
+                    "exec: mutations run commands on the server, they're only taken from \
+                     the --sets-dir files, or from jobs with --allow-exec",
+                ),
+            ));
+        }
+        set.set_exec_timeout(limits.exec_timeout);
+        sets.push(set);
+    }
+    for name in &job.sets {
+        let Some(file) = named_sets.get(name) else {
+            let mut names: Vec<&str> = named_sets.keys().map(String::as_str).collect();
+            names.sort_unstable();
+            return Err((
+                400,
+                format!(
+                    "there are no sets named {name}, there are [{}]",
+                    names.join(", ")
+                ),
+            ));
+        };
+        sets.extend(file.mutation_sets(limits.exec_timeout));
+    }
+    if sets.is_empty() {
+        sets.push(MutationSet::empty_set());
+    }
+
+    Ok(Prepared {
+        words: job.words,
+        tokens,
+        sets,
+        max_words: job.max_words,
+    })
+}
+
+/// Send the words of a job as they're made, a line each, and how many
+/// there were
+fn generate(job: &Prepared, stream: &mut TcpStream) -> io::Result<u64> {
+    let mut out = BufWriter::with_capacity(CHUNK_BYTES + 16, stream);
+    out.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+          Transfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+    )?;
+
+    let max_words = job.max_words.unwrap_or(u64::MAX);
+    let mut chunk = Vec::with_capacity(CHUNK_BYTES);
+    let mut words = 0;
+    'words: for word in job.input_words() {
+        for set in job.sets() {
+            if words >= max_words {
+                break 'words;
+            }
+            set.perform_streaming(&word, &mut |mutated| {
+                if words < max_words {
+                    chunk.extend_from_slice(mutated.as_bytes());
+                    chunk.push(b'\n');
+                    words += 1;
+                }
+            });
+            if let Some(error) = set.plugin_error() {
+                return Err(io::Error::other(error.to_owned()));
+            }
+        }
+        // a client that went away fails the write, and the job ends
+        if chunk.len() >= CHUNK_BYTES {
+            write_chunk(&mut out, &chunk)?;
+            chunk.clear();
+        }
+    }
+    if !chunk.is_empty() {
+        write_chunk(&mut out, &chunk)?;
+    }
+    out.write_all(b"0\r\n\r\n")?;
+    out.flush()?;
+    Ok(words)
+}
+
+fn write_chunk(out: &mut impl Write, chunk: &[u8]) -> io::Result<()> {
+    write!(out, "{:x}\r\n", chunk.len())?;
+    out.write_all(chunk)?;
+    out.write_all(b"\r\n")?;
+    out.flush()
+}
+
+/// How many words and bytes a job makes, like `--dry-run` tells: the first
+/// input words are mutated, and the rest are taken to make as many
+fn estimate(job: &Prepared) -> Result<serde_json::Value, Refusal> {
+    let pattern_words = job
+        .tokens
+        .as_deref()
+        .map_or(0, |tokens| token_iterator(tokens).calculate_total());
+    let input_words = job.words.len() as u128 + pattern_words;
+
+    let (mut sampled, mut words, mut bytes) = (0u64, 0u64, 0u64);
+    for word in job.input_words().take(ESTIMATE_SAMPLE) {
+        sampled += 1;
+        for set in job.sets() {
+            set.perform_streaming(&word, &mut |mutated| {
+                words += 1;
+                bytes += mutated.len() as u64 + 1;
+            });
+        }
+    }
+    if let Some(error) = job.plugin_error() {
+        return Err((500, error));
+    }
+
+    let scale = match sampled {
+        0 => 0.0,
+        sampled => input_words as f64 / sampled as f64,
+    };
+    let mut estimated_words = (words as f64 * scale).round();
+    let mut estimated_bytes = (bytes as f64 * scale).round();
+    if let Some(max_words) = job.max_words.map(|max| max as f64) {
+        if estimated_words > max_words {
+            estimated_bytes = (estimated_bytes * max_words / estimated_words).round();
+            estimated_words = max_words;
+        }
+    }
+    Ok(json!({
+        "input_words": u64::try_from(input_words).unwrap_or(u64::MAX),
+        "sampled": sampled,
+        "words": estimated_words as u64,
+        "bytes": estimated_bytes as u64,
+    }))
+}
+
+fn respond(stream: &mut TcpStream, status: u16, content_type: &str, body: &[u8]) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        reason(status),
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+fn respond_error(stream: &mut TcpStream, status: u16, message: &str) -> io::Result<()> {
+    let body = json!({ "error": message }).to_string() + "\n";
+    respond(stream, status, "application/json", body.as_bytes())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
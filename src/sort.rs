@@ -0,0 +1,195 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    mem,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+};
+
+/// How `--sort`/`--sort-length` order the output
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    /// Byte by byte, which for UTF-8 is the order of the code points
+    Lexicographic,
+    /// Shortest words first, words of the same length lexicographically
+    Length,
+}
+
+impl SortOrder {
+    pub fn compare(self, a: &[u8], b: &[u8]) -> Ordering {
+        match self {
+            SortOrder::Lexicographic => a.cmp(b),
+            SortOrder::Length => char_count(a).cmp(&char_count(b)).then_with(|| a.cmp(b)),
+        }
+    }
+}
+
+/// The length of a UTF-8 word in characters, without decoding it.
+pub fn char_count(word: &[u8]) -> usize {
+    word.iter().filter(|&&b| b & 0xC0 != 0x80).count()
+}
+
+/// Numbers the temporary files of every sort of the process, as there can
+/// be more than one at a time
+static RUNS: AtomicUsize = AtomicUsize::new(1);
+
+/// What a buffered word costs besides its bytes
+const WORD_OVERHEAD: usize = mem::size_of::<Vec<u8>>();
+
+/// Words collected for sorting. Once they take more than `memory_cap` bytes
+/// they're sorted and spilled to a temporary file, and those files are
+/// merged at the end, so outputs far larger than memory can be sorted.
+pub struct ExternalSort {
+    order: SortOrder,
+    memory_cap: usize,
+    temp_dir: PathBuf,
+    buffer: Vec<Vec<u8>>,
+    buffer_bytes: usize,
+    /// The temporary files holding sorted runs of words
+    runs: Vec<PathBuf>,
+    pub words: u64,
+}
+
+impl ExternalSort {
+    pub fn new(order: SortOrder, memory_cap: usize, temp_dir: PathBuf) -> ExternalSort {
+        ExternalSort {
+            order,
+            memory_cap,
+            temp_dir,
+            buffer: Vec::new(),
+            buffer_bytes: 0,
+            runs: Vec::new(),
+            words: 0,
+        }
+    }
+
+    pub fn push(&mut self, word: &[u8]) -> io::Result<()> {
+        self.buffer.push(word.to_vec());
+        self.buffer_bytes += word.len() + WORD_OVERHEAD;
+        self.words += 1;
+
+        if self.buffer_bytes > self.memory_cap {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// How many temporary files were written so far.
+    pub fn run_count(&self) -> usize {
+        self.runs.len()
+    }
+
+    fn sort_buffer(&mut self) {
+        let order = self.order;
+        self.buffer.sort_unstable_by(|a, b| order.compare(a, b));
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.sort_buffer();
+
+        let path = self.temp_dir.join(format!(
+            "gorilla-sort-{}-{}.tmp",
+            std::process::id(),
+            RUNS.fetch_add(1, AtomicOrdering::Relaxed)
+        ));
+        // pushing it first, so that it's removed even if writing fails
+        self.runs.push(path.clone());
+        let mut run = BufWriter::new(File::create(&path)?);
+        for word in self.buffer.drain(..) {
+            run.write_all(&word)?;
+            run.write_all(b"\n")?;
+        }
+        run.flush()?;
+
+        self.buffer_bytes = 0;
+        Ok(())
+    }
+
+    /// Hand every word to `output` in order.
+    pub fn finish(mut self, output: &mut dyn FnMut(&[u8]) -> io::Result<()>) -> io::Result<()> {
+        self.sort_buffer();
+        if self.runs.is_empty() {
+            return self.buffer.iter().try_for_each(|word| output(word));
+        }
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+
+        let mut runs: Vec<BufReader<File>> = self
+            .runs
+            .iter()
+            .map(|path| File::open(path).map(BufReader::new))
+            .collect::<io::Result<_>>()?;
+
+        let mut heap = BinaryHeap::new();
+        for (run, reader) in runs.iter_mut().enumerate() {
+            if let Some(word) = read_word(reader)? {
+                heap.push(MergeEntry {
+                    word,
+                    run,
+                    order: self.order,
+                });
+            }
+        }
+
+        while let Some(mut entry) = heap.pop() {
+            output(&entry.word)?;
+            if let Some(word) = read_word(&mut runs[entry.run])? {
+                entry.word = word;
+                heap.push(entry);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for ExternalSort {
+    fn drop(&mut self) {
+        for path in &self.runs {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn read_word(run: &mut BufReader<File>) -> io::Result<Option<Vec<u8>>> {
+    let mut word = Vec::new();
+    if run.read_until(b'\n', &mut word)? == 0 {
+        return Ok(None);
+    }
+    word.pop();
+    Ok(Some(word))
+}
+
+/// The next word of one of the runs being merged
+struct MergeEntry {
+    word: Vec<u8>,
+    run: usize,
+    order: SortOrder,
+}
+
+impl Ord for MergeEntry {
+    // reversed, as the heap pops the largest entry first, and words that
+    // are equal come out in run order
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order
+            .compare(&other.word, &self.word)
+            .then_with(|| other.run.cmp(&self.run))
+    }
+}
+
+impl PartialOrd for MergeEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MergeEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MergeEntry {}
@@ -0,0 +1,159 @@
+use serde::Serialize;
+use std::{fs::File, io};
+
+use crate::build_info::BuildInfo;
+
+/// The summary written to `--stats-file`, as pretty-printed JSON.
+///
+/// This is version 1 of the schema. Fields may be added without notice,
+/// but they're never renamed, removed or given another meaning without
+/// `version` going up. Counts are numbers, paths and words strings, and
+/// `null` stands for something that doesn't apply to the run.
+#[derive(Debug, Serialize)]
+pub struct RunStats {
+    /// Always 1 for this schema
+    pub version: u32,
+    /// Whether this was a `--dry-run`. The words are then counted as if
+    /// they were written, only for the input words in the
+    /// `--dry-run-sample` if there was one, and no bytes are written.
+    pub dry_run: bool,
+    /// The limit that ended the run early, like `--max-words 1000`, or
+    /// `Ctrl+C` if it was interrupted
+    pub stopped_by: Option<String>,
+    /// Input words that were mutated, filtered and duplicate ones not
+    /// included
+    pub input_words: u64,
+    /// Words written to the output, how ever `--output-format` grouped them
+    pub written_words: u64,
+    /// How many of the written words were distinct, `null` unless
+    /// `--unique`, `--unique-approx` or `--estimate-unique` was given
+    pub distinct_words: Option<u64>,
+    /// Whether `distinct_words` is an estimate, as it is with
+    /// `--unique-approx` and `--estimate-unique`
+    pub distinct_words_estimated: bool,
+    /// What each mutation set contributed, in the order they were given
+    pub mutation_sets: Vec<MutationSetStats>,
+    /// The words left out as repeats
+    pub duplicates: DuplicateStats,
+    /// The `--policy` and `--policy-file` policies, in the order given
+    pub policies: Vec<PolicyStats>,
+    /// Generated words left out by `--min-strength`, `null` without it
+    pub weak_words: Option<u64>,
+    /// The distinct hashes of `--crack`, `null` without it
+    pub hashes: Option<u64>,
+    /// Of those, the hashes a generated word was found for
+    pub hashes_recovered: Option<u64>,
+    /// The lengths and characters of the generated words, `null` without
+    /// `--report`
+    pub report: Option<ReportStats>,
+    /// Bytes of output before compression
+    pub bytes_written: u64,
+    /// Size of the output files on disk, `null` when writing to stdout
+    pub bytes_on_disk: Option<u64>,
+    /// Every file the output went to, more than one when it was split
+    pub output_files: Vec<String>,
+    /// How long the run took
+    pub runtime_seconds: f64,
+    pub config: RunConfig,
+    /// The gorilla that made the words
+    pub gorilla: BuildInfo,
+    /// When the run started, like `2024-03-01T12:30:00Z`
+    pub started_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MutationSetStats {
+    /// Where the set is among all of them, from 1
+    pub id: usize,
+    /// The mutations, as they're printed in the mutation sets summary
+    pub mutations: Vec<String>,
+    /// Words the set came up with, before `--exclude-output` and `--unique`
+    /// left any out
+    pub generated_words: u64,
+    /// Of those, the words written. With `--unique`, these are the words
+    /// the set contributed that no set had come up with before.
+    pub written_words: u64,
+    /// Bytes of the written words, each with its delimiter, before
+    /// compression
+    pub bytes_written: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateStats {
+    /// Input words skipped by `--dedup-input`/`--dedup-approx`
+    pub inputs: u64,
+    /// Generated words left out by `--unique`/`--unique-approx`
+    pub outputs: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PolicyStats {
+    /// The name of the preset, or the one in the policy file
+    pub name: String,
+    /// Generated words the policy didn't allow, which were left out. A
+    /// word more than one policy didn't allow is counted by each.
+    pub rejected_words: u64,
+}
+
+/// What `--report` found of the generated words. In a `--dry-run` with a
+/// `--dry-run-sample`, these are of the words of the sample.
+#[derive(Debug, Serialize)]
+pub struct ReportStats {
+    /// The lengths in characters there are words of, shortest first
+    pub lengths: Vec<LengthStats>,
+    pub composition: CompositionStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LengthStats {
+    /// In characters
+    pub length: usize,
+    /// How many of the words are that long
+    pub words: u64,
+}
+
+/// The percentages of the generated words with at least one character of
+/// each kind, to a tenth of a percent
+#[derive(Debug, Serialize)]
+pub struct CompositionStats {
+    /// ASCII digits
+    pub digits: f64,
+    /// Lowercase ASCII letters
+    pub lowercase: f64,
+    /// Uppercase ASCII letters
+    pub uppercase: f64,
+    /// ASCII characters that are neither letters nor digits, spaces
+    /// included
+    pub specials: f64,
+    /// Any character that isn't ASCII, letters of other alphabets included
+    pub non_ascii: f64,
+}
+
+/// What the run was asked to do
+#[derive(Debug, Serialize)]
+pub struct RunConfig {
+    /// Wordlists read, after expanding directories and globs
+    pub inputs: Vec<String>,
+    /// Words given on the command line
+    pub words: Vec<String>,
+    /// The `--pattern` the words were generated from
+    pub pattern: Option<String>,
+    /// The `--csv-input` file
+    pub csv_input: Option<String>,
+    /// The `--jsonl-input` file
+    pub jsonl_input: Option<String>,
+    /// The websites scraped, as given
+    pub websites: Vec<String>,
+    /// The `--output-file` as given, `null` when writing to stdout
+    pub output_file: Option<String>,
+    /// The seed everything random was based on, to repeat the run with `--seed`
+    pub seed: u64,
+}
+
+impl RunStats {
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(&file, self)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Serialize;
+use std::thread;
+
+use crate::mutation::MutationResult;
+
+/// How zxcvbn rates `word`, from 0 for guessed right away to 4 for very
+/// hard to guess
+#[cfg(feature = "strength")]
+pub fn score(word: &str) -> u8 {
+    u8::from(zxcvbn::zxcvbn(word, &[]).score())
+}
+
+/// The flags that score words were refused without the `strength` feature
+#[cfg(not(feature = "strength"))]
+pub fn score(_word: &str) -> u8 {
+    unreachable!("{}", crate::arguments::without_feature("strength"))
+}
+
+/// The scores of the words of the results of `perform_in_parallel`, in the
+/// same order, worked out on as many threads as they were mutated on
+pub fn score_in_parallel(results: &[Vec<MutationResult>], threads: usize) -> Vec<Vec<Vec<u8>>> {
+    let score_all = |results: &[Vec<MutationResult>]| -> Vec<Vec<Vec<u8>>> {
+        results
+            .iter()
+            .map(|word_results| {
+                word_results
+                    .iter()
+                    .map(|result| {
+                        result
+                            .mutated_words
+                            .iter()
+                            .map(|word| score(word))
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    if threads <= 1 || results.len() < 2 {
+        return score_all(results);
+    }
+
+    let chunk_size = results.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let workers: Vec<_> = results
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || score_all(chunk)))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("a scoring thread panicked"))
+            .collect()
+    })
+}
+
+/// `{"word":...,"mutations":[...],"strength":[...]}`, the record of
+/// `--annotate-strength`
+#[derive(Serialize)]
+struct Annotated<'a> {
+    word: &'a str,
+    mutations: &'a [String],
+    strength: &'a [u8],
+}
+
+/// Like `MutationResult::to_json`, with the score of every word
+pub fn annotated_json(result: &MutationResult, strengths: &[u8]) -> String {
+    serde_json::to_string(&Annotated {
+        word: &result.original_word,
+        mutations: &result.mutated_words,
+        strength: strengths,
+    })
+    .expect("words are always valid json")
+}
+
+/// Like `MutationResult::to_csv`, with an `original,mutated,strength` row
+/// for every word
+pub fn annotated_csv(result: &MutationResult, strengths: &[u8]) -> String {
+    let mut rows = csv::Writer::from_writer(Vec::new());
+    for (mutated, strength) in result.mutated_words.iter().zip(strengths) {
+        rows.write_record([&result.original_word, mutated, &strength.to_string()])
+            .expect("write failed");
+    }
+    let rows = rows.into_inner().expect("write failed");
+    String::from_utf8(rows).expect("words are valid UTF-8")
+}
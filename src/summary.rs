@@ -0,0 +1,654 @@
+use std::{fs, io::Write, path::Path, thread, time::SystemTime};
+
+use clap::ValueEnum;
+use colored::Colorize;
+
+#[cfg(feature = "scrape")]
+use crate::website_scraper::USER_AGENT;
+use crate::{
+    arguments::ProgramArgs,
+    benchmark,
+    build_info::{self, BuildInfo},
+    config::{self, Source, SETTINGS},
+    dedup::SeenWords,
+    input::{count_lines, InvalidUtf8},
+    mutation::{MutationSet, Trace, ACTIONS, MODIFIERS},
+    output::Unencodable,
+    patterns::TokenIter,
+    pipeline::{DryRun, Gorilla, SetCounts},
+    report::{self, WordReport},
+    stats::{DuplicateStats, MutationSetStats, PolicyStats, RunConfig, RunStats},
+};
+
+/// Finish the output and print the statistics of the run.
+pub fn finish_run(gorilla: &mut Gorilla) {
+    gorilla.mutate_batch();
+    if let Some(progress) = &gorilla.progress {
+        progress.finish(gorilla.mutation_counter, gorilla.bytes_written());
+    }
+    let stdout_bytes = gorilla.stdout_bytes;
+    let output_size = gorilla.finish_output();
+    if gorilla.pipe.is_none() {
+        gorilla.flush_stdout();
+    }
+    gorilla.pipe_status = gorilla.finish_pipe();
+
+    let end_time = SystemTime::now();
+
+    let runtime_dur = end_time
+        .duration_since(gorilla.start_time)
+        .expect("Clock may have gone backwards");
+
+    let interrupted = gorilla.stopped_by.as_deref() == Some("Ctrl+C");
+    eprintln!(
+        "gorilla: {} in {runtime_dur:?}. {} words -> {} words",
+        match interrupted {
+            true => "interrupted".yellow().bold(),
+            false => "finished".green().bold(),
+        },
+        gorilla.word_counter.to_string().red(),
+        gorilla.mutation_counter.to_string().green()
+    );
+
+    // the words left in with --unique are all distinct, the approximate
+    // filter may have taken a few new ones for repeats though
+    let distinct = match (&gorilla.unique_outputs, &gorilla.distinct_outputs) {
+        (Some(SeenWords::Exact(_)), _) => Some((gorilla.mutation_counter, false)),
+        (Some(SeenWords::Approx(_)), _) => Some((gorilla.mutation_counter, true)),
+        (None, Some(distinct_outputs)) => Some((distinct_outputs.count(), true)),
+        (None, None) => None,
+    };
+    if let Some((distinct, estimated)) = distinct {
+        eprintln!(
+            "         {}{} of the generated words are distinct",
+            if estimated { "about " } else { "" },
+            distinct.to_string().green()
+        );
+    }
+
+    match &gorilla.stopped_by {
+        Some(limit) if limit == "--stop-on-all-found" => {
+            eprintln!("         stopped early, every hash was found")
+        }
+        Some(limit) if limit == "--pipe-to" => eprintln!(
+            "         stopped early, the {} command stopped reading",
+            limit.yellow()
+        ),
+        Some(output) if output == "stdout" || output == "--output-file" => eprintln!(
+            "         stopped early, what read {} went away",
+            match output.as_str() {
+                "stdout" => "stdout".yellow().to_string(),
+                _ => format!("the {}", "output file".yellow()),
+            }
+        ),
+        Some(_) if interrupted => {
+            eprintln!("         stopped early, the output is complete up to the last word")
+        }
+        Some(limit) => eprintln!("         stopped early, {} was reached", limit.yellow()),
+        None => {}
+    }
+
+    if let Some(status) = gorilla.pipe_status {
+        let status = match status {
+            0 => status.to_string().green(),
+            _ => status.to_string().red(),
+        };
+        eprintln!("         the --pipe-to command exited with {status}");
+    }
+
+    if let Some(dry_run) = &gorilla.dry_run {
+        report_dry_run(&gorilla.mutation_sets, &gorilla.set_counts, dry_run);
+    }
+
+    if let Some((bytes, parts)) = &output_size {
+        let on_disk: u64 = parts.iter().map(|part| part.on_disk).sum();
+        eprintln!(
+            "         wrote {} bytes to the output, {} on disk",
+            bytes.to_string().green(),
+            on_disk.to_string().green()
+        );
+        if gorilla.program_args.output_split_lines.is_some()
+            || gorilla.program_args.output_split_bytes.is_some()
+        {
+            for part in parts {
+                eprintln!(
+                    "          {} -> {} lines",
+                    part.path.purple(),
+                    part.lines.to_string().green()
+                );
+            }
+        }
+    }
+
+    if gorilla.mutation_sets.len() > 1 && gorilla.dry_run.is_none() {
+        report_set_counts(gorilla);
+    }
+
+    if !gorilla.failed_inputs.is_empty() {
+        eprintln!(
+            "         {} inputs could not be read, the output is missing their words",
+            gorilla.failed_inputs.len().to_string().red()
+        );
+    }
+
+    if let Some((sampled, total)) = gorilla.input_sample {
+        eprintln!(
+            "         only {} of {total} input lines were sampled (--input-sample)",
+            sampled.to_string().red()
+        );
+    }
+
+    if gorilla.input_filter.is_active() {
+        eprintln!(
+            "         filtered out {} input words not matching --input-filter and {} matching --input-filter-not",
+            gorilla.input_filter.rejected_include.to_string().red(),
+            gorilla.input_filter.rejected_exclude.to_string().red()
+        );
+    }
+
+    if gorilla.blocklist.is_some() {
+        eprintln!(
+            "         excluded {} input words and {} generated words",
+            gorilla.excluded_inputs.to_string().red(),
+            gorilla.excluded_outputs.to_string().red()
+        );
+    }
+
+    for (policy, rejected) in &gorilla.policies {
+        eprintln!(
+            "         left out {} generated words the {} policy doesn't allow",
+            rejected.to_string().red(),
+            policy.name.purple()
+        );
+    }
+
+    if let (Some(hashes), Some(path)) = (&gorilla.hashes, &gorilla.program_args.crack) {
+        eprintln!(
+            "         recovered {} of the {} hashes of {}",
+            hashes.found().to_string().green(),
+            hashes.total,
+            path.purple()
+        );
+    }
+
+    if let Some(min) = gorilla.program_args.min_strength {
+        eprintln!(
+            "         left out {} generated words zxcvbn scored below {}",
+            gorilla.weak_outputs.to_string().red(),
+            min.to_string().yellow()
+        );
+    }
+
+    if gorilla.unique_outputs.is_some() {
+        eprintln!(
+            "         suppressed {} duplicate output words",
+            gorilla.duplicate_outputs.to_string().red()
+        );
+    }
+    if let Some(SeenWords::Approx(filter)) = &gorilla.unique_outputs {
+        eprintln!(
+            "         by the end, the filter took new words for duplicates at a {} rate",
+            format_rate(filter.false_positive_rate()).yellow()
+        );
+    }
+
+    if gorilla.unencodable_words > 0 {
+        eprintln!(
+            "         {} {} generated words --output-encoding {} can't encode",
+            match gorilla.program_args.unencodable {
+                Unencodable::Skip => "left out",
+                Unencodable::Hex => "hex-wrapped",
+            },
+            gorilla.unencodable_words.to_string().red(),
+            format!("{:?}", gorilla.program_args.output_encoding).to_lowercase()
+        );
+    }
+
+    if gorilla.seen_inputs.is_some() {
+        eprintln!(
+            "         skipped {} duplicate input words",
+            gorilla.duplicate_inputs.to_string().red()
+        );
+    }
+
+    if gorilla.invalid_lines > 0 {
+        eprintln!(
+            "         {} input lines were not valid UTF-8, {}",
+            gorilla.invalid_lines.to_string().red(),
+            match gorilla.program_args.invalid_utf8 {
+                InvalidUtf8::Lossy => "the invalid bytes were replaced",
+                InvalidUtf8::Skip => "they were skipped",
+                InvalidUtf8::Raw => "they were output unchanged",
+            }
+        );
+    }
+
+    if let Some((first, last)) = gorilla.input_range {
+        eprintln!(
+            "         processed input lines {} to {}, continue with --input-skip {last}",
+            first.to_string().green(),
+            last.to_string().green()
+        );
+    }
+
+    if let Some(scrape_save) = &gorilla.program_args.scrape_save {
+        eprintln!(
+            "         saved {} raw scraped words to {}",
+            gorilla.scrape_saved.to_string().green(),
+            scrape_save.purple()
+        );
+    }
+
+    if let (true, Some(checkpoint)) = (interrupted, gorilla.program_args.checkpoint.clone()) {
+        // the word being mutated when Ctrl+C came isn't in the output
+        gorilla.save_checkpoint(
+            gorilla.current_line.saturating_sub(1),
+            output_size.as_ref().map_or(0, |(bytes, _)| *bytes),
+        );
+        eprintln!(
+            "         saved the checkpoint {}, run the same command again to resume",
+            checkpoint.purple()
+        );
+    } else if let Some(checkpoint) = &gorilla.program_args.checkpoint {
+        match fs::remove_file(checkpoint) {
+            Ok(()) => eprintln!(
+                "         the run is complete, removed the checkpoint {}",
+                checkpoint.purple()
+            ),
+            Err(e) => info!(
+                "gorilla: (warning) could not remove the checkpoint {}: {e}",
+                checkpoint.purple()
+            ),
+        }
+    }
+
+    if let Some(stats_file) = &gorilla.program_args.stats_file {
+        let args = &gorilla.program_args;
+        let stats = RunStats {
+            version: 1,
+            dry_run: args.dry_run,
+            stopped_by: gorilla.stopped_by.clone(),
+            input_words: gorilla.word_counter,
+            written_words: gorilla.mutation_counter,
+            distinct_words: distinct.map(|(distinct, _)| distinct),
+            distinct_words_estimated: distinct.is_some_and(|(_, estimated)| estimated),
+            mutation_sets: gorilla
+                .mutation_sets
+                .iter()
+                .zip(&gorilla.set_counts)
+                .map(|(mutation_set, counts)| MutationSetStats {
+                    id: mutation_set.id,
+                    mutations: mutation_set
+                        .mutations
+                        .iter()
+                        .map(|mutation| mutation.to_string())
+                        .collect(),
+                    generated_words: counts.generated,
+                    written_words: counts.written,
+                    bytes_written: counts.bytes,
+                })
+                .collect(),
+            duplicates: DuplicateStats {
+                inputs: gorilla.duplicate_inputs,
+                outputs: gorilla.duplicate_outputs,
+            },
+            policies: gorilla
+                .policies
+                .iter()
+                .map(|(policy, rejected)| PolicyStats {
+                    name: policy.name.clone(),
+                    rejected_words: *rejected,
+                })
+                .collect(),
+            weak_words: gorilla
+                .program_args
+                .min_strength
+                .map(|_| gorilla.weak_outputs),
+            hashes: gorilla.hashes.as_ref().map(|hashes| hashes.total as u64),
+            hashes_recovered: gorilla.hashes.as_ref().map(|hashes| hashes.found() as u64),
+            report: gorilla.report.as_ref().map(WordReport::stats),
+            bytes_written: output_size
+                .as_ref()
+                .map_or(stdout_bytes, |(bytes, _)| *bytes),
+            bytes_on_disk: output_size
+                .as_ref()
+                .map(|(_, parts)| parts.iter().map(|part| part.on_disk).sum()),
+            output_files: output_size
+                .map(|(_, parts)| parts.into_iter().map(|part| part.path).collect())
+                .unwrap_or_default(),
+            runtime_seconds: runtime_dur.as_secs_f64(),
+            config: RunConfig {
+                inputs: std::mem::take(&mut gorilla.input_paths),
+                words: [args.word.clone(), args.words.clone()].concat(),
+                pattern: args.pattern_input.clone(),
+                csv_input: args.csv_input.clone(),
+                jsonl_input: args.jsonl_input.clone(),
+                websites: args.website_input.clone(),
+                output_file: args.file_save.clone(),
+                seed: gorilla.seed,
+            },
+            gorilla: BuildInfo::get(),
+            started_at: build_info::utc_time(gorilla.start_time),
+        };
+
+        match stats.write(stats_file) {
+            Ok(()) => eprintln!("         wrote the statistics to {}", stats_file.purple()),
+            Err(e) => eprintln!(
+                "gorilla: (error) could not write the statistics to {}: {e}",
+                stats_file.purple()
+            ),
+        }
+    }
+
+    // the files of --output-by-length have the same lengths as the report
+    match &gorilla.report {
+        Some(report) => report.print(),
+        None => report::print_lengths(&gorilla.words_per_length),
+    }
+}
+
+/// A false positive rate as a percentage, like `1%` or `0.013%`.
+pub fn format_rate(rate: f64) -> String {
+    let percent = rate * 100.0;
+    // two significant digits
+    let decimals = (1.0 - percent.log10().floor()).clamp(0.0, 8.0) as usize;
+    let mut formatted = format!("{percent:.decimals$}");
+    if formatted.contains('.') {
+        formatted = formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_owned();
+    }
+    formatted + "%"
+}
+
+/// Run the `--benchmark` workload and print how fast every stage of it
+/// was. The mutations run on `threads` threads as well, or on as many as
+/// there are cores without `--threads`.
+/// Print the words of `trace` below each other, each with the mutation
+/// that made it, and what the next mutations made of them indented below.
+/// The generated words are green.
+pub fn print_trace(trace: &Trace, indent: &str) {
+    if trace.words.is_empty() {
+        eprintln!("{indent}└─ {} -> {}", trace.mutation, "(nothing)".red());
+    }
+    for (i, step) in trace.words.iter().enumerate() {
+        let last = i + 1 == trace.words.len() && trace.skipped == 0;
+        let word = format!("{:?}", step.word);
+        eprintln!(
+            "{indent}{} {} -> {}",
+            if last { "└─" } else { "├─" },
+            trace.mutation,
+            if step.next.is_none() {
+                word.green()
+            } else {
+                word.normal()
+            }
+        );
+        if let Some(next) = &step.next {
+            print_trace(
+                next,
+                &format!("{indent}{}", if last { "   " } else { "│  " }),
+            );
+        }
+    }
+    if trace.skipped > 0 {
+        eprintln!("{indent}└─ ... {} more", trace.skipped);
+    }
+}
+
+/// Print every action of `ACTIONS` with its arguments, what it does and
+/// what it makes of an example word.
+pub fn list_mutations(gorilla: &mut Gorilla) {
+    for action in ACTIONS {
+        let (mutation, word, _) = action.example;
+        let _ = writeln!(
+            gorilla.stdout,
+            "{} — {} — {mutation}: {word} -> {}",
+            action.signature(),
+            action.description,
+            action.example_made()
+        );
+    }
+    let _ = writeln!(gorilla.stdout, "\n{MODIFIERS}");
+}
+
+/// Print the settings the config file can give, as the config file they'd
+/// make, each with a comment on where it came from.
+pub fn show_config(gorilla: &mut Gorilla, config_file: Option<&Path>, from_config: &[&str]) {
+    let args = &gorilla.program_args;
+    let header = match (config_file, args.no_config) {
+        (_, true) => String::from("# the config file isn't read with --no-config"),
+        (Some(path), false) if path.exists() => format!("# config file: {}", path.display()),
+        (Some(path), false) => format!("# config file: {} (there is none)", path.display()),
+        (None, false) => String::from("# no config file, HOME isn't set"),
+    };
+    let quote = |value: &str| toml::Value::from(value).to_string();
+    let _ = writeln!(gorilla.stdout, "{header}");
+
+    for id in SETTINGS {
+        let value = match id {
+            "threads" => Some(args.threads.to_string()),
+            "color" => args.color.to_possible_value().map(|v| quote(v.get_name())),
+            "mutations_file" => args.mutations_file.as_deref().map(quote),
+            #[cfg(feature = "scrape")]
+            "user_agent" => Some(quote(args.user_agent.as_deref().unwrap_or(USER_AGENT))),
+            #[cfg(not(feature = "scrape"))]
+            "user_agent" => args.user_agent.as_deref().map(quote),
+            "output_dir" => args.output_dir.as_deref().map(quote),
+            _ => unreachable!("{id} isn't a setting"),
+        };
+        let key = id.replace('_', "-");
+        let source = match config::source(args, from_config, id) {
+            Source::CommandLine => "command line",
+            Source::ConfigFile => "config file",
+            Source::Default => "default",
+        };
+        let _ = match value {
+            Some(value) => writeln!(
+                gorilla.stdout,
+                "{:<40} # {source}",
+                format!("{key} = {value}")
+            ),
+            None => writeln!(gorilla.stdout, "# {key} isn't set"),
+        };
+    }
+}
+
+pub fn report_benchmark(threads: usize) {
+    let threads = if threads > 1 {
+        threads
+    } else {
+        thread::available_parallelism().map_or(1, |cores| cores.get())
+    };
+    eprintln!(
+        "gorilla: {}, mutating {} words and generating {} from a pattern, nothing is written",
+        "benchmark".yellow().bold(),
+        benchmark::WORDS.to_string().green(),
+        benchmark::PATTERN_WORDS.to_string().green()
+    );
+
+    let stages = benchmark::run(benchmark::WORDS, benchmark::PATTERN_WORDS, threads);
+    let words_width = stages
+        .iter()
+        .map(|stage| stage.words.to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("words".len());
+    eprintln!(
+        " {:<9}  {:>7}  {:>words_width$}  {:>8}  {:>12}",
+        "stage", "threads", "words", "seconds", "words/sec"
+    );
+    for stage in &stages {
+        eprintln!(
+            " {:<9}  {:>7}  {}  {:>8.2}  {}",
+            stage.name,
+            stage.threads,
+            format!("{:>words_width$}", stage.words).green(),
+            stage.elapsed.as_secs_f64(),
+            format!("{:>12.0}", stage.words_per_second()).green()
+        );
+    }
+    if threads == 1 {
+        eprintln!(
+            "         {}",
+            "only one core, give --threads to measure the mutations and the pattern on more threads anyway"
+                .yellow()
+        );
+    }
+}
+
+/// Print a table of what every mutation set contributed, to tell which of
+/// them are worth keeping.
+/// The first `limit` words `mutation_set` makes of the `--preview` word,
+/// for the mutation sets summary
+pub fn preview_line(mutation_set: &MutationSet, word: &str, limit: usize) -> String {
+    let (words, total) = mutation_set.preview(word, limit);
+    let mut made = words.join(", ");
+    if total as usize > words.len() {
+        made.push_str(", …");
+    }
+    format!(
+        "     {} -> {} ({} {})",
+        word.dimmed(),
+        match total {
+            0 => "nothing".yellow().to_string(),
+            _ => made,
+        },
+        total.to_string().green(),
+        if total == 1 { "word" } else { "words" }
+    )
+}
+
+pub fn report_set_counts(gorilla: &Gorilla) {
+    let unique = gorilla.unique_outputs.is_some();
+    let column = |heading: &str, counts: &dyn Fn(&SetCounts) -> u64| {
+        let widest = gorilla.set_counts.iter().map(counts).max().unwrap_or(0);
+        widest.to_string().len().max(heading.len())
+    };
+    let generated_width = column("generated", &|counts| counts.generated);
+    let written_width = column("written", &|counts| counts.written);
+    let bytes_width = column("bytes", &|counts| counts.bytes);
+
+    eprintln!("gorilla: words per mutation set");
+    eprintln!(
+        " {:>3}  {:>generated_width$}  {:>written_width$}  {:>bytes_width$}",
+        "set",
+        "generated",
+        if unique { "unique" } else { "written" },
+        "bytes"
+    );
+    for (mutation_set, counts) in gorilla.mutation_sets.iter().zip(&gorilla.set_counts) {
+        let mutations: Vec<String> = mutation_set
+            .mutations
+            .iter()
+            .map(|mutation| mutation.to_string())
+            .collect();
+        eprintln!(
+            " {:>3}  {}  {}  {}  {} -> {}",
+            mutation_set.id,
+            format!("{:>generated_width$}", counts.generated).green(),
+            format!("{:>written_width$}", counts.written).green(),
+            format!("{:>bytes_width$}", counts.bytes).green(),
+            "word".dimmed(),
+            mutations.join(" -> ").blue()
+        );
+    }
+}
+
+/// Print what a `--dry-run` would have generated, per mutation set and
+/// in total.
+pub fn report_dry_run(mutation_sets: &[MutationSet], set_counts: &[SetCounts], dry_run: &DryRun) {
+    // the rest of the input words would have given as many words on average
+    let scale = match dry_run.sampled {
+        0 => 1.0,
+        sampled => (sampled + dry_run.unsampled) as f64 / sampled as f64,
+    };
+    let estimate = |count: u64| (count as f64 * scale).round() as u64;
+
+    if dry_run.unsampled > 0 {
+        eprintln!(
+            "gorilla: {}, extrapolated from the first {} of {} input words:",
+            "dry run".yellow().bold(),
+            dry_run.sampled.to_string().green(),
+            (dry_run.sampled + dry_run.unsampled).to_string().green()
+        );
+    } else {
+        eprintln!(
+            "gorilla: {}, nothing was written:",
+            "dry run".yellow().bold()
+        );
+    }
+
+    for (mutation_set, counts) in mutation_sets.iter().zip(set_counts) {
+        let mutations: Vec<String> = mutation_set
+            .mutations
+            .iter()
+            .map(|mutation| mutation.to_string())
+            .collect();
+        eprintln!(
+            "         {} -> {} gives {} words, {} bytes",
+            "word".dimmed(),
+            mutations.join(" -> ").blue(),
+            estimate(counts.written).to_string().green(),
+            estimate(counts.bytes).to_string().green()
+        );
+    }
+    if dry_run.raw.0 > 0 {
+        eprintln!(
+            "         lines output as they are give {} words, {} bytes",
+            estimate(dry_run.raw.0).to_string().green(),
+            estimate(dry_run.raw.1).to_string().green()
+        );
+    }
+
+    let words = set_counts.iter().map(|set| set.written).sum::<u64>() + dry_run.raw.0;
+    let bytes = set_counts.iter().map(|set| set.bytes).sum::<u64>() + dry_run.raw.1;
+    let bytes = estimate(bytes);
+    eprintln!(
+        "         in total {} words, {bytes} bytes / {} MB / {} GB / {} TB",
+        estimate(words).to_string().green(),
+        bytes / 1048576,
+        bytes / 1073741824,
+        bytes / 1099511627776
+    );
+}
+
+/// The number of input words, when it can be told before reading them:
+/// for words from the command line, local wordlists read whole, patterns
+/// and number ranges.
+pub fn known_total(
+    args: &ProgramArgs,
+    file_inputs: &[String],
+    pattern: Option<&TokenIter>,
+) -> Option<u64> {
+    if args.csv_input.is_some()
+        || args.jsonl_input.is_some()
+        || args.keyboard_walks.is_some()
+        || args.phrase_input.is_some()
+        || args.prince_input.is_some()
+        || args.profile.is_some()
+        || args.from_formatting.is_some()
+        || !args.website_input.is_empty()
+        || args.website_list.is_some()
+        || !args.sitemap.is_empty()
+        || !args.feed.is_empty()
+        || args.input_skip > 0
+        || args.input_count.is_some()
+        || args.input_sample.is_some()
+    {
+        return None;
+    }
+
+    let mut total = (args.word.len() + args.words.len()) as u128;
+    for input in file_inputs {
+        total += u128::from(count_lines(input)?);
+    }
+    total += pattern.map_or(0, |pattern| pattern.calculate_total());
+    total += args
+        .range_input
+        .iter()
+        .map(|range| range.calculate_total())
+        .sum::<u128>();
+    u64::try_from(total).ok()
+}
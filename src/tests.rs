@@ -1,22 +1,24 @@
 #[cfg(test)]
 mod token_tests {
-    use crate::patterns::{token_iterator, tokenize_format_string, Token};
+    use crate::patterns::{
+        generate_in_parallel, token_iterator, tokenize_format_string, Token, TOKENS,
+    };
 
     #[test]
     fn tokenize_string_repeat() {
-        let tokens = tokenize_format_string("hello{0-9}world");
+        let tokens = tokenize_format_string("hello{0-9}world").unwrap();
         assert_eq!(tokens[1], Token::Repeat(48, 57, 48))
     }
 
     #[test]
     fn tokenize_string_string() {
-        let tokens = tokenize_format_string("hello{0-9}world");
+        let tokens = tokenize_format_string("hello{0-9}world").unwrap();
         assert_eq!(tokens[2], Token::String(String::from("world")))
     }
 
     #[test]
     fn tokenize_execute_letters() {
-        let ac_toks = token_iterator(&tokenize_format_string("{a-z}{a-z}"));
+        let ac_toks = token_iterator(&tokenize_format_string("{a-z}{a-z}").unwrap());
         let result: Vec<String> = ac_toks.collect();
 
         assert_eq!(result.len(), 26 * 26)
@@ -24,25 +26,69 @@ mod token_tests {
 
     #[test]
     fn tokenize_execute_ascii() {
-        let ac_toks = token_iterator(&tokenize_format_string("{ -~}"));
+        let ac_toks = token_iterator(&tokenize_format_string("{ -~}").unwrap());
         let result: Vec<String> = ac_toks.collect();
 
         assert_eq!(result.len(), 95)
     }
+
+    #[test]
+    fn seek_and_parallel_chunks() {
+        let tokens = tokenize_format_string("x{a-c}{d}{10-12}!").unwrap();
+        let all: Vec<String> = token_iterator(&tokens).collect();
+        assert_eq!(all.len(), 90);
+
+        for index in [0, 1, 3, 31, 89] {
+            let mut words = token_iterator(&tokens);
+            words.seek(index as u128);
+            assert_eq!(words.collect::<Vec<String>>(), all[index..]);
+        }
+        let mut words = token_iterator(&tokens);
+        words.seek(90);
+        assert_eq!(words.next(), None);
+
+        let chunks = generate_in_parallel(&tokens, 5, 80, 3);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.concat(), all[5..85]);
+        assert_eq!(generate_in_parallel(&tokens, 88, 2, 4).concat(), all[88..]);
+    }
+
+    #[test]
+    fn listed_examples() {
+        for token in TOKENS {
+            let (pattern, made) = token.example;
+            let words: Vec<String> =
+                token_iterator(&tokenize_format_string(pattern).unwrap()).collect();
+            assert_eq!(words, made, "{} doesn't make its example", token.syntax);
+        }
+    }
 }
 
 #[cfg(test)]
 mod mutation_tests {
-    use crate::mutation::{Action, Mutation, MutationResult, MutationSet};
+    use std::{
+        collections::HashSet,
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        mutation::{
+            parse_mutation_string, perform_in_parallel, Action, Affixes, Mutation, MutationResult,
+            MutationSet, ACTIONS, COMMON_SUFFIXES, DEFAULT_COMMON_SUFFIXES,
+        },
+        random::Random,
+    };
 
     #[test]
     fn basic_mutations() {
         let mut mutation_result = MutationResult {
             original_word: String::from("word"),
             mutated_words: vec![],
+            mutation_set: 0,
         };
 
         let mutation_set = MutationSet {
+            id: 1,
             mutations: vec![
                 Mutation {
                     action: Action::Reverse,
@@ -50,12 +96,12 @@ mod mutation_tests {
                     keep_original: false,
                 },
                 Mutation {
-                    action: Action::Append(String::from("abc")),
+                    action: Action::Append(Affixes::new("abc").unwrap()),
                     times: 1,
                     keep_original: false,
                 },
                 Mutation {
-                    action: Action::Prepend(String::from("abc")),
+                    action: Action::Prepend(Affixes::new("abc").unwrap()),
                     times: 1,
                     keep_original: false,
                 },
@@ -64,7 +110,8 @@ mod mutation_tests {
 
         mutation_set.perform(&mut mutation_result, "word");
 
-        assert_eq!(mutation_result.mutated_words, vec!["abcdrowabc"])
+        assert_eq!(mutation_result.mutated_words, vec!["abcdrowabc"]);
+        assert_eq!(mutation_result.mutation_set, 1)
     }
 
     #[test]
@@ -72,11 +119,13 @@ mod mutation_tests {
         let mut mutation_result = MutationResult {
             original_word: String::from("word"),
             mutated_words: vec![],
+            mutation_set: 0,
         };
 
         let mutation_set = MutationSet {
+            id: 1,
             mutations: vec![Mutation {
-                action: Action::Append(String::from("{0-9}")),
+                action: Action::Append(Affixes::new("{0-9}").unwrap()),
                 times: 1,
                 keep_original: false,
             }],
@@ -92,9 +141,281 @@ mod mutation_tests {
             ]
         )
     }
+
+    fn mutation_sets(sets: &[&[&str]]) -> Vec<MutationSet> {
+        sets.iter()
+            .enumerate()
+            .map(|(id, mutations)| MutationSet {
+                id: id + 1,
+                mutations: parse_mutation_string(mutations).unwrap(),
+            })
+            .collect()
+    }
+
+    fn flatten(results: Vec<Vec<MutationResult>>) -> Vec<(String, usize, Vec<String>)> {
+        results
+            .into_iter()
+            .flatten()
+            .map(|result| {
+                (
+                    result.original_word,
+                    result.mutation_set,
+                    result.mutated_words,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parallel_mutations_keep_their_order() {
+        let sets = mutation_sets(&[&["append:{0-9}"], &["reverse"], &["nothing"]]);
+        let words: Vec<String> = (0..1001).map(|n| format!("word{n}")).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        let one_thread = flatten(perform_in_parallel(&sets, &words, 1));
+        assert_eq!(one_thread.len(), 3003);
+        assert_eq!(one_thread[4].0, "word1");
+        assert_eq!(one_thread[4].1, 2);
+        assert_eq!(one_thread[4].2, ["1drow"]);
+        for threads in [2, 3, 8, 2000] {
+            assert_eq!(
+                flatten(perform_in_parallel(&sets, &words, threads)),
+                one_thread
+            );
+        }
+        assert!(perform_in_parallel(&sets, &[], 4).is_empty());
+    }
+
+    #[test]
+    fn streaming_mutations() {
+        let sets = mutation_sets(&[
+            &["append:{0-9}", "2 prepend:{ab}", "if_length:>7"],
+            &["clone", "reverse", "remove_first_letter"],
+            &["if_contains:x", "uppercase_all"],
+            &["wipe"],
+        ]);
+        for word in ["word", "wörd", "x", ""] {
+            for mutation_set in &sets {
+                let mut collected = MutationResult {
+                    original_word: word.to_owned(),
+                    mutated_words: vec![],
+                    mutation_set: 0,
+                };
+                mutation_set.perform(&mut collected, word);
+
+                let mut streamed: Vec<String> = vec![];
+                mutation_set
+                    .perform_streaming(word, &mut |mutated| streamed.push(mutated.to_owned()));
+                assert_eq!(streamed, collected.mutated_words);
+            }
+        }
+    }
+
+    #[test]
+    fn listed_examples() {
+        for action in ACTIONS {
+            let (mutation, word, expected) = action.example;
+            assert!(mutation.contains(action.name), "{mutation}");
+            let mut made = vec![];
+            mutation_sets(&[&[mutation]])[0]
+                .perform_streaming(word, &mut |mutated| made.push(mutated.to_owned()));
+            assert_eq!(made, expected, "{mutation}");
+        }
+
+        // too few arguments for the signature
+        assert!(parse_mutation_string(&["replace:a"]).is_err());
+        assert!(parse_mutation_string(&["shuffle"]).is_err());
+    }
+
+    #[test]
+    fn random_mutations() {
+        let made = |mutation: &str, seed: u64, words: &[&str]| {
+            let mut sets = mutation_sets(&[&[mutation]]);
+            sets[0].set_random(Random::new(seed));
+            let mut made = vec![];
+            for word in words {
+                sets[0].perform_streaming(word, &mut |mutated| made.push(mutated.to_owned()));
+            }
+            made
+        };
+        let words: Vec<String> = (0..1000).map(|n| format!("password{n}")).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        let scrambled = made("scramble", 1, &words);
+        assert_eq!(scrambled, made("scramble", 1, &words));
+        assert_ne!(scrambled, made("scramble", 2, &words));
+        for (word, scrambled) in words.iter().zip(&scrambled) {
+            let mut letters: Vec<char> = word.chars().collect();
+            let mut scrambled: Vec<char> = scrambled.chars().collect();
+            letters.sort();
+            scrambled.sort();
+            assert_eq!(letters, scrambled);
+        }
+
+        let kept = made("chance:30", 1, &words).len();
+        assert!((200..400).contains(&kept), "{kept}");
+        assert_eq!(made("chance:0", 1, &words), Vec::<String>::new());
+        assert_eq!(made("chance:100", 1, &words), words);
+        assert!(parse_mutation_string(&["chance:101"]).is_err());
+        assert!(mutation_sets(&[&["reverse", "chance:5"]])[0].is_random());
+        assert!(!mutation_sets(&[&["reverse"]])[0].is_random());
+    }
+
+    #[test]
+    fn common_suffixes() {
+        let made = |mutation: &str| {
+            let mut made = vec![];
+            mutation_sets(&[&[mutation]])[0]
+                .perform_streaming("cat", &mut |mutated| made.push(mutated.to_owned()));
+            made
+        };
+        let all = made("common_suffixes");
+        assert_eq!(all.len(), DEFAULT_COMMON_SUFFIXES);
+        assert_eq!(all[..4], ["cat1", "cat123", "cat2", "cat12"]);
+        // the likeliest come first whatever the N
+        assert_eq!(made("common_suffixes:10")[..], all[..10]);
+        assert_eq!(made("common_suffixes:100").len(), COMMON_SUFFIXES.len());
+        assert_eq!(made("2 common_suffixes:2"), ["cat11", "cat123123"]);
+
+        let unique: HashSet<&str> = COMMON_SUFFIXES.iter().copied().collect();
+        assert_eq!(unique.len(), COMMON_SUFFIXES.len());
+        for count in ["0", "101", "many"] {
+            let error = parse_mutation_string(&[format!("common_suffixes:{count}")])
+                .err()
+                .unwrap();
+            assert!(error.contains("from 1 to 100"), "{error}");
+        }
+    }
+
+    #[test]
+    fn exec_plugins() {
+        // every word is answered with two, then an empty line
+        let sets = mutation_sets(&[&[
+            "exec:while read w; do printf '%s\\n%s1\\n\\n' \"$w\" \"$w\"; done",
+            "reverse",
+        ]]);
+        let mut made = vec![];
+        for word in ["cat", "dog"] {
+            sets[0].perform_streaming(word, &mut |mutated| made.push(mutated.to_owned()));
+        }
+        assert_eq!(made, ["tac", "1tac", "god", "1god"]);
+        assert_eq!(sets[0].plugin_error(), None);
+
+        // the colons of the command, and the spaces around them, stay
+        let sets = mutation_sets(&[&["exec:while read w; do echo \"$w:1 : 2\"; echo; done"]]);
+        let mut made = vec![];
+        sets[0].perform_streaming("cat", &mut |mutated| made.push(mutated.to_owned()));
+        assert_eq!(made, ["cat:1 : 2"]);
+
+        let sets = mutation_sets(&[&["exec:read w; exit 3"]]);
+        sets[0].perform_streaming("cat", &mut |_| panic!("nothing is made"));
+        let error = sets[0].plugin_error().unwrap();
+        assert!(error.contains("exit status: 3"), "{error}");
+
+        let mut sets = mutation_sets(&[&["exec:sleep 5"]]);
+        sets[0].set_exec_timeout(Duration::from_millis(100));
+        let start = Instant::now();
+        sets[0].perform_streaming("cat", &mut |_| panic!("nothing is made"));
+        sets[0].perform_streaming("dog", &mut |_| panic!("nothing is made"));
+        assert!(start.elapsed() < Duration::from_secs(2));
+        let error = sets[0].plugin_error().unwrap();
+        assert!(error.contains("didn't answer for \"cat\""), "{error}");
+    }
+
+    #[test]
+    fn explained_steps() {
+        let sets = mutation_sets(&[&["k reverse", "append:{0-9}", "if_contains:c0"]]);
+        let trace = sets[0].explain("cat", 2).unwrap();
+        assert_eq!(trace.mutation, "reverse (keeping original)");
+        let words: Vec<&str> = trace.words.iter().map(|step| step.word.as_str()).collect();
+        assert_eq!(words, ["cat", "tac"]);
+
+        let appended = trace.words[1].next.as_ref().unwrap();
+        assert_eq!(appended.words[0].word, "tac0");
+        assert_eq!(appended.skipped, 8);
+        // the last mutation, which kept tac0 and left out tac1
+        let kept = appended.words[0].next.as_ref().unwrap();
+        assert_eq!(kept.words[0].word, "tac0");
+        assert!(kept.words[0].next.is_none());
+        assert!(appended.words[1].next.as_ref().unwrap().words.is_empty());
+    }
+
+    #[test]
+    fn previewed_words() {
+        let sets = mutation_sets(&[&["k reverse", "append:{0-9}"], &["if_contains:x"]]);
+        let (words, total) = sets[0].preview("password", 3);
+        assert_eq!(words, ["password0", "password1", "password2"]);
+        assert_eq!(total, 20);
+        assert_eq!(sets[1].preview("password", 3), (vec![], 0));
+
+        let line = crate::summary::preview_line(&sets[0], "password", 2);
+        assert!(line.contains("password0, password1, …"), "{line}");
+        assert!(line.contains("20"), "{line}");
+
+        let parse =
+            |args: &[&str]| crate::arguments::ProgramArgs::try_parse_command_line(args).unwrap();
+        let args = parse(&["gorilla", "mutate", "--word", "cat", "--preview"]);
+        assert_eq!(args.preview.as_deref(), Some("password"));
+        let args = parse(&["gorilla", "mutate", "--preview=Summer", "--word", "cat"]);
+        assert_eq!(args.preview.as_deref(), Some("Summer"));
+        assert_eq!(args.preview_limit, 5);
+        // the word after a bare --preview is the word previewed, not an input
+        let args = parse(&["gorilla", "mutate", "--word", "x", "--preview", "admin"]);
+        assert_eq!(args.preview.as_deref(), Some("admin"));
+        assert_eq!(args.word, ["x"]);
+        assert!(args.words.is_empty());
+    }
+
+    #[test]
+    fn prepared_affixes() {
+        let small = Affixes::new("{0-9}!").unwrap();
+        assert_eq!(small.to_string(), "{0-9}!");
+        // more words than are generated ahead of time
+        let sets = mutation_sets(&[&["2 append:{0-9}{0-9}{0-9}{0-9}{0-9}"], &["prepend:{0-9}!"]]);
+
+        let mut appended = vec![];
+        sets[0].perform_streaming("pw", &mut |word| appended.push(word.to_owned()));
+        assert_eq!(appended.len(), 100_000);
+        assert_eq!(appended[0], "pw0000000000");
+        assert_eq!(appended[99_999], "pw9999999999");
+
+        let mut prepended = vec![];
+        sets[1].perform_streaming("pw", &mut |word| prepended.push(word.to_owned()));
+        assert_eq!(prepended[..2], ["0!pw", "1!pw"]);
+    }
+
+    #[test]
+    fn unchanged_words_are_not_copied() {
+        let sets = mutation_sets(&[
+            &["if_length:>3", "if_contains:ban", "nothing"],
+            &["replace:x:y", "lowercase_all", "remove_last_letter"],
+            &["uppercase_all"],
+            &["reverse"],
+        ]);
+        let word = "banana";
+        let borrowed = |set: &MutationSet, word: &str| {
+            let mut same = vec![];
+            set.perform_streaming(word, &mut |mutated| {
+                same.push(mutated.as_ptr() == word.as_ptr())
+            });
+            same
+        };
+        assert_eq!(borrowed(&sets[0], word), [true]);
+        assert_eq!(borrowed(&sets[1], word), [true]);
+        assert_eq!(borrowed(&sets[2], "BANANA"), [true]);
+        assert_eq!(borrowed(&sets[2], word), [false]);
+        assert_eq!(borrowed(&sets[3], "b"), [true]);
+
+        // only ascii is left as it is
+        let mut uppercased = vec![];
+        sets[2].perform_streaming("straße", &mut |mutated| {
+            uppercased.push(mutated.to_owned())
+        });
+        assert_eq!(uppercased, ["STRASSE"]);
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "yaml"))]
 mod yaml_test {
     use crate::{mutation::MutationResult, yaml_parser::get_mutation_sets};
 
@@ -103,23 +424,92 @@ mod yaml_test {
         let mut mutation_result = MutationResult {
             original_word: String::from("word"),
             mutated_words: vec![],
+            mutation_set: 0,
         };
 
         let mutation_sets = get_mutation_sets(
             "name: alphabet
 mutation_sets:
   - [ wipe, \"append:{a-z}\" ] # => a, b, c, ..., z",
-        );
+        )
+        .unwrap();
 
         mutation_sets[0].perform(&mut mutation_result, "word");
 
         assert_eq!(mutation_result.mutated_words.len(), 26);
     }
+
+    #[test]
+    fn invalid_mutation_files() {
+        let error = |yaml: &str| get_mutation_sets(yaml).err().unwrap();
+
+        assert!(error("name: [unclosed").contains("while parsing"));
+        assert_eq!(
+            error("mutation_sets:\n  - [ wipe ]"),
+            "name is missing or isn't a string"
+        );
+        assert_eq!(
+            error("name: x\nmutation_sets:\n  - wipe"),
+            "mutation set 1 isn't a list"
+        );
+        assert_eq!(
+            error("name: x\nmutation_sets:\n  - [ \"if_length:>x\" ]"),
+            "couldn't build mutation if_length:>x (invalid argument: x is not a length)"
+        );
+        assert_eq!(
+            error("name: x\nmutation_sets:\n  - [ \"if_length:\" ]"),
+            "couldn't build mutation if_length: (invalid argument: missing operator)"
+        );
+        assert_eq!(
+            error("name: x\ncommon_suffixes: []\nmutation_sets:\n  - [ common_suffixes ]"),
+            "common_suffixes is empty"
+        );
+    }
+
+    #[test]
+    fn own_common_suffixes() {
+        let yaml = "name: corp
+common_suffixes: [\"2024!\", \"@corp\", \"1\"]
+mutation_sets:
+  - [ common_suffixes ]
+  - [ \"common_suffixes:2\" ]";
+        let mut made = vec![];
+        for mutation_set in get_mutation_sets(yaml).unwrap() {
+            mutation_set.perform_streaming("jane", &mut |word| made.push(word.to_owned()));
+        }
+        assert_eq!(
+            made,
+            ["jane2024!", "jane@corp", "jane1", "jane2024!", "jane@corp"]
+        );
+
+        let error = get_mutation_sets(&yaml.replace("common_suffixes:2", "common_suffixes:4"))
+            .err()
+            .unwrap();
+        assert!(error.contains("from 1 to 3"), "{error}");
+    }
+
+    #[test]
+    fn structured_results() {
+        let mutation_result = MutationResult {
+            original_word: String::from("say \"hi\", admin"),
+            mutated_words: vec![String::from("Admin!"), String::from("line\nbreak")],
+            mutation_set: 0,
+        };
+
+        assert_eq!(
+            mutation_result.to_json(),
+            r#"{"word":"say \"hi\", admin","mutations":["Admin!","line\nbreak"]}"#
+        );
+        assert_eq!(
+            mutation_result.to_csv(),
+            "\"say \"\"hi\"\", admin\",Admin!\n\"say \"\"hi\"\", admin\",\"line\nbreak\"\n"
+        );
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "scrape"))]
 mod scrape_tests {
-    use crate::website_scraper::just_body_html_content;
+    use crate::website_scraper::{extract_words, just_body_html_content, ScrapeOptions};
     #[test]
     fn basic_scrape() {
         let html = "<!doctype html><html><head></head></body> \
@@ -164,4 +554,3569 @@ mod scrape_tests {
         assert!(!content.contains("javascript"));
         assert!(!content.contains("second"));
     }
+
+    #[test]
+    fn extract_words_skips_scripts() {
+        let html = "<html><head><title>Heading</title></head><body>\
+        <div><p>Silverback <script>var javascript = 1;</script>gorillas</p></div>\
+        <script>document.write('trailing')</script></body></html>";
+        let words: Vec<String> = extract_words(html, &ScrapeOptions::default())
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        assert_eq!(words, vec!["gorillas", "silverback"]);
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod robots_tests {
+    use crate::website_scraper::{parse_robots, split_url, USER_AGENT};
+
+    #[test]
+    fn wildcard_group() {
+        // trimmed down from wikipedia's robots.txt
+        let robots = "# robots.txt for http://www.wikipedia.org/
+User-agent: MJ12bot
+Disallow: /
+
+User-agent: *
+Allow: /w/api.php?action=mobileview&
+Disallow: /w/
+Disallow: /api/
+Disallow: /trap/
+Disallow: /wiki/Special:";
+        let rules = parse_robots(robots, USER_AGENT);
+
+        assert!(rules.is_allowed("/wiki/Gorilla"));
+        assert!(!rules.is_allowed("/w/index.php"));
+        assert!(!rules.is_allowed("/wiki/Special:Random"));
+        assert!(rules.is_allowed("/w/api.php?action=mobileview&page=1"));
+    }
+
+    #[test]
+    fn specific_group_wins() {
+        let robots = "User-agent: *
+Disallow: /
+
+User-agent: Googlebot
+User-agent: gorilla
+Disallow: /private";
+        let rules = parse_robots(robots, USER_AGENT);
+
+        assert!(rules.is_allowed("/about"));
+        assert!(!rules.is_allowed("/private/index.html"));
+    }
+
+    #[test]
+    fn wildcards_and_anchors() {
+        // patterns similar to the ones found in github's robots.txt
+        let robots = "User-agent: *
+Disallow: /*/pulse
+Disallow: /*.json$
+Disallow: /search$
+Disallow:";
+        let rules = parse_robots(robots, USER_AGENT);
+
+        assert!(!rules.is_allowed("/d4rckh/gorilla/pulse"));
+        assert!(!rules.is_allowed("/api/data.json"));
+        assert!(rules.is_allowed("/api/data.json?page=2"));
+        assert!(!rules.is_allowed("/search"));
+        assert!(rules.is_allowed("/search/advanced"));
+    }
+
+    #[test]
+    fn empty_robots_allows_everything() {
+        let rules = parse_robots("", USER_AGENT);
+        assert!(rules.is_allowed("/anything"));
+    }
+
+    #[test]
+    fn url_splitting() {
+        assert_eq!(
+            split_url("https://example.org/a/b?c=d"),
+            Some((
+                String::from("https://example.org"),
+                String::from("/a/b?c=d")
+            ))
+        );
+        assert_eq!(
+            split_url("https://example.org"),
+            Some((String::from("https://example.org"), String::from("/")))
+        );
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod sitemap_tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use crate::website_scraper::{decode_sitemap_body, parse_sitemap, Sitemap};
+
+    const URLSET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url>
+    <loc>https://example.org/</loc>
+    <lastmod>2022-06-04</lastmod>
+  </url>
+  <url>
+    <loc> https://example.org/search?q=gorilla&amp;page=2 </loc>
+  </url>
+  <url><loc><![CDATA[https://example.org/about]]></loc></url>
+</urlset>"#;
+
+    const INDEX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap>
+    <loc>https://example.org/sitemap-posts.xml.gz</loc>
+  </sitemap>
+  <sitemap>
+    <loc>https://example.org/sitemap-pages.xml</loc>
+  </sitemap>
+</sitemapindex>"#;
+
+    #[test]
+    fn parse_urlset() {
+        assert_eq!(
+            parse_sitemap(URLSET),
+            Sitemap::UrlSet(vec![
+                String::from("https://example.org/"),
+                String::from("https://example.org/search?q=gorilla&page=2"),
+                String::from("https://example.org/about"),
+            ])
+        )
+    }
+
+    #[test]
+    fn parse_index() {
+        assert_eq!(
+            parse_sitemap(INDEX),
+            Sitemap::Index(vec![
+                String::from("https://example.org/sitemap-posts.xml.gz"),
+                String::from("https://example.org/sitemap-pages.xml"),
+            ])
+        )
+    }
+
+    #[test]
+    fn gzipped_sitemap() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(URLSET.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(decode_sitemap_body(&compressed).unwrap(), URLSET);
+        assert_eq!(decode_sitemap_body(URLSET.as_bytes()).unwrap(), URLSET);
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod feed_tests {
+    use crate::website_scraper::parse_feed;
+
+    #[test]
+    fn rss_feed() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+<channel>
+  <title>Primate News</title>
+  <link>https://example.com/</link>
+  <atom:link href="https://example.com/feed.xml" rel="self"/>
+  <description>Updates from the sanctuary</description>
+  <item>
+    <title>Silverback Rescue</title>
+    <link>https://example.com/posts/rescue</link>
+    <description>&lt;p&gt;Our &lt;b&gt;keepers&lt;/b&gt; rescued a silverback&lt;/p&gt;</description>
+    <category>Conservation</category>
+  </item>
+  <item>
+    <title><![CDATA[Bananas & Enrichment]]></title>
+    <link>https://example.com/posts/bananas</link>
+    <description><![CDATA[<p>Feeding <em>schedules</em></p>]]></description>
+  </item>
+</channel>
+</rss>"#;
+        let feed = parse_feed(xml);
+
+        for expected in [
+            "Primate News",
+            "Updates from the sanctuary",
+            "Silverback Rescue",
+            "keepers",
+            "Bananas & Enrichment",
+            "schedules",
+            "Conservation",
+        ] {
+            assert!(feed.text.contains(&String::from(expected)), "{expected}");
+        }
+        assert!(!feed.text.iter().any(|t| t.contains('<')));
+        // the channel's own link isn't an entry
+        assert_eq!(
+            feed.links,
+            vec![
+                "https://example.com/posts/rescue",
+                "https://example.com/posts/bananas"
+            ]
+        );
+    }
+
+    #[test]
+    fn atom_feed() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Gorilla Journal</title>
+  <link href="https://example.org/" />
+  <entry>
+    <title type="html">Mountain Gorillas</title>
+    <link rel="alternate" href="https://example.org/2024/mountain" />
+    <link rel="edit" href="https://example.org/edit/1" />
+    <summary type="html">&lt;p&gt;Counting the &lt;i&gt;population&lt;/i&gt;&lt;/p&gt;</summary>
+    <category term="fieldwork" />
+    <category term="habitat"/>
+  </entry>
+  <entry>
+    <title>Lowland Troops</title>
+    <link href='https://example.org/2024/lowland'/>
+  </entry>
+</feed>"#;
+        let feed = parse_feed(xml);
+
+        for expected in [
+            "Gorilla Journal",
+            "Mountain Gorillas",
+            "population",
+            "fieldwork",
+            "habitat",
+            "Lowland Troops",
+        ] {
+            assert!(feed.text.contains(&String::from(expected)), "{expected}");
+        }
+        assert_eq!(
+            feed.links,
+            vec![
+                "https://example.org/2024/mountain",
+                "https://example.org/2024/lowland"
+            ]
+        );
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod meta_scrape_tests {
+    use crate::website_scraper::{extract_words, ScrapeOptions};
+
+    const PAGE: &str = "<!doctype html><html><head>\
+        <title>Gorillas Sanctuary</title>\
+        <meta name=\"description\" content=\"Protecting mountain primates\">\
+        <meta name=\"Keywords\" content=\"silverback,rainforest\">\
+        </head><body>\
+        <p>Visit our visitors center</p>\
+        <img src=\"a.png\" alt=\"Bananas\" title=\"feeding\">\
+        </body></html>";
+
+    #[test]
+    fn meta_words_ignored_by_default() {
+        let words: Vec<String> = extract_words(PAGE, &ScrapeOptions::default())
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        assert!(words.contains(&String::from("visitors")));
+        assert!(!words.contains(&String::from("sanctuary")));
+        assert!(!words.contains(&String::from("bananas")));
+    }
+
+    #[test]
+    fn meta_words_extracted() {
+        let words: Vec<String> = extract_words(
+            PAGE,
+            &ScrapeOptions {
+                meta: true,
+                ..Default::default()
+            },
+        )
+        .into_iter()
+        .map(|(word, _)| word)
+        .collect();
+
+        for expected in [
+            "visitors",
+            "gorillas",
+            "sanctuary",
+            "mountain",
+            "primates",
+            "silverback",
+            "rainforest",
+            "bananas",
+            "feeding",
+        ] {
+            assert!(words.contains(&String::from(expected)), "{expected}");
+        }
+
+        // merged and deduplicated with the body words
+        assert_eq!(words.iter().filter(|w| *w == "visitors").count(), 1);
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod email_scrape_tests {
+    use crate::website_scraper::{email_usernames, extract_emails, extract_words, ScrapeOptions};
+
+    #[test]
+    fn emails_from_text_and_mailto() {
+        let html = "<html><body>\
+            <p>Contact John.Smith@Example.org for details</p>\
+            <a href=\"mailto:sales@example.org?subject=Hello\">write to us</a>\
+            <img src=\"logo@2x.png\">\
+            </body></html>";
+
+        assert_eq!(
+            extract_emails(html),
+            vec!["john.smith@example.org", "sales@example.org"]
+        );
+    }
+
+    #[test]
+    fn usernames_from_email() {
+        assert_eq!(
+            email_usernames("john.smith@example.org"),
+            vec!["john.smith", "johnsmith", "john", "smith"]
+        );
+        assert_eq!(email_usernames("admin+news@example.org"), vec!["admin"]);
+    }
+
+    #[test]
+    fn emails_arent_words() {
+        let html = "<p>Write to bob.smith@acme.test today, or to sales@acme.test</p>";
+        let words: Vec<String> = extract_words(html, &ScrapeOptions::default())
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+        assert_eq!(words, ["today", "write"]);
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod timeout_tests {
+    use std::{net::TcpListener, thread, time::Duration};
+
+    use crate::website_scraper::{build_client, download_page, HttpOptions, ScrapeError};
+
+    #[test]
+    fn hanging_server_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        // accept the connection and never answer
+        thread::spawn(move || {
+            let (_stream, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_secs(10));
+        });
+
+        let client = build_client(&HttpOptions {
+            timeout: Some(Duration::from_millis(300)),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let result = download_page(&client, &format!("http://{address}/")).map(|p| p.body);
+        assert!(matches!(
+            result,
+            Err(ScrapeError::Http(ureq::Error::Timeout(_)))
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod scrape_case_tests {
+    use crate::website_scraper::{extract_words, ScrapeOptions, WordCase};
+
+    const PAGE: &str = "<html><body> \
+        <h1>Admin Portal</h1> \
+        <p>Contact the ADMIN or an admin, PORTAL access for Admins</p> \
+        </body></html>";
+
+    #[test]
+    fn lowercase_by_default() {
+        let words: Vec<String> = extract_words(PAGE, &ScrapeOptions::default())
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        assert_eq!(
+            words,
+            vec!["access", "admin", "admins", "contact", "portal"]
+        );
+    }
+
+    #[test]
+    fn keep_first_casing() {
+        let options = ScrapeOptions {
+            case: WordCase::First,
+            ..Default::default()
+        };
+        let words: Vec<String> = extract_words(PAGE, &options)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        assert_eq!(
+            words,
+            vec!["access", "Admin", "Admins", "Contact", "Portal"]
+        );
+    }
+
+    #[test]
+    fn clean_word() {
+        let options = ScrapeOptions {
+            case: WordCase::First,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.clean_word("Gorilla's"),
+            Some(String::from("Gorillas"))
+        );
+        assert_eq!(options.clean_word("(tiny)"), None);
+        assert_eq!(options.dedup_key("ADMIN"), options.dedup_key("admin"));
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod ngram_tests {
+    use crate::website_scraper::{extract_words, ScrapeOptions, WordCase};
+
+    const PAGE: &str = "<html><body>\
+        <h1>Acme Widgets</h1>\
+        <p>Buy Acme Widgets today. Gorilla Glue</p>\
+        </body></html>";
+
+    #[test]
+    fn ngrams_joined_both_ways() {
+        let options = ScrapeOptions {
+            case: WordCase::First,
+            ngrams: 2,
+            ..Default::default()
+        };
+        let words: Vec<String> = extract_words(PAGE, &options)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        assert!(words.contains(&String::from("Acme Widgets")));
+        assert!(words.contains(&String::from("AcmeWidgets")));
+        assert!(words.contains(&String::from("Gorilla Glue")));
+    }
+
+    #[test]
+    fn ngrams_respect_boundaries() {
+        let options = ScrapeOptions {
+            ngrams: 3,
+            ..Default::default()
+        };
+        let words: Vec<String> = extract_words(PAGE, &options)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        assert!(words.contains(&String::from("acmewidgets")));
+        assert!(words.contains(&String::from("buy acme widgets")));
+        // heading and paragraph are different elements
+        assert!(!words.contains(&String::from("widgets buy")));
+        // the period ends the sentence
+        assert!(!words.contains(&String::from("today gorilla")));
+        // text from different elements is no longer glued together
+        assert!(!words.contains(&String::from("widgetsbuy")));
+    }
+
+    #[test]
+    fn words_are_counted() {
+        let page = "<html><body><p>Silverback gorillas</p>\
+        <p>Every silverback leads, silverback!</p></body></html>";
+        let words = extract_words(page, &ScrapeOptions::default());
+
+        assert!(words.contains(&(String::from("silverback"), 3)));
+        assert!(words.contains(&(String::from("gorillas"), 1)));
+    }
+
+    #[test]
+    fn entities_decoded() {
+        let page = "<html><body><p>Bienvenue au caf&eacute;s, don&#39;t miss \
+        Salt&amp;Pepper or Silverback&nbsp;gorillas &#x47;iants</p></body></html>";
+        let words: Vec<String> = extract_words(page, &ScrapeOptions::default())
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        for expected in ["cafés", "pepper", "silverback", "gorillas", "giants"] {
+            assert!(words.contains(&String::from(expected)), "{expected}");
+        }
+        assert!(!words
+            .iter()
+            .any(|w| w.contains("amp") || w.contains("nbsp")));
+        assert!(!words.contains(&String::from("saltpepper")));
+    }
+
+    #[test]
+    fn numbers_extracted() {
+        let page = "<html><body><p>Founded in 1987, agent 007 at 90210.</p>\
+        <p>Call 5551234567 or try v2.1</p></body></html>";
+        let options = ScrapeOptions {
+            numbers: true,
+            ..Default::default()
+        };
+        let words: Vec<String> = extract_words(page, &options)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        for expected in ["1987", "007", "90210", "founded"] {
+            assert!(words.contains(&String::from(expected)), "{expected}");
+        }
+        assert!(!words.iter().any(|w| w.starts_with("555")));
+        assert!(!words.contains(&String::from("1")));
+
+        let words = extract_words(page, &ScrapeOptions::default());
+        assert!(!words.iter().any(|(w, _)| w == "1987"));
+    }
+
+    #[test]
+    fn no_ngrams_by_default() {
+        let words: Vec<String> = extract_words(PAGE, &ScrapeOptions::default())
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+        assert!(words.iter().all(|w| !w.contains(' ')));
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod local_scrape_tests {
+    use std::{env, fs, path::PathBuf};
+
+    use crate::website_scraper::{html_files, local_path};
+
+    #[test]
+    fn local_sources() {
+        assert_eq!(local_path("https://example.org/"), None);
+        assert_eq!(
+            local_path("file:///srv/mirror/index.html"),
+            Some(PathBuf::from("/srv/mirror/index.html"))
+        );
+        assert_eq!(local_path("definitely/not/a/real/path.html"), None);
+    }
+
+    #[test]
+    fn walk_html_files() {
+        let dir = env::temp_dir().join(format!("gorilla-html-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("index.html"), "<p>hello</p>").unwrap();
+        fs::write(dir.join("sub").join("about.HTM"), "<p>about</p>").unwrap();
+        fs::write(dir.join("logo.png"), [0u8, 1, 2]).unwrap();
+
+        let files = html_files(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            files.files,
+            vec![dir.join("index.html"), dir.join("sub").join("about.HTM")]
+        );
+        assert_eq!(files.skipped, vec![dir.join("logo.png")]);
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod json_scrape_tests {
+    use crate::website_scraper::{extract_json_words, ScrapeOptions};
+
+    const JSON: &str = r#"{
+        "company_name": "Gorilla Industries",
+        "products": [
+            { "title": "Banana Launcher", "price": 15, "tags": ["jungle", "fruits"] },
+            { "title": "Silverback Deluxe", "available": true }
+        ]
+    }"#;
+
+    #[test]
+    fn keys_and_strings() {
+        let words: Vec<String> = extract_json_words(JSON, &ScrapeOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        assert_eq!(
+            words,
+            vec![
+                "available",
+                "banana",
+                "company",
+                "deluxe",
+                "fruits",
+                "gorilla",
+                "industries",
+                "jungle",
+                "launcher",
+                "price",
+                "products",
+                "silverback",
+                "title"
+            ]
+        );
+    }
+
+    #[test]
+    fn size_limit() {
+        let options = ScrapeOptions {
+            max_json_size: Some(16),
+            ..Default::default()
+        };
+
+        assert!(extract_json_words(JSON, &options).unwrap().is_empty());
+        assert!(extract_json_words("{ not json", &ScrapeOptions::default()).is_err());
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod fetch_tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::website_scraper::{build_client, fetch_pages, Fetcher, HttpOptions};
+
+    /// Serve `count` requests with a tiny html page, recording when each arrived.
+    fn serve(count: usize) -> (String, Arc<Mutex<Vec<Instant>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let arrivals = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&arrivals);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().take(count) {
+                let mut stream = stream.unwrap();
+                recorded.lock().unwrap().push(Instant::now());
+                let mut buffer = [0; 1024];
+                let _ = stream.read(&mut buffer);
+                let body = "<p>hello gorillas</p>";
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+
+        (format!("http://{address}"), arrivals)
+    }
+
+    #[test]
+    fn fetches_every_page_once() {
+        let (base, _) = serve(3);
+        let client = build_client(&HttpOptions::default()).unwrap();
+        let sources = vec![
+            format!("{base}/a"),
+            format!("{base}/b"),
+            format!("{base}/a"),
+            format!("{base}/c"),
+        ];
+
+        let mut fetched: Vec<String> = fetch_pages(&client, sources, 4, Duration::ZERO)
+            .into_iter()
+            .map(|(source, page)| {
+                assert!(page.unwrap().body.contains("gorillas"));
+                source
+            })
+            .collect();
+        fetched.sort();
+
+        assert_eq!(
+            fetched,
+            vec![
+                format!("{base}/a"),
+                format!("{base}/b"),
+                format!("{base}/c")
+            ]
+        );
+    }
+
+    #[test]
+    fn delay_holds_from_a_batch_to_the_next() {
+        let (base, arrivals) = serve(2);
+        let client = build_client(&HttpOptions::default()).unwrap();
+        let fetcher = Fetcher::new(&client, 2, Duration::from_millis(150));
+
+        assert_eq!(fetcher.fetch(vec![format!("{base}/a")]).iter().count(), 1);
+        assert_eq!(fetcher.fetch(vec![format!("{base}/b")]).iter().count(), 1);
+
+        let arrivals = arrivals.lock().unwrap();
+        assert!(arrivals[1].duration_since(arrivals[0]) >= Duration::from_millis(140));
+    }
+
+    #[test]
+    fn delay_is_shared_between_workers() {
+        let (base, arrivals) = serve(3);
+        let client = build_client(&HttpOptions::default()).unwrap();
+        let sources = (0..3).map(|i| format!("{base}/{i}")).collect();
+
+        let results = fetch_pages(&client, sources, 3, Duration::from_millis(150)).into_iter();
+        assert_eq!(results.count(), 3);
+
+        let arrivals = arrivals.lock().unwrap();
+        for pair in arrivals.windows(2) {
+            assert!(pair[1].duration_since(pair[0]) >= Duration::from_millis(140));
+        }
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod download_guard_tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use crate::website_scraper::{
+        build_client, download_page, is_binary_url, HttpOptions, ScrapeError,
+    };
+
+    /// Answer a single request with the given content type and body.
+    fn serve_once(content_type: &'static str, body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 1024];
+            let _ = stream.read(&mut buffer);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        });
+
+        format!("http://{address}/")
+    }
+
+    #[test]
+    fn binary_content_type_rejected() {
+        let url = serve_once("application/pdf", String::from("%PDF-1.4"));
+        let client = build_client(&HttpOptions::default()).unwrap();
+
+        assert!(matches!(
+            download_page(&client, &url),
+            Err(ScrapeError::UnsupportedContentType(t)) if t == "application/pdf"
+        ));
+    }
+
+    #[test]
+    fn text_content_type_accepted() {
+        let url = serve_once("text/html; charset=utf-8", String::from("<p>hi</p>"));
+        let client = build_client(&HttpOptions::default()).unwrap();
+        let page = download_page(&client, &url).unwrap();
+
+        assert_eq!(page.content_type.as_deref(), Some("text/html"));
+    }
+
+    #[test]
+    fn download_size_limit() {
+        let url = serve_once("text/html", "gorilla ".repeat(1000));
+        let client = build_client(&HttpOptions {
+            max_download_size: Some(100),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(matches!(
+            download_page(&client, &url),
+            Err(ScrapeError::TooLarge(100))
+        ));
+    }
+
+    #[test]
+    fn binary_urls() {
+        assert!(is_binary_url("https://example.org/logo.PNG"));
+        assert!(is_binary_url("https://example.org/files/setup.exe?v=2"));
+        assert!(!is_binary_url("https://example.org/about.html"));
+        assert!(!is_binary_url("https://example.org/v1.2/docs"));
+        assert!(!is_binary_url("https://example.org"));
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod encoding_tests {
+    use crate::website_scraper::{decode_page_body, extract_words, ScrapeOptions};
+
+    fn words(body: &str) -> Vec<String> {
+        extract_words(body, &ScrapeOptions::default())
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect()
+    }
+
+    #[test]
+    fn charset_from_header() {
+        // "Привет, мир" in windows-1251
+        let mut page = b"<html><body><p>".to_vec();
+        page.extend([
+            0xcf, 0xf0, 0xe8, 0xe2, 0xe5, 0xf2, b',', b' ', 0xec, 0xe8, 0xf0,
+        ]);
+        page.extend(b"</p></body></html>");
+
+        let body = decode_page_body(&page, Some("text/html; charset=windows-1251"));
+        assert_eq!(words(&body), vec!["мир", "привет"]);
+    }
+
+    #[test]
+    fn charset_from_meta_tag() {
+        // "こんにちは" in Shift_JIS
+        let mut page = b"<html><head><meta charset=\"Shift_JIS\"></head><body><p>".to_vec();
+        page.extend([0x82, 0xb1, 0x82, 0xf1, 0x82, 0xc9, 0x82, 0xbf, 0x82, 0xcd]);
+        page.extend(b"</p></body></html>");
+
+        let body = decode_page_body(&page, Some("text/html"));
+        assert_eq!(words(&body), vec!["こんにちは"]);
+
+        let mut page = b"<html><head><meta http-equiv=\"Content-Type\" \
+        content=\"text/html; charset=iso-8859-1\"></head><body><p>Ma"
+            .to_vec();
+        page.extend([0xf1, b'a', b'n', b'a']);
+        page.extend(b"</p></body></html>");
+
+        let body = decode_page_body(&page, None);
+        assert_eq!(words(&body), vec!["mañana"]);
+    }
+
+    #[test]
+    fn undecodable_bytes_are_replaced() {
+        let page = b"<html><body><p>gorilla \xff\xfe\xfd bananas</p></body></html>";
+        let body = decode_page_body(page, None);
+
+        assert!(body.contains('\u{fffd}'));
+        assert_eq!(words(&body), vec!["bananas", "gorilla"]);
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod page_cache_tests {
+    use std::{env, fs, time::Duration};
+
+    use crate::{page_cache::PageCache, website_scraper::Page};
+
+    fn cache(name: &str) -> PageCache {
+        PageCache {
+            dir: env::temp_dir().join(format!("gorilla-cache-{name}-{}", std::process::id())),
+            ttl: Duration::from_secs(60),
+            refresh: false,
+        }
+    }
+
+    fn page(body: &str) -> Page {
+        Page {
+            body: String::from(body),
+            content_type: Some(String::from("text/html")),
+            from_cache: false,
+            cache_error: None,
+        }
+    }
+
+    #[test]
+    fn cached_pages_are_served() {
+        let cache = cache("serve");
+        assert!(cache.get("https://example.org/").is_none());
+
+        cache
+            .put(
+                "https://example.org/",
+                "https://example.org/",
+                &page("<p>silverback</p>\nsecond line"),
+            )
+            .unwrap();
+        let cached = cache.get("https://example.org/").unwrap();
+
+        assert!(cached.from_cache);
+        assert_eq!(cached.body, "<p>silverback</p>\nsecond line");
+        assert_eq!(cached.content_type.as_deref(), Some("text/html"));
+
+        let refreshing = PageCache {
+            refresh: true,
+            ..cache.clone()
+        };
+        assert!(refreshing.get("https://example.org/").is_none());
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn redirects_share_one_copy() {
+        let cache = cache("redirect");
+        let final_url = "https://example.org/home";
+
+        cache
+            .put("http://example.org/", final_url, &page("home"))
+            .unwrap();
+        cache
+            .put("https://example.org/", final_url, &page("home"))
+            .unwrap();
+        cache.put(final_url, final_url, &page("home")).unwrap();
+
+        for url in ["http://example.org/", "https://example.org/", final_url] {
+            assert_eq!(cache.get(url).unwrap().body, "home");
+        }
+        let pages = fs::read_dir(&cache.dir)
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().path().extension().unwrap() == "page")
+            .count();
+        assert_eq!(pages, 1);
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod crawl_tests {
+    use regex::Regex;
+
+    use crate::website_scraper::{extract_links, resolve_url, CrawlFilter};
+
+    #[test]
+    fn resolve_links() {
+        let base = "https://example.org/blog/post.html?page=2";
+
+        for (href, expected) in [
+            ("https://other.org/a", Some("https://other.org/a")),
+            ("//cdn.example.org/x", Some("https://cdn.example.org/x")),
+            ("/about", Some("https://example.org/about")),
+            ("next.html", Some("https://example.org/blog/next.html")),
+            ("../team/", Some("https://example.org/team/")),
+            ("./a/../b#top", Some("https://example.org/blog/b")),
+            ("?page=3", Some("https://example.org/blog/post.html?page=3")),
+            ("#comments", None),
+            ("mailto:ceo@example.org", None),
+            ("javascript:void(0)", None),
+            ("tel:+15555550100", None),
+        ] {
+            assert_eq!(resolve_url(base, href).as_deref(), expected, "{href}");
+        }
+    }
+
+    #[test]
+    fn links_on_a_page() {
+        let html = "<html><body><a href=\"/one\">one</a> \
+        <a href=\"two#x\">two</a><a>no href</a></body></html>";
+
+        assert_eq!(
+            extract_links(html, "http://example.org/dir/"),
+            vec!["http://example.org/one", "http://example.org/dir/two"]
+        );
+    }
+
+    #[test]
+    fn filters_count_rejections() {
+        let mut filter = CrawlFilter {
+            include: vec![Regex::new("/blog/").unwrap()],
+            exclude: vec![Regex::new("logout|calendar").unwrap()],
+            ..Default::default()
+        };
+        let page = "https://example.org/blog/";
+
+        assert!(filter.allows(page, "https://example.org/blog/post"));
+        assert!(!filter.allows(page, "https://mirror.example.net/blog/post"));
+        assert!(!filter.allows(page, "https://example.org/shop"));
+        assert!(!filter.allows(page, "https://example.org/blog/calendar"));
+        assert!(!filter.allows(page, "https://example.org/blog/logout"));
+
+        assert_eq!(filter.rejected_offsite, 1);
+        assert_eq!(filter.rejected_include, 1);
+        assert_eq!(filter.rejected_exclude, 2);
+
+        filter.offsite = true;
+        assert!(filter.allows(page, "https://mirror.example.net/blog/post"));
+    }
+}
+
+#[cfg(test)]
+mod input_tests {
+    use std::{
+        env, fs,
+        io::{BufRead, Write},
+    };
+
+    use flate2::{write::GzEncoder, Compression};
+    use xz2::write::XzEncoder;
+
+    use crate::input::HttpClient;
+    use crate::input::{
+        count_lines, decompressing_reader, display_name, expand_input, open_input, read_line_bytes,
+        InputFilter, InputLine, InputLines, InvalidUtf8, LineRef, Reservoir,
+    };
+    #[cfg(feature = "scrape")]
+    use crate::website_scraper::{build_client, HttpOptions};
+    use rand::{rngs::StdRng, SeedableRng};
+    use regex::Regex;
+
+    /// What the local wordlists are opened with, which downloads nothing
+    fn local_client() -> HttpClient {
+        #[cfg(feature = "scrape")]
+        return build_client(&HttpOptions::default()).unwrap();
+        #[cfg(not(feature = "scrape"))]
+        HttpClient
+    }
+
+    #[test]
+    fn read_input_file() {
+        let path = env::temp_dir().join(format!("gorilla-input-{}.txt", std::process::id()));
+        fs::write(&path, "silverback\nbanana\n").unwrap();
+
+        let client = local_client();
+        let lines: Vec<String> = open_input(path.to_str().unwrap(), &client)
+            .unwrap()
+            .lines()
+            .map(Result::unwrap)
+            .collect();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(lines, vec!["silverback", "banana"]);
+        assert!(open_input("definitely/not/a/wordlist.txt", &client).is_err());
+        assert_eq!(display_name("-"), "standard input");
+    }
+
+    #[test]
+    fn count_input_lines() {
+        let path = env::temp_dir().join(format!("gorilla-count-{}.txt", std::process::id()));
+        let path_str = path.to_str().unwrap();
+
+        fs::write(&path, "silverback\nbanana\n").unwrap();
+        assert_eq!(count_lines(path_str), Some(2));
+        fs::write(&path, "silverback\nbanana").unwrap();
+        assert_eq!(count_lines(path_str), Some(2));
+        fs::write(&path, "").unwrap();
+        assert_eq!(count_lines(path_str), Some(0));
+
+        let mut gzipped = GzEncoder::new(Vec::new(), Compression::default());
+        gzipped.write_all(b"a\nb\nc\n").unwrap();
+        fs::write(&path, gzipped.finish().unwrap()).unwrap();
+        assert_eq!(count_lines(path_str), Some(3));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(count_lines("-"), None);
+        assert_eq!(count_lines("https://example.com/words.txt"), None);
+        assert_eq!(count_lines("definitely/not/a/wordlist.txt"), None);
+    }
+
+    #[test]
+    fn expand_directories_and_globs() {
+        let dir = env::temp_dir().join(format!("gorilla-lists-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("b.txt"), "b\n").unwrap();
+        fs::write(dir.join("a.txt"), "a\n").unwrap();
+        fs::write(dir.join("notes.md"), "c\n").unwrap();
+        fs::write(dir.join(".hidden.txt"), "d\n").unwrap();
+        fs::write(dir.join("image.txt"), [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+        fs::write(dir.join("sub").join("deep.txt"), "e\n").unwrap();
+        let name = |file: &str| dir.join(file).display().to_string();
+        let dir_name = dir.display().to_string();
+
+        let flat = expand_input(&dir_name, false);
+        let recursive = expand_input(&dir_name, true);
+        let globbed = expand_input(&format!("{dir_name}/*.txt"), false);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(flat, vec![name("a.txt"), name("b.txt"), name("notes.md")]);
+        assert_eq!(
+            recursive,
+            vec![
+                name("a.txt"),
+                name("b.txt"),
+                name("notes.md"),
+                name("sub/deep.txt")
+            ]
+        );
+        assert_eq!(globbed, vec![name("a.txt"), name("b.txt")]);
+        assert_eq!(expand_input("-", true), vec!["-"]);
+    }
+
+    fn lines(data: &[u8]) -> Vec<String> {
+        decompressing_reader(data)
+            .unwrap()
+            .lines()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn compressed_inputs() {
+        let words = b"silverback\nbanana\n";
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(words).unwrap();
+        let gzip = gzip.finish().unwrap();
+
+        let mut xz = XzEncoder::new(Vec::new(), 6);
+        xz.write_all(words).unwrap();
+        let xz = xz.finish().unwrap();
+
+        let zstd = zstd::encode_all(&words[..], 3).unwrap();
+
+        for compressed in [&gzip, &xz, &zstd] {
+            assert_eq!(lines(compressed), vec!["silverback", "banana"]);
+        }
+        assert_eq!(lines(words), vec!["silverback", "banana"]);
+    }
+
+    #[test]
+    fn truncated_input_fails() {
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all("gorilla\n".repeat(1000).as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+
+        // a failing decoder keeps failing, so only look until the first error
+        let failed = decompressing_reader(&gzip[..gzip.len() / 2])
+            .unwrap()
+            .lines()
+            .take(2000)
+            .any(|line| line.is_err());
+
+        assert!(failed);
+    }
+
+    #[test]
+    fn lines_as_bytes() {
+        let mut reader: &[u8] = b"caf\xe9\r\nok\n\xff\xfe\nlast";
+        let mut line = Vec::new();
+        let mut lines = Vec::new();
+
+        while read_line_bytes(&mut reader, &mut line).unwrap() {
+            lines.push(line.clone());
+        }
+
+        assert_eq!(
+            lines,
+            vec![
+                b"caf\xe9".to_vec(),
+                b"ok".to_vec(),
+                vec![0xff, 0xfe],
+                b"last".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn input_lines() {
+        let path = env::temp_dir().join(format!("gorilla-lines-{}.txt", std::process::id()));
+        fs::write(&path, b"skipped\nfirst\n\xff\nlast\n").unwrap();
+        let path = path.to_str().unwrap();
+        let client = local_client();
+
+        let mut input = InputLines::open(path, InvalidUtf8::Skip, &client).unwrap();
+        assert_eq!(input.skip_lines(1), 1);
+        let lines: Vec<InputLine> = input.by_ref().collect();
+        assert_eq!(
+            lines,
+            vec![
+                InputLine::Text("first".to_owned()),
+                InputLine::Text("last".to_owned())
+            ]
+        );
+        assert_eq!((input.lines, input.invalid_lines), (3, 1));
+
+        let mut input = InputLines::open(path, InvalidUtf8::Raw, &client).unwrap();
+        assert_eq!(input.skip_lines(5), 4);
+        assert_eq!(input.next(), None);
+        let raw: Vec<InputLine> = InputLines::open(path, InvalidUtf8::Raw, &client)
+            .unwrap()
+            .filter(|line| matches!(line, InputLine::Raw(_)))
+            .collect();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(raw, vec![InputLine::Raw(vec![0xff])]);
+    }
+
+    #[test]
+    fn long_input_lines() {
+        let path = env::temp_dir().join(format!("gorilla-long-lines-{}.txt", std::process::id()));
+        let long = "x".repeat(700_000);
+        fs::write(&path, format!("crlf\r\n{long}\n\nwörd\r\r\n{long}\nend")).unwrap();
+        let path = path.to_str().unwrap();
+        let client = local_client();
+
+        let mut input = InputLines::open(path, InvalidUtf8::Lossy, &client).unwrap();
+        let mut lines = vec![];
+        while let Some((number, line)) = input.next_line() {
+            lines.push((number, line.to_input_line()));
+        }
+        let text = |number, line: &str| (number, InputLine::Text(line.to_owned()));
+        assert_eq!(
+            lines,
+            vec![
+                text(1, "crlf"),
+                text(2, &long),
+                text(3, ""),
+                text(4, "wörd\r"),
+                text(5, &long),
+                text(6, "end"),
+            ]
+        );
+
+        let mut input = InputLines::open(path, InvalidUtf8::Lossy, &client).unwrap();
+        assert_eq!(input.skip_lines(4), 4);
+        assert_eq!(input.next_line(), Some((1, LineRef::Text(&long))));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn filter_input_words() {
+        let mut filter = InputFilter {
+            include: vec![Regex::new("^s").unwrap(), Regex::new("^b").unwrap()],
+            exclude: vec![Regex::new("[0-9]").unwrap()],
+            ..Default::default()
+        };
+
+        let kept: Vec<&str> = ["silverback", "banana", "gorilla", "s3cret", "b4nana"]
+            .into_iter()
+            .filter(|word| filter.allows(word))
+            .collect();
+
+        assert_eq!(kept, vec!["silverback", "banana"]);
+        assert_eq!((filter.rejected_include, filter.rejected_exclude), (1, 2));
+        assert!(!InputFilter::default().is_active());
+    }
+
+    #[test]
+    fn reservoir_sample() {
+        let sample_of = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut reservoir = Reservoir::new(10);
+            for i in 0..1000 {
+                reservoir.add(i, &mut rng);
+            }
+            assert_eq!(reservoir.seen, 1000);
+            reservoir.into_sample()
+        };
+
+        let sample = sample_of(7);
+        assert_eq!(sample.len(), 10);
+        // kept in input order, and the same for the same seed
+        assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(sample, sample_of(7));
+        assert_ne!(sample, sample_of(8));
+        // not simply the first lines
+        assert!(sample.iter().any(|&i| i >= 10));
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut short = Reservoir::new(10);
+        short.add("banana", &mut rng);
+        assert_eq!(short.into_sample(), vec!["banana"]);
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod url_input_tests {
+    use std::{
+        env, fs,
+        io::{BufRead, Read, Write},
+        net::TcpListener,
+        thread,
+        time::Duration,
+    };
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use crate::input::{expand_input, open_input, InputLines, InvalidUtf8};
+    use crate::page_cache::PageCache;
+    use crate::website_scraper::{build_client, HttpOptions};
+
+    /// Answer `count` requests with `headers` and `body`, of which only
+    /// the first `sent` bytes are actually sent.
+    fn serve(count: usize, headers: &'static str, body: Vec<u8>, sent: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().take(count) {
+                let mut stream = stream.unwrap();
+                let mut buffer = [0; 1024];
+                let _ = stream.read(&mut buffer);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(&body[..sent]);
+            }
+        });
+
+        format!("http://{address}/words.txt")
+    }
+
+    #[test]
+    fn gzip_encoded_wordlist() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"silverback\nbanana\n").unwrap();
+        let body = encoder.finish().unwrap();
+        let url = serve(1, "Content-Encoding: gzip\r\n", body.clone(), body.len());
+        let client = build_client(&HttpOptions::default()).unwrap();
+
+        assert_eq!(expand_input(&url, false), vec![url.clone()]);
+        let lines: Vec<String> = open_input(&url, &client)
+            .unwrap()
+            .lines()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(lines, vec!["silverback", "banana"]);
+    }
+
+    #[test]
+    fn broken_off_download() {
+        let body = b"silverback\nbanana\nmountain\n".to_vec();
+        let url = serve(1, "", body, 12);
+        let client = build_client(&HttpOptions::default()).unwrap();
+
+        let lines = open_input(&url, &client).unwrap().lines().take(10);
+        assert!(lines.into_iter().any(|line| line.is_err()));
+    }
+
+    #[test]
+    fn cached_wordlist() {
+        let dir = env::temp_dir().join(format!("gorilla-input-cache-{}", std::process::id()));
+        // only one request is answered, the second read has to come from the cache
+        let url = serve(1, "", b"silverback\nbanana".to_vec(), 17);
+        let mut client = build_client(&HttpOptions::default()).unwrap();
+        client.cache = Some(PageCache {
+            dir: dir.clone(),
+            ttl: Duration::from_secs(60),
+            refresh: false,
+        });
+
+        for _ in 0..2 {
+            let lines: Vec<String> = InputLines::open(&url, InvalidUtf8::Lossy, &client)
+                .unwrap()
+                .map(|line| format!("{line:?}"))
+                .collect();
+            assert_eq!(lines, vec!["Text(\"silverback\")", "Text(\"banana\")"]);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod dedup_tests {
+    use crate::dedup::{BloomFilter, HyperLogLog, SeenWords};
+    use std::collections::HashSet;
+
+    #[test]
+    fn exact_dedup() {
+        let mut seen = SeenWords::Exact(HashSet::new());
+        assert!(seen.insert("banana"));
+        assert!(seen.insert("silverback"));
+        assert!(!seen.insert("banana"));
+        assert!(seen.contains("silverback"));
+        assert!(!seen.contains("gorilla"));
+    }
+
+    #[test]
+    fn bloom_filter() {
+        let mut filter = BloomFilter::new(10_000);
+        // 10 bits per word at 1% false positives
+        assert!(filter.size() > 11_000 && filter.size() < 13_000);
+
+        let new_words = (0..10_000)
+            .filter(|i| filter.insert(&format!("word{i}")))
+            .count();
+        assert!(new_words > 9_800);
+        // no false negatives
+        assert!((0..10_000).all(|i| !filter.insert(&format!("word{i}"))));
+
+        assert!((0..10_000).all(|i| filter.contains(&format!("word{i}"))));
+        let false_positives = (0..10_000)
+            .filter(|i| filter.contains(&format!("other{i}")))
+            .count();
+        assert!(false_positives < 200);
+
+        let mut seen = SeenWords::Approx(BloomFilter::new(100));
+        assert!(seen.insert("banana"));
+        assert!(!seen.insert("banana"));
+        assert!(seen.contains("banana"));
+        assert!(!seen.contains("silverback"));
+    }
+
+    #[test]
+    fn bloom_filter_rate() {
+        // about 14.4 bits per word at 0.1%
+        let mut filter = BloomFilter::with_rate(10_000, 0.001);
+        assert!(filter.size() > 17_000 && filter.size() < 19_000);
+
+        (0..10_000).for_each(|i| {
+            filter.insert(&format!("word{i}"));
+        });
+        let false_positives = (0..10_000)
+            .filter(|i| filter.contains(&format!("other{i}")))
+            .count();
+        assert!(false_positives < 30);
+    }
+
+    #[test]
+    fn bloom_filter_memory() {
+        let mut filter = BloomFilter::with_memory(64 * 1024, 0.01);
+        assert_eq!(filter.size(), 64 * 1024);
+        // 9.6 bits per word at 1%
+        let capacity = filter.capacity(0.01);
+        assert!(capacity > 53_000 && capacity < 56_000, "{capacity}");
+        assert_eq!(filter.false_positive_rate(), 0.0);
+
+        (0..capacity).for_each(|i| {
+            filter.insert(&format!("word{i}"));
+        });
+        assert!((0..capacity).all(|i| filter.contains(&format!("word{i}"))));
+        let rate = filter.false_positive_rate();
+        assert!(rate > 0.007 && rate < 0.013, "{rate}");
+        let false_positives = (0..10_000)
+            .filter(|i| filter.contains(&format!("other{i}")))
+            .count();
+        assert!(false_positives > 50 && false_positives < 150);
+    }
+
+    #[test]
+    fn approximate_unique_words() {
+        // 150000 distinct words, a third of them repeated, in 1 MB
+        let words: Vec<String> = (0..150_000)
+            .chain((0..150_000).step_by(3))
+            .map(|i| format!("p4ssw0rd{}", i * 7919 % 1_000_003))
+            .collect();
+        let mut exact = SeenWords::Exact(HashSet::new());
+        let mut approx = SeenWords::Approx(BloomFilter::with_memory(1024 * 1024, 0.001));
+
+        let exact_count = words.iter().filter(|word| exact.insert(word)).count();
+        let approx_count = words.iter().filter(|word| approx.insert(word)).count();
+        assert_eq!(exact_count, 150_000);
+        assert!(approx_count <= exact_count);
+        assert!(exact_count - approx_count < 150, "{approx_count}");
+    }
+
+    #[test]
+    fn estimate_distinct_words() {
+        let mut small = HyperLogLog::new();
+        for word in ["a", "b", "c", "a", "b"] {
+            small.insert(word);
+        }
+        assert_eq!(small.count(), 3);
+
+        // every word twice, the repeats shouldn't count
+        let mut large = HyperLogLog::new();
+        for i in (0..200_000).chain(0..200_000) {
+            large.insert(&format!("word{i}"));
+        }
+        let error = large.count().abs_diff(200_000) as f64 / 200_000.0;
+        assert!(error < 0.03, "{}", large.count());
+    }
+}
+
+#[cfg(test)]
+mod csv_input_tests {
+    use crate::csv_parser::{words_from_csv_columns, CsvColumn, CsvStats};
+
+    const CSV: &str = "name,email,title\n\
+    \"Smith, John\",john@example.org,\"Head of \"\"Ops\"\"\"\n\
+    Alice,alice@example.org\n\
+    Bob,bob@example.org,CTO\n\
+    ,empty@example.org,Intern\n";
+
+    fn read(columns: &[&str], has_header: bool) -> Result<(Vec<String>, CsvStats), String> {
+        let columns: Vec<CsvColumn> = columns.iter().map(|c| c.parse().unwrap()).collect();
+        let mut words = Vec::new();
+        let stats =
+            words_from_csv_columns(CSV.as_bytes(), &columns, has_header, |w| words.push(w))?;
+        Ok((words, stats))
+    }
+
+    #[test]
+    fn parse_columns() {
+        assert_eq!("name".parse(), Ok(CsvColumn::Name(String::from("name"))));
+        assert_eq!("2".parse(), Ok(CsvColumn::Index(1)));
+        assert!("0".parse::<CsvColumn>().is_err());
+    }
+
+    #[test]
+    fn columns_by_name_and_position() {
+        let (words, stats) = read(&["name", "3"], true).unwrap();
+
+        assert_eq!(
+            words,
+            vec!["Smith, John", "Head of \"Ops\"", "Bob", "CTO", "Intern"]
+        );
+        // alice's row has no third column
+        assert_eq!(
+            stats,
+            CsvStats {
+                rows: 4,
+                malformed: 1
+            }
+        );
+    }
+
+    #[test]
+    fn without_header() {
+        let (words, stats) = read(&["1"], false).unwrap();
+
+        assert_eq!(words, vec!["name", "Smith, John", "Alice", "Bob"]);
+        assert_eq!(stats.rows, 5);
+        assert!(read(&["name"], false).is_err());
+        assert!(read(&["surname"], true).is_err());
+    }
+}
+
+#[cfg(test)]
+mod jsonl_input_tests {
+    use serde_json::json;
+
+    use crate::input::{json_field, words_from_jsonl, JsonlStats};
+
+    #[test]
+    fn dotted_paths() {
+        let record = json!({
+            "user": { "name": "alice", "id": 7, "admin": false, "manager": null },
+            "emails": ["alice@example.org", "a@example.org"]
+        });
+
+        assert_eq!(json_field(&record, "user.name").as_deref(), Some("alice"));
+        assert_eq!(json_field(&record, "user.id").as_deref(), Some("7"));
+        assert_eq!(json_field(&record, "user.admin").as_deref(), Some("false"));
+        assert_eq!(
+            json_field(&record, "emails.1").as_deref(),
+            Some("a@example.org")
+        );
+        assert_eq!(json_field(&record, "user.manager"), None);
+        assert_eq!(json_field(&record, "user"), None);
+        assert_eq!(json_field(&record, "user.name.first"), None);
+    }
+
+    #[test]
+    fn records_missing_the_field() {
+        let mut jsonl: &[u8] = b"{\"user\":{\"name\":\"alice\"}}\n\n\
+        {\"user\":{}}\nnot json\n{\"user\":{\"name\":\"bob\"}}";
+        let mut words = Vec::new();
+
+        let stats = words_from_jsonl(&mut jsonl, "user.name", |w| words.push(w)).unwrap();
+
+        assert_eq!(words, vec!["alice", "bob"]);
+        assert_eq!(
+            stats,
+            JsonlStats {
+                records: 4,
+                missing: 1,
+                malformed: 1
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod number_range_tests {
+    use crate::number_range::NumberRange;
+
+    #[test]
+    fn parse_ranges() {
+        assert_eq!(
+            "0-9999:1:4".parse::<NumberRange>(),
+            Ok(NumberRange {
+                start: 0,
+                end: 9999,
+                step: 1,
+                pad: 4
+            })
+        );
+        assert_eq!("5-30".parse::<NumberRange>().unwrap().step, 1);
+        assert_eq!("5-30::3".parse::<NumberRange>().unwrap().pad, 3);
+        assert!("30-5".parse::<NumberRange>().is_err());
+        assert!("0-10:0".parse::<NumberRange>().is_err());
+        assert!("0-10:1:2:3".parse::<NumberRange>().is_err());
+        assert!("ten".parse::<NumberRange>().is_err());
+    }
+
+    #[test]
+    fn range_words() {
+        let range: NumberRange = "0-9999:1:4".parse().unwrap();
+        let words: Vec<String> = range.words().collect();
+        assert_eq!(words.len(), 10000);
+        assert_eq!((words[0].as_str(), words[9999].as_str()), ("0000", "9999"));
+        assert_eq!(range.calculate_total(), 10000);
+        assert_eq!(range.calculate_size(), 50000);
+
+        let range: NumberRange = "95-120:10".parse().unwrap();
+        assert_eq!(range.words().collect::<Vec<_>>(), vec!["95", "105", "115"]);
+        assert_eq!(range.calculate_total(), 3);
+        assert_eq!(range.calculate_size(), 3 + 4 + 4);
+
+        let top: NumberRange = format!("{}-{}", u64::MAX - 1, u64::MAX).parse().unwrap();
+        assert_eq!(top.words().count(), 2);
+        assert_eq!(top.calculate_size(), 2 * 21);
+    }
+}
+
+#[cfg(test)]
+mod keyboard_walk_tests {
+    use crate::keyboard_walk::{Keyboard, KeyboardLayout, WalkLengths};
+
+    fn lengths(s: &str) -> WalkLengths {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn count_walks() {
+        // a b
+        //  c d
+        let keyboard = Keyboard::from_rows(&["ab", "cd"], &["AB", "CD"]);
+
+        assert_eq!(keyboard.walks(&lengths("1"), 0, false).len(), 4);
+        // a-b, a-c, b-c, b-d and c-d, both ways
+        assert_eq!(keyboard.walks(&lengths("2"), 0, false).len(), 10);
+        assert_eq!(keyboard.walks(&lengths("1-2"), 0, true).len(), 28);
+        // no three keys in a row, but any two neighbours of a key
+        assert_eq!(keyboard.walks(&lengths("3"), 0, false).len(), 0);
+        assert_eq!(keyboard.walks(&lengths("3"), 1, false).len(), 16);
+        // the parallel walks: ac+bd and ca+db
+        let walks = keyboard.walks(&lengths("4"), 0, false);
+        assert_eq!(walks, vec!["acbd", "cadb"]);
+    }
+
+    #[test]
+    fn common_walks() {
+        let walks = KeyboardLayout::Qwerty
+            .keyboard()
+            .walks(&lengths("6-8"), 1, true);
+
+        for walk in [
+            "qwerty", "zxcvbn", "1qaz2wsx", "1q2w3e4r", "!QAZ@WSX", "qazwsx", "1qazxcv",
+        ] {
+            assert!(walks.iter().any(|w| w == walk), "{walk} is missing");
+        }
+        assert!(!walks.iter().any(|w| w == "qwertz"));
+
+        let walks = KeyboardLayout::Qwertz
+            .keyboard()
+            .walks(&lengths("6"), 0, false);
+        assert!(walks.iter().any(|w| w == "qwertz"));
+    }
+
+    #[test]
+    fn parse_lengths() {
+        assert_eq!(lengths("4-8"), WalkLengths { min: 4, max: 8 });
+        assert_eq!(lengths("6"), WalkLengths { min: 6, max: 6 });
+        assert!("8-4".parse::<WalkLengths>().is_err());
+        assert!("0".parse::<WalkLengths>().is_err());
+        assert!("4-40".parse::<WalkLengths>().is_err());
+    }
+}
+
+#[cfg(test)]
+mod phrase_tests {
+    use crate::phrases::{PhraseInput, Phrases};
+
+    fn phrases(count: u32, separator: &str) -> Phrases {
+        let words = ["red", "blue", "green", "red"].map(String::from).to_vec();
+        Phrases::new(words, count, separator.to_owned())
+    }
+
+    #[test]
+    fn parse_phrase_input() {
+        assert_eq!(
+            "colors.txt:3:-".parse::<PhraseInput>(),
+            Ok(PhraseInput {
+                path: String::from("colors.txt"),
+                count: 3,
+                separator: String::from("-")
+            })
+        );
+        assert_eq!(
+            "colors.txt:2::".parse::<PhraseInput>().unwrap().separator,
+            ":"
+        );
+        assert_eq!("colors.txt:2".parse::<PhraseInput>().unwrap().separator, "");
+        assert!("colors.txt".parse::<PhraseInput>().is_err());
+        assert!("colors.txt:0".parse::<PhraseInput>().is_err());
+        assert!(":2".parse::<PhraseInput>().is_err());
+    }
+
+    #[test]
+    fn every_phrase() {
+        let phrases = phrases(2, "-");
+        let all: Vec<String> = phrases.iter_from(0).collect();
+
+        assert_eq!(phrases.word_count(), 3);
+        assert_eq!(phrases.keyspace(), Some(9));
+        assert_eq!(all.len(), 9);
+        assert_eq!(all[0], "red-red");
+        assert_eq!(all[1], "red-blue");
+        assert_eq!(all[8], "green-green");
+
+        let size: usize = all.iter().map(|p| p.len() + 1).sum();
+        assert_eq!(phrases.calculate_size(), Some(size as u128));
+    }
+
+    #[test]
+    fn skip_phrases() {
+        let phrases = phrases(3, "");
+        let all: Vec<String> = phrases.iter_from(0).collect();
+
+        assert_eq!(phrases.iter_from(10).collect::<Vec<_>>(), all[10..]);
+        assert_eq!(phrases.iter_from(27).count(), 0);
+        assert_eq!(
+            phrases.iter_from(26).collect::<Vec<_>>(),
+            vec!["greengreengreen"]
+        );
+        assert_eq!(
+            Phrases::new(vec![], 2, String::new()).iter_from(0).count(),
+            0
+        );
+    }
+}
+
+#[cfg(test)]
+mod output_tests {
+    use std::{
+        env, fs,
+        fs::File,
+        io::{BufRead, Write},
+    };
+
+    use crate::input::decompressing_reader;
+    use crate::output::{OutputCompression, OutputFile};
+
+    #[test]
+    fn compression_from_path() {
+        assert_eq!(
+            OutputCompression::from_path("words.txt.gz"),
+            Some(OutputCompression::Gzip)
+        );
+        assert_eq!(
+            OutputCompression::from_path("words.zst"),
+            Some(OutputCompression::Zstd)
+        );
+        assert_eq!(OutputCompression::from_path("words.txt"), None);
+    }
+
+    #[test]
+    fn compressed_output() {
+        let words: String = (0..1000).map(|i| format!("silverback{i}\n")).collect();
+
+        for compression in [
+            None,
+            Some(OutputCompression::Gzip),
+            Some(OutputCompression::Zstd),
+        ] {
+            let path = env::temp_dir().join(format!(
+                "gorilla-output-{compression:?}-{}",
+                std::process::id()
+            ));
+            let level = compression.map(|c| (c, c.default_level()));
+            let mut output = OutputFile::new(File::create(&path).unwrap(), level).unwrap();
+            output.write_all(words.as_bytes()).unwrap();
+
+            assert_eq!(output.bytes, words.len() as u64);
+            let on_disk = output.finish().unwrap();
+            assert_eq!(on_disk, fs::metadata(&path).unwrap().len());
+            if compression.is_some() {
+                assert!(on_disk < words.len() as u64 / 4);
+            }
+
+            let lines: Vec<String> = decompressing_reader(File::open(&path).unwrap())
+                .unwrap()
+                .lines()
+                .map(Result::unwrap)
+                .collect();
+            fs::remove_file(&path).unwrap();
+
+            assert_eq!(lines.len(), 1000);
+            assert_eq!(lines[999], "silverback999");
+        }
+    }
+}
+
+#[cfg(test)]
+mod split_output_tests {
+    use std::{env, fs, io::Write};
+
+    use crate::arguments::parse_delimiter;
+    use crate::output::{
+        split_path, LengthBuckets, OpenMode, OutputEncoding, OutputPart, RollingOutput, SplitLimits,
+    };
+
+    #[test]
+    fn split_paths() {
+        assert_eq!(split_path("out.txt", 1), "out.0001.txt");
+        assert_eq!(split_path("dir.d/out.txt.gz", 12), "dir.d/out.0012.txt.gz");
+        assert_eq!(split_path("out", 2), "out.0002");
+        assert_eq!(split_path(".hidden", 3), ".hidden.0003");
+    }
+
+    fn write_split(name: &str, limits: SplitLimits, lines: &[&str]) -> Vec<(OutputPart, String)> {
+        let path = env::temp_dir()
+            .join(format!("gorilla-split-{name}-{}.txt", std::process::id()))
+            .display()
+            .to_string();
+
+        let mut output = RollingOutput::new(&path, OpenMode::Overwrite, None, limits).unwrap();
+        for line in lines {
+            output.write_all(format!("{line}\n").as_bytes()).unwrap();
+        }
+        let parts = output.finish().unwrap();
+
+        parts
+            .into_iter()
+            .map(|part| {
+                let contents = fs::read_to_string(&part.path).unwrap();
+                fs::remove_file(&part.path).unwrap();
+                (part, contents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn split_by_lines() {
+        let limits = SplitLimits {
+            lines: Some(2),
+            bytes: None,
+        };
+        let parts = write_split("lines", limits, &["a", "b", "c", "d", "e"]);
+
+        let contents: Vec<&str> = parts.iter().map(|(_, c)| c.as_str()).collect();
+        assert_eq!(contents, vec!["a\nb\n", "c\nd\n", "e\n"]);
+        assert!(parts[0].0.path.ends_with(".0001.txt"));
+        assert!(parts[2].0.path.ends_with(".0003.txt"));
+        assert_eq!(parts[2].0.lines, 1);
+    }
+
+    #[test]
+    fn split_by_bytes() {
+        let limits = SplitLimits {
+            lines: None,
+            bytes: Some(10),
+        };
+        let parts = write_split("bytes", limits, &["banana", "gorilla", "ape", "silverback"]);
+
+        // never more than 10 bytes unless a single line is longer
+        let contents: Vec<&str> = parts.iter().map(|(_, c)| c.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec!["banana\n", "gorilla\n", "ape\n", "silverback\n"]
+        );
+        assert_eq!(parts[1].0.on_disk, 8);
+    }
+
+    #[test]
+    fn no_split() {
+        let parts = write_split("none", SplitLimits::default(), &["a", "b"]);
+        assert_eq!(parts.len(), 1);
+        assert!(!parts[0].0.path.contains(".0001"));
+        assert_eq!((parts[0].0.lines, parts[0].1.as_str()), (2, "a\nb\n"));
+    }
+
+    #[test]
+    fn split_by_delimiter() {
+        let path = env::temp_dir()
+            .join(format!("gorilla-split-nul-{}.txt", std::process::id()))
+            .display()
+            .to_string();
+        let limits = SplitLimits {
+            lines: Some(2),
+            bytes: None,
+        };
+
+        let mut output = RollingOutput::new(&path, OpenMode::Overwrite, None, limits).unwrap();
+        output.set_delimiter(b"\0");
+        // in one piece with its delimiter, the way words are written
+        for word in ["line\nbreak", "ape1", "ape2"] {
+            output
+                .write_all(&[word.as_bytes(), b"\0"].concat())
+                .unwrap();
+        }
+
+        // every word is followed by the delimiter, the last one too
+        let contents: Vec<String> = output
+            .finish()
+            .unwrap()
+            .into_iter()
+            .map(|part| {
+                let contents = fs::read_to_string(&part.path).unwrap();
+                fs::remove_file(&part.path).unwrap();
+                contents
+            })
+            .collect();
+        assert_eq!(contents, ["line\nbreak\0ape1\0", "ape2\0"]);
+    }
+
+    #[test]
+    fn parse_delimiters() {
+        assert_eq!(parse_delimiter("\\n").unwrap(), "\n");
+        assert_eq!(parse_delimiter("\\0").unwrap(), "\0");
+        assert_eq!(parse_delimiter(",\\t").unwrap(), ",\t");
+        assert_eq!(parse_delimiter("a\\\\b").unwrap(), "a\\b");
+        assert!(parse_delimiter("\\x").is_err());
+        assert!(parse_delimiter("trailing\\").is_err());
+        assert!(parse_delimiter("").is_err());
+    }
+
+    #[test]
+    fn output_by_length() {
+        let dir = env::temp_dir().join(format!("gorilla-by-length-{}", std::process::id()));
+        let mut buckets =
+            LengthBuckets::new(&dir.display().to_string(), b"\n", OutputEncoding::Utf8);
+        for word in ["abc", "héé", "ab", "xyz"] {
+            buckets.write_word(word.as_bytes()).unwrap();
+        }
+        assert_eq!(buckets.bytes, 17);
+
+        let files = buckets.finish().unwrap();
+        let lengths: Vec<(usize, u64)> = files.iter().map(|(l, part)| (*l, part.lines)).collect();
+        assert_eq!(lengths, vec![(2, 1), (3, 3)]);
+        assert_eq!(
+            fs::read_to_string(dir.join("len3.txt")).unwrap(),
+            "abc\nhéé\nxyz\n"
+        );
+        assert_eq!(fs::read_to_string(dir.join("len2.txt")).unwrap(), "ab\n");
+        fs::remove_dir_all(&dir).unwrap();
+
+        let template = LengthBuckets::new("out/words.len{}.txt", b"\n", OutputEncoding::Utf8);
+        assert_eq!(template.path(8), "out/words.len8.txt");
+    }
+}
+
+#[cfg(test)]
+mod output_encoding_tests {
+    use std::{env, fs};
+
+    use crate::output::{hex_word, LengthBuckets, OutputEncoding};
+
+    #[test]
+    fn latin1_words() {
+        let latin1 = OutputEncoding::Latin1;
+        assert!(latin1.can_encode("Crème brûlée"));
+        assert!(latin1.can_encode("ÿ"));
+        assert!(!latin1.can_encode("Łódź"));
+        assert!(!latin1.can_encode("東京"));
+        assert!(OutputEncoding::Utf8.can_encode("東京"));
+
+        assert_eq!(&*latin1.encode("café".as_bytes()), b"caf\xe9");
+        assert_eq!(&*latin1.encode(b"plain"), b"plain");
+        // not UTF-8 to begin with, like a raw line
+        assert_eq!(&*latin1.encode(b"caf\xe9"), b"caf\xe9");
+        assert_eq!(
+            &*OutputEncoding::Utf8.encode("café".as_bytes()),
+            "café".as_bytes()
+        );
+
+        assert_eq!(hex_word("東".as_bytes()), "$HEX[e69db1]");
+    }
+
+    #[test]
+    fn write_latin1() {
+        assert_eq!(
+            &*OutputEncoding::Latin1.encode("Ñandú1".as_bytes()),
+            b"\xd1and\xfa1"
+        );
+
+        // still a file per length in characters, not in bytes
+        let dir = env::temp_dir().join(format!("gorilla-latin1-{}", std::process::id()));
+        let mut buckets =
+            LengthBuckets::new(&dir.display().to_string(), b"\n", OutputEncoding::Latin1);
+        buckets.write_word("héé".as_bytes()).unwrap();
+        buckets.write_word(b"abc").unwrap();
+        assert_eq!(buckets.bytes, 8);
+        buckets.finish().unwrap();
+        assert_eq!(fs::read(dir.join("len3.txt")).unwrap(), b"h\xe9\xe9\nabc\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod sort_tests {
+    use std::{env, fs};
+
+    use crate::sort::{ExternalSort, SortOrder};
+
+    fn sorted(order: SortOrder, memory_cap: usize, words: &[&str]) -> Vec<String> {
+        let dir = env::temp_dir().join(format!("gorilla-sort-{memory_cap}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut sorter = ExternalSort::new(order, memory_cap, dir.clone());
+        for word in words {
+            sorter.push(word.as_bytes()).unwrap();
+        }
+        let mut output = Vec::new();
+        sorter
+            .finish(&mut |word| {
+                output.push(String::from_utf8(word.to_vec()).unwrap());
+                Ok(())
+            })
+            .unwrap();
+
+        // the temporary files are gone
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(&dir).unwrap();
+        output
+    }
+
+    #[test]
+    fn sort_in_memory() {
+        let words = ["pear", "apple", "fig", "äpfel", "kiwi", "fig"];
+        assert_eq!(
+            sorted(SortOrder::Lexicographic, 1 << 20, &words),
+            ["apple", "fig", "fig", "kiwi", "pear", "äpfel"]
+        );
+        // äpfel has 5 characters, but 6 bytes
+        assert_eq!(
+            sorted(SortOrder::Length, 1 << 20, &words),
+            ["fig", "fig", "kiwi", "pear", "apple", "äpfel"]
+        );
+    }
+
+    #[test]
+    fn sort_with_temporary_files() {
+        let words: Vec<String> = (0..1000)
+            .rev()
+            .map(|i| format!("{}", i * 7 % 1000))
+            .collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        // room for a few dozen words at a time
+        let output = sorted(SortOrder::Length, 1000, &words);
+        let mut expected: Vec<&str> = words.clone();
+        expected.sort_by_key(|word| (word.len(), word.to_string()));
+        assert_eq!(output, expected);
+    }
+}
+
+#[cfg(test)]
+mod dry_run_tests {
+    use crate::pipeline::DryRun;
+
+    #[test]
+    fn dry_run_sample() {
+        let mut dry_run = DryRun {
+            sample: Some(2),
+            sampled: 0,
+            unsampled: 0,
+            raw: (0, 0),
+        };
+
+        let taken: Vec<bool> = (0..5).map(|_| dry_run.take_sample()).collect();
+        assert_eq!(taken, [true, true, false, false, false]);
+        assert_eq!((dry_run.sampled, dry_run.unsampled), (2, 3));
+
+        dry_run.sample = None;
+        assert!(dry_run.take_sample());
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use std::{env, fs};
+
+    use crate::{
+        build_info::BuildInfo,
+        stats::{DuplicateStats, MutationSetStats, PolicyStats, RunConfig, RunStats},
+    };
+
+    #[test]
+    fn stats_schema() {
+        let stats = RunStats {
+            version: 1,
+            dry_run: false,
+            stopped_by: None,
+            input_words: 2,
+            written_words: 4,
+            distinct_words: Some(3),
+            distinct_words_estimated: true,
+            mutation_sets: vec![MutationSetStats {
+                id: 1,
+                mutations: vec![String::from("append: 1")],
+                generated_words: 6,
+                written_words: 4,
+                bytes_written: 20,
+            }],
+            duplicates: DuplicateStats {
+                inputs: 1,
+                outputs: 0,
+            },
+            policies: vec![PolicyStats {
+                name: String::from("nist"),
+                rejected_words: 2,
+            }],
+            weak_words: None,
+            hashes: None,
+            hashes_recovered: None,
+            report: None,
+            bytes_written: 20,
+            bytes_on_disk: None,
+            output_files: vec![],
+            runtime_seconds: 0.5,
+            config: RunConfig {
+                inputs: vec![String::from("words.txt")],
+                words: vec![],
+                pattern: Some(String::from("{a-z}")),
+                csv_input: None,
+                jsonl_input: None,
+                websites: vec![],
+                output_file: None,
+                seed: 7,
+            },
+            gorilla: BuildInfo::get(),
+            started_at: String::from("2024-03-01T12:30:00Z"),
+        };
+
+        let path = env::temp_dir().join(format!("gorilla-stats-{}.json", std::process::id()));
+        stats.write(path.to_str().unwrap()).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["mutation_sets"][0]["mutations"][0], "append: 1");
+        assert_eq!(json["mutation_sets"][0]["id"], 1);
+        assert_eq!(json["written_words"], 4);
+        assert_eq!(json["mutation_sets"][0]["generated_words"], 6);
+        assert_eq!(json["mutation_sets"][0]["written_words"], 4);
+        assert_eq!(json["mutation_sets"][0]["bytes_written"], 20);
+        assert_eq!(json["duplicates"]["inputs"], 1);
+        assert_eq!(json["policies"][0]["name"], "nist");
+        assert_eq!(json["policies"][0]["rejected_words"], 2);
+        assert_eq!(json["gorilla"]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["started_at"], "2024-03-01T12:30:00Z");
+        assert_eq!(json["distinct_words"], 3);
+        assert_eq!(json["distinct_words_estimated"], true);
+        assert!(json["bytes_on_disk"].is_null());
+        assert_eq!(json["config"]["pattern"], "{a-z}");
+        assert_eq!(json["config"]["seed"], 7);
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_tests {
+    use std::{env, fs};
+
+    use crate::checkpoint::{Checkpoint, InputFingerprint};
+
+    #[test]
+    fn save_and_check_checkpoint() {
+        let dir = env::temp_dir().join(format!("gorilla-checkpoint-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("words.txt").display().to_string();
+        let path = dir.join("checkpoint.json").display().to_string();
+        fs::write(&input, "silverback\nbanana\n").unwrap();
+
+        assert_eq!(Checkpoint::load(&path).unwrap(), None);
+
+        let fingerprints = vec![InputFingerprint::of(&input).unwrap()];
+        assert_eq!(fingerprints[0].size, 18);
+        let mut checkpoint = Checkpoint::new("out.txt", fingerprints.clone(), None);
+        checkpoint.lines_done = 1;
+        checkpoint.output_bytes = 11;
+        checkpoint.save(&path).unwrap();
+
+        let loaded = Checkpoint::load(&path).unwrap().unwrap();
+        assert_eq!(loaded, checkpoint);
+        assert!(!dir.join("checkpoint.json.tmp").exists());
+        assert!(loaded.check("out.txt", &fingerprints, None).is_ok());
+        assert!(loaded.check("other.txt", &fingerprints, None).is_err());
+        assert!(loaded.check("out.txt", &[], None).is_err());
+        assert!(loaded
+            .check("out.txt", &fingerprints, Some("{a-z}"))
+            .is_err());
+
+        // same size, other contents
+        fs::write(&input, "silverback\nbonobo\n").unwrap();
+        let changed = InputFingerprint::of(&input).unwrap();
+        assert_ne!(changed.head_hash, fingerprints[0].head_hash);
+        assert!(loaded
+            .check("out.txt", &[changed], None)
+            .unwrap_err()
+            .contains("changed"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod pipe_tests {
+    use std::{env, fs, io::Write};
+
+    use crate::output::{exit_code, spawn_pipe, WordStream};
+
+    #[cfg(unix)]
+    #[test]
+    fn pipe_to_command() {
+        let path = env::temp_dir().join(format!("gorilla-pipe-{}.txt", std::process::id()));
+        let command = format!("sh -c 'cat > \"{}\"; exit 3'", path.display());
+
+        let (mut child, mut stream) = spawn_pipe(&command).unwrap();
+        stream.write_all(b"one word\ntwo\n").unwrap();
+        stream.flush().unwrap();
+        // closing its stdin lets the command finish
+        stream = WordStream::Closed;
+        stream.write_all(b"swallowed\n").unwrap();
+
+        assert_eq!(exit_code(child.wait().unwrap()), 3);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one word\ntwo\n");
+        fs::remove_file(&path).unwrap();
+
+        assert!(spawn_pipe("unclosed 'quote").is_err());
+        assert!(spawn_pipe("  ").is_err());
+    }
+}
+
+#[cfg(test)]
+mod benchmark_tests {
+    use crate::benchmark;
+
+    #[test]
+    fn benchmark_counts_the_same_words_on_every_thread_count() {
+        let stages = benchmark::run(100, 1000, 3);
+        let counts: Vec<(&str, usize, u64)> = stages
+            .iter()
+            .map(|stage| (stage.name, stage.threads, stage.words))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("mutations", 1, 18_400),
+                ("mutations", 3, 18_400),
+                ("pattern", 1, 1000),
+                ("pattern", 3, 1000)
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod command_tests {
+    use clap::CommandFactory;
+
+    use crate::{
+        arguments::{built_with, feature_of, Commands, CompletionShell, ProgramArgs},
+        mutation::ACTIONS,
+    };
+
+    #[test]
+    fn every_flag_has_a_command() {
+        for arg in ProgramArgs::command().get_arguments() {
+            let id = arg.get_id().as_str();
+            if [
+                "benchmark",
+                "generate_completions",
+                "generate_manpage",
+                "list_mutations",
+            ]
+            .contains(&id)
+            {
+                continue;
+            }
+            assert!(
+                Commands::ALL.iter().any(|command| command.takes(id)),
+                "{id} doesn't go with any command"
+            );
+        }
+    }
+
+    #[test]
+    fn commands_and_flat_flags() {
+        let parse = |args: &[&str]| ProgramArgs::try_parse_command_line(args);
+
+        let args = parse(&["gorilla", "generate", "-p", "{0-9}", "-m", "reverse"]).unwrap();
+        assert_eq!(args.command, Some(Commands::Generate));
+        assert_eq!(args.pattern_input.as_deref(), Some("{0-9}"));
+        assert_eq!(args.threads, 1);
+
+        let args = parse(&["gorilla", "mutate", "password", "--word", "admin"]).unwrap();
+        assert_eq!(args.words, ["password"]);
+        assert_eq!(args.word, ["admin"]);
+
+        assert!(parse(&["gorilla", "generate", "password"]).is_err());
+        assert!(parse(&["gorilla", "check", "-o", "out.txt"]).is_err());
+
+        // a pattern taken in turn with the wordlists
+        let args = parse(&[
+            "gorilla",
+            "mutate",
+            "-i",
+            "a.txt",
+            "-p",
+            "{0-1}",
+            "--interleave",
+        ]);
+        assert!(args.unwrap().interleave);
+        assert!(parse(&["gorilla", "generate", "-p", "{0-1}", "--interleave"]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "scrape")]
+    fn scrape_command_and_flat_flags() {
+        let parse = |args: &[&str]| ProgramArgs::try_parse_command_line(args);
+
+        // the flags of before commands
+        let args = parse(&["gorilla", "-p", "{0-9}", "-w", "https://example.org"]).unwrap();
+        assert_eq!(args.command, None);
+
+        let refused = parse(&[
+            "gorilla",
+            "scrape",
+            "-w",
+            "https://example.org",
+            "-p",
+            "{0-9}",
+        ]);
+        let message = refused.unwrap_err().to_string();
+        assert!(
+            message.contains("--from-pattern doesn't go with `gorilla scrape`"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn features_are_the_ones_built_with() {
+        assert_eq!(built_with("scrape"), cfg!(feature = "scrape"));
+        assert_eq!(built_with("yaml"), cfg!(feature = "yaml"));
+        assert_eq!(feature_of("sitemap"), Some("scrape"));
+        assert_eq!(feature_of("input_cache"), Some("scrape"));
+        assert_eq!(feature_of("from_formatting"), Some("yaml"));
+        assert_eq!(feature_of("min_strength"), Some("strength"));
+        assert_eq!(built_with("strength"), cfg!(feature = "strength"));
+        assert_eq!(feature_of("threads"), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "scrape"))]
+    fn scrape_flags_are_refused_without_the_feature() {
+        let parse = |args: &[&str]| ProgramArgs::try_parse_command_line(args);
+
+        for args in [
+            &["gorilla", "-w", "https://example.org"][..],
+            &["gorilla", "mutate", "-m", "reverse", "--timeout", "5"],
+            &["gorilla", "scrape"],
+        ] {
+            let message = parse(args).unwrap_err().to_string();
+            assert!(message.contains("without the scrape feature"), "{message}");
+        }
+        let message = parse(&["gorilla", "mutate", "-i", "https://example.org/words.txt"])
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("can't be downloaded"), "{message}");
+        assert!(parse(&["gorilla", "mutate", "-i", "words.txt"]).is_ok());
+
+        let help = ProgramArgs::cli().render_long_help().to_string();
+        assert!(!help.contains("--from-website"), "{help}");
+    }
+
+    #[test]
+    #[cfg(not(feature = "yaml"))]
+    fn yaml_flags_are_refused_without_the_feature() {
+        let message = ProgramArgs::try_parse_command_line(["gorilla", "-f", "sets.yml"])
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("without the yaml feature"), "{message}");
+
+        // the config file can still name one
+        let error = match crate::load::read_mutations_file("sets.yml") {
+            Err(e) => e.to_string(),
+            Ok(_) => panic!("the file was read"),
+        };
+        assert!(error.contains("without the yaml feature"), "{error}");
+    }
+
+    #[test]
+    fn chained_and_separate_mutation_sets() {
+        let parse = |args: &[&str]| ProgramArgs::try_parse_command_line(args).unwrap();
+
+        let args = parse(&["gorilla", "mutate", "-m", "reverse", "-m", "append:1"]);
+        assert_eq!(args.mutation_chains, [["reverse", "append:1"]]);
+
+        let args = parse(&[
+            "gorilla",
+            "mutate",
+            "--set-m",
+            "reverse",
+            "-m",
+            "append:1",
+            "--set-m",
+            "uppercase_all",
+            "--set-m",
+            "nothing",
+        ]);
+        assert_eq!(
+            args.mutation_chains,
+            [
+                vec!["reverse", "append:1"],
+                vec!["uppercase_all"],
+                vec!["nothing"]
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn command_line_sets_come_first() {
+        use std::{env, fs, process};
+
+        let parse = |args: &[&str]| ProgramArgs::try_parse_command_line(args).unwrap();
+
+        // the sets of the command line come before those of the file
+        let path = env::temp_dir().join(format!("gorilla_sets_{}.yml", process::id()));
+        fs::write(&path, "name: t\nmutation_sets:\n  - [reverse]\n").unwrap();
+        let args = parse(&[
+            "gorilla",
+            "mutate",
+            "-m",
+            "append:1",
+            "-m",
+            "append:2",
+            "--set-m",
+            "wipe",
+            "-f",
+            path.to_str().unwrap(),
+        ]);
+        let mutation_sets =
+            crate::load::load_mutation_sets(&args.mutation_chains, args.mutations_file.as_deref())
+                .unwrap();
+        fs::remove_file(&path).unwrap();
+        let sets: Vec<(usize, String)> = mutation_sets
+            .iter()
+            .map(|set| {
+                let mutations: Vec<String> = set.mutations.iter().map(|m| m.to_string()).collect();
+                (set.id, mutations.join(", "))
+            })
+            .collect();
+        assert_eq!(
+            sets,
+            [
+                (1, String::from("append: 1, append: 2")),
+                (2, String::from("wipe")),
+                (3, String::from("reverse"))
+            ]
+        );
+    }
+
+    #[test]
+    fn completions_for_every_shell() {
+        for shell in [
+            CompletionShell::Bash,
+            CompletionShell::Zsh,
+            CompletionShell::Fish,
+            CompletionShell::Powershell,
+        ] {
+            let mut script = vec![];
+            ProgramArgs::write_completions(shell, &mut script);
+            let script = String::from_utf8(script).unwrap();
+            for expected in ["from-pattern", "generate"] {
+                assert!(script.contains(expected), "{shell:?} misses {expected}");
+            }
+            // powershell's only complete the flags
+            if shell != CompletionShell::Powershell {
+                assert!(
+                    script.contains("uppercase_all"),
+                    "{shell:?} misses the actions"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn manpage_has_the_mutations_and_patterns() {
+        let mut page = vec![];
+        ProgramArgs::write_manpage(&mut page).unwrap();
+        let page = String::from_utf8(page).unwrap();
+        for section in [".SH OPTIONS", ".SH MUTATIONS", ".SH PATTERNS"] {
+            assert!(page.contains(section), "the man page misses {section}");
+        }
+        for action in ACTIONS {
+            let signature = action.signature().replace('-', "\\-");
+            assert!(page.contains(&signature), "the man page misses {signature}");
+        }
+        assert!(page.contains("pw{a\\-c}"));
+        assert!(page.contains("abcdefghijklmnopqrstuvwxyz"));
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use crate::{
+        arguments::{ColorChoice, ProgramArgs},
+        config::{self, Source, UserConfig},
+    };
+
+    const CONFIG: &str = r#"
+threads = 4
+color = "never"
+mutations-file = "rules.yml"
+user-agent = "Mozilla/5.0"
+"#;
+
+    #[test]
+    fn flags_win_over_the_config() {
+        let config = UserConfig::parse(CONFIG).unwrap();
+
+        let mut args = ProgramArgs::try_parse_command_line(["gorilla", "mutate"]).unwrap();
+        let applied = config.apply(&mut args);
+        assert_eq!(args.threads, 4);
+        assert_eq!(args.color, ColorChoice::Never);
+        assert_eq!(args.mutations_file.as_deref(), Some("rules.yml"));
+        assert_eq!(args.user_agent.as_deref(), Some("Mozilla/5.0"));
+        assert_eq!(
+            config::source(&args, &applied, "threads"),
+            Source::ConfigFile
+        );
+        assert_eq!(
+            config::source(&args, &applied, "output_dir"),
+            Source::Default
+        );
+
+        // given on the command line, even when it's the default
+        let mut args = ProgramArgs::try_parse_command_line([
+            "gorilla",
+            "mutate",
+            "--threads",
+            "1",
+            "--color",
+            "auto",
+            "-m",
+            "reverse",
+        ])
+        .unwrap();
+        let applied = config.apply(&mut args);
+        assert_eq!(args.threads, 1);
+        assert_eq!(args.color, ColorChoice::Auto);
+        // -m replaces the mutations file of the config
+        assert_eq!(args.mutations_file, None);
+        assert_eq!(applied, ["user_agent"]);
+        assert_eq!(
+            config::source(&args, &applied, "threads"),
+            Source::CommandLine
+        );
+    }
+
+    #[test]
+    fn invalid_configs() {
+        assert!(UserConfig::parse("").is_ok());
+
+        let message = UserConfig::parse("\nthread = 4").unwrap_err();
+        assert!(
+            message.starts_with("line 2: unknown field `thread`"),
+            "{message}"
+        );
+        assert!(UserConfig::parse("threads = 0").is_err());
+        assert!(UserConfig::parse("threads = \"4\"").is_err());
+        assert!(UserConfig::parse("color = \"sometimes\"").is_err());
+    }
+}
+
+#[cfg(all(test, feature = "yaml"))]
+mod repl_tests {
+    use std::{env, fs, process};
+
+    use crate::{
+        load::load_mutation_sets,
+        repl::{Repl, Reply},
+    };
+
+    fn lines(reply: Result<Reply, String>) -> Vec<String> {
+        match reply.unwrap() {
+            Reply::Print(lines) => lines,
+            Reply::Quit => panic!("the repl quit"),
+        }
+    }
+
+    #[test]
+    fn words_and_commands() {
+        let chains = vec![vec![String::from("reverse")]];
+        let mut repl = Repl::new(load_mutation_sets(&chains, None).unwrap(), None).unwrap();
+        assert_eq!(lines(repl.handle("cat")), ["tac"]);
+        assert!(repl.handle(":reload").is_err());
+        assert!(repl.handle(":toggle 2").is_err());
+        assert!(repl.handle(":shuffle").is_err());
+
+        let path = env::temp_dir().join(format!("gorilla_repl_{}.yml", process::id()));
+        fs::write(&path, "name: t\nmutation_sets:\n  - [uppercase_all]\n").unwrap();
+        let sets = lines(repl.handle(&format!(":load {}", path.display())));
+        assert_eq!(sets.len(), 2);
+        assert_eq!(lines(repl.handle("cat")), ["tac", "CAT"]);
+
+        // edited and loaded again, the sets of -m stay
+        fs::write(&path, "name: t\nmutation_sets:\n  - [\"append:1\"]\n").unwrap();
+        lines(repl.handle(":reload"));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(lines(repl.handle("cat")), ["tac", "cat1"]);
+
+        lines(repl.handle(":toggle 1"));
+        assert_eq!(lines(repl.handle("cat")), ["cat1"]);
+        lines(repl.handle(":split"));
+        let split = lines(repl.handle("cat"));
+        assert_eq!(split.len(), 2, "{split:?}");
+        assert_eq!(split[1], "  cat1");
+
+        assert_eq!(repl.handle(":quit"), Ok(Reply::Quit));
+    }
+}
+
+#[cfg(test)]
+mod logging_tests {
+    use crate::{
+        arguments::ProgramArgs,
+        logging::{self, Level},
+    };
+
+    #[test]
+    fn verbosity_levels() {
+        let level = |args: &[&str]| {
+            let args = ProgramArgs::try_parse_command_line(args).unwrap();
+            Level::from_flags(args.quiet, args.verbose)
+        };
+        assert_eq!(level(&["gorilla", "mutate"]), Level::Info);
+        assert_eq!(level(&["gorilla", "mutate", "--quiet"]), Level::Quiet);
+        assert_eq!(level(&["gorilla", "mutate", "-v"]), Level::Verbose);
+        assert_eq!(level(&["gorilla", "mutate", "-vv"]), Level::Debug);
+        assert_eq!(level(&["gorilla", "check", "-v", "-v", "-v"]), Level::Debug);
+        assert!(ProgramArgs::try_parse_command_line(["gorilla", "-v", "--quiet"]).is_err());
+
+        logging::set_level(Level::Verbose);
+        assert!(logging::enabled(Level::Info) && logging::enabled(Level::Verbose));
+        assert!(!logging::enabled(Level::Debug));
+        logging::set_level(Level::Info);
+        assert!(!logging::enabled(Level::Verbose));
+    }
+}
+
+#[cfg(test)]
+mod timer_tests {
+    use std::time::{Duration, Instant};
+
+    use crate::progress::Timer;
+
+    #[test]
+    fn throughput_reports() {
+        let mut timer = Timer::new(Duration::from_secs(5));
+        let start = Instant::now();
+
+        let report = timer.report(start + Duration::from_secs(10), 1000, 100_000);
+        assert!(
+            report.starts_with("[00:00:10] 1,000 words in, 100,000 out, "),
+            "{report}"
+        );
+        // the rate now only counts the words since the report before
+        let report = timer.report(start + Duration::from_secs(3670), 2000, 100_000);
+        assert!(
+            report.starts_with("[01:01:10] 2,000 words in, 100,000 out, 0/s now, 27/s on average"),
+            "{report}"
+        );
+    }
+}
+
+#[cfg(all(test, feature = "yaml"))]
+mod preflight_tests {
+    use std::{env, fs, process};
+
+    use crate::{arguments::ProgramArgs, error::GorillaError, preflight};
+
+    #[test]
+    fn every_problem_is_found() {
+        let dir = env::temp_dir().join(format!("gorilla_preflight_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let wordlist = dir.join("words.txt");
+        fs::write(&wordlist, "cat\n").unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        let args = ProgramArgs::try_parse_command_line([
+            "gorilla",
+            "mutate",
+            "-i",
+            &path("words.txt"),
+            "-m",
+            "reverse",
+            "-o",
+            &path("out.txt"),
+        ])
+        .unwrap();
+        assert!(preflight::check(&args, None).is_empty());
+        assert!(!dir.join("out.txt").exists());
+
+        let args = ProgramArgs::try_parse_command_line([
+            "gorilla",
+            "mutate",
+            "-i",
+            &path("words.txt"),
+            "-i",
+            &path("missing.txt"),
+            "-m",
+            "bogus",
+            "-f",
+            &path("missing.yml"),
+            "--exclude-file",
+            &path("missing-too.txt"),
+            "-o",
+            &path("missing/out.txt"),
+        ])
+        .unwrap();
+        let problems = preflight::check(&args, None);
+        assert_eq!(problems.len(), 5, "{problems:?}");
+        assert!(matches!(problems[0], GorillaError::Config(_)));
+        assert!(problems[4].to_string().contains("doesn't exist"));
+
+        // the output isn't touched by a dry run
+        let args = ProgramArgs::try_parse_command_line([
+            "gorilla",
+            "mutate",
+            "-i",
+            &path("words.txt"),
+            "-o",
+            &path("missing/out.txt"),
+            "--dry-run",
+        ])
+        .unwrap();
+        assert!(preflight::check(&args, None).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod build_info_tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{
+        build_info::{output_header, utc_time, BuildInfo},
+        mutation::{parse_mutation_string, MutationSet},
+    };
+
+    #[test]
+    fn utc_times() {
+        let at = |seconds| utc_time(UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(at(0), "1970-01-01T00:00:00Z");
+        assert_eq!(at(951_825_599), "2000-02-29T11:59:59Z");
+        assert_eq!(at(1_735_689_600), "2025-01-01T00:00:00Z");
+        assert_eq!(BuildInfo::get().build_date.len(), 10);
+    }
+
+    #[test]
+    fn header_line() {
+        let sets = [MutationSet {
+            id: 1,
+            mutations: parse_mutation_string(&["reverse"]).unwrap(),
+        }];
+        let header = output_header("//", &sets, 42, UNIX_EPOCH);
+        assert!(header.starts_with("// generated by gorilla v"), "{header}");
+        assert!(
+            header.ends_with(", sets: [reverse], seed: 42, date: 1970-01-01T00:00:00Z\n"),
+            "{header}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod serve_tests {
+    use std::{
+        io::{Read, Write},
+        net::{SocketAddr, TcpStream},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::{arguments::ProgramArgs, serve::Server};
+
+    fn start(flags: &[&str]) -> SocketAddr {
+        let mut args = vec!["gorilla", "serve", "--listen", "127.0.0.1:0"];
+        args.extend(flags);
+        let server = Server::bind(&ProgramArgs::try_parse_command_line(args).unwrap()).unwrap();
+        serve(server)
+    }
+
+    fn serve(server: Server) -> SocketAddr {
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+        address
+    }
+
+    fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+        let headers = "Content-Type: application/json\r\n";
+        request_with(address, method, path, headers, body)
+    }
+
+    fn request_with(
+        address: SocketAddr,
+        method: &str,
+        path: &str,
+        headers: &str,
+        body: &str,
+    ) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{method} {path} HTTP/1.1\r\nHost: {address}\r\n{headers}Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, mut body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head[9..12].parse().unwrap();
+        if !head.contains("Transfer-Encoding: chunked") {
+            return (status, body.to_owned());
+        }
+        let mut words = String::new();
+        loop {
+            let (size, rest) = body.split_once("\r\n").unwrap();
+            let size = usize::from_str_radix(size, 16).unwrap();
+            if size == 0 {
+                return (status, words);
+            }
+            words.push_str(&rest[..size]);
+            body = &rest[size + 2..];
+        }
+    }
+
+    #[test]
+    fn jobs() {
+        let address = start(&[]);
+        let post = |path, body| request(address, "POST", path, body);
+
+        let job =
+            r#"{"words": ["cat"], "pattern": "{0-1}", "mutations": [["reverse"], ["append:!"]]}"#;
+        assert_eq!(
+            post("/generate", job),
+            (200, String::from("tac\ncat!\n0\n0!\n1\n1!\n"))
+        );
+        let (status, estimate) = post("/estimate", job);
+        assert_eq!(status, 200);
+        let estimate: serde_json::Value = serde_json::from_str(&estimate).unwrap();
+        assert_eq!(estimate["input_words"], 3);
+        assert_eq!(estimate["words"], 6);
+
+        let limited = r#"{"pattern": "{0-9}{0-9}", "max_words": 5}"#;
+        assert_eq!(post("/generate", limited).1, "00\n10\n20\n30\n40\n");
+        let estimate: serde_json::Value =
+            serde_json::from_str(&post("/estimate", limited).1).unwrap();
+        assert_eq!(estimate["words"], 5);
+
+        assert_eq!(post("/generate", "{\"words\": [").0, 400);
+        assert_eq!(
+            post("/generate", r#"{"words": ["cat"], "colour": 1}"#).0,
+            400
+        );
+        assert_eq!(post("/generate", "{}").0, 400);
+        assert_eq!(
+            post("/generate", r#"{"words": ["cat"], "sets": ["none"]}"#).0,
+            400
+        );
+        assert_eq!(post("/words", job).0, 404);
+        assert_eq!(request(address, "GET", "/generate", "").0, 405);
+    }
+
+    #[test]
+    fn cross_site_jobs() {
+        let address = start(&[]);
+        let job = r#"{"words": ["cat"]}"#;
+        let post = |headers: &str| request_with(address, "POST", "/generate", headers, job).0;
+
+        // what a page can send anywhere without asking
+        assert_eq!(post("Content-Type: text/plain\r\n"), 415);
+        assert_eq!(post(""), 415);
+        let foreign = "Content-Type: application/json\r\nOrigin: https://example.com\r\n";
+        assert_eq!(post(foreign), 403);
+        let own = format!(
+            "Content-Type: application/json; charset=utf-8\r\nOrigin: http://{address}\r\n"
+        );
+        assert_eq!(post(&own), 200);
+    }
+
+    #[test]
+    fn exec_jobs() {
+        let job = r#"{"words": ["cat"], "mutations": [["exec:cat; echo"]]}"#;
+        let (status, body) = request(start(&[]), "POST", "/generate", job);
+        assert_eq!(status, 403, "{body}");
+        assert!(body.contains("--allow-exec"), "{body}");
+
+        if cfg!(unix) {
+            let job = r#"{"words": ["cat"], "mutations": [["exec:while read w; do echo $w!; echo; done"]]}"#;
+            let allowed = start(&["--allow-exec"]);
+            assert_eq!(
+                request(allowed, "POST", "/generate", job),
+                (200, String::from("cat!\n"))
+            );
+        }
+    }
+
+    #[test]
+    fn busy() {
+        let address = start(&["--max-jobs", "1"]);
+        // a connection that sends nothing holds the only job
+        let _held = TcpStream::connect(address).unwrap();
+        let (status, body) = request(address, "POST", "/generate", r#"{"words": ["cat"]}"#);
+        assert_eq!(status, 503, "{body}");
+    }
+
+    #[test]
+    fn slow_requests() {
+        let args =
+            ProgramArgs::try_parse_command_line(["gorilla", "serve", "--listen", "127.0.0.1:0"])
+                .unwrap();
+        let mut server = Server::bind(&args).unwrap();
+        server.set_read_timeout(Duration::from_millis(300));
+        let address = serve(server);
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"POST /generate HTTP/1.1\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 408"), "{response}");
+
+        // a header every 100ms never leaves a read waiting for 300ms, but
+        // the request still has to be whole in time
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut trickle = stream.try_clone().unwrap();
+        thread::spawn(move || {
+            let _ = trickle.write_all(b"POST /generate HTTP/1.1\r\n");
+            for _ in 0..30 {
+                thread::sleep(Duration::from_millis(100));
+                if trickle.write_all(b"X-Slow: 1\r\n").is_err() {
+                    break;
+                }
+            }
+        });
+        let start = Instant::now();
+        let _ = stream.read_to_end(&mut vec![]);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn sets_dir() {
+        use std::{env, fs, process};
+
+        let dir = env::temp_dir().join(format!("gorilla_serve_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("loud.yml"),
+            "name: loud\nmutation_sets:\n  - [uppercase_all]\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "not a mutations file").unwrap();
+        // a plugin that counts the words it was given
+        fs::write(
+            dir.join("count.yml"),
+            "name: count\nmutation_sets:\n  - ['exec:n=0; while read w; do n=$((n+1)); echo $w$n; echo; done']\n",
+        )
+        .unwrap();
+
+        let address = start(&["--sets-dir", &dir.to_string_lossy()]);
+        let job = r#"{"words": ["cat"], "sets": ["loud"], "mutations": [["reverse"]]}"#;
+        assert_eq!(
+            request(address, "POST", "/generate", job),
+            (200, String::from("tac\nCAT\n"))
+        );
+        if cfg!(unix) {
+            // every job has a plugin of its own
+            let job = r#"{"words": ["cat"], "sets": ["count"]}"#;
+            for _ in 0..2 {
+                assert_eq!(
+                    request(address, "POST", "/generate", job),
+                    (200, String::from("cat1\n"))
+                );
+            }
+        }
+
+        fs::write(
+            dir.join("again.yaml"),
+            "name: loud\nmutation_sets:\n  - [reverse]\n",
+        )
+        .unwrap();
+        let args = ProgramArgs::try_parse_command_line([
+            "gorilla",
+            "serve",
+            "--listen",
+            "127.0.0.1:0",
+            "--sets-dir",
+            &dir.to_string_lossy(),
+        ])
+        .unwrap();
+        assert!(Server::bind(&args).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod policy_tests {
+    use crate::policy::{CharClass, Policy, PRESETS};
+
+    #[test]
+    fn presets() {
+        for name in PRESETS {
+            assert_eq!(Policy::preset(name).unwrap().name, name);
+        }
+        assert!(Policy::preset("strict").is_none());
+
+        let nist = Policy::preset("nist").unwrap();
+        assert!(nist.allows("password"));
+        assert!(!nist.allows("passwor"));
+
+        let pci = Policy::preset("pci").unwrap();
+        assert!(pci.allows("correct-horse-1"));
+        assert!(!pci.allows("correct-horse"));
+        assert!(!pci.allows("c0rrect-h"));
+
+        let ad = Policy::preset("ad-default").unwrap();
+        assert!(ad.allows("pass-w0rd"));
+        assert!(!ad.allows("password1"));
+        // lengths are in characters
+        assert!(ad.allows("Pä55wör"));
+        assert!(!ad.allows("Pä55wö"));
+    }
+
+    #[test]
+    fn custom_policies() {
+        let policy = Policy {
+            name: String::from("corp"),
+            max_length: Some(10),
+            required: vec![CharClass::Upper],
+            banned: vec![String::from("acme")],
+            ..Policy::default()
+        };
+        assert!(policy.allows("Winter"));
+        assert!(!policy.allows("winter"));
+        assert!(!policy.allows("WinterWinter"));
+        assert!(!policy.allows("ACMEwinter"));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn policy_files() {
+        use crate::yaml_parser::parse_policy_yaml;
+
+        let policy = parse_policy_yaml("name: short\nmax_length: 4\n").unwrap();
+        assert_eq!(policy.max_length, Some(4));
+        assert_eq!(policy.min_classes, 0);
+        assert!(policy.allows("cat"));
+
+        for invalid in [
+            "max_length: 4\n",
+            "name: a\nmin_length: many\n",
+            "name: a\nmin_classes: 5\n",
+            "name: a\nrequire: [vowel]\n",
+            "name: a\nbanned: acme\n",
+        ] {
+            assert!(parse_policy_yaml(invalid).is_err(), "{invalid}");
+        }
+    }
+}
+
+#[cfg(all(test, feature = "strength"))]
+mod strength_tests {
+    use crate::{
+        mutation::{perform_in_parallel, MutationResult, MutationSet},
+        strength::{annotated_csv, annotated_json, score, score_in_parallel},
+    };
+
+    #[test]
+    fn scores() {
+        assert_eq!(score("password"), 0);
+        assert_eq!(score("correct horse battery staple"), 4);
+
+        let sets = [
+            MutationSet::new(&["append:1"]).unwrap(),
+            MutationSet::new(&["append:#Kq9vTz"]).unwrap(),
+        ];
+        let words = ["cat", "password", "summer", "dragon"];
+        let results = perform_in_parallel(&sets, &words, 2);
+        let strengths = score_in_parallel(&results, 2);
+        assert_eq!(strengths, score_in_parallel(&results, 1));
+        for (word_results, word_strengths) in results.iter().zip(&strengths) {
+            for (result, set_strengths) in word_results.iter().zip(word_strengths) {
+                let expected: Vec<u8> = result.mutated_words.iter().map(|w| score(w)).collect();
+                assert_eq!(set_strengths, &expected);
+            }
+        }
+    }
+
+    #[test]
+    fn annotated_records() {
+        let result = MutationResult {
+            original_word: String::from("cat"),
+            mutated_words: vec![String::from("cat1"), String::from("cat,2")],
+            mutation_set: 1,
+        };
+        assert_eq!(
+            annotated_json(&result, &[0, 1]),
+            r#"{"word":"cat","mutations":["cat1","cat,2"],"strength":[0,1]}"#
+        );
+        assert_eq!(
+            annotated_csv(&result, &[0, 1]),
+            "cat,cat1,0\ncat,\"cat,2\",1\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod prince_tests {
+    use crate::prince::Prince;
+
+    fn example(max_len: usize, max_elements: usize) -> Prince {
+        let words = ["a", "bc", "d", "xyz", "a", "", "toolong"].map(String::from);
+        Prince::new(words.to_vec(), 1, max_len, max_elements)
+    }
+
+    #[test]
+    fn shortest_first() {
+        let prince = example(3, 3);
+        let all: Vec<String> = prince.iter_from(0).collect();
+
+        assert_eq!(prince.element_count(), 4);
+        assert_eq!(prince.chain_count(), 7);
+        assert_eq!(prince.keyspace(), Some(20));
+        assert_eq!(all[..8], ["a", "d", "bc", "aa", "ad", "da", "dd", "xyz"]);
+        // of the words of 3 letters, the chains that make fewer come first
+        assert_eq!(all[8..12], ["abc", "dbc", "bca", "bcd"]);
+        assert_eq!(all[12], "aaa");
+        assert_eq!(all.len(), 20);
+
+        let size: usize = all.iter().map(|word| word.len() + 1).sum();
+        assert_eq!(prince.calculate_size(), Some(size as u128));
+
+        let two_elements: Vec<String> = example(3, 2).iter_from(0).collect();
+        assert_eq!(two_elements.len(), 12);
+        assert!(!two_elements.contains(&String::from("aaa")));
+    }
+
+    #[test]
+    fn skip_words() {
+        let prince = example(3, 3);
+        let all: Vec<String> = prince.iter_from(0).collect();
+        for skip in [1, 3, 7, 8, 13, 19] {
+            assert_eq!(
+                prince.iter_from(skip).collect::<Vec<_>>(),
+                all[skip as usize..]
+            );
+        }
+        assert_eq!(prince.iter_from(20).count(), 0);
+        assert_eq!(Prince::new(vec![], 1, 8, 4).iter_from(0).count(), 0);
+
+        let longer = Prince::new(vec![String::from("ab")], 3, 6, 4);
+        assert_eq!(longer.iter_from(0).collect::<Vec<_>>(), ["abab", "ababab"]);
+    }
+}
+
+#[cfg(test)]
+mod hybrid_tests {
+    use crate::hybrid::Hybrid;
+    use crate::patterns::tokenize_format_string;
+
+    fn example(
+        prepend: Option<&str>,
+        append: Option<&str>,
+        skip: u128,
+        limit: Option<u128>,
+    ) -> Hybrid {
+        let tokenize = |pattern: Option<&str>| pattern.map(|p| tokenize_format_string(p).unwrap());
+        Hybrid::new(tokenize(prepend), tokenize(append), skip, limit)
+    }
+
+    #[test]
+    fn around_the_word() {
+        let appended = example(None, Some("{0-9}{0-9}"), 0, None);
+        let words: Vec<String> = appended.words("pass").collect();
+        assert_eq!(appended.keyspace(), 100);
+        assert_eq!(words.len(), 100);
+        assert_eq!(words[..3], ["pass00", "pass10", "pass20"]);
+
+        let both = example(Some("{A-B}"), Some("{0-1}"), 0, None);
+        assert_eq!(
+            both.words("x").collect::<Vec<_>>(),
+            ["Ax0", "Ax1", "Bx0", "Bx1"]
+        );
+    }
+
+    #[test]
+    fn split_the_keyspace() {
+        let all: Vec<String> = example(Some("{A-C}"), Some("{0-2}"), 0, None)
+            .words("x")
+            .collect();
+        for skip in [0, 1, 3, 5, 8] {
+            for limit in [1, 2, 4] {
+                let part = example(Some("{A-C}"), Some("{0-2}"), skip, Some(limit));
+                let end = (skip as usize + limit as usize).min(all.len());
+                assert_eq!(part.words_per_input(), (end - skip as usize) as u128);
+                assert_eq!(part.words("x").collect::<Vec<_>>(), all[skip as usize..end]);
+            }
+        }
+        assert_eq!(example(None, Some("{0-2}"), 3, None).words("x").count(), 0);
+    }
+}
+
+#[cfg(test)]
+mod profile_tests {
+    use crate::profile::{Date, Profile};
+
+    fn example() -> Profile {
+        Profile {
+            names: vec![String::from("Jane Doe")],
+            birth_dates: vec![Date::parse("1990-04-12").unwrap()],
+            pets: vec![String::from("Rex")],
+            company: vec![String::from("Acme Corp")],
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn dates() {
+        let date = Date::parse("1990-04-12").unwrap();
+        assert_eq!(
+            date.numbers(),
+            [
+                "1990", "90", "1204", "0412", "120490", "041290", "12041990", "04121990",
+                "19900412"
+            ]
+        );
+        for invalid in [
+            "1990-4-12",
+            "1990-13-01",
+            "90-04-12",
+            "1990-04-12x",
+            "12/04/1990",
+        ] {
+            assert_eq!(Date::parse(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn candidates() {
+        let profile = example();
+        assert_eq!(profile.words(), ["jane", "doe", "rex", "acmecorp"]);
+        assert_eq!(profile.initials(), ["jd"]);
+
+        let candidates = profile.candidates();
+        assert_eq!(
+            candidates[..10],
+            ["jane", "Jane", "doe", "Doe", "rex", "Rex", "acmecorp", "Acmecorp", "1990", "90"]
+        );
+        for expected in [
+            "jane1990",
+            "Jane_90",
+            "Rex0412",
+            "rex.1204",
+            "1990rex",
+            "Doe123",
+            "jane!",
+            "janedoe",
+            "Jane.Doe",
+            "rex-jane",
+            "jdacmecorp",
+            "JD_Acmecorp",
+            "jd1990",
+            "JD-12041990",
+        ] {
+            assert!(candidates.contains(&String::from(expected)), "{expected}");
+        }
+        for unexpected in ["janejane", "Jane1990!", "jane doe", "JaneDoe1990"] {
+            assert!(
+                !candidates.contains(&String::from(unexpected)),
+                "{unexpected}"
+            );
+        }
+        let mut unique = candidates.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), candidates.len());
+
+        // without dates or initials, just the words and what they make together
+        let words_only = Profile {
+            names: vec![String::from("Rex")],
+            ..Profile::default()
+        };
+        assert_eq!(
+            words_only.candidates(),
+            ["rex", "Rex", "rex1", "rex12", "rex123", "rex!", "Rex1", "Rex12", "Rex123", "Rex!"]
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn profile_files() {
+        use crate::yaml_parser::parse_profile_yaml;
+
+        let profile = parse_profile_yaml(
+            "name: Jane Doe\nnames: [John Doe]\npets: Rex\ncompany: Acme Corp\nbirth_dates: [1990-04-12]\n",
+        )
+        .unwrap();
+        assert_eq!(profile.names, ["Jane Doe", "John Doe"]);
+        assert_eq!(profile.pets, ["Rex"]);
+        assert_eq!(profile.birth_dates, [Date::parse("1990-04-12").unwrap()]);
+        assert!(profile.teams.is_empty());
+
+        for invalid in [
+            "pets: Rex\n",
+            "name: Jane\nbirth_dates: [April 12]\n",
+            "name: Jane\npets: [1, 2]\n",
+        ] {
+            assert!(parse_profile_yaml(invalid).is_err(), "{invalid}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod crack_tests {
+    use crate::crack::{HashType, Hashes};
+
+    #[test]
+    fn hash_types() {
+        let hex =
+            |digest: Vec<u8>| -> String { digest.iter().map(|b| format!("{b:02x}")).collect() };
+        assert_eq!(
+            hex(HashType::Md5.hash("password")),
+            "5f4dcc3b5aa765d61d8327deb882cf99"
+        );
+        assert_eq!(
+            hex(HashType::Sha1.hash("password")),
+            "5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8"
+        );
+        assert_eq!(
+            hex(HashType::Sha256.hash("password")),
+            "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8"
+        );
+        assert_eq!(
+            hex(HashType::Ntlm.hash("password")),
+            "8846f7eaee8fb117ad06bdd830b7586c"
+        );
+    }
+
+    #[test]
+    fn find_hashes() {
+        let file = "5F4DCC3B5AA765D61D8327DEB882CF99\n\n8846f7eaee8fb117ad06bdd830b7586c\nnot a hash\n5f4dcc3b5aa765d61d8327deb882cf9\n+f4dcc3b5aa765d61d8327deb882cf99\n5f4dcc3b5aa765d61d8327deb882cf99\n";
+        let (mut hashes, invalid) = Hashes::read(file.as_bytes(), HashType::Md5).unwrap();
+        assert_eq!(invalid, [4, 5, 6]);
+        assert_eq!(hashes.total, 2);
+
+        assert_eq!(hashes.check("letmein"), None);
+        assert_eq!(
+            hashes.check("password").as_deref(),
+            Some("5f4dcc3b5aa765d61d8327deb882cf99")
+        );
+        // every hash is found once
+        assert_eq!(hashes.check("password"), None);
+        assert_eq!(hashes.found(), 1);
+        assert!(!hashes.all_found());
+
+        let (mut ntlm, invalid) = Hashes::read(file.as_bytes(), HashType::Ntlm).unwrap();
+        assert_eq!(invalid, [4, 5, 6]);
+        assert!(ntlm.check("password").is_some());
+        assert!(!ntlm.all_found());
+    }
+}
+
+#[cfg(test)]
+mod wordlist_tests {
+    use std::{
+        env,
+        io::{BufRead, Cursor},
+    };
+
+    use crate::{
+        arguments::{Commands, ProgramArgs},
+        wordlist::{combine, WordlistAction},
+    };
+
+    fn run(action: WordlistAction, inputs: &[&str], keep_order: bool) -> (Vec<String>, Vec<u64>) {
+        let inputs: Vec<Box<dyn BufRead>> = inputs
+            .iter()
+            .map(|input| Box::new(Cursor::new(input.to_string())) as Box<dyn BufRead>)
+            .collect();
+        let mut words = vec![];
+        // small enough to spill to temporary files
+        let counts = combine(
+            action,
+            inputs,
+            keep_order,
+            200,
+            env::temp_dir(),
+            &mut |word| {
+                words.push(String::from_utf8(word.to_vec()).unwrap());
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(counts.written, words.len() as u64);
+        (words, counts.inputs)
+    }
+
+    #[test]
+    fn merge() {
+        let a = "zeta\nalpha\nbeta\nalpha\n\ngamma\r\n";
+        let b = "beta\ndelta\nzeta\n";
+        let (words, inputs) = run(WordlistAction::Merge, &[a, b], false);
+        assert_eq!(words, ["alpha", "beta", "delta", "gamma", "zeta"]);
+        assert_eq!(inputs, [5, 3]);
+
+        let (words, _) = run(WordlistAction::Merge, &[a, b], true);
+        assert_eq!(words, ["zeta", "alpha", "beta", "gamma", "delta"]);
+    }
+
+    #[test]
+    fn diff() {
+        let a = "zeta\nalpha\nbeta\nalpha\ngamma\n";
+        let (words, _) = run(WordlistAction::Diff, &[a, "beta\n", "zeta\nomega\n"], false);
+        assert_eq!(words, ["alpha", "gamma"]);
+
+        let many: String = (0..100).rev().map(|n| format!("{n}\n")).collect();
+        let odd: String = (1..100).step_by(2).map(|n| format!("{n}\n")).collect();
+        let (words, inputs) = run(WordlistAction::Diff, &[&many, &odd], true);
+        // in the order of the first wordlist
+        let even: Vec<String> = (0..100)
+            .rev()
+            .filter(|n| n % 2 == 0)
+            .map(|n: u32| n.to_string())
+            .collect();
+        assert_eq!(inputs, [100, 50]);
+        assert_eq!(words, even);
+    }
+
+    #[test]
+    fn wordlist_commands() {
+        let parse = |args: &[&str]| ProgramArgs::try_parse_command_line(args);
+
+        let args = parse(&[
+            "gorilla", "wordlist", "diff", "a.txt", "b.txt", "-o", "out.txt",
+        ])
+        .unwrap();
+        assert_eq!(args.command, Some(Commands::Wordlist));
+        assert_eq!(args.wordlist_action, Some(WordlistAction::Diff));
+        assert_eq!(args.words, ["a.txt", "b.txt"]);
+        assert_eq!(args.file_save.as_deref(), Some("out.txt"));
+
+        let message = parse(&["gorilla", "wordlist", "merge", "a.txt", "-m", "reverse"])
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message.contains("doesn't go with `gorilla wordlist merge`"),
+            "{message}"
+        );
+        assert!(parse(&["gorilla", "wordlist", "a.txt"]).is_err());
+    }
+}
+
+#[cfg(test)]
+mod report_tests {
+    use crate::report::WordReport;
+
+    #[test]
+    fn lengths_and_composition() {
+        let mut report = WordReport::default();
+        for word in ["cat", "Cat1", "dog!", "café", "1234", "hunter2"] {
+            report.add(word);
+        }
+        let stats = report.stats();
+        let lengths: Vec<(usize, u64)> = stats
+            .lengths
+            .iter()
+            .map(|length| (length.length, length.words))
+            .collect();
+        // café is 4 characters, if 5 bytes
+        assert_eq!(lengths, [(3, 1), (4, 4), (7, 1)]);
+
+        let composition = stats.composition;
+        assert_eq!(composition.digits, 50.0);
+        assert_eq!(composition.lowercase, 83.3);
+        assert_eq!(composition.uppercase, 16.7);
+        assert_eq!(composition.specials, 16.7);
+        assert_eq!(composition.non_ascii, 16.7);
+    }
+
+    #[test]
+    fn nothing_generated() {
+        let stats = WordReport::default().stats();
+        assert!(stats.lengths.is_empty());
+        assert_eq!(stats.composition.digits, 0.0);
+    }
+}
+
+#[cfg(all(test, feature = "scrape"))]
+mod crawl_state_tests {
+    use crate::arguments::ProgramArgs;
+    use crate::crawl_state::{Crawl, CrawlSettings, CrawlState};
+    use std::{env, fs};
+
+    #[test]
+    fn saved_and_resumed() {
+        let args = ProgramArgs::try_parse_command_line([
+            "gorilla",
+            "scrape",
+            "-w",
+            "https://example.com",
+            "--crawl-depth",
+            "2",
+            "--crawl-include",
+            "blog",
+        ])
+        .unwrap();
+        let settings = CrawlSettings::of(&args);
+        let path = env::temp_dir().join(format!("gorilla-crawl-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut crawl = Crawl::new(path, settings.clone(), None);
+        for url in ["https://example.com", "https://example.com/blog/a"] {
+            crawl.queue(url, 0);
+        }
+        crawl.queue("https://example.com/blog/b", 1);
+        crawl.visit("https://example.com");
+        crawl.followed = 2;
+        crawl.save().unwrap();
+
+        let state = CrawlState::load(path).unwrap().unwrap();
+        let queued: Vec<&str> = state.queue.iter().map(|page| page.url.as_str()).collect();
+        assert_eq!(
+            queued,
+            ["https://example.com/blog/a", "https://example.com/blog/b"]
+        );
+        assert_eq!(state.queue[1].depth, 1);
+        assert_eq!(state.visited, ["https://example.com"]);
+        assert!(state.settings.changed(&settings).is_empty());
+
+        let resumed = Crawl::new(path, settings.clone(), Some(state));
+        assert_eq!(resumed.followed, 2);
+        assert_eq!(resumed.queued().len(), 2);
+        fs::remove_file(path).unwrap();
+        assert!(CrawlState::load(path).unwrap().is_none());
+
+        let deeper = CrawlSettings {
+            crawl_depth: 3,
+            crawl_include: vec![],
+            ..settings.clone()
+        };
+        assert_eq!(
+            settings.changed(&deeper),
+            ["--crawl-depth", "--crawl-include"]
+        );
+    }
+
+    #[test]
+    fn kept_while_pages_are_left() {
+        let args =
+            ProgramArgs::try_parse_command_line(["gorilla", "scrape", "--url-stdin"]).unwrap();
+        let path = env::temp_dir().join(format!("gorilla-crawl-left-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        // a page that couldn't be fetched stays queued
+        let mut crawl = Crawl::new(path, CrawlSettings::of(&args), None);
+        crawl.queue("https://example.com/gone", 0);
+        crawl.queue("https://example.com", 0);
+        crawl.visit("https://example.com");
+        crawl.finish();
+        let state = CrawlState::load(path).unwrap().unwrap();
+        assert_eq!(state.queue[0].url, "https://example.com/gone");
+
+        let mut crawl = Crawl::new(path, CrawlSettings::of(&args), Some(state));
+        crawl.visit("https://example.com/gone");
+        crawl.finish();
+        assert!(CrawlState::load(path).unwrap().is_none());
+    }
 }
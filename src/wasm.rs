@@ -0,0 +1,49 @@
+//! The mutations for JavaScript, built for `wasm32-unknown-unknown` with
+//! `--no-default-features --features wasm`:
+//!
+//! ```js
+//! const mutator = new Mutator("name: demo\nmutation_sets:\n  - [reverse]\n");
+//! mutator.mutate("cat"); // ["tac"]
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{mutation::MutationSet, yaml_parser::get_mutation_sets};
+
+/// The mutation sets of a mutations file
+#[wasm_bindgen]
+pub struct Mutator {
+    mutation_sets: Vec<MutationSet>,
+}
+
+#[wasm_bindgen]
+impl Mutator {
+    /// Load the mutation sets of a mutations file, throwing why it's
+    /// invalid when it is
+    #[wasm_bindgen(constructor)]
+    pub fn new(yaml: &str) -> Result<Mutator, JsError> {
+        let mutation_sets = get_mutation_sets(yaml).map_err(|e| JsError::new(&e))?;
+        Ok(Mutator { mutation_sets })
+    }
+
+    /// Every word the sets make of `word`, in the order of the sets
+    pub fn mutate(&self, word: &str) -> Vec<String> {
+        let mut words = vec![];
+        for mutation_set in &self.mutation_sets {
+            mutation_set.perform_streaming(word, &mut |mutated| words.push(mutated.to_owned()));
+        }
+        words
+    }
+
+    /// How many mutation sets were loaded
+    #[wasm_bindgen(getter, js_name = setCount)]
+    pub fn set_count(&self) -> usize {
+        self.mutation_sets.len()
+    }
+}
+
+/// `new Mutator(yaml).mutate(word)`, for a word or two
+#[wasm_bindgen]
+pub fn mutate(yaml: &str, word: &str) -> Result<Vec<String>, JsError> {
+    Ok(Mutator::new(yaml)?.mutate(word))
+}
@@ -1,26 +1,834 @@
+use crate::page_cache::PageCache;
+use encoding_rs::{Encoding, UTF_8};
+use flate2::read::GzDecoder;
+use regex::Regex;
 use scraper::{Html, Selector};
-use std::collections::BTreeSet;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::{self, Display},
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use ureq::{
+    tls::{parse_pem, PemItem, RootCerts, TlsConfig},
+    Agent, ResponseExt,
+};
 
-pub fn download_page(page_url: &str) -> Result<String, ureq::Error> {
-    let body: String = ureq::get(page_url).call()?.body_mut().read_to_string()?;
+/// A `Selector` parsed only once, rather than every time a page is scraped.
+macro_rules! selector {
+    ($css:literal) => {{
+        static SELECTOR: OnceLock<Selector> = OnceLock::new();
+        SELECTOR.get_or_init(|| Selector::parse($css).unwrap())
+    }};
+}
+
+/// Options for the HTTP client shared by every download
+#[derive(Debug, Default)]
+pub struct HttpOptions {
+    /// Skip TLS certificate verification
+    pub insecure: bool,
+    /// Path to a PEM bundle with the CA certificates to trust
+    pub ca_cert: Option<String>,
+    /// Connect and read timeout, `None` waits forever
+    pub timeout: Option<Duration>,
+    /// Pages larger than this many bytes are aborted, `None` uses ureq's 10 MB default
+    pub max_download_size: Option<u64>,
+    /// Where downloaded pages are cached, if anywhere
+    pub cache: Option<PageCache>,
+    /// Sent instead of `USER_AGENT`, robots.txt is still read for gorilla
+    pub user_agent: Option<String>,
+}
+
+/// The agent used for pages, sitemaps and robots.txt files, together with
+/// the limits applied to every download.
+#[derive(Clone)]
+pub struct HttpClient {
+    pub agent: Agent,
+    pub max_download_size: u64,
+    pub cache: Option<PageCache>,
+}
+
+#[derive(Debug)]
+pub enum ScrapeError {
+    Http(ureq::Error),
+    Io(std::io::Error),
+    UnsupportedContentType(String),
+    TooLarge(u64),
+}
+
+impl From<ureq::Error> for ScrapeError {
+    fn from(error: ureq::Error) -> Self {
+        match error {
+            ureq::Error::BodyExceedsLimit(limit) => ScrapeError::TooLarge(limit),
+            error => ScrapeError::Http(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for ScrapeError {
+    fn from(error: std::io::Error) -> Self {
+        ScrapeError::Io(error)
+    }
+}
+
+/// Describes errors, pointing at `--insecure`/`--ca-cert` when the
+/// certificate of the site could not be verified and at `--timeout` when
+/// the server took too long.
+impl Display for ScrapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScrapeError::Http(e @ ureq::Error::Timeout(_)) => {
+                write!(
+                    f,
+                    "{e} (the server is too slow, a larger --timeout may help)"
+                )
+            }
+            ScrapeError::Http(e) if e.to_string().to_lowercase().contains("certificate") => {
+                write!(
+                    f,
+                    "{e} (use --ca-cert to trust its CA or --insecure to skip verification)"
+                )
+            }
+            ScrapeError::Http(e) => write!(f, "{e}"),
+            ScrapeError::Io(e) => write!(f, "{e}"),
+            ScrapeError::UnsupportedContentType(t) => {
+                write!(f, "unsupported content type {t}")
+            }
+            ScrapeError::TooLarge(limit) => {
+                write!(f, "larger than the download limit of {limit} bytes")
+            }
+        }
+    }
+}
+
+/// The download limit ureq applies when none is given
+const DEFAULT_DOWNLOAD_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Build the client used for pages, sitemaps and robots.txt files.
+pub fn build_client(options: &HttpOptions) -> Result<HttpClient, ScrapeError> {
+    let mut tls_config = TlsConfig::builder().disable_verification(options.insecure);
+
+    if let Some(ca_cert) = &options.ca_cert {
+        let pem = fs::read(ca_cert)?;
+        let mut certs = Vec::new();
+
+        for item in parse_pem(&pem) {
+            if let PemItem::Certificate(cert) = item? {
+                certs.push(cert);
+            }
+        }
+
+        tls_config = tls_config.root_certs(RootCerts::new_with_certs(&certs));
+    }
+
+    let agent: Agent = Agent::config_builder()
+        .tls_config(tls_config.build())
+        .user_agent(options.user_agent.as_deref().unwrap_or(USER_AGENT))
+        // ureq checks each stage against the timeout of the stage before
+        // it, so every stage is bounded to get a connect and read timeout
+        .timeout_resolve(options.timeout)
+        .timeout_connect(options.timeout)
+        .timeout_send_request(options.timeout)
+        .timeout_send_body(options.timeout)
+        .timeout_recv_response(options.timeout)
+        .timeout_recv_body(options.timeout)
+        .build()
+        .into();
+
+    Ok(HttpClient {
+        agent,
+        max_download_size: options.max_download_size.unwrap_or(DEFAULT_DOWNLOAD_SIZE),
+        cache: options.cache.clone(),
+    })
+}
+
+/// A downloaded (or locally read) page
+#[derive(Debug)]
+pub struct Page {
+    pub body: String,
+    /// The media type of the page without parameters, like `text/html`
+    pub content_type: Option<String>,
+    /// Whether the page came from `--scrape-cache` rather than the network
+    pub from_cache: bool,
+    /// Why the downloaded page couldn't be put in the cache, when it
+    /// couldn't
+    pub cache_error: Option<String>,
+}
+
+impl Page {
+    pub fn is_json(&self) -> bool {
+        self.content_type.as_deref().is_some_and(is_json_type)
+    }
+}
+
+fn is_json_type(content_type: &str) -> bool {
+    content_type == "application/json" || content_type.ends_with("+json")
+}
+
+/// Whether words can be scraped from a page of this media type.
+pub fn is_text_content_type(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || is_json_type(content_type)
+        || content_type == "application/xml"
+        || content_type.ends_with("+xml")
+        || content_type == "application/javascript"
+}
+
+/// Extensions of files we never want to download because no words can
+/// be scraped from them.
+const BINARY_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "ico", "webp", "svg", "tif", "tiff", "mp3", "mp4", "avi",
+    "mov", "mkv", "webm", "wav", "flac", "ogg", "zip", "gz", "tgz", "bz2", "xz", "7z", "rar",
+    "tar", "exe", "msi", "dmg", "iso", "bin", "apk", "deb", "rpm", "pdf", "doc", "docx", "xls",
+    "xlsx", "ppt", "pptx", "woff", "woff2", "ttf", "otf", "eot",
+];
+
+/// Whether a URL points to an obviously binary file, judging by its extension.
+pub fn is_binary_url(page_url: &str) -> bool {
+    let path = split_url(page_url).map_or(page_url.to_owned(), |(_, path)| path);
+    let path = path.split(['?', '#']).next().unwrap_or("");
+
+    match path.rsplit_once('.') {
+        Some((_, extension)) if !extension.contains('/') => {
+            BINARY_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+        }
+        _ => false,
+    }
+}
+
+/// Download a page, or take it from the cache when there's a fresh copy.
+pub fn download_page(client: &HttpClient, page_url: &str) -> Result<Page, ScrapeError> {
+    if let Some(page) = client.cache.as_ref().and_then(|cache| cache.get(page_url)) {
+        return Ok(page);
+    }
+
+    let mut response = client.agent.get(page_url).call()?;
+    let final_url = response.get_uri().to_string();
+    let content_type_header = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    let content_type = content_type_header
+        .as_deref()
+        .map(|v| v.split(';').next().unwrap_or("").trim().to_lowercase());
+
+    if let Some(content_type) = &content_type {
+        if !is_text_content_type(content_type) {
+            return Err(ScrapeError::UnsupportedContentType(content_type.to_owned()));
+        }
+    }
+
+    let bytes: Vec<u8> = response
+        .body_mut()
+        .with_config()
+        .limit(client.max_download_size)
+        .read_to_vec()?;
+
+    let mut page = Page {
+        body: decode_page_body(&bytes, content_type_header.as_deref()),
+        content_type,
+        from_cache: false,
+        cache_error: None,
+    };
+
+    if let Some(cache) = &client.cache {
+        if let Err(e) = cache.put(page_url, &final_url, &page) {
+            page.cache_error = Some(e.to_string());
+        }
+    }
+
+    Ok(page)
+}
+
+fn meta_charset_regex() -> &'static Regex {
+    static META_CHARSET_REGEX: OnceLock<Regex> = OnceLock::new();
+    META_CHARSET_REGEX.get_or_init(|| {
+        Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_:.-]+)"#).unwrap()
+    })
+}
+
+/// Turn the raw bytes of a page into UTF-8. The encoding is taken from a
+/// byte order mark, the `charset` of the `Content-Type` header or a
+/// `<meta charset>` tag near the start of the page, in that order, and
+/// defaults to UTF-8. Bytes that can't be decoded are replaced.
+pub fn decode_page_body(bytes: &[u8], content_type: Option<&str>) -> String {
+    let from_header = content_type
+        .and_then(|v| {
+            v.split(';')
+                .skip(1)
+                .filter_map(|param| param.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        })
+        .and_then(|(_, label)| Encoding::for_label(label.trim().trim_matches('"').as_bytes()));
+
+    let from_meta = || {
+        // like browsers, only the start of the page is looked at
+        let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]);
+        meta_charset_regex()
+            .captures(&head)
+            .and_then(|c| Encoding::for_label(c[1].as_bytes()))
+    };
+
+    let encoding = from_header.or_else(from_meta).unwrap_or(UTF_8);
+    // decode() also honours a byte order mark, which wins over both
+    let (body, _, _) = encoding.decode(bytes);
+
+    body.into_owned()
+}
+
+/// Stream a remote file, like a wordlist, without the download limit for
+/// pages. With a cache the file is downloaded into it first and then read
+/// from there.
+pub fn download_stream(client: &HttpClient, url: &str) -> Result<Box<dyn Read>, ScrapeError> {
+    if let Some(reader) = client.cache.as_ref().and_then(|cache| cache.open(url)) {
+        return Ok(Box::new(reader));
+    }
+
+    let response = client.agent.get(url).call()?;
+    let final_url = response.get_uri().to_string();
+    let mut body = response
+        .into_body()
+        .into_with_config()
+        .limit(u64::MAX)
+        .reader();
+
+    match &client.cache {
+        Some(cache) => {
+            cache.put_reader(url, &final_url, &mut body)?;
+            let reader = cache.open(url).ok_or_else(|| {
+                std::io::Error::other("the downloaded file could not be read from the cache")
+            })?;
+            Ok(Box::new(reader))
+        }
+        None => Ok(Box::new(body)),
+    }
+}
+
+pub fn download_bytes(client: &HttpClient, page_url: &str) -> Result<Vec<u8>, ScrapeError> {
+    let body: Vec<u8> = client
+        .agent
+        .get(page_url)
+        .call()?
+        .body_mut()
+        .with_config()
+        .limit(client.max_download_size)
+        .read_to_vec()?;
 
     Ok(body)
 }
 
-pub fn extract_words(page_body: &str) -> Vec<String> {
-    let page_body = just_body_html_content(page_body);
-    let document = Html::parse_fragment(&page_body);
-    let text_content: String = document.root_element().text().collect();
-    let mut words_set = BTreeSet::new();
+/// How the casing of scraped words is handled when deduplicating them
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum WordCase {
+    /// Lowercase every word
+    #[default]
+    Lower,
+    /// Keep the casing of the first occurrence, ignoring case when deduplicating
+    First,
+}
 
-    for word in text_content.split_whitespace() {
+/// Map a `file://` URL or a plain path that exists to a local path.
+/// Web URLs give `None`.
+pub fn local_path(source: &str) -> Option<PathBuf> {
+    if let Some(path) = source.strip_prefix("file://") {
+        return Some(PathBuf::from(path));
+    }
+
+    if source.starts_with("http://") || source.starts_with("https://") {
+        return None;
+    }
+
+    let path = PathBuf::from(source);
+    path.exists().then_some(path)
+}
+
+/// The html files of a directory, and what was left out of them
+#[derive(Debug, Default)]
+pub struct HtmlFiles {
+    /// Sorted, so that runs are reproducible
+    pub files: Vec<PathBuf>,
+    /// The files that aren't html
+    pub skipped: Vec<PathBuf>,
+    /// The directories that couldn't be read, and why
+    pub unreadable: Vec<(PathBuf, std::io::Error)>,
+}
+
+/// Every `.html`/`.htm` file in a directory and its subdirectories.
+pub fn html_files(dir: &Path) -> HtmlFiles {
+    let mut result = HtmlFiles::default();
+    let mut dirs: Vec<PathBuf> = vec![dir.to_owned()];
+
+    while let Some(current) = dirs.pop() {
+        let entries = match fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(e) => {
+                result.unreadable.push((current, e));
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let extension = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(str::to_lowercase);
+
+            if path.is_dir() {
+                dirs.push(path);
+            } else if matches!(extension.as_deref(), Some("html" | "htm")) {
+                result.files.push(path);
+            } else {
+                result.skipped.push(path);
+            }
+        }
+    }
+
+    result.files.sort();
+    result
+}
+
+/// Get the contents of a page, reading it from disk for local paths and
+/// `file://` URLs or downloading it otherwise.
+pub fn load_page(client: &HttpClient, source: &str) -> Result<Page, ScrapeError> {
+    match local_path(source) {
+        Some(path) => {
+            let is_json = path.extension().is_some_and(|e| e == "json");
+
+            if fs::metadata(&path)?.len() > client.max_download_size {
+                return Err(ScrapeError::TooLarge(client.max_download_size));
+            }
+
+            Ok(Page {
+                body: decode_page_body(&fs::read(path)?, None),
+                content_type: Some(String::from(if is_json {
+                    "application/json"
+                } else {
+                    "text/html"
+                })),
+                from_cache: false,
+                cache_error: None,
+            })
+        }
+        None => download_page(client, source),
+    }
+}
+
+/// The host part of a URL, used to be polite to each host separately.
+fn url_host(page_url: &str) -> Option<String> {
+    let uri: ureq::http::Uri = page_url.parse().ok()?;
+    uri.host().map(str::to_owned)
+}
+
+/// Loads pages on worker threads, spacing the requests to the same host at
+/// least `delay` apart. One is kept for a whole crawl, so that the delay
+/// holds from one batch of pages to the next as well.
+#[derive(Clone)]
+pub struct Fetcher {
+    client: HttpClient,
+    threads: usize,
+    delay: Duration,
+    /// earliest time the next request to each host may be sent
+    next_request: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl Fetcher {
+    pub fn new(client: &HttpClient, threads: usize, delay: Duration) -> Fetcher {
+        Fetcher {
+            client: client.clone(),
+            threads: threads.max(1),
+            delay,
+            next_request: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Load `sources`, sending each result over the returned channel as
+    /// soon as it's ready. Duplicate sources are only loaded once.
+    pub fn fetch(&self, sources: Vec<String>) -> Receiver<(String, Result<Page, ScrapeError>)> {
+        let mut visited: HashSet<String> = HashSet::new();
+        let queue: VecDeque<String> = sources
+            .into_iter()
+            .filter(|s| visited.insert(s.clone()))
+            .collect();
+
+        let queue = Arc::new(Mutex::new(queue));
+        let (sender, receiver) = mpsc::sync_channel(self.threads);
+
+        for _ in 0..self.threads {
+            let fetcher = self.clone();
+            let queue = Arc::clone(&queue);
+            let sender = sender.clone();
+
+            thread::spawn(move || loop {
+                let Some(source) = queue.lock().unwrap().pop_front() else {
+                    break;
+                };
+
+                if let Some(host) = url_host(&source).filter(|_| local_path(&source).is_none()) {
+                    fetcher.wait_for(host);
+                }
+
+                let result = load_page(&fetcher.client, &source);
+                if sender.send((source, result)).is_err() {
+                    break;
+                }
+            });
+        }
+
+        receiver
+    }
+
+    /// Wait for the turn of the next request to `host`
+    fn wait_for(&self, host: String) {
+        let wait_until = {
+            let mut next_request = self.next_request.lock().unwrap();
+            let now = Instant::now();
+            let slot = next_request.get(&host).map_or(now, |t| (*t).max(now));
+            next_request.insert(host, slot + self.delay);
+            slot
+        };
+        thread::sleep(wait_until.saturating_duration_since(Instant::now()));
+    }
+}
+
+/// Load pages on `threads` worker threads, like a `Fetcher` of their own
+/// does.
+pub fn fetch_pages(
+    client: &HttpClient,
+    sources: Vec<String>,
+    threads: usize,
+    delay: Duration,
+) -> Receiver<(String, Result<Page, ScrapeError>)> {
+    Fetcher::new(client, threads, delay).fetch(sources)
+}
+
+/// Settings controlling which words `extract_words` pulls out of a page.
+#[derive(Debug, Default)]
+pub struct ScrapeOptions {
+    /// Also extract the title, description/keywords meta tags and alt/title attributes
+    pub meta: bool,
+    pub case: WordCase,
+    /// Also extract groups of up to this many adjacent words
+    pub ngrams: usize,
+    /// JSON documents larger than this many bytes are not parsed
+    pub max_json_size: Option<usize>,
+    /// Also extract standalone numbers of 2 to 6 digits (years, postcodes, ...)
+    pub numbers: bool,
+}
+
+impl ScrapeOptions {
+    /// The key words are deduplicated by, so that `Admin` and `admin`
+    /// count as the same word.
+    pub fn dedup_key(&self, word: &str) -> String {
+        word.to_lowercase()
+    }
+
+    /// Strip everything but letters from a scraped word and apply the case
+    /// setting. Returns `None` when the word is too short to be kept.
+    pub fn clean_word(&self, word: &str) -> Option<String> {
         let filtered_word: String = word.chars().filter(|c| c.is_alphabetic()).collect();
-        if filtered_word.len() > 4 {
-            words_set.insert(filtered_word.to_lowercase());
+
+        if filtered_word.len() <= 4 {
+            return None;
+        }
+
+        Some(self.apply_case(filtered_word))
+    }
+
+    /// Like `clean_word`, for an already filtered group of words.
+    fn clean_ngram(&self, ngram: &str) -> Option<String> {
+        if ngram.replace(' ', "").len() <= 4 {
+            return None;
+        }
+
+        Some(self.apply_case(ngram.to_owned()))
+    }
+
+    fn apply_case(&self, word: String) -> String {
+        match self.case {
+            WordCase::Lower => word.to_lowercase(),
+            WordCase::First => word,
+        }
+    }
+}
+
+/// Characters that end a sentence, n-grams never span across them.
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', ';', ':', '|'];
+
+/// The text of a page's `<body>` split at tag boundaries, so that text
+/// from different elements is never glued together. `<script>` elements
+/// are left out, the same way `just_body_html_content` does.
+pub fn text_segments(document: &Html) -> Vec<String> {
+    let root = match document.select(selector!("body")).next() {
+        Some(body) => body,
+        None => document.root_element(),
+    };
+
+    root.descendants()
+        .filter(|node| {
+            !node.ancestors().any(|ancestor| {
+                ancestor
+                    .value()
+                    .as_element()
+                    .is_some_and(|e| e.name() == "script")
+            })
+        })
+        .filter_map(|node| node.value().as_text())
+        .map(|text| text.trim())
+        .filter(|t| !t.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// The tokens of a segment of text. Entities are already decoded by the
+/// HTML parser, so `&nbsp;` counts as whitespace, and `&` separates words
+/// too so that `Salt&amp;Pepper` gives two of them.
+fn tokens(segment: &str) -> impl Iterator<Item = &str> {
+    segment
+        .split_whitespace()
+        .flat_map(|token| token.split('&'))
+}
+
+/// Split a segment of text into sentences, each being a list of words
+/// stripped down to their letters.
+fn sentences(segment: &str) -> Vec<Vec<String>> {
+    let mut result: Vec<Vec<String>> = Vec::new();
+    let mut sentence: Vec<String> = Vec::new();
+
+    for token in tokens(segment) {
+        let letters: String = token.chars().filter(|c| c.is_alphabetic()).collect();
+
+        if letters.is_empty() {
+            // numbers and lone punctuation break the sentence
+            result.push(std::mem::take(&mut sentence));
+            continue;
+        }
+
+        sentence.push(letters);
+
+        if token.ends_with(SENTENCE_ENDS) {
+            result.push(std::mem::take(&mut sentence));
         }
     }
 
-    words_set.into_iter().collect()
+    result.push(sentence);
+    result.retain(|s| !s.is_empty());
+    result
+}
+
+/// Extract the words of a page along with how often each one occurs.
+///
+/// ```
+/// use gorilla::website_scraper::{extract_words, ScrapeOptions};
+///
+/// let page = "<html><body><p>Gorilla Gorilla bananas</p></body></html>";
+/// let words = extract_words(page, &ScrapeOptions::default());
+/// assert!(words.contains(&(String::from("gorilla"), 2)));
+/// assert!(words.contains(&(String::from("bananas"), 1)));
+/// ```
+pub fn extract_words(page_body: &str, options: &ScrapeOptions) -> Vec<(String, usize)> {
+    let document = Html::parse_document(page_body);
+    let mut segments = text_segments(&document);
+
+    if options.meta {
+        segments.extend(extract_meta_text(&document));
+    }
+
+    words_from_segments(&segments, options)
+}
+
+/// Turn segments of text into deduplicated words (and n-grams), each
+/// with the amount of times it occurred.
+pub fn words_from_segments(segments: &[String], options: &ScrapeOptions) -> Vec<(String, usize)> {
+    // keyed by the dedup key, holding the first spelling seen and its count
+    let mut words_map: BTreeMap<String, (String, usize)> = BTreeMap::new();
+    let mut add = |word: String| {
+        words_map
+            .entry(options.dedup_key(&word))
+            .or_insert((word, 0))
+            .1 += 1;
+    };
+
+    for segment in segments {
+        // an address would be a word like bobsmithacmetest, and
+        // `--scrape-emails` makes usernames of it instead
+        let segment = &email_regex().replace_all(segment, " ");
+        for word in tokens(segment) {
+            if let Some(word) = options.clean_word(word) {
+                add(word);
+            }
+        }
+
+        if options.numbers {
+            for number in number_regex().find_iter(segment) {
+                add(number.as_str().to_owned());
+            }
+        }
+
+        if options.ngrams < 2 {
+            continue;
+        }
+
+        for sentence in sentences(segment) {
+            for n in 2..=options.ngrams {
+                for group in sentence.windows(n) {
+                    for joined in [group.join(" "), group.concat()] {
+                        if let Some(word) = options.clean_ngram(&joined) {
+                            add(word);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    words_map.into_values().collect()
+}
+
+/// How deep `extract_json_words` descends into nested arrays and objects.
+const MAX_JSON_DEPTH: usize = 64;
+
+fn json_segments(value: &serde_json::Value, depth: usize, segments: &mut Vec<String>) {
+    if depth > MAX_JSON_DEPTH {
+        return;
+    }
+
+    match value {
+        serde_json::Value::String(s) => segments.push(s.to_owned()),
+        serde_json::Value::Array(values) => {
+            for value in values {
+                json_segments(value, depth + 1, segments)
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                // snake_case keys hold several words
+                segments.push(key.replace('_', " "));
+                json_segments(value, depth + 1, segments)
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Extract words from the key names and string values of a JSON document.
+/// Documents larger than `options.max_json_size` are skipped.
+pub fn extract_json_words(
+    json: &str,
+    options: &ScrapeOptions,
+) -> Result<Vec<(String, usize)>, serde_json::Error> {
+    if options.max_json_size.is_some_and(|max| json.len() > max) {
+        return Ok(Vec::new());
+    }
+
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let mut segments: Vec<String> = Vec::new();
+    json_segments(&value, 0, &mut segments);
+
+    Ok(words_from_segments(&segments, options))
+}
+
+/// Collect the text of the `<title>` tag, the `description` and `keywords`
+/// meta tags and any `alt`/`title` attributes inside the body. These are
+/// dropped by `just_body_html_content` but often hold the best keywords.
+pub fn extract_meta_text(document: &Html) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+
+    let title_selector = selector!("title");
+    for title in document.select(title_selector) {
+        result.push(title.text().collect());
+    }
+
+    let meta_selector = selector!("meta[name][content]");
+    for meta in document.select(meta_selector) {
+        let name = meta.value().attr("name").unwrap_or("").to_lowercase();
+        if name == "description" || name == "keywords" {
+            // keywords are comma separated, without spaces most of the time
+            let content = meta.value().attr("content").unwrap_or("");
+            result.push(content.replace(',', " "));
+        }
+    }
+
+    let attr_selector = selector!("body [alt], body [title]");
+    for element in document.select(attr_selector) {
+        for attr in ["alt", "title"] {
+            if let Some(value) = element.value().attr(attr) {
+                result.push(value.to_owned());
+            }
+        }
+    }
+
+    result
+}
+
+fn number_regex() -> &'static Regex {
+    static NUMBER_REGEX: OnceLock<Regex> = OnceLock::new();
+    NUMBER_REGEX.get_or_init(|| Regex::new(r"\b[0-9]{2,6}\b").unwrap())
+}
+
+fn email_regex() -> &'static Regex {
+    static EMAIL_REGEX: OnceLock<Regex> = OnceLock::new();
+    EMAIL_REGEX.get_or_init(|| {
+        Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").unwrap()
+    })
+}
+
+/// Find every email address on a page, both in the text and in
+/// `mailto:` links which usually aren't visible. Lowercased and sorted.
+pub fn extract_emails(all_html: &str) -> Vec<String> {
+    let document = Html::parse_document(all_html);
+    let mut haystack: String = document.root_element().text().collect::<Vec<_>>().join(" ");
+
+    let link_selector = selector!("a[href]");
+    for link in document.select(link_selector) {
+        let href = link.value().attr("href").unwrap_or("");
+        if let Some(address) = href.strip_prefix("mailto:") {
+            haystack.push(' ');
+            haystack.push_str(address.split('?').next().unwrap_or(""));
+        }
+    }
+
+    let emails: BTreeSet<String> = email_regex()
+        .find_iter(&haystack)
+        .map(|m| m.as_str().to_lowercase())
+        .collect();
+
+    emails.into_iter().collect()
+}
+
+/// Username candidates derived from an email address: the local part,
+/// the local part without dots and, for `first.last`, each name on its own.
+pub fn email_usernames(email: &str) -> Vec<String> {
+    let local = email.split('@').next().unwrap_or("");
+    // drop sub-addressing tags like john+news@example.org
+    let local = local.split('+').next().unwrap_or("");
+
+    let mut result: Vec<String> = Vec::new();
+    let mut push = |candidate: &str| {
+        if !candidate.is_empty() && !result.iter().any(|r| r == candidate) {
+            result.push(candidate.to_owned())
+        }
+    };
+
+    push(local);
+    push(&local.replace('.', ""));
+
+    let names: Vec<&str> = local.split('.').collect();
+    if names.len() == 2 {
+        push(names[0]);
+        push(names[1]);
+    }
+
+    result
 }
 
 /// Remove everything from page_body except the
@@ -30,19 +838,20 @@ pub fn extract_words(page_body: &str) -> Vec<String> {
 /// If no <body> tag is found, or there's any other error,
 /// this function just silently returns the given
 /// all_html
+#[allow(dead_code)]
 pub fn just_body_html_content(all_html: &str) -> String {
     // Parse the HTML
     let document = Html::parse_document(all_html);
 
     // Select the <body> tag
-    let body_selector = Selector::parse("body").unwrap();
-    if let Some(body_element) = document.select(&body_selector).next() {
+    let body_selector = selector!("body");
+    if let Some(body_element) = document.select(body_selector).next() {
         // Extract the inner HTML of <body>
         let mut body_html = body_element.inner_html();
 
         // Remove <script> elements
-        let script_selector = Selector::parse("script").unwrap();
-        for script in body_element.select(&script_selector) {
+        let script_selector = selector!("script");
+        for script in body_element.select(script_selector) {
             let script_html = script.html();
             body_html = body_html.replace(&script_html, "");
         }
@@ -53,3 +862,554 @@ pub fn just_body_html_content(all_html: &str) -> String {
     // If no <body> tag is found, return the original HTML
     all_html.to_string()
 }
+
+/// The user agent gorilla identifies itself with, both in the
+/// `User-Agent` header and when picking a robots.txt group.
+pub const USER_AGENT: &str = concat!("gorilla/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, PartialEq)]
+pub enum RobotsRule {
+    Allow(String),
+    Disallow(String),
+}
+
+/// Rules from a robots.txt file that apply to a single user agent.
+#[derive(Debug, Default)]
+pub struct RobotsRules {
+    pub rules: Vec<RobotsRule>,
+}
+
+/// Parse a robots.txt body and keep the rules of the group that best
+/// matches `user_agent`. A group naming our product token wins over the
+/// `*` group; if neither exists everything is allowed.
+pub fn parse_robots(robots_body: &str, user_agent: &str) -> RobotsRules {
+    let product = user_agent
+        .split('/')
+        .next()
+        .unwrap_or(user_agent)
+        .to_lowercase();
+
+    let mut specific: Vec<RobotsRule> = Vec::new();
+    let mut wildcard: Vec<RobotsRule> = Vec::new();
+    let mut found_specific = false;
+
+    // user agents of the group currently being read
+    let mut group_agents: Vec<String> = Vec::new();
+    let mut reading_agents = false;
+
+    for line in robots_body.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+
+        match key.as_str() {
+            "user-agent" => {
+                if !reading_agents {
+                    group_agents.clear();
+                    reading_agents = true;
+                }
+                group_agents.push(value.to_lowercase());
+            }
+            "allow" | "disallow" => {
+                reading_agents = false;
+
+                // an empty disallow means "allow everything", which is the default anyway
+                if value.is_empty() {
+                    continue;
+                }
+
+                let is_specific = group_agents
+                    .iter()
+                    .any(|agent| agent != "*" && product.contains(agent.as_str()));
+                let is_wildcard = group_agents.iter().any(|agent| agent == "*");
+
+                let make_rule = || match key.as_str() {
+                    "allow" => RobotsRule::Allow(value.to_owned()),
+                    _ => RobotsRule::Disallow(value.to_owned()),
+                };
+
+                if is_specific {
+                    found_specific = true;
+                    specific.push(make_rule());
+                } else if is_wildcard {
+                    wildcard.push(make_rule());
+                }
+            }
+            _ => reading_agents = false,
+        }
+    }
+
+    RobotsRules {
+        rules: if found_specific { specific } else { wildcard },
+    }
+}
+
+/// Match a robots.txt path pattern against a path, supporting the
+/// `*` wildcard and the `$` end anchor. Patterns are prefix matches.
+fn robots_pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+
+    let mut pieces = pattern.split('*');
+    let first = pieces.next().unwrap_or("");
+
+    if !path.starts_with(first) {
+        return false;
+    }
+
+    let mut rest = &path[first.len()..];
+    let pieces: Vec<&str> = pieces.collect();
+
+    for (i, piece) in pieces.iter().enumerate() {
+        if anchored && i == pieces.len() - 1 {
+            return rest.ends_with(piece);
+        }
+        match rest.find(piece) {
+            Some(pos) => rest = &rest[pos + piece.len()..],
+            None => return false,
+        }
+    }
+
+    !anchored || rest.is_empty()
+}
+
+impl RobotsRules {
+    /// The longest matching rule decides; on a tie `Allow` wins.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        let mut best: Option<(usize, bool)> = None;
+
+        for rule in &self.rules {
+            let (pattern, allow) = match rule {
+                RobotsRule::Allow(p) => (p, true),
+                RobotsRule::Disallow(p) => (p, false),
+            };
+
+            if !robots_pattern_matches(pattern, path) {
+                continue;
+            }
+
+            best = match best {
+                Some((len, prev_allow))
+                    if len > pattern.len() || (len == pattern.len() && prev_allow) =>
+                {
+                    Some((len, prev_allow))
+                }
+                _ => Some((pattern.len(), allow)),
+            };
+        }
+
+        best.is_none_or(|(_, allow)| allow)
+    }
+}
+
+/// Split a URL into its `scheme://authority` origin and its path (with query).
+pub fn split_url(page_url: &str) -> Option<(String, String)> {
+    let uri: ureq::http::Uri = page_url.parse().ok()?;
+    let origin = format!("{}://{}", uri.scheme_str()?, uri.authority()?);
+    let path = uri
+        .path_and_query()
+        .map_or(String::from("/"), |p| p.as_str().to_owned());
+
+    Some((
+        origin,
+        if path.is_empty() {
+            String::from("/")
+        } else {
+            path
+        },
+    ))
+}
+
+/// Fetch the robots.txt belonging to the origin of `page_url`. A missing
+/// or unreachable robots.txt yields no rules, so everything is allowed.
+pub fn fetch_robots(client: &HttpClient, page_url: &str) -> RobotsRules {
+    let Some((origin, _)) = split_url(page_url) else {
+        return RobotsRules::default();
+    };
+
+    match download_page(client, &format!("{origin}/robots.txt")) {
+        Ok(page) => parse_robots(&page.body, USER_AGENT),
+        Err(_) => RobotsRules::default(),
+    }
+}
+
+/// Check whether robots.txt lets us fetch `page_url`.
+pub fn robots_allows(rules: &RobotsRules, page_url: &str) -> bool {
+    match split_url(page_url) {
+        Some((_, path)) => rules.is_allowed(&path),
+        None => true,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Sitemap {
+    /// A `<sitemapindex>` listing other sitemaps
+    Index(Vec<String>),
+    /// A `<urlset>` listing pages
+    UrlSet(Vec<String>),
+}
+
+fn decode_xml_text(text: &str) -> String {
+    let text = text.trim();
+    let text = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+        .unwrap_or(text);
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Pull every `<loc>` out of a sitemap. This is not a real XML parser,
+/// sitemaps are simple enough that looking for the tags does the job.
+pub fn parse_sitemap(xml: &str) -> Sitemap {
+    let mut locations: Vec<String> = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find("<loc>") {
+        rest = &rest[start + "<loc>".len()..];
+        let Some(end) = rest.find("</loc>") else {
+            break;
+        };
+        let location = decode_xml_text(&rest[..end]);
+        if !location.is_empty() {
+            locations.push(location);
+        }
+        rest = &rest[end + "</loc>".len()..];
+    }
+
+    if xml.contains("<sitemapindex") {
+        Sitemap::Index(locations)
+    } else {
+        Sitemap::UrlSet(locations)
+    }
+}
+
+/// Turn a downloaded sitemap into text, gunzipping it if it starts
+/// with the gzip magic bytes.
+pub fn decode_sitemap_body(body: &[u8]) -> Result<String, std::io::Error> {
+    if body.starts_with(&[0x1f, 0x8b]) {
+        let mut decoded = String::new();
+        GzDecoder::new(body).read_to_string(&mut decoded)?;
+        Ok(decoded)
+    } else {
+        Ok(String::from_utf8_lossy(body).into_owned())
+    }
+}
+
+/// The pages of a sitemap, and the sitemaps it points to that couldn't
+/// be read
+#[derive(Debug, Default)]
+pub struct SitemapPages {
+    pub pages: Vec<String>,
+    pub failed: Vec<(String, ScrapeError)>,
+}
+
+/// Download a sitemap and follow any sitemap indexes it points to,
+/// returning at most `max_pages` page URLs. Sitemaps that fail to
+/// download are skipped.
+pub fn collect_sitemap_urls(
+    client: &HttpClient,
+    sitemap_url: &str,
+    max_pages: Option<usize>,
+) -> SitemapPages {
+    let mut failed = Vec::new();
+    let mut pages: Vec<String> = Vec::new();
+    let mut queue: Vec<String> = vec![sitemap_url.to_owned()];
+    let mut visited: HashSet<String> = HashSet::new();
+
+    while let Some(current) = queue.pop() {
+        if max_pages.is_some_and(|max| pages.len() >= max) {
+            break;
+        }
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+
+        let body = match download_bytes(client, &current) {
+            Ok(body) => body,
+            Err(e) => {
+                failed.push((current, e));
+                continue;
+            }
+        };
+
+        let xml = match decode_sitemap_body(&body) {
+            Ok(xml) => xml,
+            Err(e) => {
+                failed.push((current, ScrapeError::from(e)));
+                continue;
+            }
+        };
+
+        match parse_sitemap(&xml) {
+            // reversed so that sub-sitemaps are visited in the listed order
+            Sitemap::Index(sitemaps) => queue.extend(sitemaps.into_iter().rev()),
+            Sitemap::UrlSet(urls) => pages.extend(urls),
+        }
+    }
+
+    if let Some(max) = max_pages {
+        pages.truncate(max);
+    }
+
+    SitemapPages { pages, failed }
+}
+
+/// The words sources and links found in an RSS or Atom feed
+#[derive(Debug, Default, PartialEq)]
+pub struct Feed {
+    /// Titles, descriptions/summaries and categories, with any HTML removed
+    pub text: Vec<String>,
+    /// The page each entry links to
+    pub links: Vec<String>,
+}
+
+/// Every `<name ...>` element in `xml`, as its attributes and the text up
+/// to the matching `</name>` (empty for self-closing tags).
+fn xml_elements<'a>(xml: &'a str, name: &str) -> Vec<(&'a str, &'a str)> {
+    let open = format!("<{name}");
+    let close = format!("</{name}>");
+    let mut elements: Vec<(&str, &str)> = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        // skip longer names sharing the prefix, <linkage> isn't <link>
+        if !rest.starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+            continue;
+        }
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let attributes = &rest[..tag_end];
+        rest = &rest[tag_end + 1..];
+
+        if let Some(attributes) = attributes.strip_suffix('/') {
+            elements.push((attributes, ""));
+            continue;
+        }
+
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        elements.push((attributes, &rest[..end]));
+        rest = &rest[end + close.len()..];
+    }
+
+    elements
+}
+
+/// The value of the `name` attribute in the attributes of an XML tag.
+fn xml_attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+
+    while let Some(start) = rest.find(name) {
+        let preceded_by_space = rest[..start].ends_with(char::is_whitespace);
+        rest = &rest[start + name.len()..];
+        if !preceded_by_space {
+            continue;
+        }
+
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            continue;
+        }
+        let value = &value[1..];
+        let end = value.find(quote)?;
+        return Some(decode_xml_text(&value[..end]));
+    }
+
+    None
+}
+
+/// Pull the word sources and entry links out of an RSS or Atom feed. Like
+/// `parse_sitemap` this only looks for the relevant tags. Descriptions
+/// usually hold (escaped) HTML, which is reduced to its text.
+pub fn parse_feed(xml: &str) -> Feed {
+    let mut feed = Feed::default();
+
+    for name in ["title", "description", "summary"] {
+        for (_, content) in xml_elements(xml, name) {
+            let html = decode_xml_text(content);
+            feed.text
+                .extend(text_segments(&Html::parse_fragment(&html)));
+        }
+    }
+
+    for (attributes, content) in xml_elements(xml, "category") {
+        // rss puts the category in the text, atom in the term attribute
+        let category = match decode_xml_text(content) {
+            text if text.is_empty() => xml_attribute(attributes, "term").unwrap_or_default(),
+            text => text,
+        };
+        if !category.is_empty() {
+            feed.text.push(category);
+        }
+    }
+
+    for (_, item) in xml_elements(xml, "item") {
+        for (_, link) in xml_elements(item, "link") {
+            let link = decode_xml_text(link);
+            if !link.is_empty() {
+                feed.links.push(link);
+            }
+        }
+    }
+
+    for (_, entry) in xml_elements(xml, "entry") {
+        for (attributes, _) in xml_elements(entry, "link") {
+            let rel = xml_attribute(attributes, "rel");
+            if rel.is_none_or(|rel| rel == "alternate") {
+                feed.links.extend(xml_attribute(attributes, "href"));
+            }
+        }
+    }
+
+    feed
+}
+
+/// Download an RSS or Atom feed (gunzipping it if needed) and parse it.
+pub fn fetch_feed(client: &HttpClient, feed_url: &str) -> Result<Feed, ScrapeError> {
+    let body = download_bytes(client, feed_url)?;
+    let xml = decode_sitemap_body(&body)?;
+
+    Ok(parse_feed(&xml))
+}
+
+/// Resolve a link found on `base_url` into an absolute http(s) URL without
+/// its fragment. Links to anything but web pages (`mailto:`, `javascript:`,
+/// ...) and links to the page itself give `None`.
+pub fn resolve_url(base_url: &str, href: &str) -> Option<String> {
+    let href = href.trim();
+    let href = href.split('#').next().unwrap_or("");
+    if href.is_empty() {
+        return None;
+    }
+
+    let (origin, base_path) = split_url(base_url)?;
+    // a scheme is a letter followed by letters, digits, `+`, `-` or `.`
+    let scheme = href
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        });
+
+    let resolved = if let Some(scheme) = scheme {
+        if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+            // mailto:, javascript:, tel: and so on
+            return None;
+        }
+        href.to_owned()
+    } else if let Some(rest) = href.strip_prefix("//") {
+        let scheme = origin.split("://").next().unwrap_or("https");
+        format!("{scheme}://{rest}")
+    } else if href.starts_with('?') {
+        let base_path = base_path.split('?').next().unwrap_or("/");
+        format!("{origin}{base_path}{href}")
+    } else {
+        let path = if href.starts_with('/') {
+            href.to_owned()
+        } else {
+            let base_path = base_path.split('?').next().unwrap_or("/");
+            let directory = &base_path[..base_path.rfind('/').map_or(0, |i| i + 1)];
+            format!("{directory}{href}")
+        };
+        format!("{origin}{}", normalize_path(&path))
+    };
+
+    split_url(&resolved)?;
+    Some(resolved)
+}
+
+/// Remove `.` and `..` segments from the path (and query) of a URL.
+fn normalize_path(path: &str) -> String {
+    let (path, query) = match path.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (path, None),
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    let parts: Vec<&str> = path.split('/').skip(1).collect();
+    for (i, part) in parts.iter().enumerate() {
+        let last = i == parts.len() - 1;
+        match *part {
+            "." if last => segments.push(""),
+            "." => {}
+            ".." => {
+                segments.pop();
+                if last {
+                    segments.push("");
+                }
+            }
+            part => segments.push(part),
+        }
+    }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+    if let Some(query) = query {
+        normalized.push('?');
+        normalized.push_str(query);
+    }
+    normalized
+}
+
+/// Every link on a page, resolved against the URL of the page.
+pub fn extract_links(all_html: &str, page_url: &str) -> Vec<String> {
+    let document = Html::parse_document(all_html);
+
+    document
+        .select(selector!("a[href], area[href]"))
+        .filter_map(|link| resolve_url(page_url, link.value().attr("href")?))
+        .collect()
+}
+
+/// Decides which links found while crawling are followed, keeping track
+/// of how many each rule turned down.
+#[derive(Debug, Default)]
+pub struct CrawlFilter {
+    /// Only follow URLs matching one of these, when there are any
+    pub include: Vec<Regex>,
+    /// Never follow URLs matching one of these
+    pub exclude: Vec<Regex>,
+    /// Follow links to other hosts than the page they're on
+    pub offsite: bool,
+    pub rejected_offsite: usize,
+    pub rejected_include: usize,
+    pub rejected_exclude: usize,
+}
+
+impl CrawlFilter {
+    /// Whether a link to `url` found on `page_url` should be followed.
+    pub fn allows(&mut self, page_url: &str, url: &str) -> bool {
+        if !self.offsite && url_host(page_url) != url_host(url) {
+            self.rejected_offsite += 1;
+            return false;
+        }
+        if !self.include.is_empty() && !self.include.iter().any(|r| r.is_match(url)) {
+            self.rejected_include += 1;
+            return false;
+        }
+        if self.exclude.iter().any(|r| r.is_match(url)) {
+            self.rejected_exclude += 1;
+            return false;
+        }
+        true
+    }
+}
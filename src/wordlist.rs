@@ -0,0 +1,257 @@
+use colored::Colorize;
+use std::{
+    env,
+    fs::File,
+    io::{self, BufRead, BufWriter, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    arguments::ProgramArgs,
+    error::GorillaError,
+    input::{display_name, open_input, HttpClient},
+    sort::{ExternalSort, SortOrder},
+};
+
+/// What `gorilla wordlist` does with the wordlists
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WordlistAction {
+    /// Every word of the wordlists, once
+    Merge,
+    /// The words of the first wordlist that none of the others have
+    Diff,
+}
+
+impl WordlistAction {
+    pub const ALL: [WordlistAction; 2] = [WordlistAction::Merge, WordlistAction::Diff];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WordlistAction::Merge => "merge",
+            WordlistAction::Diff => "diff",
+        }
+    }
+
+    pub fn about(self) -> &'static str {
+        match self {
+            WordlistAction::Merge => {
+                "Write every word of the wordlists once, sorted or in the order they come"
+            }
+            WordlistAction::Diff => {
+                "Write the words of the first wordlist that none of the others have"
+            }
+        }
+    }
+
+    /// What the `WORDLISTS` of the action are
+    pub fn wordlists_help(self) -> &'static str {
+        match self {
+            WordlistAction::Merge => "The wordlists to merge, - for stdin",
+            WordlistAction::Diff => {
+                "The wordlist to take the words from, then the wordlists of the words to leave out"
+            }
+        }
+    }
+}
+
+/// The words read from every input, and how many were written
+pub struct Counts {
+    pub inputs: Vec<u64>,
+    pub written: u64,
+}
+
+/// The words of one of the groups of a sorted word, its duplicates
+#[derive(Default)]
+struct Group {
+    word: Vec<u8>,
+    /// Whether one of the wordlists of the words to leave out has it
+    excluded: bool,
+    /// Where it came first, as 16 hex digits
+    position: Option<Vec<u8>>,
+}
+
+/// Merge the words of `inputs`, or with `Diff` take the words of the others
+/// from the first, handing every word of the result to `output` once. The
+/// words are sorted, or with `keep_order` in the order they first came,
+/// and both are done on disk once they take more than `memory_cap` bytes,
+/// so wordlists far larger than memory work. Empty lines are left out.
+pub fn combine(
+    action: WordlistAction,
+    inputs: Vec<Box<dyn BufRead>>,
+    keep_order: bool,
+    memory_cap: usize,
+    temp_dir: PathBuf,
+    output: &mut dyn FnMut(&[u8]) -> io::Result<()>,
+) -> io::Result<Counts> {
+    // with the order kept, the words are sorted a second time, by where
+    // they came first, while the first sort is merged
+    let memory_cap = if keep_order {
+        memory_cap / 2
+    } else {
+        memory_cap
+    };
+    let mut sorted = ExternalSort::new(SortOrder::Lexicographic, memory_cap, temp_dir.clone());
+
+    // every word is sorted with what it came from after a NUL: `0` for the
+    // words to leave out, so that they come first, and `1` and its
+    // position for the others
+    let mut counts = Counts {
+        inputs: vec![],
+        written: 0,
+    };
+    let mut position: u64 = 0;
+    for (index, input) in inputs.into_iter().enumerate() {
+        let leave_out = action == WordlistAction::Diff && index > 0;
+        let mut words = 0;
+        for line in input.split(b'\n') {
+            let mut record = line?;
+            if record.last() == Some(&b'\r') {
+                record.pop();
+            }
+            if record.is_empty() {
+                continue;
+            }
+            words += 1;
+            record.push(0);
+            if leave_out {
+                record.push(b'0');
+            } else {
+                record.extend_from_slice(format!("1{position:016x}").as_bytes());
+                position += 1;
+            }
+            sorted.push(&record)?;
+        }
+        counts.inputs.push(words);
+    }
+
+    let mut reordered = ExternalSort::new(SortOrder::Lexicographic, memory_cap, temp_dir);
+    let mut group = Group::default();
+    let mut emit = |group: &Group, output: &mut dyn FnMut(&[u8]) -> io::Result<()>| {
+        let Some(position) = group.position.as_ref().filter(|_| !group.excluded) else {
+            return Ok(());
+        };
+        counts.written += 1;
+        match keep_order {
+            true => reordered.push(&[position, &[0][..], &group.word].concat()),
+            false => output(&group.word),
+        }
+    };
+    sorted.finish(&mut |record| {
+        let split = record
+            .iter()
+            .rposition(|&b| b == 0)
+            .expect("every record has what it came from");
+        let (word, from) = (&record[..split], &record[split + 1..]);
+        if group.word != word {
+            emit(&group, output)?;
+            group = Group {
+                word: word.to_vec(),
+                ..Group::default()
+            };
+        }
+        match from.split_first() {
+            Some((b'0', _)) => group.excluded = true,
+            Some((_, position)) if group.position.is_none() => {
+                group.position = Some(position.to_vec())
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+    emit(&group, output)?;
+
+    if keep_order {
+        reordered.finish(&mut |record| output(&record[17..]))?;
+    }
+    Ok(counts)
+}
+
+/// `gorilla wordlist merge` and `gorilla wordlist diff`, writing to the
+/// `--output-file` or stdout
+pub fn run(
+    action: WordlistAction,
+    args: &ProgramArgs,
+    client: &HttpClient,
+) -> Result<(), GorillaError> {
+    let paths = &args.words;
+    if paths.is_empty() || (action == WordlistAction::Diff && paths.len() < 2) {
+        return Err(GorillaError::Config(format!(
+            "`gorilla wordlist {}` needs {}",
+            action.name(),
+            match action {
+                WordlistAction::Merge => "the wordlists to merge",
+                WordlistAction::Diff =>
+                    "the wordlist to take the words from and one with the words to leave out",
+            }
+        )));
+    }
+
+    let inputs = paths
+        .iter()
+        .map(|path| {
+            open_input(path, client).map_err(|e| {
+                GorillaError::Io(format!(
+                    "could not read {}: {e}",
+                    display_name(path).purple()
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut out: Box<dyn Write> = match &args.file_save {
+        Some(path) => Box::new(BufWriter::new(File::create(path).map_err(|e| {
+            GorillaError::Io(format!(
+                "could not open the output file {}: {e}",
+                path.purple()
+            ))
+        })?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let temp_dir = args
+        .sort_temp_dir
+        .as_ref()
+        .map_or_else(env::temp_dir, PathBuf::from);
+    let memory_cap = args.sort_memory.saturating_mul(1024 * 1024) as usize;
+
+    let counts = combine(
+        action,
+        inputs,
+        args.keep_order,
+        memory_cap,
+        temp_dir,
+        &mut |word| {
+            out.write_all(word)?;
+            out.write_all(b"\n")
+        },
+    )
+    .and_then(|counts| out.flush().map(|()| counts));
+    let counts = match counts {
+        Ok(counts) => counts,
+        // like `head` having enough words
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe && args.file_save.is_none() => {
+            return Ok(())
+        }
+        Err(e) => {
+            return Err(GorillaError::Io(format!(
+                "could not {} the wordlists: {e}",
+                action.name()
+            )))
+        }
+    };
+
+    let written = counts.written.to_string().green();
+    match action {
+        WordlistAction::Merge => eprintln!("gorilla: merged the wordlists into {written} words"),
+        WordlistAction::Diff => eprintln!(
+            "gorilla: {written} words of {} aren't in the others",
+            display_name(&paths[0]).purple()
+        ),
+    }
+    for (path, words) in paths.iter().zip(&counts.inputs) {
+        eprintln!(
+            "         {} -> {} words",
+            display_name(path).purple(),
+            words.to_string().green()
+        );
+    }
+    Ok(())
+}
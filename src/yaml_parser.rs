@@ -1,55 +1,111 @@
 extern crate yaml_rust;
-use yaml_rust::YamlLoader;
-
-use colored::Colorize;
+use yaml_rust::{Yaml, YamlLoader};
 
 use crate::{
     formatting::{FormatField, FormatPart, FormatSet, FormattingSets},
-    mutation::{parse_mutation_string, MutationSet},
+    mutation::{parse_mutation_string, parse_mutations_with_suffixes, MutationSet, MutationsFile},
+    policy::{CharClass, Policy},
+    profile::{Date, Profile},
 };
 
-pub fn get_mutation_sets(yaml_input: &str) -> Vec<MutationSet> {
-    let mut result: Vec<MutationSet> = vec![];
+/// The first document of a yaml file, which has to have a `name`.
+fn load_document(yaml_input: &str) -> Result<(Yaml, String), String> {
+    let mut docs = YamlLoader::load_from_str(yaml_input).map_err(|e| e.to_string())?;
+    if docs.is_empty() {
+        return Err(String::from("it's empty"));
+    }
+    let doc = docs.swap_remove(0);
+
+    let name = doc["name"]
+        .as_str()
+        .ok_or("name is missing or isn't a string")?
+        .to_owned();
+    Ok((doc, name))
+}
+
+/// The strings of a yaml list, `field` naming it in errors.
+fn string_list<'a>(yaml: &'a Yaml, field: &str) -> Result<Vec<&'a str>, String> {
+    yaml.as_vec()
+        .ok_or_else(|| format!("{field} isn't a list"))?
+        .iter()
+        .map(|item| {
+            item.as_str()
+                .ok_or_else(|| format!("{field} has an item that isn't a string"))
+        })
+        .collect()
+}
 
-    let docs = YamlLoader::load_from_str(yaml_input).unwrap();
+/// The mutation sets of a mutations file, numbered from 1.
+///
+/// ```
+/// use gorilla::yaml_parser::get_mutation_sets;
+///
+/// let yaml = "name: example\nmutation_sets:\n  - [reverse]\n  - [\"append:{0-1}\"]\n";
+/// let mut words = vec![];
+/// for mutation_set in get_mutation_sets(yaml)? {
+///     mutation_set.perform_streaming("cat", &mut |word| words.push(word.to_owned()));
+/// }
+/// assert_eq!(words, ["tac", "cat0", "cat1"]);
+/// # Ok::<(), String>(())
+/// ```
+pub fn get_mutation_sets(yaml_input: &str) -> Result<Vec<MutationSet>, String> {
+    parse_mutations_yaml(yaml_input).map(|file| file.mutation_sets)
+}
 
-    let doc = &docs[0];
+/// Like `get_mutation_sets`, keeping the name of the file too. A
+/// `common_suffixes` list replaces the suffixes of the `common_suffixes`
+/// mutations of the file, the likeliest first.
+pub fn parse_mutations_yaml(yaml_input: &str) -> Result<MutationsFile, String> {
+    let mut result: Vec<MutationSet> = vec![];
 
-    eprintln!(
-        "gorilla: loading {} yaml mutations",
-        doc["name"].as_str().unwrap().purple()
-    );
+    let (doc, name) = load_document(yaml_input)?;
 
-    for mutation_set in doc["mutation_sets"].as_vec().unwrap() {
-        let mut mutation_strings: Vec<String> = vec![];
-        for yaml_mut_string in mutation_set.as_vec().unwrap() {
-            mutation_strings.push(yaml_mut_string.as_str().unwrap().to_string());
+    let suffixes = match &doc["common_suffixes"] {
+        Yaml::BadValue => None,
+        list => {
+            let suffixes = string_list(list, "common_suffixes")?;
+            if suffixes.is_empty() {
+                return Err(String::from("common_suffixes is empty"));
+            }
+            Some(suffixes)
         }
+    };
+
+    let mutation_sets = doc["mutation_sets"]
+        .as_vec()
+        .ok_or("mutation_sets is missing or isn't a list")?;
+    for (number, mutation_set) in mutation_sets.iter().enumerate() {
+        let mutation_strings: Vec<String> =
+            string_list(mutation_set, &format!("mutation set {}", number + 1))?
+                .into_iter()
+                .map(str::to_owned)
+                .collect();
 
         result.push(MutationSet {
-            mutations: parse_mutation_string(&mutation_strings),
+            id: number + 1,
+            mutations: parse_mutations_with_suffixes(&mutation_strings, suffixes.as_deref())?,
         })
     }
 
-    result
+    Ok(MutationsFile {
+        name,
+        mutation_sets: result,
+    })
 }
 
-pub fn parse_formatting_yaml(yaml_input: &str) -> FormattingSets {
-    let docs = YamlLoader::load_from_str(yaml_input).unwrap();
-
-    let doc = &docs[0];
-    let formatting_name = doc["name"].as_str().unwrap();
-
-    eprintln!(
-        "gorilla: loading {} formatting sets",
-        formatting_name.purple()
-    );
+pub fn parse_formatting_yaml(yaml_input: &str) -> Result<FormattingSets, String> {
+    let (doc, formatting_name) = load_document(yaml_input)?;
 
     let mut format_sets: Vec<FormatSet> = Vec::new();
     let mut format_fields: Vec<FormatField> = Vec::new();
 
-    for yaml_format_field in doc["fields"].as_vec().unwrap() {
-        let fields = yaml_format_field.as_vec().unwrap();
+    let yaml_format_fields = doc["fields"]
+        .as_vec()
+        .ok_or("fields is missing or isn't a list")?;
+    for yaml_format_field in yaml_format_fields {
+        let fields = yaml_format_field
+            .as_vec()
+            .ok_or("fields has an item that isn't a list")?;
         let mut question: Option<String> = None;
 
         if fields.len() > 1 {
@@ -57,15 +113,25 @@ pub fn parse_formatting_yaml(yaml_input: &str) -> FormattingSets {
         }
 
         format_fields.push(FormatField {
-            name: fields[0].as_str().unwrap().to_owned(),
+            name: fields
+                .first()
+                .and_then(Yaml::as_str)
+                .ok_or("fields has a field without a name")?
+                .to_owned(),
             question,
         })
     }
 
-    for yaml_format_set in doc["formatting_sets"].as_vec().unwrap() {
+    let yaml_format_sets = doc["formatting_sets"]
+        .as_vec()
+        .ok_or("formatting_sets is missing or isn't a list")?;
+    for (number, yaml_format_set) in yaml_format_sets.iter().enumerate() {
         let mut format_set = FormatSet::new();
 
-        for fmt_part in yaml_format_set.as_vec().unwrap() {
+        let fmt_parts = yaml_format_set
+            .as_vec()
+            .ok_or_else(|| format!("formatting set {} isn't a list", number + 1))?;
+        for fmt_part in fmt_parts {
             if let Some(fmt_str) = fmt_part.as_str() {
                 format_set.parts.push(FormatPart {
                     text: fmt_str.to_owned(),
@@ -73,11 +139,13 @@ pub fn parse_formatting_yaml(yaml_input: &str) -> FormattingSets {
                 })
             }
             if let Some(fmt_str) = fmt_part.as_vec() {
-                let txt = fmt_str[0].as_str().unwrap();
+                let txt = fmt_str.first().and_then(Yaml::as_str).ok_or_else(|| {
+                    format!("formatting set {} has a part without text", number + 1)
+                })?;
 
                 let mut mutation_strings = Vec::new();
 
-                if let Some(yaml_mut_vec) = fmt_str[1].as_vec() {
+                if let Some(yaml_mut_vec) = fmt_str.get(1).and_then(Yaml::as_vec) {
                     for yaml_mut_str in yaml_mut_vec {
                         if let Some(fmt_str) = yaml_mut_str.as_str() {
                             mutation_strings.push(fmt_str.to_owned())
@@ -85,14 +153,15 @@ pub fn parse_formatting_yaml(yaml_input: &str) -> FormattingSets {
                     }
                 }
 
-                if let Some(yaml_mut_str) = fmt_str[1].as_str() {
+                if let Some(yaml_mut_str) = fmt_str.get(1).and_then(Yaml::as_str) {
                     mutation_strings.push(yaml_mut_str.to_owned())
                 }
 
                 format_set.parts.push(FormatPart {
                     text: txt.to_owned(),
                     mutations: MutationSet {
-                        mutations: parse_mutation_string(&mutation_strings),
+                        id: 0,
+                        mutations: parse_mutation_string(&mutation_strings)?,
                     },
                 })
             }
@@ -101,9 +170,121 @@ pub fn parse_formatting_yaml(yaml_input: &str) -> FormattingSets {
         format_sets.push(format_set)
     }
 
-    FormattingSets {
-        name: formatting_name.to_owned(),
+    Ok(FormattingSets {
+        name: formatting_name,
         fields: format_fields,
         sets: format_sets,
+    })
+}
+
+/// A password policy file, of which only the `name` has to be there:
+///
+/// ```
+/// use gorilla::yaml_parser::parse_policy_yaml;
+///
+/// let yaml = "\
+/// name: corp
+/// min_length: 10
+/// max_length: 64
+/// require: [digit]
+/// min_classes: 3
+/// banned: [Acme, jsmith]
+/// ";
+/// let policy = parse_policy_yaml(yaml)?;
+/// assert!(policy.allows("Winter2024!"));
+/// assert!(!policy.allows("AcmeWinter2024!"));
+/// assert!(!policy.allows("Winter-Summer!"));
+/// # Ok::<(), String>(())
+/// ```
+pub fn parse_policy_yaml(yaml_input: &str) -> Result<Policy, String> {
+    let (doc, name) = load_document(yaml_input)?;
+
+    let length = |field: &str| match &doc[field] {
+        Yaml::BadValue => Ok(None),
+        value => value
+            .as_i64()
+            .and_then(|length| usize::try_from(length).ok())
+            .map(Some)
+            .ok_or_else(|| format!("{field} isn't a number of characters")),
+    };
+    let min_length = length("min_length")?;
+    let max_length = length("max_length")?;
+    let min_classes = length("min_classes")?.unwrap_or(0);
+    if min_classes > CharClass::BASIC.len() {
+        return Err(String::from(
+            "min_classes can be 4 at most, for lower, upper, digit and special",
+        ));
     }
+
+    let mut required = vec![];
+    if !doc["require"].is_badvalue() {
+        for class in string_list(&doc["require"], "require")? {
+            required.push(CharClass::from_name(class).ok_or_else(|| {
+                format!("require has {class}, which isn't lower, upper, letter, digit or special")
+            })?);
+        }
+    }
+    let banned = match &doc["banned"] {
+        Yaml::BadValue => vec![],
+        banned => string_list(banned, "banned")?
+            .into_iter()
+            .map(str::to_lowercase)
+            .collect(),
+    };
+
+    Ok(Policy {
+        name,
+        min_length,
+        max_length,
+        required,
+        min_classes,
+        banned,
+    })
+}
+
+/// A profile of `--profile`, with the target's full name as its `name` and
+/// every other field a string or a list of them.
+///
+/// ```
+/// use gorilla::yaml_parser::parse_profile_yaml;
+///
+/// let profile = parse_profile_yaml("name: Jane Doe\npets: Rex\nbirth_dates: [1990-04-12]\n")?;
+/// assert_eq!(profile.names, ["Jane Doe"]);
+/// assert_eq!(profile.birth_dates[0].year, 1990);
+/// # Ok::<(), String>(())
+/// ```
+pub fn parse_profile_yaml(yaml_input: &str) -> Result<Profile, String> {
+    let (doc, name) = load_document(yaml_input)?;
+
+    let strings = |field: &str| -> Result<Vec<String>, String> {
+        match &doc[field] {
+            Yaml::BadValue | Yaml::Null => Ok(vec![]),
+            Yaml::String(value) => Ok(vec![value.clone()]),
+            value => Ok(string_list(value, field)?
+                .into_iter()
+                .map(str::to_owned)
+                .collect()),
+        }
+    };
+
+    let mut names = vec![name];
+    names.extend(strings("names")?);
+    let birth_dates = strings("birth_dates")?
+        .iter()
+        .map(|date| {
+            Date::parse(date).ok_or_else(|| {
+                format!("birth_dates has {date}, which isn't a date like 1990-04-12")
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Profile {
+        names,
+        nicknames: strings("nicknames")?,
+        birth_dates,
+        pets: strings("pets")?,
+        company: strings("company")?,
+        teams: strings("teams")?,
+        keywords: strings("keywords")?,
+    })
 }
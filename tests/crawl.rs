@@ -0,0 +1,78 @@
+//! A crawl of a small site served on localhost, and what its summary says
+//! of the links it found
+#![cfg(all(feature = "cli", feature = "scrape"))]
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    process::{Command, Output},
+    thread,
+};
+
+fn gorilla(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_gorilla"))
+        .args(args)
+        .arg("--no-config")
+        .output()
+        .unwrap()
+}
+
+/// A site with a page linking to another page, to one robots.txt
+/// disallows, to one that isn't there and to an image
+fn serve() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request_line = String::new();
+            BufReader::new(&stream)
+                .read_line(&mut request_line)
+                .unwrap();
+            let path = request_line.split(' ').nth(1).unwrap_or("/");
+            let (status, content_type, body) = match path {
+                "/robots.txt" => (
+                    "200 OK",
+                    "text/plain",
+                    "User-agent: *\nDisallow: /private\n",
+                ),
+                "/" => (
+                    "200 OK",
+                    "text/html",
+                    "<p>welcome</p><a href=\"/about\">about</a><a href=\"/private\">private</a>\
+                     <a href=\"/missing\">missing</a><a href=\"/logo.png\">logo</a>",
+                ),
+                "/about" => ("200 OK", "text/html", "<p>gorillas</p>"),
+                _ => ("404 Not Found", "text/plain", "not found"),
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+    format!("http://{address}/")
+}
+
+#[test]
+fn only_the_pages_scraped_count_as_followed() {
+    let site = serve();
+    let output = gorilla(&[
+        "scrape",
+        "-w",
+        &site,
+        "--crawl-depth",
+        "1",
+        "--respect-robots",
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("gorillas"), "{stdout}");
+    assert!(
+        stderr.contains("followed 1 links, skipped 3 that"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,180 @@
+//! The gorilla binary in a pipeline: writing to a reader that stops reading
+//! early, like `head` or hashcat reading a FIFO, and reading what's piped
+//! in as it comes
+#![cfg(feature = "cli")]
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Command, Stdio},
+};
+
+fn gorilla(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_gorilla"));
+    command
+        .args(args)
+        .arg("--no-config")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    command
+}
+
+#[test]
+fn reader_closing_early_ends_the_run() {
+    let mut child = gorilla(&["generate", "-p", "{a-z}{a-z}{a-z}{a-z}"])
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut first = String::new();
+    stdout.read_line(&mut first).unwrap();
+    assert_eq!(first, "aaaa\n");
+    drop(stdout);
+
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+    assert!(stderr.contains("what read stdout went away"), "{stderr}");
+}
+
+#[test]
+fn only_words_on_stdout() {
+    let output = gorilla(&["mutate", "-m", "reverse", "-v"])
+        .stdin(Stdio::null())
+        .arg("--word")
+        .arg("cat")
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "tac\n");
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn flush_every_word() {
+    let mut child = gorilla(&["mutate", "-m", "reverse", "--flush-every", "1"])
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    // each word comes back before the next is sent, with stdin still open
+    for (word, reversed) in [("cat", "tac\n"), ("dog", "god\n")] {
+        writeln!(stdin, "{word}").unwrap();
+        stdin.flush().unwrap();
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        assert_eq!(line, reversed);
+    }
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn quiet_keeps_the_input_notes() {
+    let path = std::env::temp_dir().join(format!("gorilla-quiet-{}.txt", std::process::id()));
+    std::fs::write(&path, b"cat\n\xff\xfedog\nbird\n").unwrap();
+    let path = path.display().to_string();
+    let args = [
+        "mutate",
+        "-m",
+        "reverse",
+        "--quiet",
+        "--input-skip",
+        "1",
+        "-i",
+        &path,
+    ];
+    let output = gorilla(&args).output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(
+        stderr.contains("1 input lines were not valid UTF-8"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("continue with --input-skip 3"), "{stderr}");
+}
+
+#[cfg(unix)]
+#[test]
+fn ctrl_c_while_waiting_for_stdin() {
+    let mut child = gorilla(&["mutate", "-m", "reverse", "--flush-every", "1"])
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    writeln!(stdin, "cat").unwrap();
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "tac\n");
+
+    // gorilla is waiting for the next line when Ctrl+C comes, and stops
+    // with stdin still open
+    let interrupted = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(interrupted.success());
+    let status = child.wait().unwrap();
+    let mut stderr = String::new();
+    std::io::Read::read_to_string(&mut child.stderr.take().unwrap(), &mut stderr).unwrap();
+    assert_eq!(status.code(), Some(130), "{stderr}");
+    assert!(stderr.contains("interrupted"), "{stderr}");
+    drop(stdin);
+}
+
+#[cfg(all(unix, feature = "scrape"))]
+#[test]
+fn urls_piped_in_resume_after_ctrl_c() {
+    let dir = std::env::temp_dir().join(format!("gorilla-url-stdin-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let page = |name: &str, words: &str| {
+        let path = dir.join(name);
+        std::fs::write(&path, format!("<html><body>{words}</body></html>")).unwrap();
+        path.display().to_string()
+    };
+    let (first, second) = (page("first.html", "alpha"), page("second.html", "gamma"));
+    let state = dir.join("crawl.json").display().to_string();
+    let args = [
+        "scrape",
+        "--url-stdin",
+        "--crawl-state",
+        &state,
+        "--flush-every",
+        "1",
+    ];
+
+    // the first page is scraped while stdin is still open
+    let mut child = gorilla(&args).spawn().unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    writeln!(stdin, "{first}").unwrap();
+    let mut word = String::new();
+    stdout.read_line(&mut word).unwrap();
+    assert_eq!(word, "alpha\n");
+
+    let interrupted = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(interrupted.success());
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(130), "{stderr}");
+    assert!(stderr.contains("saved the crawl state"), "{stderr}");
+    let saved = std::fs::read_to_string(&state).unwrap();
+    assert!(saved.contains(&first), "{saved}");
+
+    // the pages scraped before are skipped when they're piped in again
+    let mut child = gorilla(&args).spawn().unwrap();
+    writeln!(child.stdin.take().unwrap(), "{first}\n{second}").unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "gamma\n");
+    assert!(stderr.contains("the crawl is complete"), "{stderr}");
+    assert!(!std::path::Path::new(&state).exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,90 @@
+//! The random choices of a run, made the same way again with its --seed
+#![cfg(feature = "cli")]
+
+use std::{
+    env, fs,
+    process::{self, Command, Output},
+};
+
+fn gorilla(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_gorilla"))
+        .args(args)
+        .arg("--no-config")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn the_same_seed_makes_the_same_words() {
+    let dir = env::temp_dir().join(format!("gorilla-seed-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let words = dir.join("words.txt");
+    let lines: String = (0..5000).map(|n| format!("word{n}\n")).collect();
+    fs::write(&words, lines).unwrap();
+    let words = words.to_str().unwrap();
+    let stats = dir.join("stats.json");
+
+    let run = |seed: &str| {
+        let args = [
+            "mutate",
+            "-i",
+            words,
+            "--input-sample",
+            "50",
+            "--threads",
+            "2",
+            "-m",
+            "reverse",
+            "-m",
+            "scramble",
+            "--set-m",
+            "append:{0-9}",
+            "-m",
+            "chance:50",
+            "--seed",
+            seed,
+            "--stats-file",
+            stats.to_str().unwrap(),
+        ];
+        let output = gorilla(&args);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output
+    };
+
+    // the sample, the scrambles and the chances all come out the same
+    let first = run("42");
+    let again = run("42");
+    assert_eq!(first.stdout, again.stdout);
+    let words = String::from_utf8(first.stdout.clone()).unwrap();
+    let words: Vec<&str> = words.lines().collect();
+    let chances = words.len() - 50;
+    assert!((100..400).contains(&chances), "{chances} of 500 words");
+    assert!(words.iter().all(|word| word.len() >= 5), "{words:?}");
+    assert!(words.iter().any(|word| !word.starts_with("word")));
+    let stderr = String::from_utf8_lossy(&first.stderr);
+    assert!(stderr.contains("--seed 42"), "{stderr}");
+    assert!(fs::read_to_string(&stats).unwrap().contains("\"seed\": 42"));
+
+    assert_ne!(run("43").stdout, first.stdout);
+
+    // nothing random, nothing to repeat
+    let output = gorilla(&["mutate", "--word", "cat", "-m", "reverse"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("seed"), "{stderr}");
+
+    // without a --seed, the one picked is printed to repeat the run with
+    let output = gorilla(&["mutate", "--word", "cat", "-m", "scramble"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let seed = stderr
+        .split("--seed ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap_or_else(|| panic!("{stderr}"));
+    assert!(seed.parse::<u64>().is_ok(), "{stderr}");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,21 @@
+//! The wasm interface, run headless with
+//! `cargo test --target wasm32-unknown-unknown --no-default-features --features wasm`
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use gorilla::wasm::{mutate, Mutator};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const YAML: &str = "name: demo\nmutation_sets:\n  - [reverse]\n  - [\"append:{0-1}\"]\n";
+
+#[wasm_bindgen_test]
+fn mutates_with_the_sets_of_a_file() {
+    let mutator = Mutator::new(YAML).unwrap();
+    assert_eq!(mutator.set_count(), 2);
+    assert_eq!(mutator.mutate("cat"), ["tac", "cat0", "cat1"]);
+    assert_eq!(mutate(YAML, "ab").unwrap(), ["ba", "ab0", "ab1"]);
+}
+
+#[wasm_bindgen_test]
+fn invalid_files_are_errors() {
+    assert!(Mutator::new("name: demo\nmutation_sets:\n  - [shuffle]\n").is_err());
+}